@@ -0,0 +1,100 @@
+//! Compares the cost of `QueryPipeline::colliders_with_aabb_intersecting` with the cost of
+//! `QueryPipeline::intersections_with_shape` using a cuboid covering the same region.
+//!
+//! Run with `cargo run --release --bin aabb_query3`.
+
+use rapier3d::dynamics::{RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet, Cuboid, InteractionGroups, AABB};
+use rapier3d::na::{Isometry3, Point3, Vector3};
+use rapier3d::pipeline::QueryPipeline;
+use std::time::Instant;
+
+pub fn main() {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut query_pipeline = QueryPipeline::new();
+
+    /*
+     * A grid of static balls and cuboids.
+     */
+    let num = 40;
+    let rad = 0.5;
+
+    for i in 0..num {
+        for j in 0..num {
+            for k in 0..num {
+                let x = (i as f32 - num as f32 / 2.0) * rad * 3.0;
+                let y = (j as f32 - num as f32 / 2.0) * rad * 3.0;
+                let z = (k as f32 - num as f32 / 2.0) * rad * 3.0;
+
+                let rigid_body = RigidBodyBuilder::new_static().translation(x, y, z).build();
+                let handle = bodies.insert(rigid_body);
+                let collider = if (i + j + k) % 2 == 0 {
+                    ColliderBuilder::ball(rad).build()
+                } else {
+                    ColliderBuilder::cuboid(rad, rad, rad).build()
+                };
+                colliders.insert(collider, handle, &mut bodies);
+            }
+        }
+    }
+
+    query_pipeline.update(&bodies, &colliders);
+
+    let num_queries = 10_000;
+    let half_extents = Vector3::repeat(rad * 4.0);
+    let centers: Vec<_> = (0..num_queries)
+        .map(|i| {
+            let t = i as f32 / num_queries as f32;
+            let extent = num as f32 * rad * 1.5;
+            Point3::new(
+                (t * 7.0).sin() * extent,
+                (t * 11.0).cos() * extent,
+                (t * 13.0).sin() * extent,
+            )
+        })
+        .collect();
+
+    let t0 = Instant::now();
+    let mut num_aabb_hits = 0;
+    for center in &centers {
+        let aabb = AABB::new(center - half_extents, center + half_extents);
+        query_pipeline.colliders_with_aabb_intersecting(
+            &colliders,
+            &aabb,
+            InteractionGroups::all(),
+            |_, _| {
+                num_aabb_hits += 1;
+                true
+            },
+        );
+    }
+    let aabb_time = t0.elapsed();
+
+    let t0 = Instant::now();
+    let mut num_shape_hits = 0;
+    let cuboid = Cuboid::new(half_extents);
+    for center in &centers {
+        let pos = Isometry3::translation(center.x, center.y, center.z);
+        query_pipeline.intersections_with_shape(
+            &colliders,
+            &pos,
+            &cuboid,
+            InteractionGroups::all(),
+            |_, _| {
+                num_shape_hits += 1;
+                true
+            },
+        );
+    }
+    let shape_time = t0.elapsed();
+
+    println!(
+        "colliders_with_aabb_intersecting: {:.2?} ({} hits)",
+        aabb_time, num_aabb_hits
+    );
+    println!(
+        "intersections_with_shape (cuboid): {:.2?} ({} hits)",
+        shape_time, num_shape_hits
+    );
+}
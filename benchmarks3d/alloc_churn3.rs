@@ -0,0 +1,115 @@
+//! Counts the number of memory allocations performed by each timestep of a scene where the
+//! number of contacts keeps fluctuating (bouncing balls).
+//!
+//! Run with `cargo run --release --bin alloc_churn3`.
+
+use rapier3d::dynamics::{IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+use rapier3d::na::Vector3;
+use rapier3d::pipeline::PhysicsPipeline;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static NUM_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        NUM_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        NUM_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+pub fn main() {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut joints = JointSet::new();
+    let mut pipeline = PhysicsPipeline::new();
+    let mut broad_phase = BroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let params = IntegrationParameters::default();
+    let gravity = Vector3::new(0.0, -9.81, 0.0);
+
+    /*
+     * Ground
+     */
+    let ground_size = 50.0;
+    let rigid_body = RigidBodyBuilder::new_static().build();
+    let handle = bodies.insert(rigid_body);
+    let collider = ColliderBuilder::cuboid(ground_size, 0.1, ground_size).build();
+    colliders.insert(collider, handle, &mut bodies);
+
+    /*
+     * Bouncing balls, entering and leaving contact all the time.
+     */
+    let num = 20;
+    let rad = 0.5;
+
+    for i in 0..num {
+        for j in 0..num {
+            for k in 0..num {
+                let x = (i as f32 - num as f32 / 2.0) * rad * 3.0;
+                let y = 2.0 + j as f32 * rad * 3.0 + (k % 3) as f32 * 0.1;
+                let z = (k as f32 - num as f32 / 2.0) * rad * 3.0;
+
+                let rigid_body = RigidBodyBuilder::new_dynamic()
+                    .translation(x, y, z)
+                    .can_sleep(false)
+                    .build();
+                let handle = bodies.insert(rigid_body);
+                let collider = ColliderBuilder::ball(rad).restitution(1.0).build();
+                colliders.insert(collider, handle, &mut bodies);
+            }
+        }
+    }
+
+    let num_steps = 1000;
+    let report_interval = 100;
+    let mut num_allocations = 0;
+
+    for step in 0..num_steps {
+        let before = NUM_ALLOCATIONS.load(Ordering::Relaxed);
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &(),
+            &(),
+        );
+        num_allocations += NUM_ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+        if (step + 1) % report_interval == 0 {
+            println!(
+                "Steps {}..{}: {:.1} allocations per step.",
+                step + 1 - report_interval,
+                step + 1,
+                num_allocations as f32 / report_interval as f32
+            );
+            num_allocations = 0;
+        }
+    }
+
+    let before = NUM_ALLOCATIONS.load(Ordering::Relaxed);
+    pipeline.shrink_to_fit();
+    println!(
+        "Allocations performed by `shrink_to_fit`: {}",
+        NUM_ALLOCATIONS.load(Ordering::Relaxed) - before
+    );
+}
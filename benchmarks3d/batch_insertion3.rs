@@ -0,0 +1,77 @@
+//! Compares the time needed to insert 50k colliders one by one with the time needed to
+//! insert them with `ColliderSet::insert_batch`.
+//!
+//! Run with `cargo run --release --bin batch_insertion3`.
+
+use rapier3d::dynamics::{RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet};
+use std::time::Instant;
+
+pub fn main() {
+    let num_bodies = 1_000;
+    let colliders_per_body = 50;
+
+    let make_bodies = || {
+        (0..num_bodies)
+            .map(|i| {
+                RigidBodyBuilder::new_dynamic()
+                    .translation(i as f32 * 10.0, 0.0, 0.0)
+                    .build()
+            })
+            .collect::<Vec<_>>()
+    };
+    let make_colliders = || {
+        (0..colliders_per_body)
+            .map(|j| {
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5)
+                    .translation(0.0, j as f32, 0.0)
+                    .build()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    /*
+     * One by one.
+     */
+    let bodies_to_insert = make_bodies();
+    let colliders_to_insert: Vec<_> = (0..num_bodies).map(|_| make_colliders()).collect();
+
+    let t0 = Instant::now();
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    for (rb, body_colliders) in bodies_to_insert.into_iter().zip(colliders_to_insert) {
+        let handle = bodies.insert(rb);
+        for co in body_colliders {
+            colliders.insert(co, handle, &mut bodies);
+        }
+    }
+    println!(
+        "Individual insertion of {} colliders: {:.2?}",
+        colliders.len(),
+        t0.elapsed()
+    );
+
+    /*
+     * Batched.
+     */
+    let bodies_to_insert = make_bodies();
+    let colliders_to_insert: Vec<_> = (0..num_bodies).map(|_| make_colliders()).collect();
+
+    let t0 = Instant::now();
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let handles = bodies.insert_batch(bodies_to_insert);
+    let batch = handles
+        .iter()
+        .zip(colliders_to_insert)
+        .flat_map(|(handle, body_colliders)| {
+            body_colliders.into_iter().map(move |co| (co, *handle))
+        })
+        .collect();
+    colliders.insert_batch(batch, &mut bodies);
+    println!(
+        "Batch insertion of {} colliders: {:.2?}",
+        colliders.len(),
+        t0.elapsed()
+    );
+}
@@ -0,0 +1,102 @@
+//! Measures the cost of moving 1000 static colliders at each frame with
+//! `RigidBodySet::set_positions`, e.g., for animated scenery.
+//!
+//! Run with `cargo run --release --bin batch_positions3`.
+
+use rapier3d::dynamics::{IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+use rapier3d::na::{Isometry3, Vector3};
+use rapier3d::pipeline::{PhysicsPipeline, QueryPipeline};
+use std::time::{Duration, Instant};
+
+pub fn main() {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut joints = JointSet::new();
+    let mut broad_phase = BroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let mut pipeline = PhysicsPipeline::new();
+    let mut query_pipeline = QueryPipeline::new();
+    let params = IntegrationParameters::default();
+    let gravity = Vector3::new(0.0, -9.81, 0.0);
+
+    /*
+     * A grid of static platforms animated at each frame.
+     */
+    let num = 10;
+    let rad = 0.5;
+    let mut platforms = Vec::new();
+
+    for i in 0..num {
+        for j in 0..num {
+            for k in 0..num {
+                let x = (i as f32 - num as f32 / 2.0) * rad * 4.0;
+                let y = j as f32 * rad * 4.0;
+                let z = (k as f32 - num as f32 / 2.0) * rad * 4.0;
+
+                let rigid_body = RigidBodyBuilder::new_static().translation(x, y, z).build();
+                let handle = bodies.insert(rigid_body);
+                let collider = ColliderBuilder::cuboid(rad, rad * 0.2, rad).build();
+                colliders.insert(collider, handle, &mut bodies);
+                platforms.push((handle, Vector3::new(x, y, z)));
+            }
+        }
+    }
+
+    /*
+     * A few balls falling on the platforms.
+     */
+    for i in 0..num {
+        for k in 0..num {
+            let x = (i as f32 - num as f32 / 2.0) * rad * 4.0;
+            let z = (k as f32 - num as f32 / 2.0) * rad * 4.0;
+            let rigid_body = RigidBodyBuilder::new_dynamic()
+                .translation(x, num as f32 * rad * 4.0, z)
+                .build();
+            let handle = bodies.insert(rigid_body);
+            colliders.insert(
+                ColliderBuilder::ball(rad * 0.5).build(),
+                handle,
+                &mut bodies,
+            );
+        }
+    }
+
+    let num_frames = 200;
+    let mut update_time = Duration::default();
+    let t0 = Instant::now();
+
+    for frame in 0..num_frames {
+        let t = frame as f32 * params.dt;
+        let t1 = Instant::now();
+        bodies.set_positions(
+            platforms.iter().map(|(handle, origin)| {
+                let offset = Vector3::new((t + origin.y).sin() * rad, 0.0, 0.0);
+                (*handle, Isometry3::new(origin + offset, Vector3::y() * t))
+            }),
+            true,
+        );
+        update_time += t1.elapsed();
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &(),
+            &(),
+        );
+        query_pipeline.update(&bodies, &colliders);
+    }
+
+    println!(
+        "{} frames moving {} colliders: {:.2?} total, {:.2?} in set_positions",
+        num_frames,
+        platforms.len(),
+        t0.elapsed(),
+        update_time
+    );
+}
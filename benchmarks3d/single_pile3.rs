@@ -0,0 +1,95 @@
+//! Measures how the time needed to simulate a single pile of 5000 boxes scales with the number
+//! of threads. All the boxes end up in the same island, so the speedup only comes from the
+//! parallel resolution of the constraints of each color within the island.
+//!
+//! Run with `cargo run --release --features parallel --bin single_pile3`.
+
+use rapier3d::dynamics::{IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+use rapier3d::na::Vector3;
+use rapier3d::pipeline::PhysicsPipeline;
+use std::time::{Duration, Instant};
+
+fn run() -> Duration {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut joints = JointSet::new();
+    let mut pipeline = PhysicsPipeline::new();
+    let mut broad_phase = BroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let params = IntegrationParameters::default();
+    let gravity = Vector3::y() * -9.81;
+
+    let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+    colliders.insert(
+        ColliderBuilder::cuboid(100.0, 0.5, 100.0).build(),
+        ground,
+        &mut bodies,
+    );
+
+    // 20 layers of 250 boxes, all touching their neighbors.
+    let width = 25;
+    let depth = 10;
+    for layer in 0..20 {
+        for i in 0..width {
+            for j in 0..depth {
+                let x = (i as f32 - width as f32 / 2.0) * 0.99;
+                let z = (j as f32 - depth as f32 / 2.0) * 0.99;
+                let y = 0.99 + layer as f32 * 0.99;
+                let body =
+                    bodies.insert(RigidBodyBuilder::new_dynamic().translation(x, y, z).build());
+                colliders.insert(
+                    ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(),
+                    body,
+                    &mut bodies,
+                );
+            }
+        }
+    }
+
+    let t0 = Instant::now();
+    for _ in 0..100 {
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &(),
+            &(),
+        );
+    }
+    t0.elapsed()
+}
+
+#[cfg(feature = "parallel")]
+pub fn main() {
+    let num_threads = rapier3d::rayon::current_num_threads();
+    let mut reference = None;
+
+    for threads in (0..).map(|i| 1 << i).take_while(|n| *n <= num_threads) {
+        let pool = rapier3d::rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let elapsed = pool.install(run);
+        let reference = *reference.get_or_insert(elapsed);
+        println!(
+            "{} thread(s): 100 steps of a single pile of 5000 boxes in {:.2?} (speedup: {:.2})",
+            threads,
+            elapsed,
+            reference.as_secs_f32() / elapsed.as_secs_f32()
+        );
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn main() {
+    println!(
+        "Single-threaded: 100 steps of a single pile of 5000 boxes in {:.2?}",
+        run()
+    );
+    println!("Enable the `parallel` feature to compare with multiple threads.");
+}
@@ -0,0 +1,80 @@
+//! Compares the time needed to simulate a pyramid of boxes with the default solver settings
+//! with the time needed when the position solver is disabled and the penetrations are only
+//! corrected by the velocity solver.
+//!
+//! Run with `cargo run --release --bin velocity_only3`.
+
+use rapier3d::dynamics::{IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+use rapier3d::na::Vector3;
+use rapier3d::pipeline::PhysicsPipeline;
+use std::time::Instant;
+
+fn run(params: &IntegrationParameters, label: &str) {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let mut joints = JointSet::new();
+    let mut pipeline = PhysicsPipeline::new();
+    let mut broad_phase = BroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let gravity = Vector3::y() * -9.81;
+
+    let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+    colliders.insert(
+        ColliderBuilder::cuboid(100.0, 0.5, 100.0).build(),
+        ground,
+        &mut bodies,
+    );
+
+    let base = 30;
+    for layer in 0..base {
+        let width = base - layer;
+        for i in 0..width {
+            for j in 0..width {
+                let x = (i as f32 - width as f32 / 2.0) * 1.0;
+                let z = (j as f32 - width as f32 / 2.0) * 1.0;
+                let y = 1.0 + layer as f32;
+                let body =
+                    bodies.insert(RigidBodyBuilder::new_dynamic().translation(x, y, z).build());
+                colliders.insert(
+                    ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(),
+                    body,
+                    &mut bodies,
+                );
+            }
+        }
+    }
+
+    let t0 = Instant::now();
+    for _ in 0..200 {
+        pipeline.step(
+            &gravity,
+            params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &(),
+            &(),
+        );
+    }
+    println!(
+        "{}: 200 steps with {} bodies in {:.2?}",
+        label,
+        bodies.len(),
+        t0.elapsed()
+    );
+}
+
+pub fn main() {
+    let params = IntegrationParameters::default();
+    run(&params, "With position solver");
+
+    let params = IntegrationParameters {
+        max_position_iterations: 0,
+        velocity_based_erp: 0.2,
+        ..IntegrationParameters::default()
+    };
+    run(&params, "Velocity solver only");
+}
@@ -0,0 +1,529 @@
+use crate::geometry::{ColliderHandle, ColliderSet, InteractionGroups, TOI};
+use crate::math::{Isometry, Real, Translation, Vector, DEFAULT_EPSILON};
+use crate::pipeline::QueryPipeline;
+use crate::utils::DeterministicMath;
+use na::{RealField, Unit};
+use parry::shape::Shape;
+
+/// The maximum number of obstacles a character can collide with during a single movement.
+const MAX_ITERATIONS: usize = 10;
+
+/// Configuration of the automatic climbing of small obstacles (like stairs) by a
+/// character controller.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct CharacterAutostep {
+    /// The maximum height of an obstacle the character can step on.
+    pub max_height: Real,
+    /// The minimum free width the top of the obstacle must have for the character to step on it.
+    pub min_width: Real,
+}
+
+impl Default for CharacterAutostep {
+    fn default() -> Self {
+        Self {
+            max_height: 0.25,
+            min_width: 0.5,
+        }
+    }
+}
+
+/// A collision between the character and a collider, detected while moving the character.
+#[derive(Copy, Clone, Debug)]
+pub struct CharacterCollision {
+    /// The handle of the collider hit by the character.
+    pub handle: ColliderHandle,
+    /// The position of the character when it hit the collider.
+    pub character_pos: Isometry<Real>,
+    /// The translation applied to the character, including the motion leading to this collision.
+    pub translation_applied: Vector<Real>,
+    /// The translation the character still had to apply after this collision.
+    pub translation_remaining: Vector<Real>,
+    /// The time-of-impact between the character's shape and the collider.
+    ///
+    /// Its first normal and witness point are expressed in world-space and are
+    /// located on the collider.
+    pub toi: TOI,
+}
+
+/// The movement computed by a character controller.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EffectiveCharacterMovement {
+    /// The translation the character must apply.
+    pub translation: Vector<Real>,
+    /// Is the character touching a walkable ground after this movement?
+    pub grounded: bool,
+}
+
+/// A character controller for kinematic bodies.
+///
+/// Given the translation the character wants to apply, this computes the translation it
+/// can actually apply without penetrating its surroundings. The obstacles are detected
+/// with shape-casts on the `QueryPipeline`, so the query pipeline must be up-to-date.
+/// The character's own colliders must be excluded from these shape-casts with the
+/// `groups` given to `Self::move_shape`.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct KinematicCharacterController {
+    /// The direction that goes "up". Used to determine where the floor is, and the floor's angle.
+    pub up: Unit<Vector<Real>>,
+    /// The small gap preserved between the character and its surroundings.
+    ///
+    /// It must not be zero to keep the shape-casts numerically stable.
+    pub offset: Real,
+    /// Should the character slide against the obstacles it hits, instead of stopping?
+    pub slide: bool,
+    /// The maximum angle (in radians) between the floor's normal and the `up` vector
+    /// that the character is able to climb.
+    pub max_slope_climb_angle: Real,
+    /// Should the character automatically step over small obstacles?
+    pub autostep: Option<CharacterAutostep>,
+    /// If set, the character is snapped to the ground if the distance between the ground
+    /// and its feet is smaller than this threshold, unless it is moving upward.
+    pub snap_to_ground: Option<Real>,
+}
+
+impl Default for KinematicCharacterController {
+    fn default() -> Self {
+        Self {
+            up: Vector::y_axis(),
+            offset: 0.01,
+            slide: true,
+            max_slope_climb_angle: Real::frac_pi_4(),
+            autostep: None,
+            snap_to_ground: Some(0.2),
+        }
+    }
+}
+
+impl KinematicCharacterController {
+    /// Computes the movement the character can apply to move by `desired_translation`.
+    ///
+    /// # Parameters
+    /// * `colliders` - The set of colliders the character can collide with.
+    /// * `queries` - The query pipeline used for the shape-casts. It must be up-to-date.
+    /// * `character_shape` - The shape of the character.
+    /// * `character_pos` - The position of the character.
+    /// * `desired_translation` - The translation the character wants to apply, e.g., its
+    ///   velocity multiplied by the timestep length.
+    /// * `groups` - The collision groups of the character. They must exclude the colliders
+    ///   attached to the character itself.
+    /// * `events` - Called for each obstacle hit by the character.
+    pub fn move_shape(
+        &self,
+        colliders: &ColliderSet,
+        queries: &QueryPipeline,
+        character_shape: &dyn Shape,
+        character_pos: &Isometry<Real>,
+        desired_translation: Vector<Real>,
+        groups: InteractionGroups,
+        mut events: impl FnMut(CharacterCollision),
+    ) -> EffectiveCharacterMovement {
+        let mut result = EffectiveCharacterMovement {
+            translation: Vector::zeros(),
+            grounded: false,
+        };
+        let mut translation_remaining = desired_translation;
+
+        for _ in 0..MAX_ITERATIONS {
+            let (dir, length) =
+                if let Some(dir) = Unit::try_new_and_get(translation_remaining, DEFAULT_EPSILON) {
+                    dir
+                } else {
+                    break;
+                };
+
+            let pos = Translation::from(result.translation) * character_pos;
+
+            if let Some((handle, toi)) = self.cast(
+                colliders,
+                queries,
+                character_shape,
+                &pos,
+                &dir,
+                length,
+                groups,
+            ) {
+                // Move up to the obstacle, keeping a gap of `self.offset` from it.
+                result.translation += *dir * toi.toi + *toi.normal1 * self.offset;
+                translation_remaining -= *dir * toi.toi;
+
+                events(CharacterCollision {
+                    handle,
+                    character_pos: pos,
+                    translation_applied: result.translation,
+                    translation_remaining,
+                    toi,
+                });
+
+                if self.is_walkable(&toi.normal1) {
+                    result.grounded = true;
+                }
+
+                let pos = Translation::from(result.translation) * character_pos;
+
+                if let Some(step_height) = self.try_autostep(
+                    colliders,
+                    queries,
+                    character_shape,
+                    &pos,
+                    &translation_remaining,
+                    &toi.normal1,
+                    groups,
+                ) {
+                    result.translation += *self.up * step_height;
+                    result.grounded = true;
+                    continue;
+                }
+
+                if !self.slide {
+                    break;
+                }
+
+                translation_remaining = self.slide_against(&toi.normal1, translation_remaining);
+            } else {
+                result.translation += translation_remaining;
+                break;
+            }
+        }
+
+        self.detect_ground(
+            colliders,
+            queries,
+            character_shape,
+            character_pos,
+            &desired_translation,
+            groups,
+            &mut result,
+        );
+
+        result
+    }
+
+    /// Can the character stand on a surface with the given normal?
+    fn is_walkable(&self, normal: &Unit<Vector<Real>>) -> bool {
+        normal.dot(&self.up) >= self.max_slope_climb_angle.det_cos()
+    }
+
+    /// Casts the character's shape, ignoring the obstacles it is already touching but
+    /// moving away from.
+    fn cast(
+        &self,
+        colliders: &ColliderSet,
+        queries: &QueryPipeline,
+        character_shape: &dyn Shape,
+        pos: &Isometry<Real>,
+        dir: &Unit<Vector<Real>>,
+        max_dist: Real,
+        groups: InteractionGroups,
+    ) -> Option<(ColliderHandle, TOI)> {
+        let (handle, toi) = queries.cast_shape(
+            colliders,
+            pos,
+            dir.as_ref(),
+            character_shape,
+            max_dist,
+            0.0,
+            groups,
+        )?;
+
+        if toi.toi <= 0.0 && toi.normal1.dot(dir) >= 0.0 {
+            None
+        } else {
+            Some((handle, toi))
+        }
+    }
+
+    /// Removes from `translation` its component going through an obstacle with the given normal.
+    fn slide_against(
+        &self,
+        normal: &Unit<Vector<Real>>,
+        translation: Vector<Real>,
+    ) -> Vector<Real> {
+        // Slopes too steep to be climbed are handled like vertical walls.
+        let normal = if self.is_walkable(normal) {
+            normal.into_inner()
+        } else {
+            let horizontal_normal = normal.as_ref() - *self.up * normal.dot(&self.up);
+            Unit::try_new(horizontal_normal, DEFAULT_EPSILON)
+                .map_or(normal.into_inner(), |n| n.into_inner())
+        };
+
+        let dot = translation.dot(&normal);
+
+        if dot < 0.0 {
+            translation - normal * dot
+        } else {
+            translation
+        }
+    }
+
+    /// Computes the height the character must be lifted by to step over the obstacle it hit.
+    fn try_autostep(
+        &self,
+        colliders: &ColliderSet,
+        queries: &QueryPipeline,
+        character_shape: &dyn Shape,
+        pos: &Isometry<Real>,
+        translation_remaining: &Vector<Real>,
+        normal: &Unit<Vector<Real>>,
+        groups: InteractionGroups,
+    ) -> Option<Real> {
+        let autostep = self.autostep?;
+
+        if self.is_walkable(normal) {
+            // The character can already walk on this obstacle.
+            return None;
+        }
+
+        let horizontal_translation =
+            translation_remaining - *self.up * translation_remaining.dot(&self.up);
+        let horizontal_dir = Unit::try_new(horizontal_translation, DEFAULT_EPSILON)?;
+
+        if horizontal_dir.dot(normal) >= 0.0 {
+            // The character isn't moving toward the obstacle.
+            return None;
+        }
+
+        // Check there is enough room above the character.
+        let up_dist = self
+            .cast(
+                colliders,
+                queries,
+                character_shape,
+                pos,
+                &self.up,
+                autostep.max_height,
+                groups,
+            )
+            .map_or(autostep.max_height, |(_, toi)| toi.toi - self.offset);
+
+        if up_dist <= 0.0 {
+            return None;
+        }
+
+        // Check there is enough room on top of the obstacle.
+        let raised_pos = Translation::from(*self.up * up_dist) * pos;
+
+        if self
+            .cast(
+                colliders,
+                queries,
+                character_shape,
+                &raised_pos,
+                &horizontal_dir,
+                autostep.min_width,
+                groups,
+            )
+            .is_some()
+        {
+            return None;
+        }
+
+        // Find the top of the obstacle.
+        let ahead_pos = Translation::from(*horizontal_dir * autostep.min_width) * raised_pos;
+        let (_, toi) = self.cast(
+            colliders,
+            queries,
+            character_shape,
+            &ahead_pos,
+            &Unit::new_unchecked(-*self.up),
+            up_dist,
+            groups,
+        )?;
+
+        if !self.is_walkable(&toi.normal1) {
+            return None;
+        }
+
+        let step_height = up_dist - toi.toi + self.offset;
+
+        if step_height > 0.0 {
+            Some(step_height)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if the character is standing on the ground and snaps it to the ground if needed.
+    fn detect_ground(
+        &self,
+        colliders: &ColliderSet,
+        queries: &QueryPipeline,
+        character_shape: &dyn Shape,
+        character_pos: &Isometry<Real>,
+        desired_translation: &Vector<Real>,
+        groups: InteractionGroups,
+        result: &mut EffectiveCharacterMovement,
+    ) {
+        let snap_distance = self
+            .snap_to_ground
+            .filter(|_| desired_translation.dot(&self.up) <= 0.0);
+        let max_dist = snap_distance.unwrap_or(0.0).max(self.offset * 2.0);
+        let pos = Translation::from(result.translation) * character_pos;
+
+        if let Some((_, toi)) = self.cast(
+            colliders,
+            queries,
+            character_shape,
+            &pos,
+            &Unit::new_unchecked(-*self.up),
+            max_dist,
+            groups,
+        ) {
+            if self.is_walkable(&toi.normal1) {
+                result.grounded = true;
+
+                if snap_distance.is_some() && toi.toi > self.offset {
+                    result.translation -= *self.up * (toi.toi - self.offset);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CharacterAutostep, KinematicCharacterController};
+    use crate::dynamics::{RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{Ball, ColliderBuilder, ColliderSet, InteractionGroups};
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::QueryPipeline;
+    use na::RealField;
+
+    #[cfg(feature = "dim2")]
+    fn cuboid(hx: Real, hy: Real) -> ColliderBuilder {
+        ColliderBuilder::cuboid(hx, hy)
+    }
+
+    #[cfg(feature = "dim3")]
+    fn cuboid(hx: Real, hy: Real) -> ColliderBuilder {
+        ColliderBuilder::cuboid(hx, hy, 10.0)
+    }
+
+    fn at(collider: ColliderBuilder, translation: Vector<Real>) -> ColliderBuilder {
+        collider.position(Isometry::new(translation, na::zero()))
+    }
+
+    fn scene(obstacles: Vec<ColliderBuilder>) -> (ColliderSet, QueryPipeline) {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+
+        for obstacle in obstacles {
+            colliders.insert(obstacle.build(), ground, &mut bodies);
+        }
+
+        let mut queries = QueryPipeline::new();
+        queries.update(&bodies, &colliders);
+        (colliders, queries)
+    }
+
+    fn floor() -> ColliderBuilder {
+        at(cuboid(10.0, 0.5), Vector::y() * -0.5)
+    }
+
+    #[test]
+    fn character_slides_along_wall() {
+        let wall = at(cuboid(0.5, 5.0), Vector::x() * 2.0);
+        let (colliders, queries) = scene(vec![wall]);
+        let controller = KinematicCharacterController {
+            snap_to_ground: None,
+            ..KinematicCharacterController::default()
+        };
+        let mut num_collisions = 0;
+
+        let movement = controller.move_shape(
+            &colliders,
+            &queries,
+            &Ball::new(0.5),
+            &Isometry::identity(),
+            Vector::x() * 3.0 + Vector::y(),
+            InteractionGroups::all(),
+            |_| num_collisions += 1,
+        );
+
+        assert_eq!(num_collisions, 1);
+        assert!((movement.translation.x - (1.0 - controller.offset)).abs() < 1.0e-3);
+        assert!((movement.translation.y - 1.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn character_steps_up_stair() {
+        let stair = at(cuboid(5.0, 0.1), Vector::x() * 6.0 + Vector::y() * 0.1);
+        let (colliders, queries) = scene(vec![floor(), stair]);
+        let start = Isometry::new(Vector::y() * 0.51, na::zero());
+        let mut controller = KinematicCharacterController::default();
+
+        // Without autostep, the stair blocks the character.
+        let movement = controller.move_shape(
+            &colliders,
+            &queries,
+            &Ball::new(0.5),
+            &start,
+            Vector::x() * 2.0,
+            InteractionGroups::all(),
+            |_| {},
+        );
+        assert!(movement.translation.x < 1.0);
+
+        controller.autostep = Some(CharacterAutostep {
+            max_height: 0.3,
+            min_width: 0.5,
+        });
+        let movement = controller.move_shape(
+            &colliders,
+            &queries,
+            &Ball::new(0.5),
+            &start,
+            Vector::x() * 2.0,
+            InteractionGroups::all(),
+            |_| {},
+        );
+        assert!(movement.grounded);
+        assert!((movement.translation.x - 2.0).abs() < 2.0 * controller.offset);
+        assert!((movement.translation.y - 0.2).abs() < 2.0 * controller.offset);
+    }
+
+    #[test]
+    fn character_stops_at_steep_slope() {
+        let angle = Real::frac_pi_3();
+        // The top face of this slope goes through the point (2, 0).
+        let normal = Vector::y() * angle.cos() - Vector::x() * angle.sin();
+        let center = Vector::x() * 2.0 - normal * 0.5;
+        #[cfg(feature = "dim2")]
+        let rotation = angle;
+        #[cfg(feature = "dim3")]
+        let rotation = Vector::z() * angle;
+        let slope = cuboid(5.0, 0.5).position(Isometry::new(center, rotation));
+        let (colliders, queries) = scene(vec![floor(), slope]);
+        let start = Isometry::new(Vector::y() * 0.51, na::zero());
+        let mut controller = KinematicCharacterController::default();
+
+        let movement = controller.move_shape(
+            &colliders,
+            &queries,
+            &Ball::new(0.5),
+            &start,
+            Vector::x() * 3.0,
+            InteractionGroups::all(),
+            |_| {},
+        );
+        assert!(movement.translation.x < 1.75);
+        assert!(movement.translation.y.abs() < 2.0 * controller.offset);
+
+        // The same slope can be climbed with a greater slope limit.
+        let max_angle: Real = 70.0;
+        controller.max_slope_climb_angle = max_angle.to_radians();
+        let movement = controller.move_shape(
+            &colliders,
+            &queries,
+            &Ball::new(0.5),
+            &start,
+            Vector::x() * 3.0,
+            InteractionGroups::all(),
+            |_| {},
+        );
+        assert!(movement.translation.y > 0.1);
+    }
+}
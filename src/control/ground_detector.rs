@@ -0,0 +1,182 @@
+use crate::geometry::{ColliderHandle, NarrowPhase};
+use crate::math::{Real, Vector};
+use na::Unit;
+
+/// A transition of the grounded state of a collider registered into a `GroundDetector`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GroundEvent {
+    /// The first collider started being supported by the second collider.
+    Landed(ColliderHandle, ColliderHandle),
+    /// The collider stopped being supported by any collider.
+    LeftGround(ColliderHandle),
+}
+
+/// Detects when some colliders land on, or leave, the ground.
+///
+/// A collider is grounded if it has an active contact with a normal forming an angle smaller
+/// than `max_slope_angle` with the `up` direction, as detected by `NarrowPhase::ground_contact`.
+/// The detector must be updated after each timestep with `Self::update`, which reports the
+/// transitions of the grounded state of each registered collider since the last update.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct GroundDetector {
+    /// The direction that goes "up". Used to determine where the ground is, and its slope.
+    pub up: Unit<Vector<Real>>,
+    /// The maximum angle (in radians) between the contact normal and the `up` vector for the
+    /// contact to be considered as ground.
+    pub max_slope_angle: Real,
+    // The registered colliders and the collider they are standing on, if any.
+    colliders: Vec<(ColliderHandle, Option<ColliderHandle>)>,
+}
+
+impl GroundDetector {
+    /// Creates a ground detector with no registered collider.
+    pub fn new(up: Unit<Vector<Real>>, max_slope_angle: Real) -> Self {
+        Self {
+            up,
+            max_slope_angle,
+            colliders: Vec::new(),
+        }
+    }
+
+    /// Starts tracking the grounded state of the given collider.
+    ///
+    /// The collider is considered not grounded until the next call to `Self::update`, so a
+    /// `GroundEvent::Landed` is reported then if it is already touching the ground.
+    pub fn register(&mut self, collider: ColliderHandle) {
+        if !self.colliders.iter().any(|(handle, _)| *handle == collider) {
+            self.colliders.push((collider, None));
+        }
+    }
+
+    /// Stops tracking the grounded state of the given collider.
+    ///
+    /// Returns `false` if this collider was not registered.
+    pub fn unregister(&mut self, collider: ColliderHandle) -> bool {
+        let len = self.colliders.len();
+        self.colliders.retain(|(handle, _)| *handle != collider);
+        self.colliders.len() != len
+    }
+
+    /// The collider supporting the given registered collider at the last update, if any.
+    pub fn ground(&self, collider: ColliderHandle) -> Option<ColliderHandle> {
+        self.colliders
+            .iter()
+            .find(|(handle, _)| *handle == collider)
+            .and_then(|(_, ground)| *ground)
+    }
+
+    /// Was the given registered collider grounded at the last update?
+    pub fn is_grounded(&self, collider: ColliderHandle) -> bool {
+        self.ground(collider).is_some()
+    }
+
+    /// Updates the grounded state of the registered colliders from the contacts of the
+    /// narrow-phase, and returns its transitions, in the order the colliders were registered.
+    ///
+    /// A collider moving from one ground to another one during a single timestep stays
+    /// grounded, so no event is reported for it.
+    pub fn update(&mut self, narrow_phase: &NarrowPhase) -> Vec<GroundEvent> {
+        let mut events = Vec::new();
+
+        for (collider, ground) in &mut self.colliders {
+            let new_ground = narrow_phase
+                .ground_contact(*collider, &self.up, self.max_slope_angle)
+                .map(|info| info.collider);
+
+            match (*ground, new_ground) {
+                (None, Some(new_ground)) => events.push(GroundEvent::Landed(*collider, new_ground)),
+                (Some(_), None) => events.push(GroundEvent::LeftGround(*collider)),
+                _ => {}
+            }
+
+            *ground = new_ground;
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GroundDetector, GroundEvent};
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn jump_and_land() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        #[cfg(feature = "dim2")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                .build(),
+        );
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+        let player = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.5, na::zero()))
+                .lock_rotations()
+                .build(),
+        );
+        let player_collider = world
+            .colliders
+            .insert(box_shape.build(), player, &mut world.bodies);
+
+        let mut detector = GroundDetector::new(Vector::y_axis(), (45.0 as Real).to_radians());
+        detector.register(player_collider);
+
+        let mut events = Vec::new();
+        let step = |world: &mut TestWorld, detector: &mut GroundDetector| {
+            world.step();
+            detector.update(&world.narrow_phase)
+        };
+
+        // Settle on the ground.
+        for _ in 0..10 {
+            events.extend(step(&mut world, &mut detector));
+        }
+        assert_eq!(
+            events,
+            vec![GroundEvent::Landed(player_collider, ground_collider)]
+        );
+        assert!(detector.is_grounded(player_collider));
+
+        // Jump, and land back.
+        events.clear();
+        world.bodies[player].set_linvel(Vector::y() * 5.0, true);
+        for _ in 0..120 {
+            events.extend(step(&mut world, &mut detector));
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                GroundEvent::LeftGround(player_collider),
+                GroundEvent::Landed(player_collider, ground_collider),
+            ]
+        );
+        assert_eq!(detector.ground(player_collider), Some(ground_collider));
+
+        assert!(detector.unregister(player_collider));
+        assert!(!detector.unregister(player_collider));
+        assert!(!detector.is_grounded(player_collider));
+    }
+}
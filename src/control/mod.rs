@@ -0,0 +1,9 @@
+//! Utilities for controlling the trajectories of objects in a non-physical way.
+
+pub use self::character_controller::{
+    CharacterAutostep, CharacterCollision, EffectiveCharacterMovement, KinematicCharacterController,
+};
+pub use self::ground_detector::{GroundDetector, GroundEvent};
+
+mod character_controller;
+mod ground_detector;
@@ -10,6 +10,14 @@ pub struct SolverCounters {
     pub ncontacts: usize,
     /// Time spent for the resolution of the constraints (force computation).
     pub velocity_resolution_time: Timer,
+    /// Part of `velocity_resolution_time` spent solving the contact constraints.
+    ///
+    /// This is not measured with the `parallel` feature.
+    pub velocity_contact_resolution_time: Timer,
+    /// Part of `velocity_resolution_time` spent solving the joint constraints.
+    ///
+    /// This is not measured with the `parallel` feature.
+    pub velocity_joint_resolution_time: Timer,
     /// Time spent for the assembly of all the velocity constraints.
     pub velocity_assembly_time: Timer,
     /// Time spent for the update of the velocity of the bodies.
@@ -28,6 +36,8 @@ impl SolverCounters {
             ncontacts: 0,
             velocity_assembly_time: Timer::new(),
             velocity_resolution_time: Timer::new(),
+            velocity_contact_resolution_time: Timer::new(),
+            velocity_joint_resolution_time: Timer::new(),
             velocity_update_time: Timer::new(),
             position_assembly_time: Timer::new(),
             position_resolution_time: Timer::new(),
@@ -39,6 +49,8 @@ impl SolverCounters {
         self.nconstraints = 0;
         self.ncontacts = 0;
         self.velocity_resolution_time.reset();
+        self.velocity_contact_resolution_time.reset();
+        self.velocity_joint_resolution_time.reset();
         self.velocity_assembly_time.reset();
         self.velocity_update_time.reset();
         self.position_assembly_time.reset();
@@ -56,6 +68,16 @@ impl Display for SolverCounters {
             "Velocity resolution time: {}",
             self.velocity_resolution_time
         )?;
+        writeln!(
+            f,
+            "Velocity contact resolution time: {}",
+            self.velocity_contact_resolution_time
+        )?;
+        writeln!(
+            f,
+            "Velocity joint resolution time: {}",
+            self.velocity_joint_resolution_time
+        )?;
         writeln!(f, "Velocity update time: {}", self.velocity_update_time)?;
         writeln!(f, "Position assembly time: {}", self.position_assembly_time)?;
         writeln!(
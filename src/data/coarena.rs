@@ -29,6 +29,35 @@ impl<T> Coarena<T> {
             .and_then(|(gg, t)| if g == *gg { Some(t) } else { None })
     }
 
+    /// Inserts an element into this coarena, replacing the element previously associated
+    /// to the same index, if any.
+    pub fn insert(&mut self, a: Index, value: T)
+    where
+        T: Clone,
+    {
+        let (i1, g1) = a.into_raw_parts();
+
+        if self.data.len() <= i1 {
+            self.data.resize(i1 + 1, (u32::MAX as u64, value.clone()));
+        }
+
+        self.data[i1] = (g1, value);
+    }
+
+    /// Removes all the elements from this coarena.
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// Iterates through all the elements of this coarena, with their index.
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, (g, _))| *g != u32::MAX as u64)
+            .map(|(i, (g, t))| (Index::from_raw_parts(i, *g), t))
+    }
+
     /// Ensure that elements at the two given indices exist in this coarena, and return their reference.
     ///
     /// Missing elements are created automatically and initialized with the `default` value.
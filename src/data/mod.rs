@@ -1,8 +1,10 @@
 //! Data structures modified with guaranteed deterministic behavior after deserialization.
 
 pub use self::coarena::Coarena;
+pub use self::snapshot_delta::SnapshotDelta;
 
 pub mod arena;
 mod coarena;
 pub(crate) mod graph;
 pub mod pubsub;
+mod snapshot_delta;
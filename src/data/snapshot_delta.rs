@@ -0,0 +1,331 @@
+//! Differences between two serialized snapshots.
+
+use std::collections::HashMap;
+
+/// The size of the blocks of the previous snapshot indexed to find the bytes that moved.
+const BLOCK_LEN: usize = 16;
+/// The copies shorter than this number of bytes are stored as inserted bytes instead, to
+/// limit the overhead of each chunk.
+const MIN_COPY_LEN: usize = 8;
+
+/// A chunk of a snapshot delta.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+enum DeltaChunk {
+    /// Bytes copied from the given offset of the previous snapshot.
+    Copy { offset: usize, len: usize },
+    /// Bytes that don't appear in the previous snapshot.
+    Insert(Vec<u8>),
+}
+
+/// The difference between two serialized snapshots of a physics world.
+///
+/// When the physics world (the rigid-body set, the collider set, the joint set, the
+/// broad-phase and the narrow-phase) is serialized with a format like `bincode`, two
+/// snapshots taken at successive timesteps are mostly identical: only the bytes of the
+/// rigid-body positions and velocities, and of the impulses of the contacts used for
+/// warmstarting the constraints solver, are changed. A `SnapshotDelta` stores these changed
+/// bytes, and references the unchanged ones by their location in the previous snapshot, so
+/// it can be sent or stored instead of the full snapshot.
+///
+/// This is a byte-level delta that knows nothing about the structure of the snapshots. The
+/// unchanged bytes are searched in the whole previous snapshot, so contacts or rigid-bodies
+/// added or removed between both snapshots only cost their own bytes, even though they shift
+/// all the bytes serialized after them. However, the delta doesn't get smaller than the bytes
+/// that actually changed: if most rigid-bodies move, most of their serialized state is stored.
+///
+/// Applying the delta to the old snapshot reproduces the new snapshot byte-for-byte.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct SnapshotDelta {
+    len: usize,
+    chunks: Vec<DeltaChunk>,
+}
+
+impl SnapshotDelta {
+    /// Computes the delta that turns the snapshot `prev` into the snapshot `next`.
+    pub fn new(prev: &[u8], next: &[u8]) -> Self {
+        // The offsets of the first occurrence of each block of `prev`.
+        let mut blocks = HashMap::new();
+        for (i, block) in prev.chunks_exact(BLOCK_LEN).enumerate() {
+            blocks.entry(block).or_insert(i * BLOCK_LEN);
+        }
+
+        let mut result = Self {
+            len: next.len(),
+            chunks: Vec::new(),
+        };
+        // The offset of `prev` matched with the byte `i` of `next`, if the bytes are aligned
+        // like they were so far.
+        let mut prev_offset = 0;
+        let mut copy_len = 0;
+        let mut i = 0;
+
+        while i < next.len() {
+            // Look for the current bytes at another location as long as the current copy is
+            // short, since it may just be a coincidence.
+            if copy_len < BLOCK_LEN && i + BLOCK_LEN <= next.len() {
+                let block = &next[i..i + BLOCK_LEN];
+                let aligned = prev.get(prev_offset..prev_offset + BLOCK_LEN) == Some(block);
+
+                if let Some(offset) = blocks.get(block).filter(|_| !aligned) {
+                    result.push_copy(prev, prev_offset - copy_len, copy_len);
+                    prev_offset = *offset;
+                    copy_len = 0;
+                }
+            }
+
+            if prev.get(prev_offset) == Some(&next[i]) {
+                copy_len += 1;
+            } else {
+                result.push_copy(prev, prev_offset - copy_len, copy_len);
+                copy_len = 0;
+                result.push_insert(&next[i..i + 1]);
+            }
+
+            prev_offset += 1;
+            i += 1;
+        }
+
+        result.push_copy(prev, prev_offset - copy_len, copy_len);
+        result
+    }
+
+    fn push_copy(&mut self, prev: &[u8], offset: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        if len < MIN_COPY_LEN {
+            self.push_insert(&prev[offset..offset + len]);
+            return;
+        }
+
+        match self.chunks.last_mut() {
+            Some(DeltaChunk::Copy {
+                offset: last_offset,
+                len: last_len,
+            }) if *last_offset + *last_len == offset => *last_len += len,
+            _ => self.chunks.push(DeltaChunk::Copy { offset, len }),
+        }
+    }
+
+    fn push_insert(&mut self, bytes: &[u8]) {
+        match self.chunks.last_mut() {
+            Some(DeltaChunk::Insert(last)) => last.extend_from_slice(bytes),
+            _ => self.chunks.push(DeltaChunk::Insert(bytes.to_vec())),
+        }
+    }
+
+    /// Applies this delta to the snapshot `prev`, returning the resulting snapshot.
+    pub fn apply(&self, prev: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.len);
+
+        for chunk in &self.chunks {
+            match chunk {
+                DeltaChunk::Copy { offset, len } => {
+                    result.extend_from_slice(&prev[*offset..*offset + *len])
+                }
+                DeltaChunk::Insert(bytes) => result.extend_from_slice(bytes),
+            }
+        }
+
+        result
+    }
+
+    /// Applies this delta to the given snapshot, modifying it in-place.
+    pub fn apply_in_place(&self, snapshot: &mut Vec<u8>) {
+        *snapshot = self.apply(snapshot);
+    }
+
+    /// The length of the snapshot resulting from the application of this delta.
+    pub fn snapshot_len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of bytes stored by this delta, i.e., the bytes of the new snapshot that
+    /// are not copied from the previous one.
+    pub fn num_changed_bytes(&self) -> usize {
+        self.chunks
+            .iter()
+            .map(|chunk| match chunk {
+                DeltaChunk::Copy { .. } => 0,
+                DeltaChunk::Insert(bytes) => bytes.len(),
+            })
+            .sum()
+    }
+
+    /// The number of chunks of this delta, each of them being either a range of bytes copied
+    /// from the previous snapshot, or a range of changed bytes.
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+#[cfg(all(test, feature = "serde-serialize"))]
+mod test {
+    use super::SnapshotDelta;
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn snapshot_delta_reproduces_new_snapshot() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::ball(100.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        for i in 0..5 {
+            let rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * (0.5 + i as Real), na::zero()))
+                .build();
+            let handle = world.bodies.insert(rb);
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                handle,
+                &mut world.bodies,
+            );
+        }
+
+        let mut snapshots = Vec::new();
+
+        for _ in 0..30 {
+            world.step();
+            snapshots.push(
+                bincode::serialize(&(
+                    &world.bodies,
+                    &world.colliders,
+                    &world.joints,
+                    &world.broad_phase,
+                    &world.narrow_phase,
+                ))
+                .unwrap(),
+            );
+        }
+
+        for window in snapshots.windows(2) {
+            let delta = SnapshotDelta::new(&window[0], &window[1]);
+            assert_eq!(delta.apply(&window[0]), window[1]);
+            assert!(delta.num_changed_bytes() < window[1].len());
+        }
+
+        // The delta still works if the snapshot size changes.
+        let delta = SnapshotDelta::new(&snapshots[29], &snapshots[0]);
+        assert_eq!(delta.apply(&snapshots[29]), snapshots[0]);
+        let delta = SnapshotDelta::new(&[], &snapshots[0]);
+        assert_eq!(delta.apply(&[]), snapshots[0]);
+    }
+
+    #[test]
+    fn snapshot_delta_with_contacts_appearing_and_disappearing() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(200.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(200.0, 0.5, 200.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Many sleeping balls on the ground, and one ball falling next to them.
+        let mut balls = Vec::new();
+        for i in 0..100 {
+            let rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(
+                    Vector::x() * (i as Real * 2.0 - 100.0) + Vector::y() * 0.5,
+                    na::zero(),
+                ))
+                .sleeping(true)
+                .build();
+            let handle = world.bodies.insert(rb);
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                handle,
+                &mut world.bodies,
+            );
+            balls.push(handle);
+        }
+
+        let falling = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(
+                    Vector::x() * 150.0 + Vector::y() * 2.0,
+                    na::zero(),
+                ))
+                .build(),
+        );
+        let falling_collider = world.colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            falling,
+            &mut world.bodies,
+        );
+
+        let snapshot = |world: &TestWorld| {
+            bincode::serialize(&(
+                &world.bodies,
+                &world.colliders,
+                &world.joints,
+                &world.broad_phase,
+                &world.narrow_phase,
+            ))
+            .unwrap()
+        };
+
+        // The contacts added or removed shift the bytes serialized after them, but the delta
+        // must only contain the bytes that actually changed.
+        let check_delta = |prev: &[u8], next: &[u8]| {
+            let delta = SnapshotDelta::new(prev, next);
+            assert_eq!(delta.apply(prev), next);
+            assert!(
+                delta.num_changed_bytes() < next.len() / 10,
+                "{} changed bytes out of {}",
+                delta.num_changed_bytes(),
+                next.len()
+            );
+        };
+
+        world.step();
+        let mut prev = snapshot(&world);
+
+        // The falling ball hits the ground.
+        for _ in 0..60 {
+            world.step();
+            let next = snapshot(&world);
+            check_delta(&prev, &next);
+            prev = next;
+        }
+
+        let pair = world
+            .narrow_phase
+            .contact_pair(ground_collider, falling_collider);
+        assert!(pair.unwrap().has_any_active_contact);
+
+        // Removing a ball removes its collider and its contacts with the ground.
+        world
+            .bodies
+            .remove(balls[50], &mut world.colliders, &mut world.joints);
+        world.step();
+        let next = snapshot(&world);
+        assert!(next.len() < prev.len());
+        check_delta(&prev, &next);
+    }
+}
@@ -1,5 +1,79 @@
 use crate::math::Real;
 
+/// The strategy used by the velocity constraints solver to select its number of iterations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum SolverMode {
+    /// Always perform `IntegrationParameters::max_velocity_iterations` iterations.
+    Fixed,
+    /// Iterate until the residual of the velocity solver drops below a tolerance.
+    ///
+    /// The residual of an iteration is the largest change, during this iteration, of the
+    /// linear or (inertia-weighted) angular velocity of a rigid-body of the island being
+    /// solved. This mode is not supported by the `parallel` solver, which always performs
+    /// `max_iterations` iterations.
+    Adaptive {
+        /// The residual below which the solver stops iterating.
+        tolerance: Real,
+        /// The maximum number of iterations, performed if the solver does not converge.
+        max_iterations: usize,
+    },
+}
+
+impl Default for SolverMode {
+    fn default() -> Self {
+        SolverMode::Fixed
+    }
+}
+
+/// How the velocity solver corrects the penetrations of the contacts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum PenetrationCorrection {
+    /// The penetration bias is added to the velocity constraints of the resting contacts.
+    ///
+    /// This adds energy to the bodies pushed apart, since their velocities keep the bias.
+    Baumgarte,
+    /// The penetration bias of all the contacts is resolved by a separate pass computing
+    /// pseudo-velocities, which are only used to integrate the positions of the rigid-bodies.
+    ///
+    /// Their velocities are not affected by the penetration correction so the restitution
+    /// remains energy-accurate. This mode is not supported by the `parallel` solver, which
+    /// always uses `PenetrationCorrection::Baumgarte`.
+    SplitImpulse,
+}
+
+impl Default for PenetrationCorrection {
+    fn default() -> Self {
+        PenetrationCorrection::Baumgarte
+    }
+}
+
+/// The order in which the velocity solver processes the joint and contact constraints during
+/// each of its iterations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum ConstraintOrder {
+    /// All the joint constraints are solved before the contact constraints.
+    ///
+    /// The contacts then have the last word, which keeps the bodies from being pushed into the
+    /// ground by strong motors.
+    JointsFirst,
+    /// All the contact constraints are solved before the joint constraints.
+    ContactsFirst,
+    /// The joint and contact constraints are solved alternately, one of each at a time.
+    ///
+    /// The time spent solving both kinds of constraints is then accounted for in
+    /// `SolverCounters::velocity_contact_resolution_time`.
+    Interleaved,
+}
+
+impl Default for ConstraintOrder {
+    fn default() -> Self {
+        ConstraintOrder::JointsFirst
+    }
+}
+
 /// Parameters for a time-step of the physics engine.
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -20,13 +94,35 @@ pub struct IntegrationParameters {
     pub return_after_ccd_substep: bool,
     /// The Error Reduction Parameter in `[0, 1]` is the proportion of
     /// the positional error to be corrected at each time step (default: `0.2`).
+    ///
+    /// This is only used by the position solver for resolving contact penetrations, and is
+    /// independent from the `velocity_based_erp` used by the velocity solver. The correction
+    /// applied at each position iteration is bounded by `max_linear_correction`.
     pub erp: Real,
     /// The Error Reduction Parameter for joints in `[0, 1]` is the proportion of
     /// the positional error to be corrected at each time step (default: `0.2`).
     pub joint_erp: Real,
     /// Each cached impulse are multiplied by this coefficient in `[0, 1]`
     /// when they are re-used to initialize the solver (default `1.0`).
+    ///
+    /// If this is zero, the warmstart of the contact and joint constraints is skipped entirely.
     pub warmstart_coeff: Real,
+    /// If `false`, the friction of all the contacts is ignored (default: `true`).
+    ///
+    /// This skips the friction part of the contact constraints (including its warmstart)
+    /// entirely, which is cheaper than setting the friction coefficient of every collider to
+    /// zero. The friction impulses are not accumulated while this is disabled.
+    pub friction_enabled: bool,
+    /// If `false`, the restitution of all the contacts is ignored, i.e., all the contacts are
+    /// treated as non-bouncy (default: `true`).
+    pub restitution_enabled: bool,
+    /// The approach speed below which contacts don't bounce (default: `0.0`, i.e., disabled).
+    ///
+    /// The approach speed of a contact is the relative velocity of the two bodies at the contact
+    /// point, along the contact normal, positive if they move toward each other. This avoids
+    /// jitter from objects bouncing slightly when they come to rest. This can be overridden for
+    /// specific colliders with `ColliderBuilder::restitution_velocity_threshold`.
+    pub restitution_velocity_threshold: Real,
 
     /// 0-1: how much of the velocity to dampen out in the constraint solver?
     /// (default `1.0`).
@@ -39,6 +135,16 @@ pub struct IntegrationParameters {
     /// A good non-zero value is around `0.2`.
     /// (default `0.0`).
     pub velocity_based_erp: Real,
+    /// Number of timesteps over which the `velocity_based_erp` applied to a new contact manifold
+    /// ramps up linearly to its full value (default: `0`, i.e., no ramp).
+    ///
+    /// This makes the resolution of the penetrations of newly spawned overlapping objects softer,
+    /// avoiding them being launched apart. The age of a contact manifold (see
+    /// `ContactManifoldData::age`) is reset together with the data of its contacts.
+    pub contact_erp_ramp_steps: usize,
+    /// How the `velocity_based_erp` is applied to the contacts
+    /// (default: `PenetrationCorrection::Baumgarte`).
+    pub penetration_correction: PenetrationCorrection,
 
     /// Amount of penetration the engine wont attempt to correct (default: `0.005m`).
     pub allowed_linear_error: Real,
@@ -48,8 +154,19 @@ pub struct IntegrationParameters {
     /// attempt to correct (default: `0.001rad`).
     pub allowed_angular_error: Real,
     /// Maximum linear correction during one step of the non-linear position solver (default: `0.2`).
+    ///
+    /// This bounds the penetration resolved at each iteration of the position solver for each
+    /// contact, regardless of the value of `erp`. This is also a per-body bound: the total
+    /// displacement of the center-of-mass of each rigid-body by the position solver during one
+    /// timestep, accumulated over all its contacts, joints, and iterations, cannot exceed it.
+    /// Deep penetrations are thus resolved progressively over several timesteps.
     pub max_linear_correction: Real,
     /// Maximum angular correction during one step of the non-linear position solver (default: `0.2`).
+    ///
+    /// This is a per-body bound: the total rotation applied to each rigid-body by the position
+    /// solver during one timestep, accumulated over all its contacts, joints, and iterations,
+    /// cannot exceed it. This prevents deep contacts far from the center-of-mass from suddenly
+    /// spinning it.
     pub max_angular_correction: Real,
     /// Maximum nonlinear SOR-prox scaling parameter when the constraint
     /// correction direction is close to the kernel of the involved multibody's
@@ -57,10 +174,50 @@ pub struct IntegrationParameters {
     pub max_stabilization_multiplier: Real,
     /// Maximum number of iterations performed by the velocity constraints solver (default: `4`).
     pub max_velocity_iterations: usize,
+    /// The strategy used to select the number of iterations of the velocity solver
+    /// (default: `SolverMode::Fixed`).
+    ///
+    /// With `SolverMode::Adaptive`, the number of iterations actually performed for each island
+    /// can be read with `PhysicsPipeline::velocity_iterations`.
+    pub solver_mode: SolverMode,
+    /// The order in which the joint and contact constraints are solved during each iteration of
+    /// the velocity solver (default: `ConstraintOrder::JointsFirst`).
+    ///
+    /// This is ignored by the `parallel` solver, which always solves the joints first.
+    pub constraint_order: ConstraintOrder,
+    /// If `true`, the velocity solver records, after each of its iterations, the sum of the
+    /// magnitudes of the contact impulses of each island (default: `false`).
+    ///
+    /// This history can be read with `PhysicsPipeline::velocity_convergence_history` to
+    /// tune `max_velocity_iterations`.
+    pub record_velocity_convergence: bool,
     /// Maximum number of iterations performed by the position-based constraints solver (default: `1`).
+    ///
+    /// If this is zero, the position solver is skipped entirely, including the assembly of its
+    /// constraints (except with the `parallel` feature). The penetrations and joint drifts are then
+    /// only corrected by the velocity solver, so `velocity_based_erp` should be non-zero.
     pub max_position_iterations: usize,
     /// Minimum number of dynamic bodies in each active island (default: `128`).
     pub min_island_size: usize,
+    /// If `true`, the constraints solver skips the active islands that are at rest (default: `false`).
+    ///
+    /// An active island is at rest if none of its bodies (including the kinematic bodies it
+    /// touches) has a pseudo-kinetic energy above its sleep threshold or a user-defined force
+    /// applied to it, none of its contacts is new or penetrating more than `allowed_linear_error`,
+    /// each of its dynamic bodies has a contact with a normal opposing its gravity, and it has no
+    /// joint or custom constraint. The velocities of the bodies of a skipped island
+    /// are set to zero and their positions are left unchanged, so they may still fall asleep.
+    /// Unlike sleeping, this check is performed at each timestep, so an island stops being skipped
+    /// as soon as it is touched. The islands actually solved at the last timestep can be read with
+    /// `PhysicsPipeline::solved_islands`.
+    ///
+    /// This is ignored with the `parallel` feature.
+    pub skip_resting_islands: bool,
+    /// The contact force above which a `ContactForceEvent` is emitted for a pair of colliders,
+    /// unless one of them has a smaller threshold (default: `Real::MAX`, i.e., disabled).
+    ///
+    /// See `ColliderSet::set_contact_force_event_threshold` for per-collider thresholds.
+    pub contact_force_event_threshold: Real,
     /// Maximum number of iterations performed by the position-based constraints solver for CCD steps (default: `10`).
     ///
     /// This should be sufficiently high so all penetration get resolved. For example, if CCD cause your
@@ -105,7 +262,7 @@ impl IntegrationParameters {
         erp: Real,
         joint_erp: Real,
         warmstart_coeff: Real,
-        _restitution_velocity_threshold: Real,
+        restitution_velocity_threshold: Real,
         allowed_linear_error: Real,
         allowed_angular_error: Real,
         max_linear_correction: Real,
@@ -126,6 +283,7 @@ impl IntegrationParameters {
             erp,
             joint_erp,
             warmstart_coeff,
+            restitution_velocity_threshold,
             allowed_linear_error,
             allowed_angular_error,
             max_linear_correction,
@@ -143,6 +301,50 @@ impl IntegrationParameters {
         }
     }
 
+    /// Integration parameters suited for most games.
+    ///
+    /// These are the default parameters, except that slow contacts don't bounce, to avoid the
+    /// jitter of objects coming to rest.
+    pub fn game_default() -> Self {
+        Self {
+            restitution_velocity_threshold: 1.0,
+            ..Self::default()
+        }
+    }
+
+    /// Integration parameters favoring accuracy over performance.
+    ///
+    /// The constraints solvers perform more iterations and tolerate smaller penetrations, and all
+    /// the contacts may bounce.
+    pub fn accurate() -> Self {
+        Self {
+            erp: 0.1,
+            restitution_velocity_threshold: 0.0,
+            allowed_linear_error: 0.001,
+            max_velocity_iterations: 16,
+            solver_mode: SolverMode::Fixed,
+            constraint_order: ConstraintOrder::JointsFirst,
+            max_position_iterations: 4,
+            ..Self::default()
+        }
+    }
+
+    /// Integration parameters favoring performance over accuracy.
+    ///
+    /// The constraints solvers perform fewer iterations, and correct the penetrations more
+    /// aggressively to compensate.
+    pub fn fast() -> Self {
+        Self {
+            erp: 0.4,
+            restitution_velocity_threshold: 2.0,
+            allowed_linear_error: 0.01,
+            max_velocity_iterations: 2,
+            solver_mode: SolverMode::Fixed,
+            max_position_iterations: 1,
+            ..Self::default()
+        }
+    }
+
     /// The current time-stepping length.
     #[inline(always)]
     #[deprecated = "You can just read the `IntegrationParams::dt` value directly"]
@@ -150,6 +352,18 @@ impl IntegrationParameters {
         self.dt
     }
 
+    /// The maximum number of iterations of the velocity solver, and the residual below which
+    /// it stops iterating, if any.
+    pub(crate) fn velocity_iterations_limits(&self) -> (usize, Option<Real>) {
+        match self.solver_mode {
+            SolverMode::Fixed => (self.max_velocity_iterations, None),
+            SolverMode::Adaptive {
+                tolerance,
+                max_iterations,
+            } => (max_iterations, Some(tolerance)),
+        }
+    }
+
     /// The inverse of the time-stepping length, i.e. the steps per seconds (Hz).
     ///
     /// This is zero if `self.dt` is zero.
@@ -187,6 +401,33 @@ impl IntegrationParameters {
     pub(crate) fn velocity_based_erp_inv_dt(&self) -> Real {
         self.velocity_based_erp * self.inv_dt()
     }
+
+    /// Is the penetration of the contacts corrected by a separate pseudo-velocity pass?
+    #[inline]
+    pub(crate) fn split_impulse_enabled(&self) -> bool {
+        !cfg!(feature = "parallel")
+            && self.penetration_correction == PenetrationCorrection::SplitImpulse
+    }
+
+    /// The coefficient `compliance / dt²` used by the velocity constraints of a compliant joint.
+    #[inline]
+    pub(crate) fn joint_compliance_coeff(&self, compliance: Real) -> Real {
+        compliance * self.inv_dt() * self.inv_dt()
+    }
+
+    /// The proportion of the positional error of a joint corrected by its velocity
+    /// constraints, divided by `dt`.
+    ///
+    /// Compliant joints are ignored by the position solver, so their velocity constraints
+    /// account for their whole positional error.
+    #[inline]
+    pub(crate) fn joint_velocity_based_erp_inv_dt(&self, compliance: Real) -> Real {
+        if compliance != 0.0 {
+            self.inv_dt()
+        } else {
+            self.velocity_based_erp_inv_dt()
+        }
+    }
 }
 
 impl Default for IntegrationParameters {
@@ -199,7 +440,12 @@ impl Default for IntegrationParameters {
             joint_erp: 0.2,
             velocity_solve_fraction: 1.0,
             velocity_based_erp: 0.0,
+            contact_erp_ramp_steps: 0,
+            penetration_correction: PenetrationCorrection::Baumgarte,
             warmstart_coeff: 1.0,
+            friction_enabled: true,
+            restitution_enabled: true,
+            restitution_velocity_threshold: 0.0,
             allowed_linear_error: 0.005,
             prediction_distance: 0.002,
             allowed_angular_error: 0.001,
@@ -207,6 +453,9 @@ impl Default for IntegrationParameters {
             max_angular_correction: 0.2,
             max_stabilization_multiplier: 0.2,
             max_velocity_iterations: 4,
+            solver_mode: SolverMode::Fixed,
+            constraint_order: ConstraintOrder::JointsFirst,
+            record_velocity_convergence: false,
             max_position_iterations: 1,
             // FIXME: what is the optimal value for min_island_size?
             // It should not be too big so that we don't end up with
@@ -214,6 +463,8 @@ impl Default for IntegrationParameters {
             // However we don't want it to be too small and end up with
             // tons of islands, reducing SIMD parallelism opportunities.
             min_island_size: 128,
+            skip_resting_islands: false,
+            contact_force_event_threshold: Real::MAX,
             max_ccd_position_iterations: 10,
             max_ccd_substeps: 1,
             multiple_ccd_substep_sensor_events_enabled: false,
@@ -221,3 +472,284 @@ impl Default for IntegrationParameters {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::IntegrationParameters;
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn presets() {
+        let default = IntegrationParameters::default();
+        let game = IntegrationParameters::game_default();
+        let accurate = IntegrationParameters::accurate();
+        let fast = IntegrationParameters::fast();
+
+        assert_eq!(
+            game.max_velocity_iterations,
+            default.max_velocity_iterations
+        );
+        assert!(game.restitution_velocity_threshold > default.restitution_velocity_threshold);
+
+        assert!(fast.max_velocity_iterations < game.max_velocity_iterations);
+        assert!(game.max_velocity_iterations < accurate.max_velocity_iterations);
+        assert!(fast.max_position_iterations < accurate.max_position_iterations);
+        assert!(fast.erp > accurate.erp);
+        assert!(fast.allowed_linear_error > accurate.allowed_linear_error);
+        assert!(fast.restitution_velocity_threshold > accurate.restitution_velocity_threshold);
+
+        // None of the presets skips the resting islands.
+        for preset in &[game, accurate, fast] {
+            assert!(!preset.skip_resting_islands);
+        }
+    }
+
+    #[test]
+    fn contact_erp_ramp_avoids_launching_overlapping_bodies() {
+        fn max_height_after_spawn_overlap(contact_erp_ramp_steps: usize) -> (Real, Real) {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params = IntegrationParameters {
+                velocity_based_erp: 0.8,
+                max_position_iterations: 0,
+                contact_erp_ramp_steps,
+                ..Default::default()
+            };
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(100.0).build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            // The ball is spawned half-way into the ground.
+            let ball = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+            let mut max_height: Real = 0.0;
+
+            for _ in 0..300 {
+                world.step();
+                max_height = max_height.max(world.bodies[ball].position().translation.vector.y);
+            }
+
+            (
+                max_height,
+                world.bodies[ball].position().translation.vector.y,
+            )
+        }
+
+        let (launched_height, _) = max_height_after_spawn_overlap(0);
+        let (ramped_height, final_height) = max_height_after_spawn_overlap(60);
+
+        assert!(launched_height > 5.0);
+        assert!(ramped_height < 1.0);
+        assert!((final_height - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn position_erp_is_independent_from_velocities() {
+        let simulate = |erp: Real| {
+            let mut world = TestWorld::new();
+            world.params.erp = erp;
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(100.0).build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            // A ball penetrating the ground by 0.5.
+            let ball = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+            for _ in 0..10 {
+                world.step();
+
+                // The penetration is only resolved by the position solver, which
+                // doesn't affect the velocities.
+                assert!(world.bodies[ball].linvel().norm() < 1.0e-5);
+            }
+
+            0.5 - world.bodies[ball].position().translation.vector.y
+        };
+
+        let fast_penetration = simulate(0.2);
+        let slow_penetration = simulate(0.05);
+        assert!(fast_penetration < 0.1);
+        assert!(slow_penetration > fast_penetration + 0.1);
+    }
+
+    #[test]
+    fn friction_disabled_box_slides_on_slope() {
+        let run = |friction_enabled: bool| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params.friction_enabled = friction_enabled;
+
+            // A slope steep enough for the box to start sliding only without friction.
+            #[cfg(feature = "dim2")]
+            let slope = Isometry::new(na::zero(), 0.3);
+            #[cfg(feature = "dim3")]
+            let slope = Isometry::new(na::zero(), Vector::z() * 0.3);
+
+            let ground = world
+                .bodies
+                .insert(RigidBodyBuilder::new_static().position(slope).build());
+            #[cfg(feature = "dim2")]
+            let floor = ColliderBuilder::cuboid(50.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let floor = ColliderBuilder::cuboid(50.0, 0.5, 50.0);
+            world
+                .colliders
+                .insert(floor.build(), ground, &mut world.bodies);
+
+            let mut position = slope;
+            position.translation.vector = slope.rotation * Vector::y();
+            let body = world
+                .bodies
+                .insert(RigidBodyBuilder::new_dynamic().position(position).build());
+            #[cfg(feature = "dim2")]
+            let cuboid = ColliderBuilder::cuboid(0.5, 0.5).friction(1.0);
+            #[cfg(feature = "dim3")]
+            let cuboid = ColliderBuilder::cuboid(0.5, 0.5, 0.5).friction(1.0);
+            world
+                .colliders
+                .insert(cuboid.build(), body, &mut world.bodies);
+
+            for _ in 0..60 {
+                world.step();
+            }
+
+            world.bodies[body].linvel().norm()
+        };
+
+        assert!(run(true) < 0.1);
+        // Without friction, the box keeps accelerating at `g * sin(0.3) ~= 2.9 m/s²`.
+        assert!(run(false) > 2.5);
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn split_impulse_penetration_correction() {
+        use crate::dynamics::PenetrationCorrection;
+
+        let simulate = |penetration_correction| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params = IntegrationParameters {
+                max_position_iterations: 0,
+                velocity_based_erp: 0.2,
+                penetration_correction,
+                ..IntegrationParameters::default()
+            };
+
+            #[cfg(feature = "dim2")]
+            let (ground_shape, box_shape) = (
+                ColliderBuilder::cuboid(1.0, 0.5),
+                ColliderBuilder::cuboid(0.5, 0.5),
+            );
+            #[cfg(feature = "dim3")]
+            let (ground_shape, box_shape) = (
+                ColliderBuilder::cuboid(1.0, 0.5, 1.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            );
+
+            // A perfectly elastic ball dropped from 1m above the ground.
+            let ground1 = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ground_shape.clone().restitution(1.0).build(),
+                ground1,
+                &mut world.bodies,
+            );
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * 1.5, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).restitution(1.0).build(),
+                ball,
+                &mut world.bodies,
+            );
+
+            // A box at rest, initially penetrating the ground by 0.1m.
+            let ground2 = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(
+                        Vector::x() * 10.0 - Vector::y() * 0.5,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ground_shape.build(), ground2, &mut world.bodies);
+            let cube = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * 10.0 + Vector::y() * 0.4,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(box_shape.build(), cube, &mut world.bodies);
+
+            let mut bounced = false;
+            let mut rebound_height = Real::MIN;
+            let mut max_cube_upward_speed = Real::MIN;
+
+            for _ in 0..90 {
+                world.step();
+
+                bounced = bounced || world.bodies[ball].linvel().y > 0.0;
+                if bounced {
+                    rebound_height =
+                        rebound_height.max(world.bodies[ball].position().translation.y);
+                }
+                max_cube_upward_speed = max_cube_upward_speed.max(world.bodies[cube].linvel().y);
+            }
+
+            (
+                rebound_height,
+                max_cube_upward_speed,
+                world.bodies[cube].position().translation.y,
+            )
+        };
+
+        // The penetration of the box is resolved by giving it an upward velocity.
+        let (_, max_cube_upward_speed, _) = simulate(PenetrationCorrection::Baumgarte);
+        assert!(max_cube_upward_speed > 0.5);
+
+        // The penetration of the box is resolved without affecting its velocity, and the
+        // ball bounces back to its drop height.
+        let (rebound_height, max_cube_upward_speed, cube_height) =
+            simulate(PenetrationCorrection::SplitImpulse);
+        assert!((rebound_height - 1.5).abs() < 0.1);
+        assert!(max_cube_upward_speed < 0.1);
+        assert!(cube_height > 0.48);
+    }
+}
@@ -29,6 +29,10 @@ pub struct BallJoint {
     /// See the documentation of `SpringModel` for more information on this parameter.
     pub motor_damping: Real,
     /// The maximal impulse the motor is able to deliver.
+    ///
+    /// This bounds the total impulse applied by the motor during a timestep, accumulated over
+    /// all the velocity solver iterations, so the maximal motor force is `motor_max_impulse / dt`
+    /// regardless of `IntegrationParameters::max_velocity_iterations`.
     pub motor_max_impulse: Real,
     /// The angular impulse applied by the motor.
     #[cfg(feature = "dim2")]
@@ -0,0 +1,203 @@
+#[cfg(feature = "dim3")]
+use crate::math::Vector;
+use crate::math::{AngVector, Real, Rotation};
+use crate::utils::WDot;
+#[cfg(feature = "dim3")]
+use na::Unit;
+
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+/// A joint that couples the angular velocities of two bodies along one axis each, such that
+/// `angvel1 = ratio * angvel2`.
+///
+/// This is typically used to couple two gears, each attached with a revolute joint to a common
+/// body. The angular velocities are measured in world-space, so the common body is assumed not
+/// to rotate along the gear axes. Two meshing gears usually rotate in opposite directions: this
+/// can be obtained by flipping one of the axes, or by using a negative `ratio`.
+pub struct GearJoint {
+    /// The rotation axis of the first gear, expressed in the local space of the first attached body.
+    #[cfg(feature = "dim3")]
+    pub local_axis1: Unit<Vector<Real>>,
+    /// The rotation axis of the second gear, expressed in the local space of the second attached body.
+    #[cfg(feature = "dim3")]
+    pub local_axis2: Unit<Vector<Real>>,
+    /// The gear ratio, i.e., the angular velocity of the first gear divided by the angular
+    /// velocity of the second gear.
+    pub ratio: Real,
+    /// The angular impulse applied by this joint along the axis of the first body.
+    ///
+    /// The angular impulse applied along the axis of the second body is given by `-ratio * impulse`.
+    pub impulse: Real,
+
+    // The orientations of both bodies when the accumulated angle error was last updated.
+    pub(crate) reference_rotations: Option<[Rotation<Real>; 2]>,
+    // The accumulated drift `angle1 - ratio * angle2` since the joint started being simulated.
+    pub(crate) angle_error: Real,
+}
+
+impl GearJoint {
+    /// Creates a new gear joint with the given gear ratio.
+    #[cfg(feature = "dim2")]
+    pub fn new(ratio: Real) -> Self {
+        Self {
+            ratio,
+            impulse: 0.0,
+            reference_rotations: None,
+            angle_error: 0.0,
+        }
+    }
+
+    /// Creates a new gear joint with the given rotation axes, expressed in the local-space
+    /// of the affected bodies, and the given gear ratio.
+    #[cfg(feature = "dim3")]
+    pub fn new(
+        local_axis1: Unit<Vector<Real>>,
+        local_axis2: Unit<Vector<Real>>,
+        ratio: Real,
+    ) -> Self {
+        Self {
+            local_axis1,
+            local_axis2,
+            ratio,
+            impulse: 0.0,
+            reference_rotations: None,
+            angle_error: 0.0,
+        }
+    }
+
+    /// Can a SIMD constraint be used for resolving this joint?
+    pub fn supports_simd_constraints(&self) -> bool {
+        false
+    }
+
+    /// The angular drift `angle1 - ratio * angle2` accumulated by this joint, which the
+    /// constraints solver attempts to correct.
+    pub fn angle_error(&self) -> Real {
+        self.angle_error
+    }
+
+    /// The world-space jacobians `(axis1, -ratio * axis2)` of this joint for the given body orientations.
+    #[cfg(feature = "dim2")]
+    pub(crate) fn jacobians(&self, _: &Rotation<Real>, _: &Rotation<Real>) -> (Real, Real) {
+        (1.0, -self.ratio)
+    }
+
+    /// The world-space jacobians `(axis1, -ratio * axis2)` of this joint for the given body orientations.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn jacobians(
+        &self,
+        rot1: &Rotation<Real>,
+        rot2: &Rotation<Real>,
+    ) -> (Vector<Real>, Vector<Real>) {
+        (
+            rot1 * self.local_axis1.into_inner(),
+            rot2 * self.local_axis2.into_inner() * -self.ratio,
+        )
+    }
+
+    /// The angular drift of this joint for the given body orientations.
+    pub(crate) fn angle_error_at(&self, rot1: &Rotation<Real>, rot2: &Rotation<Real>) -> Real {
+        if let Some([ref1, ref2]) = &self.reference_rotations {
+            let (j1, j2) = self.jacobians(rot1, rot2);
+            self.angle_error
+                + j1.gdot(rotation_vector(&(rot1 * ref1.inverse())))
+                + j2.gdot(rotation_vector(&(rot2 * ref2.inverse())))
+        } else {
+            self.angle_error
+        }
+    }
+}
+
+/// The rotation vector (axis times angle) of the given rotation.
+#[cfg(feature = "dim2")]
+pub(crate) fn rotation_vector(rot: &Rotation<Real>) -> AngVector<Real> {
+    rot.angle()
+}
+
+/// The rotation vector (axis times angle) of the given rotation.
+#[cfg(feature = "dim3")]
+pub(crate) fn rotation_vector(rot: &Rotation<Real>) -> AngVector<Real> {
+    rot.scaled_axis()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBody, RigidBodyBuilder};
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn gear_joint_ratio() {
+        use crate::dynamics::GearJoint;
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let mut gears = Vec::new();
+
+        for i in 0..2 {
+            let center = Vector::x() * (i as Real * 3.0);
+            let gear = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(center, na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(1.0).build(), gear, &mut world.bodies);
+
+            #[cfg(feature = "dim2")]
+            let mut pivot = crate::dynamics::BallJoint::new(Point::from(center), Point::origin());
+            #[cfg(feature = "dim3")]
+            let mut pivot = crate::dynamics::RevoluteJoint::new(
+                Point::from(center),
+                Vector::z_axis(),
+                Point::origin(),
+                Vector::z_axis(),
+            );
+
+            if i == 0 {
+                // The first gear is driven by a motor.
+                pivot.configure_motor_velocity(1.0, 1.0);
+            }
+
+            world.joints.insert(&mut world.bodies, ground, gear, pivot);
+            gears.push(gear);
+        }
+
+        #[cfg(feature = "dim2")]
+        let gear = GearJoint::new(2.0);
+        #[cfg(feature = "dim3")]
+        let gear = GearJoint::new(Vector::z_axis(), Vector::z_axis(), 2.0);
+        world
+            .joints
+            .insert(&mut world.bodies, gears[0], gears[1], gear);
+
+        #[cfg(feature = "dim2")]
+        let spin = |rb: &RigidBody| rb.angvel();
+        #[cfg(feature = "dim3")]
+        let spin = |rb: &RigidBody| rb.angvel().z;
+
+        for _ in 0..60 {
+            world.step();
+
+            let spin1 = spin(&world.bodies[gears[0]]);
+            let spin2 = spin(&world.bodies[gears[1]]);
+            assert!((spin2 - spin1 / 2.0).abs() < 2.0e-2);
+        }
+
+        assert!(spin(&world.bodies[gears[0]]).abs() > 0.1);
+
+        // The position-level correction prevents the angles from drifting.
+        #[cfg(feature = "dim2")]
+        let angle = |rb: &RigidBody| rb.position().rotation.angle();
+        #[cfg(feature = "dim3")]
+        let angle = |rb: &RigidBody| rb.position().rotation.scaled_axis().z;
+        let angle1 = angle(&world.bodies[gears[0]]);
+        let angle2 = angle(&world.bodies[gears[1]]);
+        assert!(angle1.abs() > 0.1);
+        assert!((angle2 - angle1 / 2.0).abs() < 2.0e-2);
+    }
+}
@@ -1,6 +1,10 @@
 #[cfg(feature = "dim3")]
 use crate::dynamics::RevoluteJoint;
-use crate::dynamics::{BallJoint, FixedJoint, JointHandle, PrismaticJoint, RigidBodyHandle};
+use crate::dynamics::{
+    BallJoint, FixedJoint, GearJoint, JointHandle, PrismaticJoint, RigidBodyHandle,
+};
+use crate::geometry::ColliderHandle;
+use crate::math::{Isometry, Real};
 
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -17,6 +21,8 @@ pub enum JointParams {
     /// A revolute joint that removes all degrees of degrees of freedom between the affected
     /// bodies except for the translation along one axis.
     RevoluteJoint(RevoluteJoint),
+    /// A gear joint that couples the angular velocities of two bodies along one axis each.
+    GearJoint(GearJoint),
     // GenericJoint(GenericJoint),
 }
 
@@ -30,6 +36,48 @@ impl JointParams {
             // JointParams::GenericJoint(_) => 3,
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(_) => 4,
+            JointParams::GearJoint(_) => 5,
+        }
+    }
+
+    /// Transforms all the local anchors, axes, and frames of this joint.
+    ///
+    /// The quantities relative to the first (resp. second) body are transformed by
+    /// `pos1` (resp. `pos2`).
+    pub(crate) fn transform_local_frames(&mut self, pos1: &Isometry<Real>, pos2: &Isometry<Real>) {
+        match self {
+            JointParams::BallJoint(j) => {
+                j.local_anchor1 = pos1 * j.local_anchor1;
+                j.local_anchor2 = pos2 * j.local_anchor2;
+            }
+            JointParams::FixedJoint(j) => {
+                j.local_anchor1 = pos1 * j.local_anchor1;
+                j.local_anchor2 = pos2 * j.local_anchor2;
+            }
+            JointParams::PrismaticJoint(j) => {
+                j.local_anchor1 = pos1 * j.local_anchor1;
+                j.local_anchor2 = pos2 * j.local_anchor2;
+                j.local_axis1 = pos1 * j.local_axis1;
+                j.local_axis2 = pos2 * j.local_axis2;
+                j.basis1.iter_mut().for_each(|v| *v = pos1 * *v);
+                j.basis2.iter_mut().for_each(|v| *v = pos2 * *v);
+            }
+            #[cfg(feature = "dim3")]
+            JointParams::RevoluteJoint(j) => {
+                j.local_anchor1 = pos1 * j.local_anchor1;
+                j.local_anchor2 = pos2 * j.local_anchor2;
+                j.local_axis1 = pos1 * j.local_axis1;
+                j.local_axis2 = pos2 * j.local_axis2;
+                j.basis1.iter_mut().for_each(|v| *v = pos1 * *v);
+                j.basis2.iter_mut().for_each(|v| *v = pos2 * *v);
+            }
+            #[cfg(feature = "dim3")]
+            JointParams::GearJoint(j) => {
+                j.local_axis1 = pos1 * j.local_axis1;
+                j.local_axis2 = pos2 * j.local_axis2;
+            }
+            #[cfg(feature = "dim2")]
+            JointParams::GearJoint(_) => {}
         }
     }
 
@@ -78,6 +126,15 @@ impl JointParams {
             None
         }
     }
+
+    /// Gets a reference to the underlying gear joint, if `self` is one.
+    pub fn as_gear_joint(&self) -> Option<&GearJoint> {
+        if let JointParams::GearJoint(j) = self {
+            Some(j)
+        } else {
+            None
+        }
+    }
 }
 
 impl From<BallJoint> for JointParams {
@@ -111,6 +168,12 @@ impl From<PrismaticJoint> for JointParams {
     }
 }
 
+impl From<GearJoint> for JointParams {
+    fn from(j: GearJoint) -> Self {
+        JointParams::GearJoint(j)
+    }
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone)]
 /// A joint attached to two bodies.
@@ -127,17 +190,306 @@ pub struct Joint {
     pub(crate) position_constraint_index: usize,
     /// The joint geometric parameters and impulse.
     pub params: JointParams,
+    pub(crate) enabled: bool,
+    pub(crate) compliance: Real,
+    pub(crate) solver_iterations_multiplier: usize,
+    // The colliders the local frames of `params` are expressed relative to, if any.
+    pub(crate) anchor_colliders: [Option<ColliderHandle>; 2],
+    // The positions of the anchor colliders relative to their parent bodies.
+    pub(crate) anchor_collider_frames: [Isometry<Real>; 2],
 }
 
 impl Joint {
+    /// Is this joint enabled?
+    ///
+    /// A disabled joint is ignored by the constraints solver.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables this joint.
+    ///
+    /// A disabled joint is ignored by the constraints solver but keeps its handle
+    /// and its parameters. Note that this does not wake up the attached rigid-bodies.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// The compliance, i.e., the inverse of the stiffness, of this joint.
+    pub fn compliance(&self) -> Real {
+        self.compliance
+    }
+
+    /// Sets the compliance, i.e., the inverse of the stiffness, of this joint.
+    ///
+    /// A compliant joint behaves like a spring pulling the bodies back toward the
+    /// configuration satisfying the joint, instead of enforcing it rigidly. Its positional
+    /// error is then only corrected by the velocity solver, so the position solver (and
+    /// `IntegrationParameters::joint_erp`) don't affect it. Setting the compliance to zero
+    /// (the default) makes the joint rigid again.
+    pub fn set_compliance(&mut self, compliance: Real) {
+        self.compliance = compliance.max(0.0);
+    }
+
+    /// The factor applied to the number of velocity iterations of the island containing this joint.
+    pub fn solver_iterations_multiplier(&self) -> usize {
+        self.solver_iterations_multiplier
+    }
+
+    /// Sets the factor applied to the number of velocity iterations of the island containing
+    /// this joint.
+    ///
+    /// The velocity solver of an island runs `IntegrationParameters::max_velocity_iterations`
+    /// times the largest multiplier of the joints of this island. This lets long chains of
+    /// joints get more Gauss-Seidel iterations, and stretch less, without increasing the cost of
+    /// simulating the rest of the scene. The multiplier is clamped to be at least `1` (the default).
+    /// This is ignored if the `parallel` feature is enabled.
+    pub fn set_solver_iterations_multiplier(&mut self, multiplier: usize) {
+        self.solver_iterations_multiplier = multiplier.max(1);
+    }
+
+    /// The colliders the anchors of this joint are expressed relative to, if any.
+    pub fn anchor_colliders(&self) -> [Option<ColliderHandle>; 2] {
+        self.anchor_colliders
+    }
+
+    /// Sets the colliders the anchors of this joint are expressed relative to.
+    ///
+    /// If `collider1` is set, all the local anchors, axes, and frames of `self.params` relative
+    /// to the first body are expressed in the local-space of `collider1` instead of the local-space
+    /// of the first body. The same applies to `collider2` and the second body. They are converted
+    /// to the local-space of the bodies each time the joint constraints are assembled, so the
+    /// joint follows the collider if it is replaced by another one at a different position relative
+    /// to its body.
+    ///
+    /// A collider that doesn't exist, or that isn't attached to the corresponding body, is
+    /// ignored: the local-space of the body is used instead.
+    pub fn set_anchor_colliders(
+        &mut self,
+        collider1: Option<ColliderHandle>,
+        collider2: Option<ColliderHandle>,
+    ) {
+        self.anchor_colliders = [collider1, collider2];
+    }
+
+    /// A copy of this joint with the local frames of its parameters expressed relative to
+    /// the attached bodies instead of the anchor colliders.
+    ///
+    /// Returns `None` if this joint doesn't have any anchor collider.
+    pub(crate) fn with_body_frames(&self) -> Option<Joint> {
+        if self.anchor_colliders == [None, None] {
+            return None;
+        }
+
+        let mut result = self.clone();
+        result.params.transform_local_frames(
+            &self.anchor_collider_frames[0],
+            &self.anchor_collider_frames[1],
+        );
+        Some(result)
+    }
+
     /// Can this joint use SIMD-accelerated constraint formulations?
     pub fn supports_simd_constraints(&self) -> bool {
+        if self.compliance != 0.0 {
+            // SIMD constraints don't support compliance right now.
+            return false;
+        }
+
+        if self.anchor_colliders != [None, None] {
+            // SIMD constraints don't support anchor colliders right now.
+            return false;
+        }
+
         match &self.params {
             JointParams::PrismaticJoint(joint) => joint.supports_simd_constraints(),
             JointParams::FixedJoint(joint) => joint.supports_simd_constraints(),
             JointParams::BallJoint(joint) => joint.supports_simd_constraints(),
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(joint) => joint.supports_simd_constraints(),
+            JointParams::GearJoint(joint) => joint.supports_simd_constraints(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn compliant_ball_joint_stretches_under_load() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let simulate = |compliance: Real| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            let body = world
+                .bodies
+                .insert(RigidBodyBuilder::new_dynamic().linear_damping(2.0).build());
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), body, &mut world.bodies);
+
+            let handle = world.joints.insert(
+                &mut world.bodies,
+                ground,
+                body,
+                BallJoint::new(Point::origin(), Point::origin()),
+            );
+            world
+                .joints
+                .get_mut(handle)
+                .unwrap()
+                .set_compliance(compliance);
+
+            let mut stretches = Vec::new();
+            for _ in 0..600 {
+                world.step();
+                stretches.push(-world.bodies[body].position().translation.vector.y);
+            }
+
+            // The stretch must have settled during the last second.
+            let last_second = &stretches[540..];
+            let stretch = *last_second.last().unwrap();
+            assert!(stretch.is_finite());
+            assert!(last_second.iter().all(|s| (s - stretch).abs() < 1.0e-3));
+
+            (stretch, world.bodies[body].mass())
+        };
+
+        // The rigid joint only stretches by the error tolerated by the position solver.
+        let (rigid_stretch, _) = simulate(0.0);
+        assert!(rigid_stretch.abs() < 0.01);
+
+        // The compliant joint stretches like a spring: `stretch = force * compliance`.
+        for compliance in &[0.02, 0.05] {
+            let (stretch, mass) = simulate(*compliance);
+            let expected_stretch = mass * 9.81 * *compliance;
+            assert!((stretch - expected_stretch).abs() < expected_stretch * 0.1);
+        }
+
+        // The stretch is proportional to the compliance.
+        let ratio = simulate(0.05).0 / simulate(0.02).0;
+        assert!((ratio - 2.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn joint_anchored_to_collider_frame() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let body = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), body, &mut world.bodies);
+        let handle = world.colliders.insert(
+            ColliderBuilder::ball(0.25)
+                .position(Isometry::new(Vector::x(), na::zero()))
+                .build(),
+            body,
+            &mut world.bodies,
+        );
+
+        // Pin the center of the second collider to the world origin.
+        let joint = BallJoint::new(Point::origin(), Point::origin());
+        let joint_handle = world.joints.insert(&mut world.bodies, ground, body, joint);
+        world
+            .joints
+            .get_mut(joint_handle)
+            .unwrap()
+            .set_anchor_colliders(None, Some(handle));
+
+        for _ in 0..100 {
+            world.step();
+        }
+        let anchor = world.colliders[handle].position().translation.vector;
+        assert!(anchor.norm() < 1.0e-2, "{}", anchor);
+        assert!(world.bodies[body].position().translation.vector.norm() > 0.9);
+
+        // Replace the collider with one at another position wrt. the body.
+        world.colliders.remove(handle, &mut world.bodies, true);
+        let handle = world.colliders.insert(
+            ColliderBuilder::ball(0.25)
+                .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                .build(),
+            body,
+            &mut world.bodies,
+        );
+        world
+            .joints
+            .get_mut(joint_handle)
+            .unwrap()
+            .set_anchor_colliders(None, Some(handle));
+
+        for _ in 0..100 {
+            world.step();
         }
+        let anchor = world.colliders[handle].position().translation.vector;
+        assert!(anchor.norm() < 1.0e-2, "{}", anchor);
+        assert!(world.bodies[body].position().translation.vector.norm() > 1.9);
+    }
+
+    #[test]
+    fn joint_solver_iterations_multiplier() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        // Returns how much a chain of 50 links with a heavy load stretches.
+        let simulate = |multiplier: usize| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            let num_links = 50;
+
+            let mut prev = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            for i in 1..=num_links {
+                let mut rb = RigidBodyBuilder::new_dynamic().build();
+                rb.set_position(
+                    Isometry::new(Vector::y() * (-0.5 * i as Real), na::zero()),
+                    true,
+                );
+                let link = world.bodies.insert(rb);
+
+                // The last link carries a heavy load.
+                let density = if i == num_links { 1000.0 } else { 1.0 };
+                world.colliders.insert(
+                    ColliderBuilder::ball(0.1).density(density).build(),
+                    link,
+                    &mut world.bodies,
+                );
+
+                let handle = world.joints.insert(
+                    &mut world.bodies,
+                    prev,
+                    link,
+                    BallJoint::new(Point::origin(), Point::from(Vector::y() * 0.5)),
+                );
+                world
+                    .joints
+                    .get_mut(handle)
+                    .unwrap()
+                    .set_solver_iterations_multiplier(multiplier);
+                prev = link;
+            }
+
+            for _ in 0..60 {
+                world.step();
+            }
+
+            -world.bodies[prev].position().translation.vector.y - 0.5 * num_links as Real
+        };
+
+        let default_stretch = simulate(1);
+        let stretch = simulate(10);
+        assert!(default_stretch > 0.0);
+        assert!(stretch < default_stretch, "{} {}", stretch, default_stretch);
     }
 }
@@ -1,5 +1,8 @@
 use super::Joint;
-use crate::geometry::{InteractionGraph, RigidBodyGraphIndex, TemporaryInteractionIndex};
+use crate::geometry::{
+    ColliderSet, InteractionGraph, RigidBodyGraphIndex, TemporaryInteractionIndex,
+};
+use crate::math::Isometry;
 
 use crate::data::arena::Arena;
 use crate::dynamics::{JointParams, RigidBodyHandle, RigidBodySet};
@@ -134,6 +137,26 @@ impl JointSet {
             .map(|e| (e.weight.handle, &mut e.weight))
     }
 
+    /// Iterates through the handles of all the joints attached to the given rigid-body.
+    ///
+    /// The iterator is empty if the rigid-body does not exist or has no joint attached to it.
+    pub fn attached_joints<'a>(
+        &'a self,
+        bodies: &RigidBodySet,
+        body: RigidBodyHandle,
+    ) -> impl Iterator<Item = JointHandle> + 'a {
+        let graph_index = bodies
+            .get(body)
+            .map(|rb| rb.joint_graph_index)
+            .filter(|id| InteractionGraph::<(), ()>::is_graph_index_valid(*id));
+
+        graph_index.into_iter().flat_map(move |id| {
+            self.joint_graph
+                .interactions_with(id)
+                .map(|(_, _, joint)| joint.handle)
+        })
+    }
+
     // /// The set of joints as an array.
     // pub(crate) fn joints(&self) -> &[JointGraphEdge] {
     //     // self.joint_graph
@@ -174,6 +197,11 @@ impl JointSet {
             #[cfg(feature = "parallel")]
             position_constraint_index: 0,
             params: joint_params.into(),
+            enabled: true,
+            compliance: 0.0,
+            solver_iterations_multiplier: 1,
+            anchor_colliders: [None, None],
+            anchor_collider_frames: [Isometry::identity(); 2],
         };
 
         let (rb1, rb2) = bodies.get2_mut_internal(joint.body1, joint.body2);
@@ -216,6 +244,11 @@ impl JointSet {
         // FIXME: don't iterate through all the interactions.
         for (i, edge) in self.joint_graph.graph.edges.iter().enumerate() {
             let joint = &edge.weight;
+
+            if !joint.enabled {
+                continue;
+            }
+
             let rb1 = &bodies[joint.body1];
             let rb2 = &bodies[joint.body2];
 
@@ -234,6 +267,45 @@ impl JointSet {
         }
     }
 
+    /// Reads the positions, relative to their parent bodies, of the colliders the joint anchors
+    /// are attached to.
+    pub(crate) fn update_anchor_collider_frames(&mut self, colliders: &ColliderSet) {
+        for edge in &mut self.joint_graph.graph.edges {
+            let joint = &mut edge.weight;
+            let bodies = [joint.body1, joint.body2];
+
+            for i in 0..2 {
+                if let Some(handle) = joint.anchor_colliders[i] {
+                    joint.anchor_collider_frames[i] = colliders
+                        .get(handle)
+                        .filter(|co| co.parent() == bodies[i])
+                        .map(|co| *co.position_wrt_parent())
+                        .unwrap_or_else(Isometry::identity);
+                }
+            }
+        }
+    }
+
+    /// Updates the cumulative angles of the revolute joints from the positions of their bodies.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn update_revolute_angles(&mut self, bodies: &RigidBodySet) {
+        for edge in &mut self.joint_graph.graph.edges {
+            let joint = &mut edge.weight;
+
+            if let JointParams::RevoluteJoint(revolute) = &mut joint.params {
+                if let (Some(rb1), Some(rb2)) = (bodies.get(joint.body1), bodies.get(joint.body2)) {
+                    if rb1.is_sleeping() && rb2.is_sleeping() {
+                        continue;
+                    }
+
+                    let pos1 = rb1.position() * joint.anchor_collider_frames[0];
+                    let pos2 = rb2.position() * joint.anchor_collider_frames[1];
+                    revolute.update_unwrapped_angle(&pos1, &pos2);
+                }
+            }
+        }
+    }
+
     /// Removes a joint from this set.
     ///
     /// If `wake_up` is set to `true`, then the bodies attached to this joint will be
@@ -305,3 +377,54 @@ impl JointSet {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
+
+    #[test]
+    fn attached_joints() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut joints = JointSet::new();
+        let mut bodies = RigidBodySet::new();
+
+        let body1 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let body2 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let body3 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let lonely = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+
+        let joint1 = joints.insert(
+            &mut bodies,
+            body1,
+            body2,
+            BallJoint::new(Point::origin(), Point::origin()),
+        );
+        let joint2 = joints.insert(
+            &mut bodies,
+            body3,
+            body2,
+            BallJoint::new(Point::origin(), Point::origin()),
+        );
+
+        let mut attached: Vec<_> = joints.attached_joints(&bodies, body2).collect();
+        attached.sort_by_key(|handle| handle.into_raw_parts());
+        let mut expected = vec![joint1, joint2];
+        expected.sort_by_key(|handle| handle.into_raw_parts());
+        assert_eq!(attached, expected);
+
+        assert_eq!(
+            joints.attached_joints(&bodies, body1).collect::<Vec<_>>(),
+            vec![joint1]
+        );
+        assert_eq!(joints.attached_joints(&bodies, lonely).count(), 0);
+
+        joints.remove(joint1, &mut bodies, true);
+        assert_eq!(joints.attached_joints(&bodies, body1).count(), 0);
+        assert_eq!(
+            joints.attached_joints(&bodies, body2).collect::<Vec<_>>(),
+            vec![joint2]
+        );
+    }
+}
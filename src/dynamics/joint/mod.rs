@@ -1,5 +1,7 @@
 pub use self::ball_joint::BallJoint;
 pub use self::fixed_joint::FixedJoint;
+pub(crate) use self::gear_joint::rotation_vector;
+pub use self::gear_joint::GearJoint;
 // pub use self::generic_joint::GenericJoint;
 pub use self::joint::{Joint, JointParams};
 pub(crate) use self::joint_set::{JointGraphEdge, JointIndex};
@@ -11,6 +13,7 @@ pub use self::spring_model::SpringModel;
 
 mod ball_joint;
 mod fixed_joint;
+mod gear_joint;
 // mod generic_joint;
 mod joint;
 mod joint_set;
@@ -37,6 +37,25 @@ pub struct PrismaticJoint {
     ///
     /// The impulse applied to the second body is given by `-impulse`.
     pub limits_impulse: Real,
+    /// The stiffness of the soft limits.
+    ///
+    /// If both `limits_stiffness` and `limits_damping` are zero, the limits are hard.
+    /// See the documentation of `SpringModel` for more information on this parameter.
+    pub limits_stiffness: Real,
+    /// The damping of the soft limits.
+    /// See the documentation of `SpringModel` for more information on this parameter.
+    pub limits_damping: Real,
+    /// The spring-like model used by the soft limits to push the bodies back within the limits.
+    pub limits_model: SpringModel,
+    /// The restitution coefficient of the hard limits (default: `0.0`).
+    ///
+    /// If non-zero, the bodies bounce back when they hit a limit with a relative velocity
+    /// along the joint axis larger than `limits_restitution_threshold`, just like for a
+    /// contact with the same restitution coefficient.
+    pub limits_restitution: Real,
+    /// The relative velocity along the joint axis below which hitting a limit does not
+    /// make the bodies bounce back, whatever the `limits_restitution` (default: `1.0`).
+    pub limits_restitution_threshold: Real,
 
     /// The target relative angular velocity the motor will attempt to reach.
     pub motor_target_vel: Real,
@@ -49,11 +68,24 @@ pub struct PrismaticJoint {
     /// See the documentation of `SpringModel` for more information on this parameter.
     pub motor_damping: Real,
     /// The maximal impulse the motor is able to deliver.
+    ///
+    /// This bounds the total impulse applied by the motor during a timestep, accumulated over
+    /// all the velocity solver iterations, so the maximal motor force is `motor_max_impulse / dt`
+    /// regardless of `IntegrationParameters::max_velocity_iterations`.
     pub motor_max_impulse: Real,
     /// The angular impulse applied by the motor.
     pub motor_impulse: Real,
     /// The spring-like model used by the motor to reach the target velocity and .
     pub motor_model: SpringModel,
+    /// The maximal force the joint friction can apply to resist the relative translation along
+    /// the joint axis.
+    ///
+    /// Unlike the motor damping, this dry friction doesn't depend on the relative velocity: the
+    /// joint doesn't slide as long as the force needed to keep it still is smaller than this
+    /// value. Set to zero to disable the joint friction.
+    pub friction: Real,
+    /// The impulse applied by the joint friction.
+    pub friction_impulse: Real,
 }
 
 impl PrismaticJoint {
@@ -77,6 +109,11 @@ impl PrismaticJoint {
             limits_enabled: false,
             limits: [-Real::MAX, Real::MAX],
             limits_impulse: 0.0,
+            limits_stiffness: 0.0,
+            limits_damping: 0.0,
+            limits_model: SpringModel::VelocityBased,
+            limits_restitution: 0.0,
+            limits_restitution_threshold: 1.0,
             motor_target_vel: 0.0,
             motor_target_pos: 0.0,
             motor_stiffness: 0.0,
@@ -84,6 +121,8 @@ impl PrismaticJoint {
             motor_max_impulse: Real::MAX,
             motor_impulse: 0.0,
             motor_model: SpringModel::VelocityBased,
+            friction: 0.0,
+            friction_impulse: 0.0,
         }
     }
 
@@ -135,6 +174,11 @@ impl PrismaticJoint {
             limits_enabled: false,
             limits: [-Real::MAX, Real::MAX],
             limits_impulse: 0.0,
+            limits_stiffness: 0.0,
+            limits_damping: 0.0,
+            limits_model: SpringModel::VelocityBased,
+            limits_restitution: 0.0,
+            limits_restitution_threshold: 1.0,
             motor_target_vel: 0.0,
             motor_target_pos: 0.0,
             motor_stiffness: 0.0,
@@ -142,6 +186,8 @@ impl PrismaticJoint {
             motor_max_impulse: Real::MAX,
             motor_impulse: 0.0,
             motor_model: SpringModel::VelocityBased,
+            friction: 0.0,
+            friction_impulse: 0.0,
         }
     }
 
@@ -157,8 +203,21 @@ impl PrismaticJoint {
 
     /// Can a SIMD constraint be used for resolving this joint?
     pub fn supports_simd_constraints(&self) -> bool {
-        // SIMD revolute constraints don't support motors right now.
-        self.motor_max_impulse == 0.0 || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0)
+        // SIMD revolute constraints don't support motors, soft limits, bouncy limits,
+        // nor friction right now.
+        (self.motor_max_impulse == 0.0
+            || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0))
+            && !self.has_soft_limits()
+            && self.limits_restitution == 0.0
+            && self.friction == 0.0
+    }
+
+    /// Are the limits of this joint enabled and soft?
+    ///
+    /// Soft limits are enforced by a one-sided spring-damper acting along the joint axis once
+    /// a limit is exceeded, instead of a hard stop.
+    pub fn has_soft_limits(&self) -> bool {
+        self.limits_enabled && (self.limits_stiffness != 0.0 || self.limits_damping != 0.0)
     }
 
     // FIXME: precompute this?
@@ -222,6 +281,32 @@ impl PrismaticJoint {
         self.motor_model = model;
     }
 
+    /// Set the spring-like model used by the soft limits.
+    pub fn configure_limits_model(&mut self, model: SpringModel) {
+        self.limits_model = model;
+    }
+
+    /// Makes the limits of this joint soft.
+    ///
+    /// Once a limit is exceeded, the bodies are pushed back within the limits by a
+    /// spring-damper with the given stiffness and damping instead of being stopped
+    /// instantaneously. Its impulse is only ever directed toward the allowed range, so it
+    /// adds up with the motor impulse instead of fighting it. Setting both coefficients to
+    /// zero makes the limits hard again.
+    pub fn configure_soft_limits(&mut self, stiffness: Real, damping: Real) {
+        self.limits_stiffness = stiffness;
+        self.limits_damping = damping;
+    }
+
+    /// Makes the hard limits of this joint bouncy.
+    ///
+    /// The bodies hitting a limit with a relative velocity along the joint axis larger than
+    /// `threshold` bounce back with their relative velocity multiplied by `restitution`.
+    pub fn configure_limits_restitution(&mut self, restitution: Real, threshold: Real) {
+        self.limits_restitution = restitution;
+        self.limits_restitution_threshold = threshold;
+    }
+
     /// Sets the target velocity this motor needs to reach.
     pub fn configure_motor_velocity(&mut self, target_vel: Real, factor: Real) {
         self.configure_motor(self.motor_target_pos, target_vel, 0.0, factor)
@@ -246,3 +331,204 @@ impl PrismaticJoint {
         self.motor_damping = damping;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn prismatic_soft_limits_decelerate_smoothly() {
+        use crate::dynamics::PrismaticJoint;
+        use crate::math::Point;
+
+        // An elevator driven upward by a PD position motor targeting a height above its top limit.
+        let simulate = |soft_limits: bool| {
+            let mut world = TestWorld::new();
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            let elevator = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                elevator,
+                &mut world.bodies,
+            );
+
+            #[cfg(feature = "dim2")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::y_axis(),
+                Point::origin(),
+                Vector::y_axis(),
+            );
+            #[cfg(feature = "dim3")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::y_axis(),
+                Vector::x(),
+                Point::origin(),
+                Vector::y_axis(),
+                Vector::x(),
+            );
+            joint.limits_enabled = true;
+            joint.limits = [0.0, 2.0];
+            joint.configure_motor_position(3.0, 0.05, 1.0);
+            joint.motor_max_impulse = world.bodies[elevator].mass() * 0.1;
+
+            if soft_limits {
+                joint.configure_soft_limits(0.02, 0.2);
+            }
+
+            world
+                .joints
+                .insert(&mut world.bodies, ground, elevator, joint);
+
+            let mut max_decel: Real = 0.0;
+            let mut max_height: Real = 0.0;
+
+            for _ in 0..300 {
+                let prev_vel = world.bodies[elevator].linvel().y;
+                world.step();
+
+                let rb = &world.bodies[elevator];
+                max_decel = max_decel.max(prev_vel - rb.linvel().y);
+                max_height = max_height.max(rb.position().translation.vector.y);
+            }
+
+            let final_height = world.bodies[elevator].position().translation.vector.y;
+            (max_decel, max_height, final_height)
+        };
+
+        let (hard_decel, _, hard_height) = simulate(false);
+        let (soft_decel, soft_max_height, soft_height) = simulate(true);
+
+        // The hard limit stops the elevator in a single step.
+        assert!(hard_decel > 3.0);
+        assert!((hard_height - 2.0).abs() < 0.05);
+
+        // The soft limit spreads the deceleration over several steps, and the motor
+        // settles against the limit instead of pushing through it.
+        assert!(soft_decel < hard_decel / 2.0);
+        assert!(soft_max_height < 2.5);
+        assert!((soft_height - 2.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn prismatic_limits_restitution() {
+        use crate::dynamics::PrismaticJoint;
+        use crate::math::Point;
+
+        // A slider launched toward its top limit, which has a restitution of 0.5.
+        let simulate = |initial_vel: Real| {
+            let mut world = TestWorld::new();
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            let mut slider_body = RigidBodyBuilder::new_dynamic().build();
+            slider_body.set_linvel(Vector::y() * initial_vel, true);
+            let slider = world.bodies.insert(slider_body);
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                slider,
+                &mut world.bodies,
+            );
+
+            #[cfg(feature = "dim2")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::y_axis(),
+                Point::origin(),
+                Vector::y_axis(),
+            );
+            #[cfg(feature = "dim3")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::y_axis(),
+                Vector::x(),
+                Point::origin(),
+                Vector::y_axis(),
+                Vector::x(),
+            );
+            joint.limits_enabled = true;
+            joint.limits = [-10.0, 1.0];
+            joint.configure_limits_restitution(0.5, 1.0);
+            world
+                .joints
+                .insert(&mut world.bodies, ground, slider, joint);
+
+            let mut min_vel = Real::MAX;
+            for _ in 0..60 {
+                world.step();
+                min_vel = min_vel.min(world.bodies[slider].linvel().y);
+            }
+
+            (
+                min_vel,
+                world.bodies[slider].position().translation.vector.y,
+            )
+        };
+
+        // Hitting the limit at speed bounces back with half the velocity.
+        let (min_vel, height) = simulate(4.0);
+        assert!((min_vel + 2.0).abs() < 0.2);
+        assert!(height < 0.5);
+
+        // Hitting the limit below the velocity threshold just stops the slider.
+        let (min_vel, height) = simulate(0.5);
+        assert!(min_vel > -0.1);
+        assert!((height - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn motor_max_impulse_is_independent_from_iterations() {
+        use crate::dynamics::PrismaticJoint;
+        use crate::math::Point;
+
+        // Returns the velocity of a slider driven by a saturated velocity motor after one step.
+        let simulate = |max_velocity_iterations: usize| {
+            let mut world = TestWorld::new();
+            world.params.max_velocity_iterations = max_velocity_iterations;
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            let slider = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                slider,
+                &mut world.bodies,
+            );
+
+            #[cfg(feature = "dim2")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::x_axis(),
+                Point::origin(),
+                Vector::x_axis(),
+            );
+            #[cfg(feature = "dim3")]
+            let mut joint = PrismaticJoint::new(
+                Point::origin(),
+                Vector::x_axis(),
+                Vector::y(),
+                Point::origin(),
+                Vector::x_axis(),
+                Vector::y(),
+            );
+            joint.configure_motor_velocity(100.0, 1.0);
+            joint.motor_max_impulse = 0.1;
+            world
+                .joints
+                .insert(&mut world.bodies, ground, slider, joint);
+
+            world.step();
+
+            let rb = &world.bodies[slider];
+            (rb.linvel().x.abs(), 0.1 / rb.mass())
+        };
+
+        for iterations in [1, 4, 16].iter() {
+            let (vel, expected_vel) = simulate(*iterations);
+            assert!((vel - expected_vel).abs() < 1.0e-4);
+        }
+    }
+}
@@ -35,14 +35,29 @@ pub struct RevoluteJoint {
     /// See the documentation of `SpringModel` for more information on this parameter.
     pub motor_damping: Real,
     /// The maximal impulse the motor is able to deliver.
+    ///
+    /// This bounds the total impulse applied by the motor during a timestep, accumulated over
+    /// all the velocity solver iterations, so the maximal motor force is `motor_max_impulse / dt`
+    /// regardless of `IntegrationParameters::max_velocity_iterations`.
     pub motor_max_impulse: Real,
     /// The angular impulse applied by the motor.
     pub motor_impulse: Real,
     /// The spring-like model used by the motor to reach the target velocity and .
     pub motor_model: SpringModel,
+    /// The maximal torque the joint friction can apply to resist the relative rotation along the
+    /// joint axis.
+    ///
+    /// Unlike the motor damping, this dry friction doesn't depend on the relative velocity: the
+    /// joint doesn't rotate as long as the torque needed to keep it still is smaller than this
+    /// value. Set to zero to disable the joint friction.
+    pub friction: Real,
+    /// The angular impulse applied by the joint friction.
+    pub friction_impulse: Real,
 
     // Used to handle cases where the position target ends up being more than pi radians away.
     pub(crate) motor_last_angle: Real,
+    // The cumulative angle of the joint, updated at the end of each timestep.
+    pub(crate) unwrapped_angle: Real,
     // The angular impulse expressed in world-space.
     pub(crate) world_ang_impulse: Vector<Real>,
     // The world-space orientation of the free axis of the first attached body.
@@ -76,13 +91,18 @@ impl RevoluteJoint {
             prev_axis1: *local_axis1,
             motor_model: SpringModel::default(),
             motor_last_angle: 0.0,
+            unwrapped_angle: 0.0,
+            friction: 0.0,
+            friction_impulse: 0.0,
         }
     }
 
     /// Can a SIMD constraint be used for resolving this joint?
     pub fn supports_simd_constraints(&self) -> bool {
-        // SIMD revolute constraints don't support motors right now.
-        self.motor_max_impulse == 0.0 || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0)
+        // SIMD revolute constraints don't support motors nor friction right now.
+        (self.motor_max_impulse == 0.0
+            || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0))
+            && self.friction == 0.0
     }
 
     /// Set the spring-like model used by the motor to reach the desired target velocity and position.
@@ -119,12 +139,41 @@ impl RevoluteJoint {
         &self,
         body_pos1: &Isometry<Real>,
         body_pos2: &Isometry<Real>,
+    ) -> Real {
+        self.unwrap_angle(self.motor_last_angle, body_pos1, body_pos2)
+    }
+
+    /// The cumulative rotation angle of this joint, in radians, since its creation.
+    ///
+    /// Unlike an angle measured from the current positions of the attached bodies, this doesn't
+    /// wrap around: after 3.5 turns, this returns `3.5 * 2 * pi`. This is updated at the end of
+    /// each timestep from the smallest angle between the previous and the new relative rotation,
+    /// so it can't keep track of rotations faster than half a turn per timestep, nor of the
+    /// rotations caused by teleporting one of the attached bodies.
+    pub fn unwrapped_angle(&self) -> Real {
+        self.unwrapped_angle
+    }
+
+    pub(crate) fn update_unwrapped_angle(
+        &mut self,
+        body_pos1: &Isometry<Real>,
+        body_pos2: &Isometry<Real>,
+    ) {
+        self.unwrapped_angle = self.unwrap_angle(self.unwrapped_angle, body_pos1, body_pos2);
+    }
+
+    // The angle of this joint closest to `last_angle`.
+    fn unwrap_angle(
+        &self,
+        last_angle: Real,
+        body_pos1: &Isometry<Real>,
+        body_pos2: &Isometry<Real>,
     ) -> Real {
         let motor_axis1 = body_pos1 * self.local_axis1;
         let ref1 = body_pos1 * self.basis1[0];
         let ref2 = body_pos2 * self.basis2[0];
 
-        let last_angle_cycles = (self.motor_last_angle / Real::two_pi()).trunc() * Real::two_pi();
+        let last_angle_cycles = (last_angle / Real::two_pi()).trunc() * Real::two_pi();
 
         // Measure the position between 0 and 2-pi
         let new_angle = if ref1.cross(&ref2).dot(&motor_axis1) < 0.0 {
@@ -134,7 +183,7 @@ impl RevoluteJoint {
         };
 
         // The last angle between 0 and 2-pi
-        let last_angle_zero_two_pi = self.motor_last_angle - last_angle_cycles;
+        let last_angle_zero_two_pi = last_angle - last_angle_cycles;
 
         // Figure out the smallest angle differance.
         let mut angle_diff = new_angle - last_angle_zero_two_pi;
@@ -144,6 +193,98 @@ impl RevoluteJoint {
             angle_diff += Real::two_pi()
         }
 
-        self.motor_last_angle + angle_diff
+        last_angle + angle_diff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn revolute_joint_friction() {
+        // A horizontal arm hinged at one of its ends, loaded by gravity.
+        let run = |friction_ratio: Real| {
+            let mut world = TestWorld::new();
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            let arm = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .translation(1.0, 0.0, 0.0)
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), arm, &mut world.bodies);
+
+            world.gravity = Vector::y() * -9.81;
+            let load_torque = world.bodies[arm].mass() * 9.81;
+            let mut hinge = crate::dynamics::RevoluteJoint::new(
+                crate::math::Point::origin(),
+                Vector::z_axis(),
+                crate::math::Point::new(-1.0, 0.0, 0.0),
+                Vector::z_axis(),
+            );
+            hinge.friction = load_torque * friction_ratio;
+            world.joints.insert(&mut world.bodies, ground, arm, hinge);
+
+            for _ in 0..60 {
+                world.step();
+            }
+
+            world.bodies[arm].position().translation.vector
+        };
+
+        // The friction is strong enough to hold the load statically.
+        let held = run(1.5);
+        assert!(held.y.abs() < 1.0e-2, "{}", held);
+
+        // The arm falls when the friction is too weak.
+        let yielded = run(0.5);
+        assert!(yielded.y < -0.5, "{}", yielded);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn revolute_joint_unwrapped_angle() {
+        use crate::dynamics::{JointParams, RevoluteJoint};
+        use crate::math::Point;
+        use na::RealField;
+
+        let mut world = TestWorld::new();
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let mut winch = RigidBodyBuilder::new_dynamic().can_sleep(false).build();
+        // One turn per second.
+        winch.set_angvel(Vector::y() * Real::two_pi(), true);
+        let winch = world.bodies.insert(winch);
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), winch, &mut world.bodies);
+
+        let hinge = RevoluteJoint::new(
+            Point::origin(),
+            Vector::y_axis(),
+            Point::origin(),
+            Vector::y_axis(),
+        );
+        let handle = world.joints.insert(&mut world.bodies, ground, winch, hinge);
+
+        for _ in 0..210 {
+            world.step();
+        }
+
+        // The winch did 3.5 turns.
+        match &world.joints.get(handle).unwrap().params {
+            JointParams::RevoluteJoint(hinge) => {
+                let turns = hinge.unwrapped_angle() / Real::two_pi();
+                assert!((turns - 3.5).abs() < 1.0e-2, "{}", turns);
+            }
+            _ => unreachable!(),
+        }
     }
 }
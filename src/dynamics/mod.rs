@@ -1,12 +1,15 @@
 //! Structures related to dynamics: bodies, joints, etc.
 
-pub use self::integration_parameters::IntegrationParameters;
-pub(crate) use self::joint::JointIndex;
+pub use self::integration_parameters::{
+    ConstraintOrder, IntegrationParameters, PenetrationCorrection, SolverMode,
+};
 #[cfg(feature = "dim3")]
 pub use self::joint::RevoluteJoint;
+pub(crate) use self::joint::{rotation_vector, JointIndex};
 pub use self::joint::{
     BallJoint,
     FixedJoint,
+    GearJoint,
     Joint,
     JointHandle,
     JointParams,
@@ -15,16 +18,21 @@ pub use self::joint::{
     SpringModel, // GenericJoint
 };
 pub use self::rigid_body::{ActivationStatus, BodyStatus, RigidBody, RigidBodyBuilder};
-pub use self::rigid_body_set::{BodyPair, RigidBodyHandle, RigidBodySet};
+pub use self::rigid_body_set::{
+    BodyPair, RigidBodyHandle, RigidBodyPositionSnapshot, RigidBodySet,
+};
 pub use parry::mass_properties::MassProperties;
 // #[cfg(not(feature = "parallel"))]
 pub use self::coefficient_combine_rule::CoefficientCombineRule;
 pub(crate) use self::joint::JointGraphEdge;
 pub(crate) use self::rigid_body::RigidBodyChanges;
+pub use self::solver::DeltaVel;
 #[cfg(not(feature = "parallel"))]
 pub(crate) use self::solver::IslandSolver;
 #[cfg(feature = "parallel")]
 pub(crate) use self::solver::ParallelIslandSolver;
+#[cfg(not(feature = "parallel"))]
+pub use self::solver::{CustomVelocityConstraint, SolverBody};
 
 mod coefficient_combine_rule;
 mod integration_parameters;
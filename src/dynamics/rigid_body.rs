@@ -1,12 +1,11 @@
-use crate::dynamics::MassProperties;
+use crate::dynamics::{DeltaVel, MassProperties};
 use crate::geometry::{
     Collider, ColliderHandle, ColliderSet, InteractionGraph, RigidBodyGraphIndex,
 };
 use crate::math::{
     AngVector, AngularInertia, Isometry, Point, Real, Rotation, Translation, Vector,
 };
-use crate::utils::{self, WCross, WDot};
-use na::ComplexField;
+use crate::utils::{self, DeterministicMath, WAngularInertia, WCross, WDot};
 use num::Zero;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -47,6 +46,7 @@ bitflags::bitflags! {
         const POSITION  = 1 << 1;
         const SLEEP     = 1 << 2;
         const COLLIDERS = 1 << 3;
+        const TELEPORT  = 1 << 4;
     }
 }
 
@@ -59,8 +59,19 @@ pub struct RigidBody {
     /// The world-space position of the rigid-body.
     pub(crate) position: Isometry<Real>,
     pub(crate) predicted_position: Isometry<Real>,
+    /// The world-space position of the rigid-body before the last timestep.
+    pub(crate) previous_position: Isometry<Real>,
     /// The local mass properties of the rigid-body.
     pub(crate) mass_properties: MassProperties,
+    /// The local mass properties computed from the attached colliders, plus the ones
+    /// given explicitly by the user with `set_mass_properties`.
+    pub(crate) colliders_mass_properties: MassProperties,
+    /// Mass properties added to, or replacing, the ones computed from the colliders.
+    pub(crate) additional_mass_properties: MassProperties,
+    /// Do the `additional_mass_properties` replace the ones computed from the colliders?
+    pub(crate) additional_mass_properties_overwrite: bool,
+    /// The user-defined local center-of-mass, replacing the one computed from the mass properties.
+    pub(crate) local_com_override: Option<Point<Real>>,
     /// The world-space center of mass of the rigid-body.
     pub world_com: Point<Real>,
     /// The inverse mass taking into account translation locking.
@@ -68,14 +79,24 @@ pub struct RigidBody {
     /// The square-root of the world-space inverse angular inertia tensor of the rigid-body,
     /// taking into account rotation locking.
     pub effective_world_inv_inertia_sqrt: AngularInertia<Real>,
+    /// The multiplier of the mass and angular inertia seen by the constraints solver during
+    /// the next timestep only.
+    pub(crate) solver_mass_multiplier: Real,
     /// The linear velocity of the rigid-body.
     pub(crate) linvel: Vector<Real>,
     /// The angular velocity of the rigid-body.
     pub(crate) angvel: AngVector<Real>,
+    // The velocity changes applied by the constraints solver during the last timestep.
+    pub(crate) solver_linvel_change: Vector<Real>,
+    pub(crate) solver_angvel_change: AngVector<Real>,
     /// Damping factor for gradually slowing down the translational motion of the rigid-body.
     pub linear_damping: Real,
     /// Damping factor for gradually slowing down the angular motion of the rigid-body.
     pub angular_damping: Real,
+    /// The maximum norm of the linear velocity of this rigid-body, enforced after each velocity solve.
+    pub max_linvel: Real,
+    /// The maximum norm of the angular velocity of this rigid-body, enforced after each velocity solve.
+    pub max_angvel: Real,
     /// Accumulation of external forces (only for dynamic bodies).
     pub(crate) force: Vector<Real>,
     /// Accumulation of external torques (only for dynamic bodies).
@@ -104,17 +125,27 @@ impl RigidBody {
         Self {
             position: Isometry::identity(),
             predicted_position: Isometry::identity(),
+            previous_position: Isometry::identity(),
             mass_properties: MassProperties::zero(),
+            colliders_mass_properties: MassProperties::zero(),
+            additional_mass_properties: MassProperties::zero(),
+            additional_mass_properties_overwrite: false,
+            local_com_override: None,
             world_com: Point::origin(),
             effective_inv_mass: 0.0,
             effective_world_inv_inertia_sqrt: AngularInertia::zero(),
+            solver_mass_multiplier: 1.0,
             linvel: Vector::zeros(),
             angvel: na::zero(),
+            solver_linvel_change: Vector::zeros(),
+            solver_angvel_change: na::zero(),
             force: Vector::zeros(),
             torque: na::zero(),
             gravity_scale: 1.0,
             linear_damping: 0.0,
             angular_damping: 0.0,
+            max_linvel: Real::MAX,
+            max_angvel: Real::MAX,
             colliders: Vec::new(),
             activation: ActivationStatus::new_active(),
             joint_graph_index: InteractionGraph::<(), ()>::invalid_graph_index(),
@@ -186,7 +217,75 @@ impl RigidBody {
             self.wake_up(true);
         }
 
-        self.mass_properties = props;
+        self.colliders_mass_properties = props;
+        self.update_local_mass_properties();
+    }
+
+    /// Sets the mass properties combined with the mass properties computed from the colliders
+    /// attached to this rigid-body.
+    ///
+    /// If `overwrite` is `true`, these mass properties completely replace the mass properties
+    /// computed from the colliders, leaving the collision shapes untouched. Otherwise, they are
+    /// added to the mass properties computed from the colliders. Calling this method again
+    /// replaces the additional mass properties set previously.
+    pub fn set_additional_mass_properties(&mut self, props: MassProperties, overwrite: bool) {
+        if self.is_dynamic() {
+            self.wake_up(true);
+        }
+
+        self.additional_mass_properties = props;
+        self.additional_mass_properties_overwrite = overwrite;
+        self.update_local_mass_properties();
+    }
+
+    /// The mass properties added to, or replacing, the mass properties computed
+    /// from the colliders attached to this rigid-body.
+    pub fn additional_mass_properties(&self) -> &MassProperties {
+        &self.additional_mass_properties
+    }
+
+    pub(crate) fn update_local_mass_properties(&mut self) {
+        self.mass_properties = if self.additional_mass_properties_overwrite {
+            self.additional_mass_properties
+        } else {
+            self.colliders_mass_properties + self.additional_mass_properties
+        };
+
+        if let Some(local_com) = self.local_com_override {
+            self.mass_properties.local_com = local_com;
+        }
+
+        self.update_world_mass_properties();
+    }
+
+    /// Sets the center-of-mass of this rigid-body, expressed in its local-space.
+    ///
+    /// This replaces the center-of-mass computed from the attached colliders and the additional
+    /// mass properties, and is preserved when colliders are added or removed. The mass and the
+    /// principal angular inertia are left unchanged, i.e., the angular inertia is now expressed
+    /// relative to this new center-of-mass. The world-space center-of-mass used by the constraints
+    /// solver for the contact lever arms is updated accordingly.
+    ///
+    /// If `wake_up` is `true` then the rigid-body will be woken up if it was
+    /// put to sleep because it did not move for a while.
+    pub fn set_local_com(&mut self, local_com: Point<Real>, wake_up: bool) {
+        if self.is_dynamic() && wake_up {
+            self.wake_up(true);
+        }
+
+        self.local_com_override = Some(local_com);
+        self.update_local_mass_properties();
+    }
+
+    /// Removes the center-of-mass set with `set_local_com`, reverting to the center-of-mass
+    /// computed from the colliders and the additional mass properties.
+    pub fn reset_local_com(&mut self, wake_up: bool) {
+        if self.is_dynamic() && wake_up {
+            self.wake_up(true);
+        }
+
+        self.local_com_override = None;
+        self.update_local_mass_properties();
     }
 
     /// The handles of colliders attached to this rigid body.
@@ -222,6 +321,21 @@ impl RigidBody {
         utils::inv(self.mass_properties.inv_mass)
     }
 
+    /// The inverse mass used by the constraints solver, taking into account translation locking.
+    ///
+    /// This is updated at the beginning of each timestep.
+    pub fn effective_inv_mass(&self) -> Real {
+        self.effective_inv_mass
+    }
+
+    /// The world-space inverse angular inertia tensor used by the constraints solver, taking
+    /// into account rotation locking.
+    ///
+    /// This is updated at the beginning of each timestep.
+    pub fn effective_angular_inertia_inverse(&self) -> AngularInertia<Real> {
+        self.effective_world_inv_inertia_sqrt.squared()
+    }
+
     /// The predicted position of this rigid-body.
     ///
     /// If this rigid-body is kinematic this value is set by the `set_next_kinematic_position`
@@ -246,8 +360,46 @@ impl RigidBody {
         self.gravity_scale = scale;
     }
 
+    /// The multiplier of the mass and angular inertia of this rigid-body used by the
+    /// constraints solver during the next timestep.
+    pub fn solver_mass_multiplier(&self) -> Real {
+        self.solver_mass_multiplier
+    }
+
+    /// Makes this rigid-body behave as if its mass and angular inertia were multiplied by
+    /// `multiplier` during the constraints resolution of the next timestep it is awake.
+    ///
+    /// This doesn't affect the mass properties of this rigid-body, nor the accelerations due to
+    /// the forces applied to it, e.g., gravity. The multiplier is reset to `1.0` at the end of
+    /// the timestep. This can be used to give a "heavy hit" feel to a body for a single frame.
+    pub fn set_solver_mass_multiplier(&mut self, multiplier: Real, wake_up: bool) {
+        assert!(
+            multiplier > 0.0,
+            "The solver mass multiplier must be strictly positive."
+        );
+
+        if wake_up && self.activation.sleeping {
+            self.changes.insert(RigidBodyChanges::SLEEP);
+            self.activation.sleeping = false;
+        }
+
+        self.solver_mass_multiplier = multiplier;
+    }
+
     /// Adds a collider to this rigid-body.
     pub(crate) fn add_collider(&mut self, handle: ColliderHandle, coll: &Collider) {
+        self.add_collider_without_mass_update(handle, coll);
+        self.update_local_mass_properties();
+    }
+
+    /// Adds a collider to this rigid-body without updating its mass properties.
+    ///
+    /// The caller must call `self.update_local_mass_properties()` afterwards.
+    pub(crate) fn add_collider_without_mass_update(
+        &mut self,
+        handle: ColliderHandle,
+        coll: &Collider,
+    ) {
         self.changes.set(
             RigidBodyChanges::MODIFIED | RigidBodyChanges::COLLIDERS,
             true,
@@ -257,8 +409,20 @@ impl RigidBody {
             .mass_properties()
             .transform_by(coll.position_wrt_parent());
         self.colliders.push(handle);
-        self.mass_properties += mass_properties;
-        self.update_world_mass_properties();
+        self.colliders_mass_properties += mass_properties;
+    }
+
+    /// Replaces the contribution of one of the colliders of this rigid-body to its mass
+    /// properties, e.g., after the shape of this collider changed.
+    pub(crate) fn replace_collider_mass_properties(
+        &mut self,
+        old: MassProperties,
+        new: MassProperties,
+    ) {
+        self.changes.set(RigidBodyChanges::COLLIDERS, true);
+        self.colliders_mass_properties -= old;
+        self.colliders_mass_properties += new;
+        self.update_local_mass_properties();
     }
 
     pub(crate) fn update_colliders_positions(&mut self, colliders: &mut ColliderSet) {
@@ -277,8 +441,8 @@ impl RigidBody {
             let mass_properties = coll
                 .mass_properties()
                 .transform_by(coll.position_wrt_parent());
-            self.mass_properties -= mass_properties;
-            self.update_world_mass_properties();
+            self.colliders_mass_properties -= mass_properties;
+            self.update_local_mass_properties();
         }
     }
 
@@ -292,6 +456,9 @@ impl RigidBody {
         self.activation.sleeping = true;
         self.linvel = na::zero();
         self.angvel = na::zero();
+        // The previous position is only saved for the active bodies, so snapshot it now to
+        // prevent a sleeping body from being interpolated along its last motion.
+        self.previous_position = self.position;
     }
 
     /// Wakes up this rigid body if it is sleeping.
@@ -325,25 +492,84 @@ impl RigidBody {
         self.activation.sleeping
     }
 
+    /// Can this rigid body be put to sleep automatically when it stops moving?
+    pub fn can_sleep(&self) -> bool {
+        self.activation.threshold >= 0.0
+    }
+
+    /// Sets whether this rigid body can be put to sleep automatically when it stops moving.
+    ///
+    /// A rigid body that can't sleep is never put to sleep by the island sleep check, and keeps
+    /// awake all the rigid bodies of its island, i.e., in contact or attached to it by a joint.
+    /// If `can_sleep` is `false`, this rigid body is also woken up.
+    pub fn set_can_sleep(&mut self, can_sleep: bool) {
+        // A negative threshold prevents the body from sleeping since its energy is never negative.
+        if can_sleep {
+            self.activation.threshold = self.activation.threshold.abs();
+        } else {
+            self.activation.threshold = -self.activation.threshold.abs().max(Real::EPSILON);
+            self.wake_up(true);
+        }
+    }
+
     /// Is the velocity of this body not zero?
     pub fn is_moving(&self) -> bool {
         !self.linvel.is_zero() || !self.angvel.is_zero()
     }
 
     fn integrate_velocity(&self, dt: Real) -> Isometry<Real> {
+        self.displacement(self.linvel, self.angvel, dt)
+    }
+
+    // The rigid motion of this body moving at the given velocities for `dt` seconds.
+    fn displacement(
+        &self,
+        linvel: Vector<Real>,
+        angvel: AngVector<Real>,
+        dt: Real,
+    ) -> Isometry<Real> {
         let com = self.position * self.mass_properties.local_com;
         let shift = Translation::from(com.coords);
-        shift * Isometry::new(self.linvel * dt, self.angvel * dt) * shift.inverse()
+        shift * Isometry::new(linvel * dt, angvel * dt) * shift.inverse()
     }
 
     pub(crate) fn integrate(&mut self, dt: Real) {
         // TODO: do we want to apply damping before or after the velocity integration?
         self.linvel *= 1.0 / (1.0 + dt * self.linear_damping);
         self.angvel *= 1.0 / (1.0 + dt * self.angular_damping);
+        self.clamp_velocities();
 
         self.position = self.integrate_velocity(dt) * self.position;
     }
 
+    /// Moves this rigid-body by the pseudo-velocities computed by the split-impulse
+    /// penetration correction, without modifying its velocities.
+    pub(crate) fn integrate_pseudo_velocity(&mut self, pseudo_vel: DeltaVel<Real>, dt: Real) {
+        let angvel = self
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(pseudo_vel.angular);
+        self.position = self.displacement(pseudo_vel.linear, angvel, dt) * self.position;
+    }
+
+    fn clamp_velocities(&mut self) {
+        let linvel_norm = utils::det_norm(&self.linvel);
+        if linvel_norm > self.max_linvel {
+            self.linvel *= self.max_linvel / linvel_norm;
+        }
+
+        #[cfg(feature = "dim2")]
+        {
+            self.angvel = self.angvel.max(-self.max_angvel).min(self.max_angvel);
+        }
+        #[cfg(feature = "dim3")]
+        {
+            let angvel_norm = utils::det_norm(&self.angvel);
+            if angvel_norm > self.max_angvel {
+                self.angvel *= self.max_angvel / angvel_norm;
+            }
+        }
+    }
+
     /// The linear velocity of this rigid-body.
     pub fn linvel(&self) -> &Vector<Real> {
         &self.linvel
@@ -404,6 +630,43 @@ impl RigidBody {
         &self.position
     }
 
+    /// The velocity change applied to this rigid-body by the constraints solver during the last
+    /// timestep it was simulated.
+    ///
+    /// This only includes the effect of the contacts, joints, and custom constraints (and of
+    /// `PhysicsHooks::modify_island`), not the effect of the external forces like the gravity,
+    /// nor of the damping. Unlike the delta-velocities used internally by the solver, the
+    /// `angular` part of the result is the actual change of angular velocity. This is always
+    /// zero with the `parallel` feature.
+    pub fn last_solver_delta_vel(&self) -> DeltaVel<Real> {
+        DeltaVel {
+            linear: self.solver_linvel_change,
+            angular: self.solver_angvel_change,
+        }
+    }
+
+    /// The world-space position of this rigid-body before the last timestep.
+    ///
+    /// This is equal to `Self::position` if the rigid-body didn't move during the last timestep,
+    /// if it was teleported with `Self::set_position` since then, or if it is sleeping.
+    pub fn previous_position(&self) -> &Isometry<Real> {
+        &self.previous_position
+    }
+
+    /// Interpolates between the positions of this rigid-body before and after the last timestep.
+    ///
+    /// This is useful for rendering at a higher frequency than the simulation: with `alpha`
+    /// being the fraction of the timestep elapsed since the last call to `PhysicsPipeline::step`,
+    /// the rendered motion is smooth. The translation is interpolated linearly and the rotation
+    /// with a spherical linear interpolation. An `alpha` of `0.0` returns
+    /// `Self::previous_position`, and an `alpha` of `1.0` returns `Self::position`.
+    ///
+    /// Teleporting the rigid-body with `Self::set_position` disables the interpolation until
+    /// the next timestep, so the rigid-body doesn't appear to travel to its new position.
+    pub fn interpolated_position(&self, alpha: Real) -> Isometry<Real> {
+        self.previous_position.lerp_slerp(&self.position, alpha)
+    }
+
     /// Sets the position and `next_kinematic_position` of this rigid body.
     ///
     /// This will teleport the rigid-body to the specified position/orientation,
@@ -416,6 +679,7 @@ impl RigidBody {
     pub fn set_position(&mut self, pos: Isometry<Real>, wake_up: bool) {
         self.changes.insert(RigidBodyChanges::POSITION);
         self.set_position_internal(pos);
+        self.previous_position = pos;
 
         // TODO: Do we really need to check that the body isn't dynamic?
         if wake_up && self.is_dynamic() {
@@ -423,6 +687,23 @@ impl RigidBody {
         }
     }
 
+    /// Teleports this rigid body to the given position, discarding the state of its contacts.
+    ///
+    /// Unlike `Self::set_position`, which keeps the contacts of this body (and the impulses used
+    /// to warmstart the constraints solver) to preserve the continuity of the simulation, this
+    /// resets the contacts involving this body so the next timestep starts clean. If
+    /// `reset_velocities` is `true`, the linear and angular velocities of this body are also set
+    /// to zero. The rigid-body is woken up if it is dynamic.
+    pub fn teleport(&mut self, pos: Isometry<Real>, reset_velocities: bool) {
+        self.set_position(pos, true);
+        self.changes.insert(RigidBodyChanges::TELEPORT);
+
+        if reset_velocities {
+            self.linvel = na::zero();
+            self.angvel = na::zero();
+        }
+    }
+
     pub(crate) fn set_position_internal(&mut self, pos: Isometry<Real>) {
         self.position = pos;
 
@@ -439,6 +720,20 @@ impl RigidBody {
         }
     }
 
+    /// The position this kinematic rigid-body will reach at the end of the next timestep.
+    ///
+    /// This is the position set by `set_next_kinematic_position`, or the current position of the
+    /// rigid-body if it wasn't called since the last timestep. This is the target the velocity of
+    /// the kinematic body is derived from at the beginning of the next timestep. Returns `None`
+    /// if this rigid-body isn't kinematic.
+    pub fn next_position(&self) -> Option<&Isometry<Real>> {
+        if self.is_kinematic() {
+            Some(&self.predicted_position)
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn compute_velocity_from_predicted_position(&mut self, inv_dt: Real) {
         let dpos = self.predicted_position * self.position.inverse();
         #[cfg(feature = "dim2")]
@@ -449,13 +744,55 @@ impl RigidBody {
         {
             self.angvel = dpos.rotation.scaled_axis() * inv_dt;
         }
-        self.linvel = dpos.translation.vector * inv_dt;
+        // The linear velocity must be the one of the center of mass, because that's the
+        // point the angular velocity is applied around (e.g. at the contact points).
+        let local_com = &self.mass_properties.local_com;
+        self.linvel = (self.predicted_position * local_com - self.position * local_com) * inv_dt;
     }
 
     pub(crate) fn update_predicted_position(&mut self, dt: Real) {
         self.predicted_position = self.integrate_velocity(dt) * self.position;
     }
 
+    /// Applies the solver mass multiplier to the effective mass properties.
+    ///
+    /// The forces accumulated so far are scaled accordingly, so the accelerations they cause
+    /// are not affected.
+    pub(crate) fn apply_solver_mass_multiplier(&mut self) {
+        let multiplier = self.solver_mass_multiplier;
+        if multiplier == 1.0 {
+            return;
+        }
+
+        self.effective_inv_mass /= multiplier;
+        self.force *= multiplier;
+        self.torque *= multiplier;
+
+        let inertia_scale = 1.0 / multiplier.det_sqrt();
+        #[cfg(feature = "dim2")]
+        {
+            self.effective_world_inv_inertia_sqrt *= inertia_scale;
+        }
+        #[cfg(feature = "dim3")]
+        {
+            let ii = &mut self.effective_world_inv_inertia_sqrt;
+            ii.m11 *= inertia_scale;
+            ii.m12 *= inertia_scale;
+            ii.m13 *= inertia_scale;
+            ii.m22 *= inertia_scale;
+            ii.m23 *= inertia_scale;
+            ii.m33 *= inertia_scale;
+        }
+    }
+
+    /// Resets the solver mass multiplier to `1.0`, and restores the effective mass properties.
+    pub(crate) fn reset_solver_mass_multiplier(&mut self) {
+        if self.solver_mass_multiplier != 1.0 {
+            self.solver_mass_multiplier = 1.0;
+            self.update_world_mass_properties();
+        }
+    }
+
     pub(crate) fn update_world_mass_properties(&mut self) {
         self.world_com = self.mass_properties.world_com(&self.position);
         self.effective_inv_mass = self.mass_properties.inv_mass;
@@ -497,7 +834,47 @@ impl RigidBody {
 }
 
 /// ## Applying forces and torques
+///
+/// Forces and torques are accumulated on the rigid-body until the next simulation step.
+/// During that step, the accumulated values are integrated, together with gravity, into
+/// the rigid-body velocities before the constraints solver runs. The accumulators are then
+/// cleared, so persistent forces (e.g. aerodynamic forces) must be applied again before
+/// each step.
 impl RigidBody {
+    /// The force accumulated on this rigid-body, to be applied at the next simulation step.
+    ///
+    /// This does not include gravity.
+    pub fn force(&self) -> Vector<Real> {
+        self.force
+    }
+
+    /// The torque accumulated on this rigid-body, to be applied at the next simulation step.
+    pub fn torque(&self) -> AngVector<Real> {
+        self.torque
+    }
+
+    /// Resets to zero the force accumulated on this rigid-body.
+    pub fn reset_forces(&mut self, wake_up: bool) {
+        if !self.force.is_zero() {
+            self.force = na::zero();
+
+            if wake_up {
+                self.wake_up(true);
+            }
+        }
+    }
+
+    /// Resets to zero the torque accumulated on this rigid-body.
+    pub fn reset_torques(&mut self, wake_up: bool) {
+        if !self.torque.is_zero() {
+            self.torque = na::zero();
+
+            if wake_up {
+                self.wake_up(true);
+            }
+        }
+    }
+
     /// Applies a force at the center-of-mass of this rigid-body.
     /// The force will be applied in the next simulation step.
     /// This does nothing on non-dynamic bodies.
@@ -628,7 +1005,8 @@ impl RigidBody {
         #[cfg(feature = "dim2")]
         if !self.effective_world_inv_inertia_sqrt.is_zero() {
             let inertia_sqrt = 1.0 / self.effective_world_inv_inertia_sqrt;
-            energy += (inertia_sqrt * self.angvel).powi(2) / 2.0;
+            let scaled_angvel = inertia_sqrt * self.angvel;
+            energy += scaled_angvel * scaled_angvel / 2.0;
         }
 
         #[cfg(feature = "dim3")]
@@ -660,6 +1038,8 @@ pub struct RigidBodyBuilder {
     gravity_scale: Real,
     linear_damping: Real,
     angular_damping: Real,
+    max_linvel: Real,
+    max_angvel: Real,
     body_status: BodyStatus,
     flags: RigidBodyFlags,
     mass_properties: MassProperties,
@@ -679,6 +1059,8 @@ impl RigidBodyBuilder {
             gravity_scale: 1.0,
             linear_damping: 0.0,
             angular_damping: 0.0,
+            max_linvel: Real::MAX,
+            max_angvel: Real::MAX,
             body_status,
             flags: RigidBodyFlags::empty(),
             mass_properties: MassProperties::zero(),
@@ -805,8 +1187,7 @@ impl RigidBodyBuilder {
     /// Sets the angular inertia of this rigid-body.
     #[cfg(feature = "dim2")]
     pub fn principal_angular_inertia(mut self, inertia: Real) -> Self {
-        self.mass_properties.inv_principal_inertia_sqrt =
-            utils::inv(ComplexField::sqrt(inertia.max(0.0)));
+        self.mass_properties.inv_principal_inertia_sqrt = utils::inv(inertia.max(0.0).det_sqrt());
         self
     }
 
@@ -832,7 +1213,7 @@ impl RigidBodyBuilder {
     #[cfg(feature = "dim3")]
     pub fn principal_angular_inertia(mut self, inertia: AngVector<Real>) -> Self {
         self.mass_properties.inv_principal_inertia_sqrt =
-            inertia.map(|e| utils::inv(ComplexField::sqrt(e.max(0.0))));
+            inertia.map(|e| utils::inv(e.max(0.0).det_sqrt()));
         self
     }
 
@@ -861,6 +1242,24 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Sets the maximum norm of the linear velocity of the rigid-body.
+    ///
+    /// The linear velocity is clamped after each velocity solve, before the position
+    /// of the rigid-body is updated.
+    pub fn max_linvel(mut self, max_linvel: Real) -> Self {
+        self.max_linvel = max_linvel;
+        self
+    }
+
+    /// Sets the maximum norm of the angular velocity of the rigid-body.
+    ///
+    /// The angular velocity is clamped after each velocity solve, before the position
+    /// of the rigid-body is updated.
+    pub fn max_angvel(mut self, max_angvel: Real) -> Self {
+        self.max_angvel = max_angvel;
+        self
+    }
+
     /// Sets the initial linear velocity of the rigid-body to be created.
     #[cfg(feature = "dim2")]
     pub fn linvel(mut self, x: Real, y: Real) -> Self {
@@ -898,13 +1297,17 @@ impl RigidBodyBuilder {
         let mut rb = RigidBody::new();
         rb.predicted_position = self.position; // FIXME: compute the correct value?
         rb.set_position_internal(self.position);
+        rb.previous_position = self.position;
         rb.linvel = self.linvel;
         rb.angvel = self.angvel;
         rb.body_status = self.body_status;
         rb.user_data = self.user_data;
         rb.mass_properties = self.mass_properties;
+        rb.colliders_mass_properties = self.mass_properties;
         rb.linear_damping = self.linear_damping;
         rb.angular_damping = self.angular_damping;
+        rb.max_linvel = self.max_linvel;
+        rb.max_angvel = self.max_angvel;
         rb.gravity_scale = self.gravity_scale;
         rb.flags = self.flags;
         rb.dominance_group = self.dominance_group;
@@ -914,7 +1317,7 @@ impl RigidBodyBuilder {
         }
 
         if !self.can_sleep {
-            rb.activation.threshold = -1.0;
+            rb.activation.threshold = -ActivationStatus::default_threshold();
         }
 
         rb
@@ -966,3 +1369,559 @@ impl ActivationStatus {
         self.energy != 0.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBody, RigidBodyBuilder};
+    use crate::geometry::{ColliderBuilder, NarrowPhase};
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn additional_mass_properties_override_inertia() {
+        use crate::dynamics::{MassProperties, RigidBodySet};
+        use crate::geometry::ColliderSet;
+        use crate::math::Point;
+
+        let mut colliders = ColliderSet::new();
+        let mut bodies = RigidBodySet::new();
+
+        let rb = RigidBodyBuilder::new_dynamic().build();
+        let h1 = bodies.insert(rb.clone());
+        let h2 = bodies.insert(rb);
+        let co = ColliderBuilder::cuboid(1.0, 1.0, 1.0).build();
+        colliders.insert(co.clone(), h1, &mut bodies);
+        let c2 = colliders.insert(co, h2, &mut bodies);
+
+        // Same mass as the collider, but a much larger angular inertia.
+        let mass = bodies[h2].mass();
+        let props = MassProperties::new(Point::origin(), mass, Vector::repeat(100.0));
+        bodies[h2].set_additional_mass_properties(props, true);
+
+        for handle in &[h1, h2] {
+            bodies[*handle].apply_impulse_at_point(Vector::x(), Point::new(0.0, 1.0, 0.0), true);
+        }
+
+        let (rb1, rb2) = (&bodies[h1], &bodies[h2]);
+        assert!((rb1.linvel() - rb2.linvel()).norm() < 1.0e-5);
+        assert!(rb2.angvel().norm() < rb1.angvel().norm() * 0.1);
+        assert!(colliders[c2].shape().as_cuboid().is_some());
+    }
+
+    #[test]
+    fn max_linvel_clamping() {
+        let mut world = TestWorld::new();
+        let max_linvel = 10.0;
+
+        let rb = RigidBodyBuilder::new_dynamic()
+            .max_linvel(max_linvel)
+            .build();
+        let handle = world.bodies.insert(rb);
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            handle,
+            &mut world.bodies,
+        );
+        world.bodies[handle].apply_impulse(Vector::x() * 1.0e6, true);
+
+        for _ in 0..2 {
+            let x0 = world.bodies[handle].position().translation.x;
+            world.step();
+
+            let rb = &world.bodies[handle];
+            let dx = rb.position().translation.x - x0;
+            assert!((rb.linvel().norm() - max_linvel).abs() < 1.0e-3);
+            assert!((dx - max_linvel * world.params.dt).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn spinning_kinematic_body_drags_resting_bodies() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // A turntable away from the origin, spinning around its own center.
+        let center = Vector::x() * 10.0;
+        let angvel = 0.5;
+        let turntable = world.bodies.insert(
+            RigidBodyBuilder::new_kinematic()
+                .translation(center.x, center.y, center.z)
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::cuboid(4.0, 0.5, 4.0).build(),
+            turntable,
+            &mut world.bodies,
+        );
+
+        let rider = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .translation(center.x + 2.0, 0.75, center.z)
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::cuboid(0.25, 0.25, 0.25).build(),
+            rider,
+            &mut world.bodies,
+        );
+
+        for i in 1..=120 {
+            let angle = angvel * world.params.dt * i as Real;
+            world
+                .bodies
+                .get_mut(turntable)
+                .unwrap()
+                .set_next_kinematic_position(Isometry::new(center, Vector::y() * angle));
+
+            world.step();
+        }
+
+        // After two seconds, the turntable rotated by one radian, and so did its rider.
+        let rel_pos = world.bodies[rider].position().translation.vector - center;
+        let orbit_angle = (-rel_pos.z).atan2(rel_pos.x);
+        let orbit_radius = (rel_pos.x * rel_pos.x + rel_pos.z * rel_pos.z).sqrt();
+        assert!(orbit_angle > 0.8 && orbit_angle < 1.1);
+        assert!((orbit_radius - 2.0).abs() < 0.3);
+    }
+
+    #[test]
+    fn constant_torque_angular_acceleration() {
+        use crate::math::AngVector;
+
+        let mut world = TestWorld::new();
+
+        let rad = 0.5;
+        let handle = world
+            .bodies
+            .insert(RigidBodyBuilder::new_dynamic().can_sleep(false).build());
+        world.colliders.insert(
+            ColliderBuilder::ball(rad).build(),
+            handle,
+            &mut world.bodies,
+        );
+
+        // The angular inertia of a ball (or a disk in 2D).
+        let mass = world.bodies[handle].mass();
+        #[cfg(feature = "dim2")]
+        let (torque, inertia) = (2.0, mass * rad * rad / 2.0);
+        #[cfg(feature = "dim3")]
+        let (torque, inertia) = (Vector::new(1.0, -2.0, 3.0), mass * rad * rad * 2.0 / 5.0);
+
+        let num_steps = 10;
+
+        for _ in 0..num_steps {
+            let rb = &mut world.bodies[handle];
+            rb.apply_torque(torque, true);
+            assert_eq!(rb.torque(), torque);
+
+            world.step();
+
+            // The accumulator is cleared by the step.
+            assert_eq!(world.bodies[handle].torque(), na::zero::<AngVector<Real>>());
+        }
+
+        let expected = torque / inertia * (world.params.dt * num_steps as Real);
+        #[cfg(feature = "dim2")]
+        assert!((world.bodies[handle].angvel() - expected).abs() < 1.0e-3);
+        #[cfg(feature = "dim3")]
+        assert!((world.bodies[handle].angvel() - expected).norm() < 1.0e-3);
+
+        // Resetting the accumulators cancels the pending forces and torques.
+        let rb = &mut world.bodies[handle];
+        rb.apply_force(Vector::x(), true);
+        rb.apply_torque(torque, true);
+        rb.reset_forces(true);
+        rb.reset_torques(true);
+        assert_eq!(rb.force(), Vector::zeros());
+        assert_eq!(rb.torque(), na::zero::<AngVector<Real>>());
+    }
+
+    #[test]
+    fn offset_local_com_tips_box_over() {
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Two identical planks resting on the ground. The second one has its
+        // center-of-mass moved past its right end, like an overhanging ballast.
+        #[cfg(feature = "dim2")]
+        let plank_shape = ColliderBuilder::cuboid(1.0, 0.1);
+        #[cfg(feature = "dim3")]
+        let plank_shape = ColliderBuilder::cuboid(1.0, 0.1, 1.0);
+        let mut planks = Vec::new();
+        for x in [-5.0, 5.0].iter() {
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * 0.6,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(plank_shape.build(), handle, &mut world.bodies);
+            planks.push(handle);
+        }
+
+        let ballast = Point::from(Vector::x() * 1.5);
+        world.bodies[planks[1]].set_local_com(ballast, true);
+        assert_eq!(world.bodies[planks[1]].mass_properties().local_com, ballast);
+        let expected_world_com = world.bodies[planks[1]].position() * ballast;
+        assert!((world.bodies[planks[1]].world_com - expected_world_com).norm() < 1.0e-5);
+
+        for _ in 0..60 {
+            world.step();
+        }
+
+        // The height difference between the left and right ends of each plank.
+        let tilt = |rb: &RigidBody| {
+            let left = rb.position() * Point::from(-Vector::x());
+            let right = rb.position() * Point::from(Vector::x());
+            left.y - right.y
+        };
+
+        assert!(tilt(&world.bodies[planks[0]]).abs() < 1.0e-3);
+        assert!(tilt(&world.bodies[planks[1]]) > 0.5);
+    }
+
+    #[test]
+    fn effective_inverse_inertia_matches_solver() {
+        use crate::utils::WAngularInertia;
+
+        let mut world = TestWorld::new();
+
+        #[cfg(feature = "dim2")]
+        let (rotation, shape, torque) = (0.7, ColliderBuilder::cuboid(2.0, 0.5), 3.0);
+        #[cfg(feature = "dim3")]
+        let (rotation, shape, torque) = (
+            Vector::new(0.3, 0.7, -0.2),
+            ColliderBuilder::cuboid(2.0, 0.5, 1.0),
+            Vector::new(1.0, -2.0, 3.0),
+        );
+        let handle = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::zeros(), rotation))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(shape.build(), handle, &mut world.bodies);
+
+        // Initialize the world-space mass properties.
+        world.step();
+        let rb = &world.bodies[handle];
+        let inv_mass = rb.effective_inv_mass();
+        let inv_inertia = rb.effective_angular_inertia_inverse();
+        assert!((inv_mass - 1.0 / rb.mass()).abs() < 1.0e-5);
+
+        let force = Vector::x() * 2.0;
+        world.bodies[handle].apply_force(force, true);
+        world.bodies[handle].apply_torque(torque, true);
+        world.step();
+
+        let rb = &world.bodies[handle];
+        let expected_linvel = force * inv_mass * world.params.dt;
+        let expected_angvel = inv_inertia.transform_vector(torque) * world.params.dt;
+        assert!((rb.linvel() - expected_linvel).norm() < 1.0e-5);
+        #[cfg(feature = "dim2")]
+        assert!((rb.angvel() - expected_angvel).abs() < 1.0e-5);
+        #[cfg(feature = "dim3")]
+        assert!((rb.angvel() - expected_angvel).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn interpolated_position() {
+        let mut world = TestWorld::new();
+
+        #[cfg(feature = "dim2")]
+        let angvel = 6.0;
+        #[cfg(feature = "dim3")]
+        let angvel = Vector::z() * 6.0;
+        let mut rb = RigidBodyBuilder::new_dynamic().angvel(angvel).build();
+        rb.set_linvel(Vector::x() * 6.0, true);
+        let handle = world.bodies.insert(rb);
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            handle,
+            &mut world.bodies,
+        );
+
+        let initial_pos = *world.bodies[handle].position();
+        world.step();
+
+        let rb = &world.bodies[handle];
+        assert_eq!(rb.previous_position(), &initial_pos);
+        assert_eq!(rb.interpolated_position(0.0), initial_pos);
+        assert_eq!(rb.interpolated_position(1.0), *rb.position());
+
+        // Half-way, the body moved by half its displacement and rotated by half its rotation.
+        let half = rb.interpolated_position(0.5);
+        let expected_translation = rb.position().translation.vector / 2.0;
+        assert!((half.translation.vector - expected_translation).norm() < 1.0e-5);
+        assert!((half.rotation.angle() - rb.position().rotation.angle() / 2.0).abs() < 1.0e-5);
+        assert!(rb.position().rotation.angle() > 0.05);
+
+        // Teleporting the body disables the interpolation.
+        let teleport = Isometry::new(Vector::y() * 10.0, na::zero());
+        world
+            .bodies
+            .get_mut(handle)
+            .unwrap()
+            .set_position(teleport, true);
+        assert_eq!(world.bodies[handle].interpolated_position(0.3), teleport);
+
+        // A body put to sleep stays still, and so does its interpolated position.
+        world.step();
+        let rb = world.bodies.get_mut(handle).unwrap();
+        assert_ne!(rb.interpolated_position(0.3), *rb.position());
+        rb.sleep();
+        assert_eq!(rb.interpolated_position(0.3), *rb.position());
+        world.step();
+        let rb = &world.bodies[handle];
+        assert!(rb.is_sleeping());
+        assert_eq!(rb.interpolated_position(0.3), *rb.position());
+    }
+
+    #[test]
+    fn last_solver_delta_vel() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .can_sleep(false)
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..60 {
+            world.step();
+        }
+
+        // The contact cancels the velocity gained from the gravity during the timestep.
+        let dvel = world.bodies[ball].last_solver_delta_vel();
+        let gravity_dvel = Vector::y() * -9.81 * world.params.dt;
+        assert!((dvel.linear + gravity_dvel).norm() < 1.0e-3);
+        assert!(world.bodies[ball].linvel().norm() < 1.0e-3);
+    }
+
+    #[test]
+    fn teleport_resets_contacts() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .build(),
+        );
+        let ball_collider =
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..50 {
+            world.step();
+        }
+
+        let total_impulse = |nf: &NarrowPhase| -> Real {
+            nf.contacts_with(ball_collider)
+                .unwrap()
+                .flat_map(|(_, _, pair)| pair.manifolds.iter())
+                .flat_map(|manifold| manifold.points.iter())
+                .map(|contact| contact.data.impulse)
+                .sum()
+        };
+        assert!(total_impulse(&world.narrow_phase) > 0.0);
+
+        // Moving the ball along the ground with `set_position` keeps the contact impulses.
+        let new_pos = Isometry::new(Vector::x() * 2.0 + Vector::y() * 1.0, na::zero());
+        world.bodies[ball].set_position(new_pos, true);
+        world.bodies.maintain(&mut world.colliders);
+        world
+            .narrow_phase
+            .maintain(&mut world.colliders, &mut world.bodies);
+        assert!(total_impulse(&world.narrow_phase) > 0.0);
+
+        // Teleporting the ball discards them.
+        let new_pos = Isometry::new(Vector::x() * 4.0 + Vector::y() * 1.0, na::zero());
+        world.bodies[ball].teleport(new_pos, true);
+        world.bodies.maintain(&mut world.colliders);
+        world
+            .narrow_phase
+            .maintain(&mut world.colliders, &mut world.bodies);
+        assert_eq!(total_impulse(&world.narrow_phase), 0.0);
+        assert_eq!(*world.bodies[ball].linvel(), Vector::zeros());
+
+        // The simulation resumes smoothly from the new position.
+        world.step();
+        let rb = &world.bodies[ball];
+        assert!((rb.position().translation.vector - new_pos.translation.vector).norm() < 1.0e-2);
+    }
+
+    #[test]
+    fn set_can_sleep_keeps_island_awake() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+
+        // A turntable at rest, and a body attached to it.
+        let turntable = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            turntable,
+            &mut world.bodies,
+        );
+        let body = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::x() * 2.0, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), body, &mut world.bodies);
+        world.joints.insert(
+            &mut world.bodies,
+            turntable,
+            body,
+            BallJoint::new(
+                Point::from(Vector::x() * 1.0),
+                Point::from(-Vector::x() * 1.0),
+            ),
+        );
+
+        // Without any motion, both bodies fall asleep.
+        for _ in 0..1000 {
+            world.step();
+        }
+        assert!(world.bodies[turntable].is_sleeping());
+        assert!(world.bodies[body].is_sleeping());
+
+        world.bodies[turntable].set_can_sleep(false);
+        assert!(!world.bodies[turntable].can_sleep());
+        assert!(!world.bodies[turntable].is_sleeping());
+
+        for _ in 0..5000 {
+            world.step();
+            assert!(!world.bodies[turntable].is_sleeping());
+            assert!(!world.bodies[body].is_sleeping());
+        }
+
+        world.bodies[turntable].set_can_sleep(true);
+        assert!(world.bodies[turntable].can_sleep());
+
+        for _ in 0..1000 {
+            world.step();
+        }
+        assert!(world.bodies[turntable].is_sleeping());
+        assert!(world.bodies[body].is_sleeping());
+    }
+
+    #[test]
+    fn kinematic_next_position() {
+        let mut world = TestWorld::new();
+
+        let platform = world
+            .bodies
+            .insert(RigidBodyBuilder::new_kinematic().build());
+        let ball = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        assert!(world.bodies[ball].next_position().is_none());
+
+        // Without any target, the kinematic body stays where it is.
+        assert_eq!(
+            world.bodies[platform].next_position(),
+            Some(&Isometry::identity())
+        );
+
+        let target = Isometry::new(Vector::y() * 0.1, na::zero());
+        world.bodies[platform].set_next_kinematic_position(target);
+        assert_eq!(world.bodies[platform].next_position(), Some(&target));
+
+        world.step();
+
+        let platform = &world.bodies[platform];
+        assert!(
+            (platform.position().translation.vector - target.translation.vector).norm() < 1.0e-5
+        );
+        assert!((platform.linvel() - Vector::y() * 0.1 / world.params.dt).norm() < 1.0e-3);
+    }
+
+    #[test]
+    fn one_frame_solver_mass_multiplier() {
+        let hit = |multiplier: Real| {
+            let mut world = TestWorld::new();
+
+            // Two touching balls of the same mass, the first one moving toward the second one.
+            let projectile = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                projectile,
+                &mut world.bodies,
+            );
+            world.bodies[projectile].set_linvel(Vector::x(), true);
+            let target = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::x(), na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                target,
+                &mut world.bodies,
+            );
+            world.bodies[target].set_solver_mass_multiplier(multiplier, true);
+
+            world.step();
+
+            // The multiplier only lasted for this timestep.
+            let target = &world.bodies[target];
+            assert_eq!(target.solver_mass_multiplier(), 1.0);
+            assert!((target.effective_inv_mass() * target.mass() - 1.0).abs() < 1.0e-5);
+
+            target.linvel().x
+        };
+
+        let normal_speed = hit(1.0);
+        let heavy_speed = hit(10.0);
+
+        // Both balls move at the same speed after an inelastic collision, unless the target
+        // behaves as if it were ten times heavier.
+        assert!((normal_speed - 0.5).abs() < 0.05);
+        assert!((heavy_speed - 1.0 / 11.0).abs() < 0.05);
+    }
+}
@@ -2,10 +2,17 @@
 use rayon::prelude::*;
 
 use crate::data::arena::Arena;
-use crate::dynamics::{Joint, JointSet, RigidBody, RigidBodyChanges};
+use crate::data::Coarena;
+use crate::dynamics::{
+    Joint, JointSet, MassProperties, RigidBody, RigidBodyBuilder, RigidBodyChanges,
+};
 use crate::geometry::{ColliderSet, InteractionGraph, NarrowPhase};
+use crate::math::{AngVector, Isometry, Real, Vector};
+use crate::utils::{WAngularInertia, WCross};
+use num::Zero;
 use parry::partitioning::IndexedData;
 use std::ops::{Index, IndexMut};
+use std::sync::Arc;
 
 /// The unique handle of a rigid body added to a `RigidBodySet`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -60,6 +67,37 @@ impl BodyPair {
     }
 }
 
+#[derive(Clone, Debug)]
+/// The positions of the rigid-bodies of a `RigidBodySet` at the end of a timestep.
+///
+/// Only the positions of the rigid-bodies are snapshotted. Their other properties
+/// (velocities, forces, mass properties, etc.) are not part of the snapshot.
+pub struct RigidBodyPositionSnapshot {
+    positions: Coarena<Isometry<Real>>,
+}
+
+impl RigidBodyPositionSnapshot {
+    fn new() -> Self {
+        Self {
+            positions: Coarena::new(),
+        }
+    }
+
+    /// The position of the given rigid-body at the time this snapshot was taken.
+    ///
+    /// Returns `None` if the rigid-body did not exist at that time.
+    pub fn get(&self, handle: RigidBodyHandle) -> Option<&Isometry<Real>> {
+        self.positions.get(handle.0)
+    }
+
+    /// Iterates through the positions of all the rigid-bodies of this snapshot.
+    pub fn iter(&self) -> impl Iterator<Item = (RigidBodyHandle, &Isometry<Real>)> {
+        self.positions
+            .iter()
+            .map(|(h, pos)| (RigidBodyHandle(h), pos))
+    }
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone)]
 /// A set of rigid bodies that can be handled by a physics pipeline.
@@ -74,6 +112,8 @@ pub struct RigidBodySet {
     // Set of inactive bodies which have been modified.
     // This typically include static bodies which have been modified.
     pub(crate) modified_inactive_set: Vec<RigidBodyHandle>,
+    // Set of bodies teleported since the last update of the narrow-phase.
+    pub(crate) teleported_bodies: Vec<RigidBodyHandle>,
     pub(crate) active_islands: Vec<usize>,
     active_set_timestamp: u32,
     pub(crate) modified_bodies: Vec<RigidBodyHandle>,
@@ -82,6 +122,13 @@ pub struct RigidBodySet {
     can_sleep: Vec<RigidBodyHandle>, // Workspace.
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     stack: Vec<RigidBodyHandle>, // Workspace.
+    position_snapshot_enabled: bool,
+    // Double-buffered position snapshots: the front one is shared with the readers, the
+    // back one is reused for the next snapshot if no reader still holds it.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    position_snapshot: Option<Arc<RigidBodyPositionSnapshot>>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    position_snapshot_back: Option<Arc<RigidBodyPositionSnapshot>>,
 }
 
 impl RigidBodySet {
@@ -92,12 +139,16 @@ impl RigidBodySet {
             active_dynamic_set: Vec::new(),
             active_kinematic_set: Vec::new(),
             modified_inactive_set: Vec::new(),
+            teleported_bodies: Vec::new(),
             active_islands: Vec::new(),
             active_set_timestamp: 0,
             modified_bodies: Vec::new(),
             modified_all_bodies: false,
             can_sleep: Vec::new(),
             stack: Vec::new(),
+            position_snapshot_enabled: false,
+            position_snapshot: None,
+            position_snapshot_back: None,
         }
     }
 
@@ -136,6 +187,20 @@ impl RigidBodySet {
         handle
     }
 
+    /// Insert several rigid bodies into this set and retrieve their handles.
+    ///
+    /// This yields the same handles as calling `self.insert` on each rigid-body in order,
+    /// but the internal buffers are grown only once for the whole batch.
+    pub fn insert_batch(&mut self, bodies: Vec<RigidBody>) -> Vec<RigidBodyHandle> {
+        // Only reserve if there are no free slots, to preserve the handle allocation order.
+        if self.bodies.len() == self.bodies.capacity() {
+            self.bodies.reserve(bodies.len());
+        }
+
+        self.modified_bodies.reserve(bodies.len());
+        bodies.into_iter().map(|rb| self.insert(rb)).collect()
+    }
+
     /// Removes a rigid-body, and all its attached colliders and joints, from these sets.
     pub fn remove(
         &mut self,
@@ -174,6 +239,106 @@ impl RigidBodySet {
         Some(rb)
     }
 
+    /// Fuses several distinct dynamic rigid-bodies into a single dynamic rigid-body, e.g., to simulate a
+    /// pile of debris that settled as one body.
+    ///
+    /// The colliders of all the given rigid-bodies are attached to a new rigid-body placed at
+    /// the position of the first one, keeping their current relative positions. They get new
+    /// handles, and their contacts are recomputed during the next timestep. The new rigid-body
+    /// gets the total linear momentum, and the total angular momentum around its center of mass,
+    /// of the welded bodies. Handles given several times are only welded once.
+    ///
+    /// The welded rigid-bodies, and all the joints attached to them, are removed. Returns the
+    /// handle of the new rigid-body, or `None` (without modifying anything) if `handles` is
+    /// empty or contains a rigid-body that doesn't exist or isn't dynamic.
+    pub fn weld(
+        &mut self,
+        handles: &[RigidBodyHandle],
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+    ) -> Option<RigidBodyHandle> {
+        // Keep the order of the handles since the first one gives the position of the result.
+        let mut unique_handles = Vec::with_capacity(handles.len());
+        for handle in handles {
+            if !unique_handles.contains(handle) {
+                unique_handles.push(*handle);
+            }
+        }
+
+        let first = self.get(*unique_handles.first()?)?;
+        let position = first.position;
+
+        if unique_handles
+            .iter()
+            .any(|h| self.get(*h).map(|rb| !rb.is_dynamic()) != Some(false))
+        {
+            return None;
+        }
+
+        // The mass properties of the welded bodies, expressed in the local frame of the result.
+        let mut mass_properties = MassProperties::zero();
+        let mut mass = 0.0;
+        let mut linear_momentum = Vector::zeros();
+
+        for handle in &unique_handles {
+            let rb = &self[*handle];
+            let rb_mass = rb.mass();
+            mass_properties += rb
+                .mass_properties()
+                .transform_by(&position.inv_mul(&rb.position));
+            mass += rb_mass;
+            linear_momentum += rb.linvel * rb_mass;
+        }
+
+        // The total angular momentum of the welded bodies, around their common center of mass.
+        let world_com = mass_properties.world_com(&position);
+        let mut angular_momentum: AngVector<Real> = na::zero();
+
+        for handle in &unique_handles {
+            let rb = &self[*handle];
+            let inv_inertia_sqrt = rb
+                .mass_properties()
+                .world_inv_inertia_sqrt(&rb.position.rotation);
+
+            #[cfg(feature = "dim2")]
+            if !inv_inertia_sqrt.is_zero() {
+                angular_momentum += rb.angvel / (inv_inertia_sqrt * inv_inertia_sqrt);
+            }
+
+            #[cfg(feature = "dim3")]
+            if !inv_inertia_sqrt.is_zero() {
+                let inertia_sqrt = inv_inertia_sqrt.inverse_unchecked();
+                angular_momentum += inertia_sqrt * (inertia_sqrt * rb.angvel);
+            }
+
+            angular_momentum += (rb.world_com - world_com).gcross(rb.linvel * rb.mass());
+        }
+
+        let welded = self.insert(RigidBodyBuilder::new_dynamic().position(position).build());
+
+        if mass != 0.0 {
+            let inv_inertia_sqrt = mass_properties.world_inv_inertia_sqrt(&position.rotation);
+            let rb = &mut self[welded];
+            rb.linvel = linear_momentum / mass;
+            rb.angvel = inv_inertia_sqrt * (inv_inertia_sqrt * angular_momentum);
+        }
+
+        for handle in &unique_handles {
+            let collider_handles = self[*handle].colliders.clone();
+
+            for collider_handle in collider_handles {
+                if let Some(mut collider) = colliders.remove(collider_handle, self, false) {
+                    collider.delta = position.inv_mul(&collider.position);
+                    colliders.insert(collider, welded, self);
+                }
+            }
+
+            self.remove(*handle, colliders, joints);
+        }
+
+        Some(welded)
+    }
+
     pub(crate) fn num_islands(&self) -> usize {
         self.active_islands.len() - 1
     }
@@ -196,6 +361,26 @@ impl RigidBodySet {
         }
     }
 
+    /// Forces all the sleeping dynamic rigid-bodies to wake up.
+    ///
+    /// This is useful after a change affecting all the bodies, e.g., a change of gravity.
+    /// If `strong` is `true` then it is assured that the rigid-bodies will
+    /// remain awake during multiple subsequent timesteps.
+    pub fn wake_up_all(&mut self, strong: bool) {
+        for (handle, rb) in self.bodies.iter_mut() {
+            let handle = RigidBodyHandle(handle);
+
+            if rb.is_dynamic() && rb.is_sleeping() {
+                rb.wake_up(strong);
+
+                if self.active_dynamic_set.get(rb.active_set_id) != Some(&handle) {
+                    rb.active_set_id = self.active_dynamic_set.len();
+                    self.active_dynamic_set.push(handle);
+                }
+            }
+        }
+    }
+
     /// Gets the rigid-body with the given handle without a known generation.
     ///
     /// This is useful when you know you want the rigid-body at position `i` but
@@ -258,6 +443,30 @@ impl RigidBodySet {
         Some(result)
     }
 
+    /// Sets the positions of several rigid-bodies at once.
+    ///
+    /// This is equivalent to calling `RigidBody::set_position` on each rigid-body. The positions
+    /// of the attached colliders, as well as the broad-phase and narrow-phase, are not updated
+    /// immediately: this is done in a single pass by the next call to `PhysicsPipeline::step`
+    /// or `CollisionPipeline::step`. Handles of rigid-bodies that no longer exist are ignored.
+    pub fn set_positions(
+        &mut self,
+        positions: impl IntoIterator<Item = (RigidBodyHandle, Isometry<Real>)>,
+        wake_up: bool,
+    ) {
+        for (handle, pos) in positions {
+            if let Some(rb) = self.bodies.get_mut(handle.0) {
+                Self::mark_as_modified(
+                    handle,
+                    rb,
+                    &mut self.modified_bodies,
+                    self.modified_all_bodies,
+                );
+                rb.set_position(pos, wake_up);
+            }
+        }
+    }
+
     pub(crate) fn get_mut_internal(&mut self, handle: RigidBodyHandle) -> Option<&mut RigidBody> {
         self.bodies.get_mut(handle.0)
     }
@@ -275,6 +484,58 @@ impl RigidBodySet {
         self.bodies.iter().map(|(h, b)| (RigidBodyHandle(h), b))
     }
 
+    /// Enables or disables the snapshot of the positions of all the rigid-bodies taken at
+    /// the end of each timestep.
+    ///
+    /// See `RigidBodySet::position_snapshot` for details.
+    pub fn set_position_snapshot_enabled(&mut self, enabled: bool) {
+        self.position_snapshot_enabled = enabled;
+
+        if !enabled {
+            self.position_snapshot = None;
+            self.position_snapshot_back = None;
+        }
+    }
+
+    /// Is the snapshot of the rigid-body positions enabled?
+    pub fn is_position_snapshot_enabled(&self) -> bool {
+        self.position_snapshot_enabled
+    }
+
+    /// The positions of all the rigid-bodies at the end of the last timestep.
+    ///
+    /// This is `None` if position snapshots are disabled (see
+    /// `RigidBodySet::set_position_snapshot_enabled`), or if no timestep was run since they were
+    /// enabled. The returned snapshot is immutable and can be sent to another thread (e.g., a
+    /// render thread) which can read it without any lock while this set is being modified by the
+    /// next timestep. Only the positions of the rigid-bodies are snapshotted.
+    pub fn position_snapshot(&self) -> Option<Arc<RigidBodyPositionSnapshot>> {
+        self.position_snapshot.clone()
+    }
+
+    pub(crate) fn update_position_snapshot(&mut self) {
+        if !self.position_snapshot_enabled {
+            return;
+        }
+
+        // Reuse the back buffer if nobody is reading it anymore.
+        let mut snapshot = self
+            .position_snapshot_back
+            .take()
+            .filter(|snapshot| Arc::strong_count(snapshot) == 1)
+            .unwrap_or_else(|| Arc::new(RigidBodyPositionSnapshot::new()));
+        let positions = &mut Arc::get_mut(&mut snapshot)
+            .expect("The position snapshot back buffer must not be shared.")
+            .positions;
+        positions.clear();
+
+        for (handle, rb) in self.bodies.iter() {
+            positions.insert(handle, rb.position);
+        }
+
+        self.position_snapshot_back = self.position_snapshot.replace(snapshot);
+    }
+
     /// Iterates mutably through all the rigid-bodies on this set.
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (RigidBodyHandle, &mut RigidBody)> {
         self.modified_bodies.clear();
@@ -434,6 +695,7 @@ impl RigidBodySet {
         handle: RigidBodyHandle,
         rb: &mut RigidBody,
         modified_inactive_set: &mut Vec<RigidBodyHandle>,
+        teleported_bodies: &mut Vec<RigidBodyHandle>,
         active_kinematic_set: &mut Vec<RigidBodyHandle>,
         active_dynamic_set: &mut Vec<RigidBodyHandle>,
     ) {
@@ -453,6 +715,10 @@ impl RigidBodySet {
             }
         }
 
+        if rb.changes.contains(RigidBodyChanges::TELEPORT) {
+            teleported_bodies.push(handle);
+        }
+
         // Push the body to the active set if it is not
         // sleeping and if it is not already inside of the active set.
         if rb.changes.contains(RigidBodyChanges::SLEEP)
@@ -475,6 +741,7 @@ impl RigidBodySet {
                     RigidBodyHandle(handle),
                     rb,
                     &mut self.modified_inactive_set,
+                    &mut self.teleported_bodies,
                     &mut self.active_kinematic_set,
                     &mut self.active_dynamic_set,
                 )
@@ -490,6 +757,7 @@ impl RigidBodySet {
                         handle,
                         rb,
                         &mut self.modified_inactive_set,
+                        &mut self.teleported_bodies,
                         &mut self.active_kinematic_set,
                         &mut self.active_dynamic_set,
                     )
@@ -619,6 +887,10 @@ impl RigidBodySet {
             push_contacting_bodies(rb, colliders, narrow_phase, &mut self.stack);
 
             for inter in joint_graph.interactions_with(rb.joint_graph_index) {
+                if !inter.2.is_enabled() {
+                    continue;
+                }
+
                 let other = crate::utils::select_other((inter.0, inter.1), handle);
                 self.stack.push(other);
             }
@@ -656,3 +928,241 @@ impl IndexMut<RigidBodyHandle> for RigidBodySet {
         &mut self.bodies[index.0]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::{ColliderBuilder, ColliderSet};
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn position_snapshot_is_stable_during_next_step() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        world.bodies.set_position_snapshot_enabled(true);
+        assert!(world.bodies.position_snapshot().is_none());
+
+        let handles: Vec<_> = (0..3)
+            .map(|i| {
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::x() * (i as Real * 2.0), na::zero()))
+                    .build();
+                let handle = world.bodies.insert(rb);
+                world.colliders.insert(
+                    ColliderBuilder::ball(0.5).build(),
+                    handle,
+                    &mut world.bodies,
+                );
+                handle
+            })
+            .collect();
+
+        world.step();
+        let snapshot = world.bodies.position_snapshot().unwrap();
+        assert_eq!(snapshot.iter().count(), handles.len());
+
+        for handle in &handles {
+            assert_eq!(
+                snapshot.get(*handle),
+                Some(world.bodies[*handle].position())
+            );
+        }
+
+        // The snapshot held by the reader is not affected by the next steps.
+        let old_positions: Vec<_> = handles.iter().map(|h| world.bodies[*h].position).collect();
+        world.step();
+        world.step();
+
+        for (handle, old_pos) in handles.iter().zip(old_positions.iter()) {
+            assert_eq!(snapshot.get(*handle), Some(old_pos));
+            assert_ne!(
+                snapshot.get(*handle),
+                Some(world.bodies[*handle].position())
+            );
+        }
+
+        let new_snapshot = world.bodies.position_snapshot().unwrap();
+        for handle in &handles {
+            assert_eq!(
+                new_snapshot.get(*handle),
+                Some(world.bodies[*handle].position())
+            );
+        }
+    }
+
+    #[test]
+    fn batched_position_updates_produce_correct_contacts() {
+        let mut world = TestWorld::new();
+
+        // A row of static platforms, initially far below a row of balls.
+        let num = 10;
+        let mut platforms = Vec::new();
+        let mut balls = Vec::new();
+
+        for i in 0..num {
+            let x = i as Real * 3.0;
+            let platform = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(
+                        Vector::x() * x - Vector::y() * 100.0,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            #[cfg(feature = "dim2")]
+            let platform_shape = ColliderBuilder::cuboid(1.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let platform_shape = ColliderBuilder::cuboid(1.0, 0.5, 1.0);
+            let platform_collider =
+                world
+                    .colliders
+                    .insert(platform_shape.build(), platform, &mut world.bodies);
+            platforms.push((platform, platform_collider));
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * x + Vector::y() * 1.0,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            let ball_collider =
+                world
+                    .colliders
+                    .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+            balls.push(ball_collider);
+        }
+
+        world.step();
+        for ((_, platform_collider), ball_collider) in platforms.iter().zip(balls.iter()) {
+            assert!(world
+                .narrow_phase
+                .contact_pair(*platform_collider, *ball_collider)
+                .is_none());
+        }
+
+        // Move all the platforms right below the balls at once.
+        world.bodies.set_positions(
+            platforms.iter().enumerate().map(|(i, (platform, _))| {
+                let x = i as Real * 3.0;
+                (*platform, Isometry::new(Vector::x() * x, na::zero()))
+            }),
+            true,
+        );
+
+        // Nothing is updated before the next step.
+        assert!(
+            world.colliders[platforms[0].1]
+                .position()
+                .translation
+                .vector
+                .y
+                < -99.0
+        );
+
+        world.step();
+        for ((platform, platform_collider), ball_collider) in platforms.iter().zip(balls.iter()) {
+            assert_eq!(
+                world.colliders[*platform_collider].position(),
+                world.bodies[*platform].position()
+            );
+            let pair = world
+                .narrow_phase
+                .contact_pair(*platform_collider, *ball_collider)
+                .unwrap();
+            assert!(pair.has_any_active_contact);
+        }
+    }
+
+    #[test]
+    fn weld_settled_stack() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        #[cfg(feature = "dim2")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let mut boxes = vec![];
+        let mut box_colliders = vec![];
+        for i in 0..3 {
+            let body = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * (0.5 + i as Real), na::zero()))
+                    .build(),
+            );
+            box_colliders.push(
+                world
+                    .colliders
+                    .insert(box_shape.build(), body, &mut world.bodies),
+            );
+            boxes.push(body);
+        }
+
+        for _ in 0..100 {
+            world.step();
+        }
+
+        let relative_positions = |colliders: &ColliderSet, handles: &[_]| {
+            let pos0 = colliders[handles[0]].position();
+            handles
+                .iter()
+                .map(|h| pos0.inv_mul(colliders[*h].position()))
+                .collect::<Vec<_>>()
+        };
+        let settled = relative_positions(&world.colliders, &box_colliders[..]);
+
+        assert!(world
+            .bodies
+            .weld(&[boxes[0], ground], &mut world.colliders, &mut world.joints)
+            .is_none());
+        // Repeated handles are only welded once.
+        let handles = [boxes[0], boxes[1], boxes[2], boxes[0]];
+        let welded = world
+            .bodies
+            .weld(&handles, &mut world.colliders, &mut world.joints)
+            .unwrap();
+        assert_eq!(world.bodies.len(), 2);
+        assert!(boxes.iter().all(|h| !world.bodies.contains(*h)));
+        assert!(box_colliders.iter().all(|h| !world.colliders.contains(*h)));
+
+        let welded_colliders = world.bodies[welded].colliders().to_vec();
+        assert_eq!(welded_colliders.len(), 3);
+        assert!((world.bodies[welded].mass() - 3.0).abs() < 1.0e-3);
+
+        // Throw the welded body, and check that its colliders move rigidly.
+        world.bodies[welded].set_linvel(Vector::x() * 2.0 + Vector::y() * 5.0, true);
+        for _ in 0..30 {
+            world.step();
+        }
+
+        assert!(world.bodies[welded].position().translation.vector.x > 0.5);
+
+        for (before, after) in settled
+            .iter()
+            .zip(relative_positions(&world.colliders, &welded_colliders[..]).iter())
+        {
+            assert!((before.translation.vector - after.translation.vector).norm() < 1.0e-3);
+            assert!(before.rotation.angle_to(&after.rotation) < 1.0e-3);
+        }
+    }
+}
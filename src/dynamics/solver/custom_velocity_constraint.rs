@@ -0,0 +1,178 @@
+use super::DeltaVel;
+use crate::dynamics::{IntegrationParameters, RigidBody, RigidBodyHandle};
+use crate::math::{AngVector, AngularInertia, Isometry, Point, Real, Vector};
+
+/// The state of a rigid-body, as seen by the constraints solver.
+#[derive(Copy, Clone, Debug)]
+pub struct SolverBody {
+    /// The index of this rigid-body's delta-velocity in the `mj_lambdas` given to the constraint.
+    ///
+    /// This is `None` if the rigid-body isn't simulated as part of the island being solved, e.g.,
+    /// because it is static, kinematic, sleeping, or part of another island. Such rigid-bodies
+    /// must be treated as having an infinite mass.
+    pub solver_index: Option<usize>,
+    /// The position of the rigid-body.
+    pub position: Isometry<Real>,
+    /// The world-space center of mass of the rigid-body.
+    pub world_com: Point<Real>,
+    /// The linear velocity of the rigid-body, before the constraints resolution.
+    pub linvel: Vector<Real>,
+    /// The angular velocity of the rigid-body, before the constraints resolution.
+    pub angvel: AngVector<Real>,
+    /// The inverse mass of the rigid-body, taking into account translation locking.
+    pub inv_mass: Real,
+    /// The square-root of the world-space inverse angular inertia tensor of the rigid-body,
+    /// taking into account rotation locking.
+    pub world_inv_inertia_sqrt: AngularInertia<Real>,
+}
+
+impl SolverBody {
+    pub(crate) fn new(island_id: usize, rb: &RigidBody) -> Self {
+        let is_solved = rb.is_dynamic() && !rb.is_sleeping() && rb.active_island_id == island_id;
+
+        Self {
+            solver_index: if is_solved {
+                Some(rb.active_set_offset)
+            } else {
+                None
+            },
+            position: rb.position,
+            world_com: rb.world_com,
+            linvel: rb.linvel,
+            angvel: rb.angvel,
+            inv_mass: rb.effective_inv_mass,
+            world_inv_inertia_sqrt: rb.effective_world_inv_inertia_sqrt,
+        }
+    }
+}
+
+/// A user-defined velocity constraint, solved alongside the contact and joint constraints.
+///
+/// Custom constraints are registered with `PhysicsPipeline::custom_constraints`. At each timestep,
+/// each constraint is solved as part of the island of its first awake dynamic rigid-body. It has
+/// no effect on the rigid-bodies from other islands, so constraints between several dynamic bodies
+/// should only be used on bodies that are already part of the same island (e.g. because they are
+/// also attached by a joint).
+///
+/// The `mj_lambdas` given to the solver methods are the delta-velocities of the island's
+/// rigid-bodies, indexed by `SolverBody::solver_index`. Their angular part is the angular
+/// velocity change multiplied by the square-root of the body's angular inertia tensor, i.e.,
+/// an angular impulse `torque_impulse` is applied with
+/// `mj_lambdas[i].angular += world_inv_inertia_sqrt.transform_vector(torque_impulse)`.
+pub trait CustomVelocityConstraint: Send + Sync {
+    /// The rigid-bodies affected by this constraint.
+    ///
+    /// All these rigid-bodies must exist in the `RigidBodySet` given to `PhysicsPipeline::step`.
+    fn bodies(&self) -> &[RigidBodyHandle];
+
+    /// Initializes this constraint for the current timestep.
+    ///
+    /// The `bodies` are given in the same order as `Self::bodies`.
+    fn prepare(&mut self, params: &IntegrationParameters, bodies: &[SolverBody]);
+
+    /// Applies the impulses of the previous timestep at the beginning of the velocity resolution.
+    fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]);
+
+    /// Runs one iteration of the velocity resolution for this constraint.
+    fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]);
+
+    /// Called once the velocity resolution for the current timestep completes.
+    fn writeback(&self);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{IntegrationParameters, RigidBodyBuilder};
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn custom_spring_constraint_oscillates() {
+        use crate::dynamics::{CustomVelocityConstraint, DeltaVel, RigidBodyHandle, SolverBody};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // A linear spring attaching the center of mass of a rigid-body to the origin.
+        struct Spring {
+            body: [RigidBodyHandle; 1],
+            stiffness: Real,
+            solver_index: Option<usize>,
+            impulse: Vector<Real>,
+            num_writebacks: Arc<AtomicUsize>,
+        }
+
+        impl CustomVelocityConstraint for Spring {
+            fn bodies(&self) -> &[RigidBodyHandle] {
+                &self.body
+            }
+
+            fn prepare(&mut self, params: &IntegrationParameters, bodies: &[SolverBody]) {
+                self.solver_index = bodies[0].solver_index;
+                self.impulse =
+                    -bodies[0].world_com.coords * (self.stiffness * params.dt * bodies[0].inv_mass);
+            }
+
+            fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+                // The spring force only depends on the positions, so it is applied once.
+                if let Some(i) = self.solver_index {
+                    mj_lambdas[i].linear += self.impulse;
+                }
+            }
+
+            fn solve(&mut self, _: &mut [DeltaVel<Real>]) {}
+
+            fn writeback(&self) {
+                self.num_writebacks.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut world = TestWorld::new();
+
+        let handle = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::x(), na::zero()))
+                .can_sleep(false)
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            handle,
+            &mut world.bodies,
+        );
+
+        let num_writebacks = Arc::new(AtomicUsize::new(0));
+        world.pipeline.custom_constraints.push(Box::new(Spring {
+            body: [handle],
+            stiffness: 10.0,
+            solver_index: None,
+            impulse: Vector::zeros(),
+            num_writebacks: num_writebacks.clone(),
+        }));
+
+        let mut num_crossings = 0;
+        let mut min_x = Real::MAX;
+        let mut max_x = -Real::MAX;
+        let mut prev_x = world.bodies[handle].position().translation.vector.x;
+
+        for _ in 0..300 {
+            world.step();
+
+            let x = world.bodies[handle].position().translation.vector.x;
+            if (x > 0.0) != (prev_x > 0.0) {
+                num_crossings += 1;
+            }
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            prev_x = x;
+        }
+
+        // The body oscillates around the origin without gaining or losing too much energy.
+        assert!(num_crossings >= 3, "{}", num_crossings);
+        assert!(min_x < -0.9 && min_x > -1.1, "{}", min_x);
+        assert!(max_x <= 1.1, "{}", max_x);
+        assert_eq!(num_writebacks.load(Ordering::SeqCst), 300);
+        assert_eq!(world.pipeline.custom_constraints.len(), 1);
+    }
+}
@@ -3,12 +3,17 @@ use na::{Scalar, SimdRealField};
 
 #[derive(Copy, Clone, Debug)]
 //#[repr(align(64))]
-pub(crate) struct DeltaVel<N: Scalar + Copy> {
+/// The velocity change of a rigid-body, accumulated by the constraints solver.
+pub struct DeltaVel<N: Scalar + Copy> {
+    /// The linear velocity change.
     pub linear: Vector<N>,
+    /// The angular velocity change, multiplied by the square-root of the angular inertia tensor
+    /// of the rigid-body.
     pub angular: AngVector<N>,
 }
 
 impl<N: SimdRealField> DeltaVel<N> {
+    /// A zero velocity change.
     pub fn zero() -> Self {
         Self {
             linear: na::zero(),
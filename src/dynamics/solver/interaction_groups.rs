@@ -27,6 +27,7 @@ pub(crate) struct ParallelInteractionGroups {
     bodies_color: Vec<u128>,         // Workspace.
     interaction_indices: Vec<usize>, // Workspace.
     interaction_colors: Vec<usize>,  // Workspace.
+    color_len: Vec<usize>,           // Workspace.
     sorted_interactions: Vec<usize>,
     groups: Vec<usize>,
 }
@@ -38,11 +39,21 @@ impl ParallelInteractionGroups {
             bodies_color: Vec::new(),
             interaction_indices: Vec::new(),
             interaction_colors: Vec::new(),
+            color_len: Vec::new(),
             sorted_interactions: Vec::new(),
             groups: Vec::new(),
         }
     }
 
+    pub fn shrink_to_fit(&mut self) {
+        self.bodies_color.shrink_to_fit();
+        self.interaction_indices.shrink_to_fit();
+        self.interaction_colors.shrink_to_fit();
+        self.color_len.shrink_to_fit();
+        self.sorted_interactions.shrink_to_fit();
+        self.groups.shrink_to_fit();
+    }
+
     pub fn group(&self, i: usize) -> &[usize] {
         let range = self.groups[i]..self.groups[i + 1];
         &self.sorted_interactions[range]
@@ -51,6 +62,13 @@ impl ParallelInteractionGroups {
         self.groups.len() - 1
     }
 
+    /// Colors the given interactions so that two interactions sharing a dynamic body never
+    /// have the same color.
+    ///
+    /// The interactions of a color are stored contiguously, and can be solved in parallel. A body
+    /// tracks at most 128 colors at a time: the interactions that can't be colored because one of
+    /// their bodies already uses all of them are colored during an extra pass with a fresh set of
+    /// 128 colors.
     pub fn group_interactions<Interaction: PairInteraction>(
         &mut self,
         island_id: usize,
@@ -59,64 +77,74 @@ impl ParallelInteractionGroups {
         interaction_indices: &[usize],
     ) {
         let num_island_bodies = bodies.active_island(island_id).len();
-        self.bodies_color.clear();
         self.interaction_indices.clear();
         self.groups.clear();
         self.sorted_interactions.clear();
         self.interaction_colors.clear();
+        self.color_len.clear();
 
-        let mut color_len = [0; 128];
-        self.bodies_color.resize(num_island_bodies, 0u128);
+        // Positions, in `interaction_indices`, of the interactions that still need a color.
         self.interaction_indices
-            .extend_from_slice(interaction_indices);
+            .extend(0..interaction_indices.len());
         self.interaction_colors.resize(interaction_indices.len(), 0);
-        let bcolors = &mut self.bodies_color;
+        let mut color_shift = 0;
+
+        while !self.interaction_indices.is_empty() {
+            let bcolors = &mut self.bodies_color;
+            bcolors.clear();
+            bcolors.resize(num_island_bodies, 0u128);
+            self.color_len.resize(color_shift + 128, 0);
+            let mut num_deferred = 0;
+
+            for i in 0..self.interaction_indices.len() {
+                let position = self.interaction_indices[i];
+                let body_pair = interactions[interaction_indices[position]].body_pair();
+                let rb1 = &bodies[body_pair.body1];
+                let rb2 = &bodies[body_pair.body2];
+
+                let color_mask = match (rb1.is_static(), rb2.is_static()) {
+                    (false, false) => {
+                        bcolors[rb1.active_set_offset] | bcolors[rb2.active_set_offset]
+                    }
+                    (true, false) => bcolors[rb2.active_set_offset],
+                    (false, true) => bcolors[rb1.active_set_offset],
+                    (true, true) => unreachable!(),
+                };
 
-        for (interaction_id, color) in self
-            .interaction_indices
-            .iter()
-            .zip(self.interaction_colors.iter_mut())
-        {
-            let body_pair = interactions[*interaction_id].body_pair();
-            let rb1 = &bodies[body_pair.body1];
-            let rb2 = &bodies[body_pair.body2];
-
-            match (rb1.is_static(), rb2.is_static()) {
-                (false, false) => {
-                    let color_mask =
-                        bcolors[rb1.active_set_offset] | bcolors[rb2.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb1.active_set_offset] |= 1 << *color;
-                    bcolors[rb2.active_set_offset] |= 1 << *color;
+                if color_mask == u128::MAX {
+                    // All the colors of this pass are already used by one of the bodies.
+                    self.interaction_indices[num_deferred] = position;
+                    num_deferred += 1;
+                    continue;
                 }
-                (true, false) => {
-                    let color_mask = bcolors[rb2.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb2.active_set_offset] |= 1 << *color;
+
+                let color = (!color_mask).trailing_zeros() as usize;
+                self.interaction_colors[position] = color_shift + color;
+                self.color_len[color_shift + color] += 1;
+
+                if !rb1.is_static() {
+                    bcolors[rb1.active_set_offset] |= 1 << color;
                 }
-                (false, true) => {
-                    let color_mask = bcolors[rb1.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb1.active_set_offset] |= 1 << *color;
+                if !rb2.is_static() {
+                    bcolors[rb2.active_set_offset] |= 1 << color;
                 }
-                (true, true) => unreachable!(),
             }
+
+            self.interaction_indices.truncate(num_deferred);
+            color_shift += 128;
         }
 
-        let mut sort_offsets = [0; 128];
         let mut last_offset = 0;
 
-        for i in 0..128 {
-            if color_len[i] == 0 {
-                break;
+        // Turn the color lengths into the offset of the first interaction of each color.
+        for len in &mut self.color_len {
+            if *len != 0 {
+                self.groups.push(last_offset);
             }
 
-            self.groups.push(last_offset);
-            sort_offsets[i] = last_offset;
-            last_offset += color_len[i];
+            let offset = last_offset;
+            last_offset += *len;
+            *len = offset;
         }
 
         self.sorted_interactions
@@ -126,8 +154,8 @@ impl ParallelInteractionGroups {
             .iter()
             .zip(self.interaction_colors.iter())
         {
-            self.sorted_interactions[sort_offsets[*color]] = *interaction_id;
-            sort_offsets[*color] += 1;
+            self.sorted_interactions[self.color_len[*color]] = *interaction_id;
+            self.color_len[*color] += 1;
         }
 
         self.groups.push(self.sorted_interactions.len());
@@ -157,6 +185,17 @@ impl InteractionGroups {
         }
     }
 
+    /// Releases the memory of the workspace buffers that is not currently used.
+    pub fn shrink_to_fit(&mut self) {
+        #[cfg(feature = "simd-is-enabled")]
+        {
+            self.buckets.shrink_to_fit();
+            self.body_masks.shrink_to_fit();
+            self.grouped_interactions.shrink_to_fit();
+        }
+        self.nongrouped_interactions.shrink_to_fit();
+    }
+
     // FIXME: there is a lot of duplicated code with group_manifolds here.
     // But we don't refactor just now because we may end up with distinct
     // grouping strategies in the future.
@@ -371,6 +410,19 @@ impl InteractionGroups {
                     continue;
                 }
 
+                #[cfg(feature = "dim3")]
+                if interaction.data.anisotropic_friction.is_some() {
+                    // Anisotropic friction does not support simd constraints yet.
+                    self.nongrouped_interactions.push(*interaction_i);
+                    continue;
+                }
+
+                if interaction.data.has_bounded_normal_impulse() {
+                    // Bounded normal impulses are not supported by simd constraints yet.
+                    self.nongrouped_interactions.push(*interaction_i);
+                    continue;
+                }
+
                 let i1 = body1.active_set_offset;
                 let i2 = body2.active_set_offset;
                 let conflicts = self.body_masks[i1] | self.body_masks[i2];
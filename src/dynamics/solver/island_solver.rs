@@ -2,16 +2,19 @@ use super::{PositionSolver, VelocitySolver};
 use crate::counters::Counters;
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyJointVelocityConstraint, AnyPositionConstraint,
-    AnyVelocityConstraint, SolverConstraints,
+    AnyVelocityConstraint, CustomVelocityConstraint, SolverBody, SolverConstraints,
 };
 use crate::dynamics::{IntegrationParameters, JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use crate::math::Real;
+use crate::pipeline::{PhysicsHooks, PhysicsHooksFlags};
 
 pub struct IslandSolver {
     contact_constraints: SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint>,
     joint_constraints: SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint>,
     velocity_solver: VelocitySolver,
     position_solver: PositionSolver,
+    solver_bodies: Vec<SolverBody>,
 }
 
 impl IslandSolver {
@@ -21,9 +24,31 @@ impl IslandSolver {
             joint_constraints: SolverConstraints::new(),
             velocity_solver: VelocitySolver::new(),
             position_solver: PositionSolver::new(),
+            solver_bodies: Vec::new(),
         }
     }
 
+    /// Releases the memory of the solver buffers that is not currently used.
+    pub fn shrink_to_fit(&mut self) {
+        self.contact_constraints.shrink_to_fit();
+        self.joint_constraints.shrink_to_fit();
+        self.velocity_solver.shrink_to_fit();
+        self.position_solver.shrink_to_fit();
+        self.solver_bodies.shrink_to_fit();
+    }
+
+    /// The contact impulse magnitudes recorded after each velocity iteration during the last
+    /// resolution of this island.
+    pub fn velocity_convergence_history(&self) -> &[Real] {
+        &self.velocity_solver.convergence_history
+    }
+
+    /// The number of iterations performed by the velocity solver during the last resolution
+    /// of this island.
+    pub fn num_velocity_iterations(&self) -> usize {
+        self.velocity_solver.num_iterations
+    }
+
     pub fn solve_island(
         &mut self,
         island_id: usize,
@@ -34,17 +59,49 @@ impl IslandSolver {
         manifold_indices: &[ContactManifoldIndex],
         joints: &mut [JointGraphEdge],
         joint_indices: &[JointIndex],
+        custom_constraints: &mut Vec<Box<dyn CustomVelocityConstraint>>,
+        hooks: &dyn PhysicsHooks,
     ) {
-        let has_constraints = manifold_indices.len() != 0 || joint_indices.len() != 0;
+        let has_constraints = manifold_indices.len() != 0
+            || joint_indices.len() != 0
+            || custom_constraints.len() != 0;
+        // The island hook modifies the delta-velocities computed by the velocity solver.
+        let has_island_hook = hooks
+            .active_hooks()
+            .contains(PhysicsHooksFlags::MODIFY_ISLAND);
 
-        if has_constraints {
+        if has_constraints || has_island_hook {
             counters.solver.velocity_assembly_time.resume();
             self.contact_constraints
                 .init(island_id, params, bodies, manifolds, manifold_indices);
             self.joint_constraints
                 .init(island_id, params, bodies, joints, joint_indices);
+
+            // The custom constraints are moved to the end of the contact constraints
+            // for the duration of the velocity resolution.
+            let num_custom_constraints = custom_constraints.len();
+            for mut constraint in custom_constraints.drain(..) {
+                self.solver_bodies.clear();
+                self.solver_bodies.extend(
+                    constraint
+                        .bodies()
+                        .iter()
+                        .map(|handle| SolverBody::new(island_id, &bodies[*handle])),
+                );
+                constraint.prepare(params, &self.solver_bodies);
+                self.contact_constraints
+                    .velocity_constraints
+                    .push(AnyVelocityConstraint::Custom(constraint));
+            }
             counters.solver.velocity_assembly_time.pause();
 
+            // Islands containing joints may require more iterations.
+            let iterations_multiplier = joint_indices
+                .iter()
+                .map(|i| joints[*i].weight.solver_iterations_multiplier)
+                .max()
+                .unwrap_or(1);
+
             counters.solver.velocity_resolution_time.resume();
             self.velocity_solver.solve(
                 island_id,
@@ -54,28 +111,54 @@ impl IslandSolver {
                 joints,
                 &mut self.contact_constraints.velocity_constraints,
                 &mut self.joint_constraints.velocity_constraints,
+                iterations_multiplier,
+                hooks,
+                &mut counters.solver,
             );
+
+            let num_velocity_constraints = self.contact_constraints.velocity_constraints.len();
+            for constraint in self
+                .contact_constraints
+                .velocity_constraints
+                .drain(num_velocity_constraints - num_custom_constraints..)
+            {
+                if let AnyVelocityConstraint::Custom(constraint) = constraint {
+                    custom_constraints.push(constraint);
+                }
+            }
             counters.solver.velocity_resolution_time.pause();
 
             counters.solver.velocity_update_time.resume();
             bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
                 rb.integrate(params.dt)
             });
+
+            if params.split_impulse_enabled() {
+                self.velocity_solver
+                    .integrate_pseudo_velocities(island_id, params, bodies);
+            }
             counters.solver.velocity_update_time.pause();
 
-            counters.solver.position_resolution_time.resume();
-            self.position_solver.solve(
-                island_id,
-                params,
-                bodies,
-                &self.contact_constraints.position_constraints,
-                &self.joint_constraints.position_constraints,
-            );
-            counters.solver.position_resolution_time.pause();
+            if params.max_position_iterations > 0 {
+                counters.solver.position_resolution_time.resume();
+                self.position_solver.solve(
+                    island_id,
+                    params,
+                    bodies,
+                    &self.contact_constraints.position_constraints,
+                    &self.joint_constraints.position_constraints,
+                );
+                counters.solver.position_resolution_time.pause();
+            }
         } else {
+            self.velocity_solver.convergence_history.clear();
+            self.velocity_solver.num_iterations = 0;
+
             counters.solver.velocity_update_time.resume();
             bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
                 // Since we didn't run the velocity solver we need to integrate the accelerations here
+                rb.solver_linvel_change = na::zero();
+                rb.solver_angvel_change = na::zero();
                 rb.integrate_accelerations(params.dt);
                 rb.integrate(params.dt);
             });
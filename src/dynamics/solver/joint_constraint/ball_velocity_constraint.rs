@@ -14,6 +14,7 @@ pub(crate) struct BallVelocityConstraint {
 
     rhs: Vector<Real>,
     impulse: Vector<Real>,
+    compliance_coeff: Real,
 
     r1: Vector<Real>,
     r2: Vector<Real>,
@@ -39,6 +40,7 @@ impl BallVelocityConstraint {
         rb1: &RigidBody,
         rb2: &RigidBody,
         joint: &BallJoint,
+        compliance: Real,
     ) -> Self {
         let anchor_world1 = rb1.position * joint.local_anchor1;
         let anchor_world2 = rb2.position * joint.local_anchor2;
@@ -51,7 +53,8 @@ impl BallVelocityConstraint {
         let im2 = rb2.effective_inv_mass;
 
         let rhs = (vel2 - vel1) * params.velocity_solve_fraction
-            + (anchor_world2 - anchor_world1) * params.velocity_based_erp_inv_dt();
+            + (anchor_world2 - anchor_world1) * params.joint_velocity_based_erp_inv_dt(compliance);
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
 
         let lhs;
         let cmat1 = anchor1.gcross_matrix();
@@ -68,7 +71,7 @@ impl BallVelocityConstraint {
                     .effective_world_inv_inertia_sqrt
                     .squared()
                     .quadform(&cmat1)
-                    .add_diagonal(im1);
+                    .add_diagonal(im1 + compliance_coeff);
         }
 
         // In 2D we just unroll the computation because
@@ -77,9 +80,11 @@ impl BallVelocityConstraint {
         {
             let ii1 = rb1.effective_world_inv_inertia_sqrt.squared();
             let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-            let m11 = im1 + im2 + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
+            let m11 =
+                im1 + im2 + compliance_coeff + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
             let m12 = cmat1.x * cmat1.y * ii1 + cmat2.x * cmat2.y * ii2;
-            let m22 = im1 + im2 + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
+            let m22 =
+                im1 + im2 + compliance_coeff + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
@@ -156,6 +161,7 @@ impl BallVelocityConstraint {
             im1,
             im2,
             impulse: joint.impulse * params.warmstart_coeff,
+            compliance_coeff,
             r1: anchor1,
             r2: anchor2,
             rhs,
@@ -191,7 +197,7 @@ impl BallVelocityConstraint {
         let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
         let vel1 = mj_lambda1.linear + ang_vel1.gcross(self.r1);
         let vel2 = mj_lambda2.linear + ang_vel2.gcross(self.r2);
-        let dvel = -vel1 + vel2 + self.rhs;
+        let dvel = -vel1 + vel2 + self.rhs - self.impulse * self.compliance_coeff;
 
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
@@ -254,6 +260,7 @@ pub(crate) struct BallVelocityGroundConstraint {
 
     rhs: Vector<Real>,
     impulse: Vector<Real>,
+    compliance_coeff: Real,
     inv_lhs: SdpMatrix<Real>,
 
     motor_rhs: AngVector<Real>,
@@ -273,6 +280,7 @@ impl BallVelocityGroundConstraint {
         rb2: &RigidBody,
         joint: &BallJoint,
         flipped: bool,
+        compliance: Real,
     ) -> Self {
         let (anchor_world1, anchor_world2) = if flipped {
             (
@@ -294,7 +302,8 @@ impl BallVelocityGroundConstraint {
         let vel2 = rb2.linvel + rb2.angvel.gcross(anchor2);
 
         let rhs = (vel2 - vel1) * params.velocity_solve_fraction
-            + (anchor_world2 - anchor_world1) * params.velocity_based_erp_inv_dt();
+            + (anchor_world2 - anchor_world1) * params.joint_velocity_based_erp_inv_dt(compliance);
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
 
         let cmat2 = anchor2.gcross_matrix();
 
@@ -306,15 +315,15 @@ impl BallVelocityGroundConstraint {
                 .effective_world_inv_inertia_sqrt
                 .squared()
                 .quadform(&cmat2)
-                .add_diagonal(im2);
+                .add_diagonal(im2 + compliance_coeff);
         }
 
         #[cfg(feature = "dim2")]
         {
             let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-            let m11 = im2 + cmat2.x * cmat2.x * ii2;
+            let m11 = im2 + compliance_coeff + cmat2.x * cmat2.x * ii2;
             let m12 = cmat2.x * cmat2.y * ii2;
-            let m22 = im2 + cmat2.y * cmat2.y * ii2;
+            let m22 = im2 + compliance_coeff + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
@@ -387,6 +396,7 @@ impl BallVelocityGroundConstraint {
             mj_lambda2: rb2.active_set_offset,
             im2,
             impulse: joint.impulse * params.warmstart_coeff,
+            compliance_coeff,
             r2: anchor2,
             rhs,
             inv_lhs,
@@ -410,7 +420,7 @@ impl BallVelocityGroundConstraint {
     fn solve_dofs(&mut self, mj_lambda2: &mut DeltaVel<Real>) {
         let angvel = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
         let vel2 = mj_lambda2.linear + angvel.gcross(self.r2);
-        let dvel = vel2 + self.rhs;
+        let dvel = vel2 + self.rhs - self.impulse * self.compliance_coeff;
 
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
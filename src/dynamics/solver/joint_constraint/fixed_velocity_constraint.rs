@@ -17,6 +17,7 @@ pub(crate) struct FixedVelocityConstraint {
     joint_id: JointIndex,
 
     impulse: SpacialVector<Real>,
+    compliance_coeff: Real,
 
     #[cfg(feature = "dim3")]
     inv_lhs: Matrix6<Real>, // FIXME: replace by Cholesky.
@@ -48,6 +49,7 @@ impl FixedVelocityConstraint {
         rb1: &RigidBody,
         rb2: &RigidBody,
         cparams: &FixedJoint,
+        compliance: Real,
     ) -> Self {
         let anchor1 = rb1.position * cparams.local_anchor1;
         let anchor2 = rb2.position * cparams.local_anchor2;
@@ -60,7 +62,6 @@ impl FixedVelocityConstraint {
         let rmat1 = r1.gcross_matrix();
         let rmat2 = r2.gcross_matrix();
 
-        #[allow(unused_mut)] // For 2D
         let mut lhs;
 
         #[cfg(feature = "dim3")]
@@ -92,6 +93,10 @@ impl FixedVelocityConstraint {
             lhs = Matrix3::new(m11, m12, m13, m12, m22, m23, m13, m23, m33)
         }
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+        lhs.set_diagonal(&diagonal);
+
         // NOTE: we don't use cholesky in 2D because we only have a 3x3 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
@@ -111,7 +116,7 @@ impl FixedVelocityConstraint {
             lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y, ang_dvel.z,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let lin_err = anchor2.translation.vector - anchor1.translation.vector;
             let ang_err = anchor2.rotation * anchor1.rotation.inverse();
@@ -142,6 +147,7 @@ impl FixedVelocityConstraint {
             ii1_sqrt: rb1.effective_world_inv_inertia_sqrt,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse: cparams.impulse * params.warmstart_coeff,
+            compliance_coeff,
             inv_lhs,
             r1,
             r2,
@@ -192,7 +198,7 @@ impl FixedVelocityConstraint {
             dlinvel.x, dlinvel.y, dlinvel.z, dangvel.x, dangvel.y, dangvel.z,
         ) + self.rhs;
 
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
         self.impulse += impulse;
         let lin_impulse = impulse.fixed_rows::<Dim>(0).into_owned();
         #[cfg(feature = "dim2")]
@@ -229,6 +235,7 @@ pub(crate) struct FixedVelocityGroundConstraint {
     joint_id: JointIndex,
 
     impulse: SpacialVector<Real>,
+    compliance_coeff: Real,
 
     #[cfg(feature = "dim3")]
     inv_lhs: Matrix6<Real>, // FIXME: replace by Cholesky.
@@ -254,6 +261,7 @@ impl FixedVelocityGroundConstraint {
         rb2: &RigidBody,
         cparams: &FixedJoint,
         flipped: bool,
+        compliance: Real,
     ) -> Self {
         let (anchor1, anchor2) = if flipped {
             (
@@ -274,7 +282,6 @@ impl FixedVelocityGroundConstraint {
         let r2 = anchor2.translation.vector - rb2.world_com.coords;
         let rmat2 = r2.gcross_matrix();
 
-        #[allow(unused_mut)] // For 2D.
         let mut lhs;
 
         #[cfg(feature = "dim3")]
@@ -305,6 +312,10 @@ impl FixedVelocityGroundConstraint {
             lhs = Matrix3::new(m11, m12, m13, m12, m22, m23, m13, m23, m33)
         }
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+        lhs.set_diagonal(&diagonal);
+
         #[cfg(feature = "dim2")]
         let inv_lhs = lhs.try_inverse().expect("Singular system.");
         #[cfg(feature = "dim3")]
@@ -321,7 +332,7 @@ impl FixedVelocityGroundConstraint {
             lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y, ang_dvel.z,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let lin_err = anchor2.translation.vector - anchor1.translation.vector;
             let ang_err = anchor2.rotation * anchor1.rotation.inverse();
@@ -348,6 +359,7 @@ impl FixedVelocityGroundConstraint {
             ii2,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse: cparams.impulse * params.warmstart_coeff,
+            compliance_coeff,
             inv_lhs,
             r2,
             rhs,
@@ -385,7 +397,7 @@ impl FixedVelocityGroundConstraint {
             dlinvel.x, dlinvel.y, dlinvel.z, dangvel.x, dangvel.y, dangvel.z,
         ) + self.rhs;
 
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
 
         self.impulse += impulse;
         let lin_impulse = impulse.fixed_rows::<Dim>(0).into_owned();
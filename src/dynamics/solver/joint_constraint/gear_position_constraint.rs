@@ -0,0 +1,116 @@
+use crate::dynamics::{rotation_vector, GearJoint, IntegrationParameters, RigidBody};
+use crate::math::{AngVector, AngularInertia, Isometry, Real, Rotation};
+use crate::utils::{WAngularInertia, WDot};
+
+#[derive(Debug)]
+pub(crate) struct GearPositionConstraint {
+    position1: usize,
+    position2: usize,
+
+    ii1: AngularInertia<Real>,
+    ii2: AngularInertia<Real>,
+
+    j1: AngVector<Real>,
+    j2: AngVector<Real>,
+
+    // The angle error at the beginning of the timestep.
+    angle_error: Real,
+    // The orientations of the bodies at the beginning of the timestep.
+    rotations: [Rotation<Real>; 2],
+}
+
+impl GearPositionConstraint {
+    pub fn from_params(rb1: &RigidBody, rb2: &RigidBody, cparams: &GearJoint) -> Self {
+        let rot1 = rb1.position.rotation;
+        let rot2 = rb2.position.rotation;
+        let (j1, j2) = cparams.jacobians(&rot1, &rot2);
+
+        Self {
+            position1: rb1.active_set_offset,
+            position2: rb2.active_set_offset,
+            ii1: rb1.effective_world_inv_inertia_sqrt.squared(),
+            ii2: rb2.effective_world_inv_inertia_sqrt.squared(),
+            j1,
+            j2,
+            angle_error: cparams.angle_error_at(&rot1, &rot2),
+            rotations: [rot1, rot2],
+        }
+    }
+
+    pub fn solve(&self, params: &IntegrationParameters, positions: &mut [Isometry<Real>]) {
+        let mut position1 = positions[self.position1];
+        let mut position2 = positions[self.position2];
+
+        let err = self.angle_error
+            + self.j1.gdot(rotation_vector(
+                &(position1.rotation * self.rotations[0].inverse()),
+            ))
+            + self.j2.gdot(rotation_vector(
+                &(position2.rotation * self.rotations[1].inverse()),
+            ));
+
+        let ii1_j1 = self.ii1.transform_vector(self.j1);
+        let ii2_j2 = self.ii2.transform_vector(self.j2);
+        let lhs = self.j1.gdot(ii1_j1) + self.j2.gdot(ii2_j2);
+        let impulse = -err * params.joint_erp * crate::utils::inv(lhs);
+
+        position1.rotation = Rotation::new(ii1_j1 * impulse) * position1.rotation;
+        position2.rotation = Rotation::new(ii2_j2 * impulse) * position2.rotation;
+
+        positions[self.position1] = position1;
+        positions[self.position2] = position2;
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct GearPositionGroundConstraint {
+    position2: usize,
+    ii2: AngularInertia<Real>,
+    j2: AngVector<Real>,
+    // The angle error at the beginning of the timestep, including the
+    // contribution of the motion of the non-dynamic body during this timestep.
+    angle_error: Real,
+    // The orientation of the dynamic body at the beginning of the timestep.
+    rotation2: Rotation<Real>,
+}
+
+impl GearPositionGroundConstraint {
+    pub fn from_params(
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        cparams: &GearJoint,
+        flipped: bool,
+    ) -> Self {
+        // The rb1 and rb2 have already been flipped by the caller, but the
+        // gear jacobians are expressed in the original order of the bodies.
+        let (orb1, orb2) = if flipped { (rb2, rb1) } else { (rb1, rb2) };
+        let (j1, j2) = cparams.jacobians(&orb1.position.rotation, &orb2.position.rotation);
+        let angle_error = cparams.angle_error_at(&orb1.position.rotation, &orb2.position.rotation);
+        let (j_ground, j_dynamic) = if flipped { (j2, j1) } else { (j1, j2) };
+        let ground_motion = rb1.predicted_position.rotation * rb1.position.rotation.inverse();
+
+        Self {
+            position2: rb2.active_set_offset,
+            ii2: rb2.effective_world_inv_inertia_sqrt.squared(),
+            j2: j_dynamic,
+            angle_error: angle_error + j_ground.gdot(rotation_vector(&ground_motion)),
+            rotation2: rb2.position.rotation,
+        }
+    }
+
+    pub fn solve(&self, params: &IntegrationParameters, positions: &mut [Isometry<Real>]) {
+        let mut position2 = positions[self.position2];
+
+        let err = self.angle_error
+            + self.j2.gdot(rotation_vector(
+                &(position2.rotation * self.rotation2.inverse()),
+            ));
+
+        let ii2_j2 = self.ii2.transform_vector(self.j2);
+        let lhs = self.j2.gdot(ii2_j2);
+        let impulse = -err * params.joint_erp * crate::utils::inv(lhs);
+
+        position2.rotation = Rotation::new(ii2_j2 * impulse) * position2.rotation;
+        positions[self.position2] = position2;
+    }
+}
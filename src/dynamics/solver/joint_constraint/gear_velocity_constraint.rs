@@ -0,0 +1,182 @@
+use crate::dynamics::solver::DeltaVel;
+use crate::dynamics::{
+    GearJoint, IntegrationParameters, JointGraphEdge, JointIndex, JointParams, RigidBody,
+};
+use crate::math::{AngVector, Real, Rotation};
+use crate::utils::{WAngularInertia, WDot};
+
+#[derive(Debug)]
+pub(crate) struct GearVelocityConstraint {
+    mj_lambda1: usize,
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+    compliance_coeff: Real,
+
+    // The jacobians multiplied by the square root of the inverse angular inertia.
+    ii1_sqrt_j1: AngVector<Real>,
+    ii2_sqrt_j2: AngVector<Real>,
+
+    angle_error: Real,
+    rotations: [Rotation<Real>; 2],
+}
+
+impl GearVelocityConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &GearJoint,
+        compliance: Real,
+    ) -> Self {
+        let rot1 = rb1.position.rotation;
+        let rot2 = rb2.position.rotation;
+        let (j1, j2) = joint.jacobians(&rot1, &rot2);
+        let angle_error = joint.angle_error_at(&rot1, &rot2);
+
+        let ii1_sqrt_j1 = rb1.effective_world_inv_inertia_sqrt.transform_vector(j1);
+        let ii2_sqrt_j2 = rb2.effective_world_inv_inertia_sqrt.transform_vector(j2);
+        let lhs = ii1_sqrt_j1.gdot(ii1_sqrt_j1) + ii2_sqrt_j2.gdot(ii2_sqrt_j2);
+
+        let rhs = (j1.gdot(rb1.angvel) + j2.gdot(rb2.angvel)) * params.velocity_solve_fraction
+            + angle_error * params.joint_velocity_based_erp_inv_dt(compliance);
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+
+        GearVelocityConstraint {
+            mj_lambda1: rb1.active_set_offset,
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs: crate::utils::inv(lhs + compliance_coeff),
+            compliance_coeff,
+            ii1_sqrt_j1,
+            ii2_sqrt_j2,
+            angle_error,
+            rotations: [rot1, rot2],
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        mj_lambdas[self.mj_lambda1].angular += self.ii1_sqrt_j1 * self.impulse;
+        mj_lambdas[self.mj_lambda2].angular += self.ii2_sqrt_j2 * self.impulse;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2];
+
+        let dvel = self.ii1_sqrt_j1.gdot(mj_lambda1.angular)
+            + self.ii2_sqrt_j2.gdot(mj_lambda2.angular)
+            + self.rhs
+            + self.impulse * self.compliance_coeff;
+        let impulse = -self.inv_lhs * dvel;
+        self.impulse += impulse;
+
+        mj_lambda1.angular += self.ii1_sqrt_j1 * impulse;
+        mj_lambda2.angular += self.ii2_sqrt_j2 * impulse;
+
+        mj_lambdas[self.mj_lambda1] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2] = mj_lambda2;
+    }
+
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::GearJoint(gear) = &mut joint.params {
+            gear.impulse = self.impulse;
+            gear.angle_error = self.angle_error;
+            gear.reference_rotations = Some(self.rotations);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct GearVelocityGroundConstraint {
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+    compliance_coeff: Real,
+
+    ii2_sqrt_j2: AngVector<Real>,
+
+    angle_error: Real,
+    rotations: [Rotation<Real>; 2],
+}
+
+impl GearVelocityGroundConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &GearJoint,
+        flipped: bool,
+        compliance: Real,
+    ) -> Self {
+        // The rb1 and rb2 have already been flipped by the caller, but the
+        // gear jacobians are expressed in the original order of the bodies.
+        let (orb1, orb2) = if flipped { (rb2, rb1) } else { (rb1, rb2) };
+        let rot1 = orb1.position.rotation;
+        let rot2 = orb2.position.rotation;
+        let (j1, j2) = joint.jacobians(&rot1, &rot2);
+        let angle_error = joint.angle_error_at(&rot1, &rot2);
+
+        let j_dynamic = if flipped { j1 } else { j2 };
+        let ii2_sqrt_j2 = rb2
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(j_dynamic);
+        let lhs = ii2_sqrt_j2.gdot(ii2_sqrt_j2);
+
+        let rhs = (j1.gdot(orb1.angvel) + j2.gdot(orb2.angvel)) * params.velocity_solve_fraction
+            + angle_error * params.joint_velocity_based_erp_inv_dt(compliance);
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+
+        GearVelocityGroundConstraint {
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs: crate::utils::inv(lhs + compliance_coeff),
+            compliance_coeff,
+            ii2_sqrt_j2,
+            angle_error,
+            rotations: [rot1, rot2],
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        mj_lambdas[self.mj_lambda2].angular += self.ii2_sqrt_j2 * self.impulse;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2];
+
+        let dvel = self.ii2_sqrt_j2.gdot(mj_lambda2.angular)
+            + self.rhs
+            + self.impulse * self.compliance_coeff;
+        let impulse = -self.inv_lhs * dvel;
+        self.impulse += impulse;
+
+        mj_lambda2.angular += self.ii2_sqrt_j2 * impulse;
+        mj_lambdas[self.mj_lambda2] = mj_lambda2;
+    }
+
+    // FIXME: duplicated code with the non-ground constraint.
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::GearJoint(gear) = &mut joint.params {
+            gear.impulse = self.impulse;
+            gear.angle_error = self.angle_error;
+            gear.reference_rotations = Some(self.rotations);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
     BallVelocityConstraint, BallVelocityGroundConstraint, FixedVelocityConstraint,
-    FixedVelocityGroundConstraint, PrismaticVelocityConstraint, PrismaticVelocityGroundConstraint,
+    FixedVelocityGroundConstraint, GearVelocityConstraint, GearVelocityGroundConstraint,
+    PrismaticVelocityConstraint, PrismaticVelocityGroundConstraint,
 };
 #[cfg(feature = "dim3")]
 use super::{RevoluteVelocityConstraint, RevoluteVelocityGroundConstraint};
@@ -59,6 +60,8 @@ pub(crate) enum AnyJointVelocityConstraint {
     #[cfg(feature = "dim3")]
     #[cfg(feature = "simd-is-enabled")]
     WRevoluteGroundConstraint(WRevoluteVelocityGroundConstraint),
+    GearConstraint(GearVelocityConstraint),
+    GearGroundConstraint(GearVelocityGroundConstraint),
     #[allow(dead_code)] // The Empty variant is only used with parallel code.
     Empty,
 }
@@ -77,23 +80,27 @@ impl AnyJointVelocityConstraint {
     ) -> Self {
         let rb1 = &bodies[joint.body1];
         let rb2 = &bodies[joint.body2];
+        let compliance = joint.compliance;
 
         match &joint.params {
             JointParams::BallJoint(p) => AnyJointVelocityConstraint::BallConstraint(
-                BallVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+                BallVelocityConstraint::from_params(params, joint_id, rb1, rb2, p, compliance),
             ),
             JointParams::FixedJoint(p) => AnyJointVelocityConstraint::FixedConstraint(
-                FixedVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+                FixedVelocityConstraint::from_params(params, joint_id, rb1, rb2, p, compliance),
             ),
             JointParams::PrismaticJoint(p) => AnyJointVelocityConstraint::PrismaticConstraint(
-                PrismaticVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+                PrismaticVelocityConstraint::from_params(params, joint_id, rb1, rb2, p, compliance),
             ),
             // JointParams::GenericJoint(p) => AnyJointVelocityConstraint::GenericConstraint(
             //     GenericVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
             // ),
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(p) => AnyJointVelocityConstraint::RevoluteConstraint(
-                RevoluteVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+                RevoluteVelocityConstraint::from_params(params, joint_id, rb1, rb2, p, compliance),
+            ),
+            JointParams::GearJoint(p) => AnyJointVelocityConstraint::GearConstraint(
+                GearVelocityConstraint::from_params(params, joint_id, rb1, rb2, p, compliance),
             ),
         }
     }
@@ -142,6 +149,7 @@ impl AnyJointVelocityConstraint {
                     WRevoluteVelocityConstraint::from_params(params, joint_id, rbs1, rbs2, joints),
                 )
             }
+            JointParams::GearJoint(_) => unreachable!("Gear joints don't have SIMD constraints."),
         }
     }
 
@@ -154,6 +162,7 @@ impl AnyJointVelocityConstraint {
         let mut rb1 = &bodies[joint.body1];
         let mut rb2 = &bodies[joint.body2];
         let flipped = !rb2.is_dynamic();
+        let compliance = joint.compliance;
 
         if flipped {
             std::mem::swap(&mut rb1, &mut rb2);
@@ -161,10 +170,14 @@ impl AnyJointVelocityConstraint {
 
         match &joint.params {
             JointParams::BallJoint(p) => AnyJointVelocityConstraint::BallGroundConstraint(
-                BallVelocityGroundConstraint::from_params(params, joint_id, rb1, rb2, p, flipped),
+                BallVelocityGroundConstraint::from_params(
+                    params, joint_id, rb1, rb2, p, flipped, compliance,
+                ),
             ),
             JointParams::FixedJoint(p) => AnyJointVelocityConstraint::FixedGroundConstraint(
-                FixedVelocityGroundConstraint::from_params(params, joint_id, rb1, rb2, p, flipped),
+                FixedVelocityGroundConstraint::from_params(
+                    params, joint_id, rb1, rb2, p, flipped, compliance,
+                ),
             ),
             // JointParams::GenericJoint(p) => AnyJointVelocityConstraint::GenericGroundConstraint(
             //     GenericVelocityGroundConstraint::from_params(
@@ -174,13 +187,18 @@ impl AnyJointVelocityConstraint {
             JointParams::PrismaticJoint(p) => {
                 AnyJointVelocityConstraint::PrismaticGroundConstraint(
                     PrismaticVelocityGroundConstraint::from_params(
-                        params, joint_id, rb1, rb2, p, flipped,
+                        params, joint_id, rb1, rb2, p, flipped, compliance,
                     ),
                 )
             }
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(p) => RevoluteVelocityGroundConstraint::from_params(
-                params, joint_id, rb1, rb2, p, flipped,
+                params, joint_id, rb1, rb2, p, flipped, compliance,
+            ),
+            JointParams::GearJoint(p) => AnyJointVelocityConstraint::GearGroundConstraint(
+                GearVelocityGroundConstraint::from_params(
+                    params, joint_id, rb1, rb2, p, flipped, compliance,
+                ),
             ),
         }
     }
@@ -247,6 +265,7 @@ impl AnyJointVelocityConstraint {
                     ),
                 )
             }
+            JointParams::GearJoint(_) => unreachable!("Gear joints don't have SIMD constraints."),
         }
     }
 
@@ -286,6 +305,8 @@ impl AnyJointVelocityConstraint {
             #[cfg(feature = "dim3")]
             #[cfg(feature = "simd-is-enabled")]
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::GearConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.warmstart(mj_lambdas),
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -326,6 +347,8 @@ impl AnyJointVelocityConstraint {
             #[cfg(feature = "dim3")]
             #[cfg(feature = "simd-is-enabled")]
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::GearConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.solve(mj_lambdas),
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -386,6 +409,8 @@ impl AnyJointVelocityConstraint {
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => {
                 c.writeback_impulses(joints_all)
             }
+            AnyJointVelocityConstraint::GearConstraint(c) => c.writeback_impulses(joints_all),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.writeback_impulses(joints_all),
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
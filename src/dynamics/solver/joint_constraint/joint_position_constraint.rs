@@ -1,6 +1,7 @@
 use super::{
     BallPositionConstraint, BallPositionGroundConstraint, FixedPositionConstraint,
-    FixedPositionGroundConstraint, PrismaticPositionConstraint, PrismaticPositionGroundConstraint,
+    FixedPositionGroundConstraint, GearPositionConstraint, GearPositionGroundConstraint,
+    PrismaticPositionConstraint, PrismaticPositionGroundConstraint,
 };
 #[cfg(feature = "dim3")]
 use super::{RevolutePositionConstraint, RevolutePositionGroundConstraint};
@@ -51,12 +52,19 @@ pub(crate) enum AnyJointPositionConstraint {
     WRevoluteJoint(WRevolutePositionConstraint),
     #[cfg(all(feature = "dim3", feature = "simd-is-enabled"))]
     WRevoluteGroundConstraint(WRevolutePositionGroundConstraint),
-    #[allow(dead_code)] // The Empty variant is only used with parallel code.
+    GearJoint(GearPositionConstraint),
+    GearGroundConstraint(GearPositionGroundConstraint),
+    // Used by compliant joints, and as a placeholder by the parallel solver.
     Empty,
 }
 
 impl AnyJointPositionConstraint {
     pub fn from_joint(joint: &Joint, bodies: &RigidBodySet) -> Self {
+        if joint.compliance != 0.0 {
+            // Compliant joints are only handled by the velocity solver.
+            return AnyJointPositionConstraint::Empty;
+        }
+
         let rb1 = &bodies[joint.body1];
         let rb2 = &bodies[joint.body2];
 
@@ -77,6 +85,9 @@ impl AnyJointPositionConstraint {
             JointParams::RevoluteJoint(p) => AnyJointPositionConstraint::RevoluteJoint(
                 RevolutePositionConstraint::from_params(rb1, rb2, p),
             ),
+            JointParams::GearJoint(p) => AnyJointPositionConstraint::GearJoint(
+                GearPositionConstraint::from_params(rb1, rb2, p),
+            ),
         }
     }
 
@@ -119,10 +130,16 @@ impl AnyJointPositionConstraint {
                     WRevolutePositionConstraint::from_params(rbs1, rbs2, joints),
                 )
             }
+            JointParams::GearJoint(_) => unreachable!("Gear joints don't have SIMD constraints."),
         }
     }
 
     pub fn from_joint_ground(joint: &Joint, bodies: &RigidBodySet) -> Self {
+        if joint.compliance != 0.0 {
+            // Compliant joints are only handled by the velocity solver.
+            return AnyJointPositionConstraint::Empty;
+        }
+
         let mut rb1 = &bodies[joint.body1];
         let mut rb2 = &bodies[joint.body2];
         let flipped = !rb2.is_dynamic();
@@ -150,6 +167,9 @@ impl AnyJointPositionConstraint {
             JointParams::RevoluteJoint(p) => AnyJointPositionConstraint::RevoluteGroundConstraint(
                 RevolutePositionGroundConstraint::from_params(rb1, rb2, p, flipped),
             ),
+            JointParams::GearJoint(p) => AnyJointPositionConstraint::GearGroundConstraint(
+                GearPositionGroundConstraint::from_params(rb1, rb2, p, flipped),
+            ),
         }
     }
 
@@ -200,6 +220,7 @@ impl AnyJointPositionConstraint {
                     WRevolutePositionGroundConstraint::from_params(rbs1, rbs2, joints, flipped),
                 )
             }
+            JointParams::GearJoint(_) => unreachable!("Gear joints don't have SIMD constraints."),
         }
     }
 
@@ -237,7 +258,9 @@ impl AnyJointPositionConstraint {
             AnyJointPositionConstraint::WRevoluteJoint(c) => c.solve(params, positions),
             #[cfg(all(feature = "dim3", feature = "simd-is-enabled"))]
             AnyJointPositionConstraint::WRevoluteGroundConstraint(c) => c.solve(params, positions),
-            AnyJointPositionConstraint::Empty => unreachable!(),
+            AnyJointPositionConstraint::GearJoint(c) => c.solve(params, positions),
+            AnyJointPositionConstraint::GearGroundConstraint(c) => c.solve(params, positions),
+            AnyJointPositionConstraint::Empty => {}
         }
     }
 }
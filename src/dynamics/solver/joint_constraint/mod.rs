@@ -33,6 +33,8 @@ pub(self) use fixed_velocity_constraint_wide::{
 //     WGenericVelocityConstraint, WGenericVelocityGroundConstraint,
 // };
 
+pub(self) use gear_position_constraint::{GearPositionConstraint, GearPositionGroundConstraint};
+pub(self) use gear_velocity_constraint::{GearVelocityConstraint, GearVelocityGroundConstraint};
 pub(crate) use joint_constraint::AnyJointVelocityConstraint;
 pub(crate) use joint_position_constraint::AnyJointPositionConstraint;
 pub(self) use prismatic_position_constraint::{
@@ -78,6 +80,8 @@ mod fixed_position_constraint_wide;
 mod fixed_velocity_constraint;
 #[cfg(feature = "simd-is-enabled")]
 mod fixed_velocity_constraint_wide;
+mod gear_position_constraint;
+mod gear_velocity_constraint;
 // mod generic_position_constraint;
 // #[cfg(feature = "simd-is-enabled")]
 // mod generic_position_constraint_wide;
@@ -48,7 +48,12 @@ impl PrismaticPositionConstraint {
             local_axis2: cparams.local_axis2,
             position1: rb1.active_set_offset,
             position2: rb2.active_set_offset,
-            limits: cparams.limits,
+            // Soft limits are only enforced by the velocity solver.
+            limits: if cparams.has_soft_limits() {
+                [-Real::MAX, Real::MAX]
+            } else {
+                cparams.limits
+            },
         }
     }
 
@@ -136,7 +141,12 @@ impl PrismaticPositionGroundConstraint {
             axis1,
             local_axis2,
             position2: rb2.active_set_offset,
-            limits: cparams.limits,
+            // Soft limits are only enforced by the velocity solver.
+            limits: if cparams.has_soft_limits() {
+                [-Real::MAX, Real::MAX]
+            } else {
+                cparams.limits
+            },
         }
     }
 
@@ -40,6 +40,7 @@ pub(crate) struct PrismaticVelocityConstraint {
     rhs: Vector2<Real>,
     #[cfg(feature = "dim2")]
     impulse: Vector2<Real>,
+    compliance_coeff: Real,
 
     motor_axis1: Vector<Real>,
     motor_axis2: Vector<Real>,
@@ -48,6 +49,11 @@ pub(crate) struct PrismaticVelocityConstraint {
     motor_inv_lhs: Real,
     motor_max_impulse: Real,
 
+    friction_inv_lhs: Real,
+    friction_rhs: Real,
+    friction_impulse: Real,
+    friction_max_impulse: Real,
+
     limits_active: bool,
     limits_impulse: Real,
     /// World-coordinate direction of the limit force on rb2.
@@ -77,6 +83,7 @@ impl PrismaticVelocityConstraint {
         rb1: &RigidBody,
         rb2: &RigidBody,
         joint: &PrismaticJoint,
+        compliance: Real,
     ) -> Self {
         // Linear part.
         let anchor1 = rb1.position * joint.local_anchor1;
@@ -101,7 +108,6 @@ impl PrismaticVelocityConstraint {
         let r2 = anchor2 - rb2.world_com;
         let r2_mat = r2.gcross_matrix();
 
-        #[allow(unused_mut)] // For 2D.
         let mut lhs;
 
         #[cfg(feature = "dim3")]
@@ -130,6 +136,18 @@ impl PrismaticVelocityConstraint {
             lhs = SdpMatrix2::new(m11, m12, m22);
         }
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        #[cfg(feature = "dim2")]
+        {
+            lhs.m11 += compliance_coeff;
+            lhs.m22 += compliance_coeff;
+        }
+        #[cfg(feature = "dim3")]
+        {
+            let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+            lhs.set_diagonal(&diagonal);
+        }
+
         let anchor_linvel1 = rb1.linvel + rb1.angvel.gcross(r1);
         let anchor_linvel2 = rb2.linvel + rb2.angvel.gcross(r2);
 
@@ -154,7 +172,7 @@ impl PrismaticVelocityConstraint {
             angvel_err.z,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let linear_err = basis1.tr_mul(&(anchor2 - anchor1));
 
@@ -207,6 +225,24 @@ impl PrismaticVelocityConstraint {
             joint.motor_max_impulse,
         );
 
+        /*
+         * Setup friction.
+         */
+        let mut friction_inv_lhs = 0.0;
+        let mut friction_rhs = 0.0;
+        let friction_max_impulse = joint.friction.max(0.0) * params.dt;
+
+        if friction_max_impulse != 0.0 {
+            friction_inv_lhs = crate::utils::inv(im1 + im2);
+            friction_rhs = rb2.linvel.dot(&axis2) - rb1.linvel.dot(&axis1);
+        }
+
+        let friction_impulse = na::clamp(
+            joint.friction_impulse,
+            -friction_max_impulse,
+            friction_max_impulse,
+        ) * params.warmstart_coeff;
+
         // Setup limit constraint.
         let mut limits_active = false;
         let limits_forcedir2 = axis2.into_inner(); // hopefully axis1 is colinear with axis2
@@ -230,11 +266,8 @@ impl PrismaticVelocityConstraint {
 
             limits_active = min_enabled || max_enabled;
             if limits_active {
-                limits_rhs = (anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1))
-                    * params.velocity_solve_fraction;
-
-                limits_rhs += ((dist - max_limit).max(0.0) - (min_limit - dist).max(0.0))
-                    * velocity_based_erp_inv_dt;
+                let curr_vel = anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1);
+                let limit_err = (dist - max_limit).max(0.0) - (min_limit - dist).max(0.0);
 
                 let gcross1 = r1.gcross(*axis1);
                 let gcross2 = r2.gcross(*axis2);
@@ -244,6 +277,31 @@ impl PrismaticVelocityConstraint {
                         + gcross2.gdot(ii2.transform_vector(gcross2)),
                 );
 
+                if joint.has_soft_limits() {
+                    let (stiffness, damping, gamma, keep_lhs) =
+                        joint.limits_model.combine_coefficients(
+                            params.dt,
+                            joint.limits_stiffness,
+                            joint.limits_damping,
+                        );
+                    limits_rhs =
+                        (limit_err * stiffness + curr_vel * damping) * crate::utils::inv(gamma);
+                    limits_inv_lhs = if keep_lhs {
+                        gamma * limits_inv_lhs
+                    } else {
+                        gamma
+                    };
+                } else if joint.limits_restitution != 0.0
+                    && (if max_enabled { curr_vel } else { -curr_vel })
+                        > joint.limits_restitution_threshold
+                {
+                    // The limit is hit fast enough to bounce back, like a bouncy contact.
+                    limits_rhs = (1.0 + joint.limits_restitution) * curr_vel;
+                } else {
+                    limits_rhs = curr_vel * params.velocity_solve_fraction
+                        + limit_err * velocity_based_erp_inv_dt;
+                }
+
                 limits_impulse = joint
                     .limits_impulse
                     .max(limits_impulse_limits.0)
@@ -260,6 +318,7 @@ impl PrismaticVelocityConstraint {
             im2,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse: joint.impulse * params.warmstart_coeff,
+            compliance_coeff,
             limits_active,
             limits_impulse: limits_impulse * params.warmstart_coeff,
             limits_forcedir2,
@@ -272,6 +331,10 @@ impl PrismaticVelocityConstraint {
             motor_axis1: *axis1,
             motor_axis2: *axis2,
             motor_max_impulse: joint.motor_max_impulse,
+            friction_inv_lhs,
+            friction_rhs,
+            friction_impulse,
+            friction_max_impulse,
             basis1,
             inv_lhs,
             rhs,
@@ -304,6 +367,10 @@ impl PrismaticVelocityConstraint {
         mj_lambda1.linear += self.motor_axis1 * (self.im1 * self.motor_impulse);
         mj_lambda2.linear -= self.motor_axis2 * (self.im2 * self.motor_impulse);
 
+        // Warmstart friction.
+        mj_lambda1.linear += self.motor_axis1 * (self.im1 * self.friction_impulse);
+        mj_lambda2.linear -= self.motor_axis2 * (self.im2 * self.friction_impulse);
+
         // Warmstart limits.
         if self.limits_active {
             let limit_impulse1 = -self.limits_forcedir2 * self.limits_impulse;
@@ -334,7 +401,7 @@ impl PrismaticVelocityConstraint {
         #[cfg(feature = "dim3")]
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, ang_dvel.x, ang_dvel.y, ang_dvel.z) + self.rhs;
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
         self.impulse += impulse;
         let lin_impulse = self.basis1 * impulse.fixed_rows::<LinImpulseDim>(0).into_owned();
         #[cfg(feature = "dim2")]
@@ -398,12 +465,31 @@ impl PrismaticVelocityConstraint {
         }
     }
 
+    fn solve_friction(&mut self, mj_lambda1: &mut DeltaVel<Real>, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.friction_inv_lhs != 0.0 {
+            let lin_dvel = self.motor_axis2.dot(&mj_lambda2.linear)
+                - self.motor_axis1.dot(&mj_lambda1.linear)
+                + self.friction_rhs;
+            let new_impulse = na::clamp(
+                self.friction_impulse + lin_dvel * self.friction_inv_lhs,
+                -self.friction_max_impulse,
+                self.friction_max_impulse,
+            );
+            let dimpulse = new_impulse - self.friction_impulse;
+            self.friction_impulse = new_impulse;
+
+            mj_lambda1.linear += self.motor_axis1 * (self.im1 * dimpulse);
+            mj_lambda2.linear -= self.motor_axis2 * (self.im2 * dimpulse);
+        }
+    }
+
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         self.solve_limits(&mut mj_lambda1, &mut mj_lambda2);
         self.solve_motors(&mut mj_lambda1, &mut mj_lambda2);
+        self.solve_friction(&mut mj_lambda1, &mut mj_lambda2);
         self.solve_dofs(&mut mj_lambda1, &mut mj_lambda2);
 
         mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
@@ -415,6 +501,7 @@ impl PrismaticVelocityConstraint {
         if let JointParams::PrismaticJoint(revolute) = &mut joint.params {
             revolute.impulse = self.impulse;
             revolute.motor_impulse = self.motor_impulse;
+            revolute.friction_impulse = self.friction_impulse;
             revolute.limits_impulse = self.limits_impulse;
         }
     }
@@ -434,6 +521,7 @@ pub(crate) struct PrismaticVelocityGroundConstraint {
     rhs: Vector2<Real>,
     #[cfg(feature = "dim2")]
     impulse: Vector2<Real>,
+    compliance_coeff: Real,
 
     #[cfg(feature = "dim3")]
     inv_lhs: Matrix5<Real>,
@@ -446,6 +534,7 @@ pub(crate) struct PrismaticVelocityGroundConstraint {
     limits_forcedir2: Vector<Real>,
     limits_impulse: Real,
     limits_rhs: Real,
+    limits_inv_lhs: Real,
     /// min/max applied impulse due to limits
     limits_impulse_limits: (Real, Real),
 
@@ -455,6 +544,11 @@ pub(crate) struct PrismaticVelocityGroundConstraint {
     motor_inv_lhs: Real,
     motor_max_impulse: Real,
 
+    friction_inv_lhs: Real,
+    friction_rhs: Real,
+    friction_impulse: Real,
+    friction_max_impulse: Real,
+
     #[cfg(feature = "dim2")]
     basis1: Vector2<Real>,
     #[cfg(feature = "dim3")]
@@ -472,6 +566,7 @@ impl PrismaticVelocityGroundConstraint {
         rb2: &RigidBody,
         joint: &PrismaticJoint,
         flipped: bool,
+        compliance: Real,
     ) -> Self {
         let anchor2;
         let anchor1;
@@ -533,7 +628,6 @@ impl PrismaticVelocityGroundConstraint {
         let r2 = anchor2 - rb2.world_com;
         let r2_mat = r2.gcross_matrix();
 
-        #[allow(unused_mut)] // For 2D.
         let mut lhs;
 
         #[cfg(feature = "dim3")]
@@ -559,6 +653,18 @@ impl PrismaticVelocityGroundConstraint {
             lhs = SdpMatrix2::new(m11, m12, m22);
         }
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        #[cfg(feature = "dim2")]
+        {
+            lhs.m11 += compliance_coeff;
+            lhs.m22 += compliance_coeff;
+        }
+        #[cfg(feature = "dim3")]
+        {
+            let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+            lhs.set_diagonal(&diagonal);
+        }
+
         let anchor_linvel1 = rb1.linvel + rb1.angvel.gcross(r1);
         let anchor_linvel2 = rb2.linvel + rb2.angvel.gcross(r2);
 
@@ -583,7 +689,7 @@ impl PrismaticVelocityGroundConstraint {
             angvel_err.z,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let linear_err = basis1.tr_mul(&(anchor2 - anchor1));
 
@@ -642,6 +748,24 @@ impl PrismaticVelocityGroundConstraint {
             joint.motor_max_impulse,
         );
 
+        /*
+         * Setup friction.
+         */
+        let mut friction_inv_lhs = 0.0;
+        let mut friction_rhs = 0.0;
+        let friction_max_impulse = joint.friction.max(0.0) * params.dt;
+
+        if friction_max_impulse != 0.0 {
+            friction_inv_lhs = crate::utils::inv(im2);
+            friction_rhs = rb2.linvel.dot(&axis2) - rb1.linvel.dot(&axis1);
+        }
+
+        let friction_impulse = na::clamp(
+            joint.friction_impulse,
+            -friction_max_impulse,
+            friction_max_impulse,
+        ) * params.warmstart_coeff;
+
         /*
          * Setup limit constraint.
          */
@@ -649,6 +773,7 @@ impl PrismaticVelocityGroundConstraint {
         let limits_forcedir2 = axis2.into_inner();
         let mut limits_rhs = 0.0;
         let mut limits_impulse = 0.0;
+        let mut limits_inv_lhs = 0.0;
         let mut limits_impulse_limits = (0.0, 0.0);
 
         if joint.limits_enabled {
@@ -666,11 +791,34 @@ impl PrismaticVelocityGroundConstraint {
 
             limits_active = min_enabled || max_enabled;
             if limits_active {
-                limits_rhs = (anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1))
-                    * params.velocity_solve_fraction;
-
-                limits_rhs += ((dist - max_limit).max(0.0) - (min_limit - dist).max(0.0))
-                    * velocity_based_erp_inv_dt;
+                let curr_vel = anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1);
+                let limit_err = (dist - max_limit).max(0.0) - (min_limit - dist).max(0.0);
+                limits_inv_lhs = crate::utils::inv(im2);
+
+                if joint.has_soft_limits() {
+                    let (stiffness, damping, gamma, keep_lhs) =
+                        joint.limits_model.combine_coefficients(
+                            params.dt,
+                            joint.limits_stiffness,
+                            joint.limits_damping,
+                        );
+                    limits_rhs =
+                        (limit_err * stiffness + curr_vel * damping) * crate::utils::inv(gamma);
+                    limits_inv_lhs = if keep_lhs {
+                        gamma * limits_inv_lhs
+                    } else {
+                        gamma
+                    };
+                } else if joint.limits_restitution != 0.0
+                    && (if max_enabled { curr_vel } else { -curr_vel })
+                        > joint.limits_restitution_threshold
+                {
+                    // The limit is hit fast enough to bounce back, like a bouncy contact.
+                    limits_rhs = (1.0 + joint.limits_restitution) * curr_vel;
+                } else {
+                    limits_rhs = curr_vel * params.velocity_solve_fraction
+                        + limit_err * velocity_based_erp_inv_dt;
+                }
 
                 limits_impulse = joint
                     .limits_impulse
@@ -685,15 +833,21 @@ impl PrismaticVelocityGroundConstraint {
             im2,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse: joint.impulse * params.warmstart_coeff,
+            compliance_coeff,
             limits_active,
             limits_forcedir2,
             limits_impulse: limits_impulse * params.warmstart_coeff,
             limits_rhs,
+            limits_inv_lhs,
             limits_impulse_limits,
             motor_rhs,
             motor_inv_lhs,
             motor_impulse,
             motor_max_impulse: joint.motor_max_impulse,
+            friction_inv_lhs,
+            friction_rhs,
+            friction_impulse,
+            friction_max_impulse,
             basis1,
             inv_lhs,
             rhs,
@@ -719,6 +873,9 @@ impl PrismaticVelocityGroundConstraint {
         // Warmstart motors.
         mj_lambda2.linear -= self.axis2 * (self.im2 * self.motor_impulse);
 
+        // Warmstart friction.
+        mj_lambda2.linear -= self.axis2 * (self.im2 * self.friction_impulse);
+
         // Warmstart limits.
         mj_lambda2.linear += self.limits_forcedir2 * (self.im2 * self.limits_impulse);
 
@@ -735,7 +892,7 @@ impl PrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, ang_dvel.x, ang_dvel.y, ang_dvel.z) + self.rhs;
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
         self.impulse += impulse;
         let lin_impulse = self.basis1 * impulse.fixed_rows::<LinImpulseDim>(0).into_owned();
         #[cfg(feature = "dim2")]
@@ -757,7 +914,7 @@ impl PrismaticVelocityGroundConstraint {
                 .limits_forcedir2
                 .dot(&(mj_lambda2.linear + ang_vel2.gcross(self.r2)))
                 + self.limits_rhs;
-            let new_impulse = (self.limits_impulse - lin_dvel / self.im2)
+            let new_impulse = (self.limits_impulse - lin_dvel * self.limits_inv_lhs)
                 .max(self.limits_impulse_limits.0)
                 .min(self.limits_impulse_limits.1);
             let dimpulse = new_impulse - self.limits_impulse;
@@ -782,11 +939,27 @@ impl PrismaticVelocityGroundConstraint {
         }
     }
 
+    fn solve_friction(&mut self, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.friction_inv_lhs != 0.0 {
+            let lin_dvel = self.axis2.dot(&mj_lambda2.linear) + self.friction_rhs;
+            let new_impulse = na::clamp(
+                self.friction_impulse + lin_dvel * self.friction_inv_lhs,
+                -self.friction_max_impulse,
+                self.friction_max_impulse,
+            );
+            let dimpulse = new_impulse - self.friction_impulse;
+            self.friction_impulse = new_impulse;
+
+            mj_lambda2.linear -= self.axis2 * (self.im2 * dimpulse);
+        }
+    }
+
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         self.solve_limits(&mut mj_lambda2);
         self.solve_motors(&mut mj_lambda2);
+        self.solve_friction(&mut mj_lambda2);
         self.solve_dofs(&mut mj_lambda2);
 
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
@@ -798,6 +971,7 @@ impl PrismaticVelocityGroundConstraint {
         if let JointParams::PrismaticJoint(revolute) = &mut joint.params {
             revolute.impulse = self.impulse;
             revolute.motor_impulse = self.motor_impulse;
+            revolute.friction_impulse = self.friction_impulse;
             revolute.limits_impulse = self.limits_impulse;
         }
     }
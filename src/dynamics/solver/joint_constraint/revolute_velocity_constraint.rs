@@ -19,6 +19,7 @@ pub(crate) struct RevoluteVelocityConstraint {
     inv_lhs: Matrix5<Real>,
     rhs: Vector5<Real>,
     impulse: Vector5<Real>,
+    compliance_coeff: Real,
 
     motor_inv_lhs: Real,
     motor_rhs: Real,
@@ -26,6 +27,11 @@ pub(crate) struct RevoluteVelocityConstraint {
     motor_max_impulse: Real,
     motor_angle: Real, // Exists only to write it back into the joint.
 
+    friction_inv_lhs: Real,
+    friction_rhs: Real,
+    friction_impulse: Real,
+    friction_max_impulse: Real,
+
     motor_axis1: Vector<Real>,
     motor_axis2: Vector<Real>,
 
@@ -46,6 +52,7 @@ impl RevoluteVelocityConstraint {
         rb1: &RigidBody,
         rb2: &RigidBody,
         joint: &RevoluteJoint,
+        compliance: Real,
     ) -> Self {
         // Linear part.
         let anchor1 = rb1.position * joint.local_anchor1;
@@ -87,6 +94,10 @@ impl RevoluteVelocityConstraint {
         lhs.fixed_slice_mut::<U2, U3>(3, 0).copy_from(&lhs10);
         lhs.fixed_slice_mut::<U2, U2>(3, 3).copy_from(&lhs11);
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+        lhs.set_diagonal(&diagonal);
+
         let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
 
         let linvel_err =
@@ -101,7 +112,7 @@ impl RevoluteVelocityConstraint {
             angvel_err.y,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let lin_err = anchor2 - anchor1;
 
@@ -153,6 +164,27 @@ impl RevoluteVelocityConstraint {
             motor_rhs /= gamma;
         }
 
+        /*
+         * Friction.
+         */
+        let mut friction_inv_lhs = 0.0;
+        let mut friction_rhs = 0.0;
+        let friction_max_impulse = joint.friction.max(0.0) * params.dt;
+
+        if friction_max_impulse != 0.0 {
+            friction_inv_lhs = crate::utils::inv(
+                motor_axis2.dot(&ii2.transform_vector(motor_axis2))
+                    + motor_axis1.dot(&ii1.transform_vector(motor_axis1)),
+            );
+            friction_rhs = rb2.angvel.dot(&motor_axis2) - rb1.angvel.dot(&motor_axis1);
+        }
+
+        let friction_impulse = na::clamp(
+            joint.friction_impulse,
+            -friction_max_impulse,
+            friction_max_impulse,
+        ) * params.warmstart_coeff;
+
         /*
          * Adjust the warmstart impulse.
          * If the velocity along the free axis is somewhat high,
@@ -180,6 +212,7 @@ impl RevoluteVelocityConstraint {
             im2,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse,
+            compliance_coeff,
             inv_lhs,
             rhs,
             r1,
@@ -191,6 +224,10 @@ impl RevoluteVelocityConstraint {
             motor_axis2,
             motor_impulse,
             motor_angle,
+            friction_inv_lhs,
+            friction_rhs,
+            friction_impulse,
+            friction_max_impulse,
         }
     }
 
@@ -225,6 +262,18 @@ impl RevoluteVelocityConstraint {
                 .transform_vector(self.motor_axis2 * self.motor_impulse);
         }
 
+        /*
+         * Friction
+         */
+        if self.friction_inv_lhs != 0.0 {
+            mj_lambda1.angular += self
+                .ii1_sqrt
+                .transform_vector(self.motor_axis1 * self.friction_impulse);
+            mj_lambda2.angular -= self
+                .ii2_sqrt
+                .transform_vector(self.motor_axis2 * self.friction_impulse);
+        }
+
         mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
@@ -238,7 +287,7 @@ impl RevoluteVelocityConstraint {
         let ang_dvel = self.basis2.tr_mul(&ang_vel2) - self.basis1.tr_mul(&ang_vel1);
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y) + self.rhs;
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
         self.impulse += impulse;
         let lin_impulse1 = impulse.fixed_rows::<U3>(0).into_owned();
         let lin_impulse2 = impulse.fixed_rows::<U3>(0).into_owned();
@@ -276,12 +325,33 @@ impl RevoluteVelocityConstraint {
         }
     }
 
+    fn solve_friction(&mut self, mj_lambda1: &mut DeltaVel<Real>, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.friction_inv_lhs != 0.0 {
+            let ang_vel1 = self.ii1_sqrt.transform_vector(mj_lambda1.angular);
+            let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
+            let ang_dvel = ang_vel2.dot(&self.motor_axis2) - ang_vel1.dot(&self.motor_axis1);
+            let rhs = ang_dvel + self.friction_rhs;
+
+            let new_friction_impulse = na::clamp(
+                self.friction_impulse + self.friction_inv_lhs * rhs,
+                -self.friction_max_impulse,
+                self.friction_max_impulse,
+            );
+            let impulse = new_friction_impulse - self.friction_impulse;
+            self.friction_impulse = new_friction_impulse;
+
+            mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.motor_axis1 * impulse);
+            mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.motor_axis2 * impulse);
+        }
+    }
+
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         self.solve_dofs(&mut mj_lambda1, &mut mj_lambda2);
         self.solve_motors(&mut mj_lambda1, &mut mj_lambda2);
+        self.solve_friction(&mut mj_lambda1, &mut mj_lambda2);
 
         mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
@@ -296,6 +366,7 @@ impl RevoluteVelocityConstraint {
             revolute.prev_axis1 = self.motor_axis1;
             revolute.motor_last_angle = self.motor_angle;
             revolute.motor_impulse = self.motor_impulse;
+            revolute.friction_impulse = self.friction_impulse;
         }
     }
 }
@@ -311,6 +382,7 @@ pub(crate) struct RevoluteVelocityGroundConstraint {
     inv_lhs: Matrix5<Real>,
     rhs: Vector5<Real>,
     impulse: Vector5<Real>,
+    compliance_coeff: Real,
 
     motor_axis2: Vector<Real>,
     motor_inv_lhs: Real,
@@ -319,6 +391,11 @@ pub(crate) struct RevoluteVelocityGroundConstraint {
     motor_max_impulse: Real,
     motor_angle: Real, // Exists just for writing it into the joint.
 
+    friction_inv_lhs: Real,
+    friction_rhs: Real,
+    friction_impulse: Real,
+    friction_max_impulse: Real,
+
     basis2: Matrix3x2<Real>,
 
     im2: Real,
@@ -334,6 +411,7 @@ impl RevoluteVelocityGroundConstraint {
         rb2: &RigidBody,
         joint: &RevoluteJoint,
         flipped: bool,
+        compliance: Real,
     ) -> AnyJointVelocityConstraint {
         let anchor2;
         let anchor1;
@@ -390,6 +468,10 @@ impl RevoluteVelocityGroundConstraint {
         lhs.fixed_slice_mut::<U2, U3>(3, 0).copy_from(&lhs10);
         lhs.fixed_slice_mut::<U2, U2>(3, 3).copy_from(&lhs11);
 
+        let compliance_coeff = params.joint_compliance_coeff(compliance);
+        let diagonal = lhs.diagonal().add_scalar(compliance_coeff);
+        lhs.set_diagonal(&diagonal);
+
         let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
 
         let linvel_err =
@@ -403,7 +485,7 @@ impl RevoluteVelocityGroundConstraint {
             angvel_err.y,
         ) * params.velocity_solve_fraction;
 
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.joint_velocity_based_erp_inv_dt(compliance);
         if velocity_based_erp_inv_dt != 0.0 {
             let lin_err = anchor2 - anchor1;
 
@@ -458,12 +540,31 @@ impl RevoluteVelocityGroundConstraint {
         let motor_impulse = na::clamp(joint.motor_impulse, -motor_max_impulse, motor_max_impulse)
             * params.warmstart_coeff;
 
+        /*
+         * Friction part.
+         */
+        let mut friction_inv_lhs = 0.0;
+        let mut friction_rhs = 0.0;
+        let friction_max_impulse = joint.friction.max(0.0) * params.dt;
+
+        if friction_max_impulse != 0.0 {
+            friction_inv_lhs = crate::utils::inv(axis2.dot(&ii2.transform_vector(axis2)));
+            friction_rhs = rb2.angvel.dot(&axis2) - rb1.angvel.dot(&axis1);
+        }
+
+        let friction_impulse = na::clamp(
+            joint.friction_impulse,
+            -friction_max_impulse,
+            friction_max_impulse,
+        ) * params.warmstart_coeff;
+
         let result = RevoluteVelocityGroundConstraint {
             joint_id,
             mj_lambda2: rb2.active_set_offset,
             im2,
             ii2_sqrt: rb2.effective_world_inv_inertia_sqrt,
             impulse: joint.impulse * params.warmstart_coeff,
+            compliance_coeff,
             basis2,
             inv_lhs,
             rhs,
@@ -474,6 +575,10 @@ impl RevoluteVelocityGroundConstraint {
             motor_max_impulse,
             motor_rhs,
             motor_angle,
+            friction_inv_lhs,
+            friction_rhs,
+            friction_impulse,
+            friction_max_impulse,
         };
 
         AnyJointVelocityConstraint::RevoluteGroundConstraint(result)
@@ -499,6 +604,15 @@ impl RevoluteVelocityGroundConstraint {
                 .transform_vector(self.motor_axis2 * self.motor_impulse);
         }
 
+        /*
+         * Friction
+         */
+        if self.friction_inv_lhs != 0.0 {
+            mj_lambda2.angular -= self
+                .ii2_sqrt
+                .transform_vector(self.motor_axis2 * self.friction_impulse);
+        }
+
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
@@ -509,7 +623,7 @@ impl RevoluteVelocityGroundConstraint {
         let ang_dvel = self.basis2.tr_mul(&ang_vel2);
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y) + self.rhs;
-        let impulse = self.inv_lhs * rhs;
+        let impulse = self.inv_lhs * (rhs - self.impulse * self.compliance_coeff);
         self.impulse += impulse;
         let lin_impulse = impulse.fixed_rows::<U3>(0).into_owned();
         let ang_impulse = self.basis2 * impulse.fixed_rows::<U2>(3).into_owned();
@@ -538,11 +652,30 @@ impl RevoluteVelocityGroundConstraint {
         }
     }
 
+    fn solve_friction(&mut self, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.friction_inv_lhs != 0.0 {
+            let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
+            let ang_dvel = ang_vel2.dot(&self.motor_axis2);
+            let rhs = ang_dvel + self.friction_rhs;
+
+            let new_friction_impulse = na::clamp(
+                self.friction_impulse + self.friction_inv_lhs * rhs,
+                -self.friction_max_impulse,
+                self.friction_max_impulse,
+            );
+            let impulse = new_friction_impulse - self.friction_impulse;
+            self.friction_impulse = new_friction_impulse;
+
+            mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.motor_axis2 * impulse);
+        }
+    }
+
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         self.solve_dofs(&mut mj_lambda2);
         self.solve_motors(&mut mj_lambda2);
+        self.solve_friction(&mut mj_lambda2);
 
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
@@ -553,6 +686,7 @@ impl RevoluteVelocityGroundConstraint {
         if let JointParams::RevoluteJoint(revolute) = &mut joint.params {
             revolute.impulse = self.impulse;
             revolute.motor_impulse = self.motor_impulse;
+            revolute.friction_impulse = self.friction_impulse;
             revolute.motor_last_angle = self.motor_angle;
         }
     }
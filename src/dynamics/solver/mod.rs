@@ -1,4 +1,6 @@
 #[cfg(not(feature = "parallel"))]
+pub use self::custom_velocity_constraint::{CustomVelocityConstraint, SolverBody};
+#[cfg(not(feature = "parallel"))]
 pub(crate) use self::island_solver::IslandSolver;
 #[cfg(feature = "parallel")]
 pub(crate) use self::parallel_island_solver::{ParallelIslandSolver, ThreadContext};
@@ -14,7 +16,7 @@ pub(self) use self::position_solver::PositionSolver;
 pub(self) use self::solver_constraints::SolverConstraints;
 #[cfg(not(feature = "parallel"))]
 pub(self) use self::velocity_solver::VelocitySolver;
-pub(self) use delta_vel::DeltaVel;
+pub use delta_vel::DeltaVel;
 pub(self) use interaction_groups::*;
 pub(self) use joint_constraint::*;
 pub(self) use position_constraint::*;
@@ -31,6 +33,8 @@ pub(self) use velocity_ground_constraint::*;
 pub(self) use velocity_ground_constraint_wide::*;
 
 mod categorization;
+#[cfg(not(feature = "parallel"))]
+mod custom_velocity_constraint;
 mod delta_vel;
 mod interaction_groups;
 #[cfg(not(feature = "parallel"))]
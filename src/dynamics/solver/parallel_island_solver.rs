@@ -1,3 +1,4 @@
+use super::clamp_position_correction;
 use super::{DeltaVel, ParallelInteractionGroups, ParallelVelocitySolver};
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyJointVelocityConstraint, AnyPositionConstraint,
@@ -6,6 +7,7 @@ use crate::dynamics::solver::{
 use crate::dynamics::{IntegrationParameters, JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
 use crate::math::{Isometry, Real};
+use crate::pipeline::{IslandModificationContext, PhysicsHooks, PhysicsHooksFlags};
 use crate::utils::WAngularInertia;
 use rayon::Scope;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -146,6 +148,16 @@ impl ParallelIslandSolver {
         }
     }
 
+    /// Releases the memory of the solver buffers that is not currently used.
+    pub fn shrink_to_fit(&mut self) {
+        self.mj_lambdas.shrink_to_fit();
+        self.positions.shrink_to_fit();
+        self.parallel_groups.shrink_to_fit();
+        self.parallel_joint_groups.shrink_to_fit();
+        self.parallel_contact_constraints.shrink_to_fit();
+        self.parallel_joint_constraints.shrink_to_fit();
+    }
+
     pub fn solve_island<'s>(
         &'s mut self,
         scope: &Scope<'s>,
@@ -156,6 +168,7 @@ impl ParallelIslandSolver {
         manifold_indices: &'s [ContactManifoldIndex],
         joints: &'s mut Vec<JointGraphEdge>,
         joint_indices: &[JointIndex],
+        hooks: &dyn PhysicsHooks,
     ) {
         let num_threads = rayon::current_num_threads();
         let num_task_per_island = num_threads; // (num_threads / num_islands).max(1); // TODO: not sure this is the best value. Also, perhaps it is better to interleave tasks of each island?
@@ -209,6 +222,17 @@ impl ParallelIslandSolver {
             }
         }
 
+        // The constraints only depend on the velocities of the rigid-bodies, not on
+        // `mj_lambdas`, so the island hook can run before they are initialized by the tasks.
+        if hooks
+            .active_hooks()
+            .contains(PhysicsHooksFlags::MODIFY_ISLAND)
+        {
+            let mut context =
+                IslandModificationContext::new(island_id, bodies, &mut self.mj_lambdas);
+            hooks.modify_island(&mut context);
+        }
+
         for _ in 0..num_task_per_island {
             // We use AtomicPtr because it is Send+Sync while *mut is not.
             // See https://internals.rust-lang.org/t/shouldnt-pointers-be-send-sync-or/8818
@@ -298,7 +322,8 @@ impl ParallelIslandSolver {
                     let batch_size = thread.batch_size;
                     for handle in active_bodies[thread.position_writeback_index] {
                         let rb = &mut bodies[handle.0];
-                        rb.set_position_internal(positions[rb.active_set_offset]);
+                        let new_pos = clamp_position_correction(rb, &positions[rb.active_set_offset], params);
+                        rb.set_position_internal(new_pos);
                     }
                 }
             })
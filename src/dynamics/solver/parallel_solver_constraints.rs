@@ -67,6 +67,21 @@ impl<VelocityConstraint, PositionConstraint>
             parallel_desc_groups: Vec::new(),
         }
     }
+
+    /// Releases the memory of the constraint buffers that is not currently used.
+    ///
+    /// The constraint buffers are cleared but never shrunk automatically from one step to
+    /// the other, so they keep enough capacity for the largest number of constraints seen so far.
+    pub fn shrink_to_fit(&mut self) {
+        self.not_ground_interactions.shrink_to_fit();
+        self.ground_interactions.shrink_to_fit();
+        self.interaction_groups.shrink_to_fit();
+        self.ground_interaction_groups.shrink_to_fit();
+        self.velocity_constraints.shrink_to_fit();
+        self.position_constraints.shrink_to_fit();
+        self.constraint_descs.shrink_to_fit();
+        self.parallel_desc_groups.shrink_to_fit();
+    }
 }
 
 macro_rules! impl_init_constraints_group {
@@ -276,6 +291,8 @@ impl ParallelSolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConst
                 match &desc.1 {
                     ConstraintDesc::NongroundNongrouped(joint_id) => {
                         let joint = &joints_all[*joint_id].weight;
+                        let resolved_joint = joint.with_body_frames();
+                        let joint = resolved_joint.as_ref().unwrap_or(joint);
                         let velocity_constraint = AnyJointVelocityConstraint::from_joint(params, *joint_id, joint, bodies);
                         let position_constraint = AnyJointPositionConstraint::from_joint(joint, bodies);
                         self.velocity_constraints[joint.constraint_index] = velocity_constraint;
@@ -283,6 +300,8 @@ impl ParallelSolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConst
                     }
                     ConstraintDesc::GroundNongrouped(joint_id) => {
                         let joint = &joints_all[*joint_id].weight;
+                        let resolved_joint = joint.with_body_frames();
+                        let joint = resolved_joint.as_ref().unwrap_or(joint);
                         let velocity_constraint = AnyJointVelocityConstraint::from_joint_ground(params, *joint_id, joint, bodies);
                         let position_constraint = AnyJointPositionConstraint::from_joint_ground(joint, bodies);
                         self.velocity_constraints[joint.constraint_index] = velocity_constraint;
@@ -61,8 +61,11 @@ impl ParallelVelocitySolver {
                                 &mut $part.velocity_constraints[$part.constraint_descs[start_index].0..$part.constraint_descs[end_index].0]
                             };
 
-                            for constraint in constraints {
-                                constraint.warmstart(mj_lambdas);
+                            // All the cached impulses are zero if the warmstart is disabled.
+                            if params.warmstart_coeff != 0.0 {
+                                for constraint in constraints {
+                                    constraint.warmstart(mj_lambdas);
+                                }
                             }
 
                             let num_solved = end_index - start_index;
@@ -111,7 +114,8 @@ impl ParallelVelocitySolver {
             let mut target_num_desc = 0;
             let mut shift = 0;
 
-            for _ in 0..params.max_velocity_iterations {
+            // NOTE: the parallel solver doesn't stop iterating early with `SolverMode::Adaptive`.
+            for _ in 0..params.velocity_iterations_limits().0 {
                 macro_rules! solve {
                     ($part: expr) => {
                         // Joint groups.
@@ -195,3 +199,75 @@ impl ParallelVelocitySolver {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_solver_with_many_contacts_per_body() {
+        // Runs a few steps of a dynamic plate supporting more than 128 balls, with the given
+        // number of threads, and returns the positions of all the bodies.
+        let run = |num_threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            #[cfg(feature = "dim2")]
+            let plate_shape = ColliderBuilder::cuboid(30.0, 0.1);
+            #[cfg(feature = "dim3")]
+            let plate_shape = ColliderBuilder::cuboid(30.0, 0.1, 30.0);
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -0.195, na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(plate_shape.build(), ground, &mut world.bodies);
+            let plate = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            world
+                .colliders
+                .insert(plate_shape.build(), plate, &mut world.bodies);
+
+            for i in 0..200 {
+                let ball = world.bodies.insert(
+                    RigidBodyBuilder::new_dynamic()
+                        .position(Isometry::new(
+                            Vector::x() * ((i as Real - 100.0) * 0.25) + Vector::y() * 0.195,
+                            na::zero(),
+                        ))
+                        .build(),
+                );
+                world
+                    .colliders
+                    .insert(ColliderBuilder::ball(0.1).build(), ball, &mut world.bodies);
+            }
+
+            pool.install(|| {
+                for _ in 0..10 {
+                    world.step();
+                }
+            });
+
+            assert!(world.bodies[plate].position().translation.vector.y > -0.01);
+            world
+                .bodies
+                .iter()
+                .map(|(_, rb)| format!("{:?}", rb.position()))
+                .collect::<Vec<_>>()
+        };
+
+        // The constraints are solved color by color, so the result doesn't depend on the
+        // number of threads.
+        assert_eq!(run(1), run(4));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::dynamics::solver::PositionGroundConstraint;
 #[cfg(feature = "simd-is-enabled")]
 use crate::dynamics::solver::{WPositionConstraint, WPositionGroundConstraint};
-use crate::dynamics::{IntegrationParameters, RigidBodySet};
+use crate::dynamics::{IntegrationParameters, RigidBody, RigidBodySet};
 use crate::geometry::ContactManifold;
 use crate::math::{
     AngularInertia, Isometry, Point, Real, Rotation, Translation, Vector, MAX_MANIFOLD_POINTS,
@@ -152,3 +152,43 @@ impl PositionConstraint {
         positions[self.rb2] = pos2;
     }
 }
+
+/// Limits the displacement of the center-of-mass of `rb` and its rotation, from its current
+/// position to `new_pos`, to the maximum corrections allowed by `params` for one timestep.
+pub(crate) fn clamp_position_correction(
+    rb: &RigidBody,
+    new_pos: &Isometry<Real>,
+    params: &IntegrationParameters,
+) -> Isometry<Real> {
+    let local_com = &rb.mass_properties.local_com;
+    let com = rb.position * local_com;
+    let mut delta_com = new_pos * local_com - com;
+    let mut delta_rot = new_pos.rotation * rb.position.rotation.inverse();
+
+    let linear_correction = delta_com.norm();
+    if linear_correction > params.max_linear_correction {
+        delta_com *= params.max_linear_correction / linear_correction;
+    }
+
+    #[cfg(feature = "dim2")]
+    {
+        let angle = delta_rot.angle();
+        if angle.abs() > params.max_angular_correction {
+            delta_rot = Rotation::new(params.max_angular_correction.copysign(angle));
+        }
+    }
+
+    #[cfg(feature = "dim3")]
+    {
+        let scaled_axis = delta_rot.scaled_axis();
+        let angle = scaled_axis.norm();
+        if angle > params.max_angular_correction {
+            delta_rot = Rotation::new(scaled_axis * (params.max_angular_correction / angle));
+        }
+    }
+
+    // Rebuild the position so the rotation is applied around the center-of-mass.
+    let rotation = delta_rot * rb.position.rotation;
+    let translation = com + delta_com - rotation * local_com;
+    Isometry::from_parts(translation.into(), rotation)
+}
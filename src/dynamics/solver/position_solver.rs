@@ -1,4 +1,4 @@
-use super::AnyJointPositionConstraint;
+use super::{clamp_position_correction, AnyJointPositionConstraint};
 use crate::dynamics::{solver::AnyPositionConstraint, IntegrationParameters, RigidBodySet};
 use crate::math::{Isometry, Real};
 
@@ -13,6 +13,10 @@ impl PositionSolver {
         }
     }
 
+    pub fn shrink_to_fit(&mut self) {
+        self.positions.shrink_to_fit();
+    }
+
     pub fn solve(
         &mut self,
         island_id: usize,
@@ -39,7 +43,97 @@ impl PositionSolver {
         }
 
         bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
-            rb.set_position_internal(self.positions[rb.active_set_offset])
+            let new_pos =
+                clamp_position_correction(rb, &self.positions[rb.active_set_offset], params);
+            rb.set_position_internal(new_pos)
         });
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn buried_rod_is_untangled_progressively() {
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.max_linear_correction = 0.05;
+        world.params.max_angular_correction = 0.02;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // A long rod, tilted, with one of its ends buried deep into the ground.
+        #[cfg(feature = "dim2")]
+        let tilt = 0.3;
+        #[cfg(feature = "dim3")]
+        let tilt = Vector::z() * 0.3;
+        let rod = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.5, tilt))
+                .can_sleep(false)
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let rod_shape = ColliderBuilder::cuboid(2.0, 0.1);
+        #[cfg(feature = "dim3")]
+        let rod_shape = ColliderBuilder::cuboid(2.0, 0.1, 0.1);
+        world
+            .colliders
+            .insert(rod_shape.build(), rod, &mut world.bodies);
+
+        let lowest_point = |pos: &Isometry<Real>| {
+            let mut lowest = Real::MAX;
+            for sx in [-2.0, 2.0].iter() {
+                for sy in [-0.1, 0.1].iter() {
+                    let pt = pos * Point::from(Vector::x() * *sx + Vector::y() * *sy);
+                    lowest = lowest.min(pt.y);
+                }
+            }
+            lowest
+        };
+        assert!(lowest_point(world.bodies[rod].position()) < -0.1);
+
+        for _ in 0..200 {
+            let prev_pos = *world.bodies[rod].position();
+            let prev_angvel = world.bodies[rod].angvel().clone();
+            world.step();
+
+            // The rotation due to the velocities is small, so most of the rotation
+            // of each step is due to the position solver.
+            let rb = &world.bodies[rod];
+            let delta_rot = rb.position().rotation * prev_pos.rotation.inverse();
+            #[cfg(feature = "dim2")]
+            let (angle, angvel) = (
+                delta_rot.angle().abs(),
+                prev_angvel.abs().max(rb.angvel().abs()),
+            );
+            #[cfg(feature = "dim3")]
+            let (angle, angvel) = (
+                delta_rot.angle(),
+                prev_angvel.norm().max(rb.angvel().norm()),
+            );
+            assert!(
+                angle <= world.params.max_angular_correction + angvel * world.params.dt + 1.0e-4,
+                "{} > {}",
+                angle,
+                world.params.max_angular_correction
+            );
+        }
+
+        // The rod got out of the ground.
+        assert!(lowest_point(world.bodies[rod].position()) > 0.5 - 0.02);
+    }
+}
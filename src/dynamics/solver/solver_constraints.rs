@@ -38,6 +38,19 @@ impl<VelocityConstraint, PositionConstraint>
             position_constraints: Vec::new(),
         }
     }
+
+    /// Releases the memory of the constraint buffers that is not currently used.
+    ///
+    /// The constraint buffers are cleared but never shrunk automatically from one step to
+    /// the other, so they keep enough capacity for the largest number of constraints seen so far.
+    pub fn shrink_to_fit(&mut self) {
+        self.not_ground_interactions.shrink_to_fit();
+        self.ground_interactions.shrink_to_fit();
+        self.interaction_groups.shrink_to_fit();
+        self.ground_interaction_groups.shrink_to_fit();
+        self.velocity_constraints.shrink_to_fit();
+        self.position_constraints.shrink_to_fit();
+    }
 }
 
 impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
@@ -130,13 +143,15 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
-            WPositionConstraint::generate(
-                params,
-                manifolds,
-                bodies,
-                &mut self.position_constraints,
-                true,
-            );
+            if params.max_position_iterations > 0 {
+                WPositionConstraint::generate(
+                    params,
+                    manifolds,
+                    bodies,
+                    &mut self.position_constraints,
+                    true,
+                );
+            }
         }
     }
 
@@ -156,13 +171,15 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
-            PositionConstraint::generate(
-                params,
-                manifold,
-                bodies,
-                &mut self.position_constraints,
-                true,
-            );
+            if params.max_position_iterations > 0 {
+                PositionConstraint::generate(
+                    params,
+                    manifold,
+                    bodies,
+                    &mut self.position_constraints,
+                    true,
+                );
+            }
         }
     }
 
@@ -188,13 +205,15 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
-            WPositionGroundConstraint::generate(
-                params,
-                manifolds,
-                bodies,
-                &mut self.position_constraints,
-                true,
-            );
+            if params.max_position_iterations > 0 {
+                WPositionGroundConstraint::generate(
+                    params,
+                    manifolds,
+                    bodies,
+                    &mut self.position_constraints,
+                    true,
+                );
+            }
         }
     }
 
@@ -214,13 +233,15 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
-            PositionGroundConstraint::generate(
-                params,
-                manifold,
-                bodies,
-                &mut self.position_constraints,
-                true,
-            )
+            if params.max_position_iterations > 0 {
+                PositionGroundConstraint::generate(
+                    params,
+                    manifold,
+                    bodies,
+                    &mut self.position_constraints,
+                    true,
+                );
+            }
         }
     }
 }
@@ -291,11 +312,15 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
     ) {
         for joint_i in &self.ground_interaction_groups.nongrouped_interactions {
             let joint = &joints_all[*joint_i].weight;
+            let resolved_joint = joint.with_body_frames();
+            let joint = resolved_joint.as_ref().unwrap_or(joint);
             let vel_constraint =
                 AnyJointVelocityConstraint::from_joint_ground(params, *joint_i, joint, bodies);
             self.velocity_constraints.push(vel_constraint);
-            let pos_constraint = AnyJointPositionConstraint::from_joint_ground(joint, bodies);
-            self.position_constraints.push(pos_constraint);
+            if params.max_position_iterations > 0 {
+                let pos_constraint = AnyJointPositionConstraint::from_joint_ground(joint, bodies);
+                self.position_constraints.push(pos_constraint);
+            }
         }
     }
 
@@ -318,8 +343,11 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             );
             self.velocity_constraints.push(vel_constraint);
 
-            let pos_constraint = AnyJointPositionConstraint::from_wide_joint_ground(joints, bodies);
-            self.position_constraints.push(pos_constraint);
+            if params.max_position_iterations > 0 {
+                let pos_constraint =
+                    AnyJointPositionConstraint::from_wide_joint_ground(joints, bodies);
+                self.position_constraints.push(pos_constraint);
+            }
         }
     }
 
@@ -331,11 +359,15 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
     ) {
         for joint_i in &self.interaction_groups.nongrouped_interactions {
             let joint = &joints_all[*joint_i].weight;
+            let resolved_joint = joint.with_body_frames();
+            let joint = resolved_joint.as_ref().unwrap_or(joint);
             let vel_constraint =
                 AnyJointVelocityConstraint::from_joint(params, *joint_i, joint, bodies);
             self.velocity_constraints.push(vel_constraint);
-            let pos_constraint = AnyJointPositionConstraint::from_joint(joint, bodies);
-            self.position_constraints.push(pos_constraint);
+            if params.max_position_iterations > 0 {
+                let pos_constraint = AnyJointPositionConstraint::from_joint(joint, bodies);
+                self.position_constraints.push(pos_constraint);
+            }
         }
     }
 
@@ -357,8 +389,10 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
                 AnyJointVelocityConstraint::from_wide_joint(params, joints_id, joints, bodies);
             self.velocity_constraints.push(vel_constraint);
 
-            let pos_constraint = AnyJointPositionConstraint::from_wide_joint(joints, bodies);
-            self.position_constraints.push(pos_constraint);
+            if params.max_position_iterations > 0 {
+                let pos_constraint = AnyJointPositionConstraint::from_wide_joint(joints, bodies);
+                self.position_constraints.push(pos_constraint);
+            }
         }
     }
 }
@@ -1,15 +1,21 @@
 use super::DeltaVel;
+#[cfg(not(feature = "parallel"))]
+use crate::dynamics::solver::CustomVelocityConstraint;
 use crate::dynamics::solver::VelocityGroundConstraint;
 #[cfg(feature = "simd-is-enabled")]
 use crate::dynamics::solver::{WVelocityConstraint, WVelocityGroundConstraint};
 use crate::dynamics::{IntegrationParameters, RigidBodySet};
+#[cfg(feature = "dim3")]
+use crate::geometry::AnisotropicFriction;
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+#[cfg(all(debug_assertions, feature = "simd-is-enabled"))]
+use crate::math::SIMD_WIDTH;
 use crate::math::{AngVector, Real, Vector, DIM, MAX_MANIFOLD_POINTS};
 use crate::utils::{WAngularInertia, WBasis, WCross, WDot};
 use simba::simd::SimdPartialOrd;
 
 //#[repr(align(64))]
-#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "parallel", derive(Copy, Clone, Debug))]
 pub(crate) enum AnyVelocityConstraint {
     NongroupedGround(VelocityGroundConstraint),
     Nongrouped(VelocityConstraint),
@@ -17,6 +23,8 @@ pub(crate) enum AnyVelocityConstraint {
     GroupedGround(WVelocityGroundConstraint),
     #[cfg(feature = "simd-is-enabled")]
     Grouped(WVelocityConstraint),
+    #[cfg(not(feature = "parallel"))]
+    Custom(Box<dyn CustomVelocityConstraint>),
     #[allow(dead_code)] // The Empty variant is only used with parallel code.
     Empty,
 }
@@ -48,6 +56,8 @@ impl AnyVelocityConstraint {
             AnyVelocityConstraint::GroupedGround(c) => c.warmstart(mj_lambdas),
             #[cfg(feature = "simd-is-enabled")]
             AnyVelocityConstraint::Grouped(c) => c.warmstart(mj_lambdas),
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(c) => c.warmstart(mj_lambdas),
             AnyVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -60,6 +70,123 @@ impl AnyVelocityConstraint {
             AnyVelocityConstraint::GroupedGround(c) => c.solve(mj_lambdas),
             #[cfg(feature = "simd-is-enabled")]
             AnyVelocityConstraint::Grouped(c) => c.solve(mj_lambdas),
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(c) => c.solve(mj_lambdas),
+            AnyVelocityConstraint::Empty => unreachable!(),
+        }
+    }
+
+    /// Solves the penetration bias of this constraint for the pseudo-velocities used by the
+    /// split-impulse penetration correction.
+    pub fn solve_bias(&mut self, pseudo_mj_lambdas: &mut [DeltaVel<Real>]) {
+        match self {
+            AnyVelocityConstraint::NongroupedGround(c) => c.solve_bias(pseudo_mj_lambdas),
+            AnyVelocityConstraint::Nongrouped(c) => c.solve_bias(pseudo_mj_lambdas),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::GroupedGround(c) => c.solve_bias(pseudo_mj_lambdas),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::Grouped(c) => c.solve_bias(pseudo_mj_lambdas),
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(_) => {}
+            AnyVelocityConstraint::Empty => unreachable!(),
+        }
+    }
+
+    /// The sum of the absolute values of the normal and friction impulses of this constraint.
+    pub fn impulse_magnitude(&self) -> Real {
+        match self {
+            AnyVelocityConstraint::NongroupedGround(c) => c.impulse_magnitude(),
+            AnyVelocityConstraint::Nongrouped(c) => c.impulse_magnitude(),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::GroupedGround(c) => c.impulse_magnitude(),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::Grouped(c) => c.impulse_magnitude(),
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(_) => 0.0,
+            AnyVelocityConstraint::Empty => unreachable!(),
+        }
+    }
+
+    /// Checks the invariants of this constraint, and panics with a message naming the
+    /// offending contact manifold if one of them is violated.
+    ///
+    /// The `num_bodies` is the number of delta-velocities of the island being solved.
+    #[cfg(debug_assertions)]
+    pub fn validate(&self, num_bodies: usize, manifolds_all: &[&mut ContactManifold]) {
+        #[cfg(feature = "simd-is-enabled")]
+        use simba::simd::SimdValue;
+
+        match self {
+            AnyVelocityConstraint::NongroupedGround(c) => validate_constraint(
+                &[c.manifold_id],
+                &[c.mj_lambda2],
+                c.num_contacts,
+                |i| {
+                    let elt = &c.elements[i];
+                    std::iter::once(&elt.normal_part)
+                        .chain(elt.tangent_part.iter())
+                        .map(|part| (part.rhs, part.r))
+                        .collect()
+                },
+                num_bodies,
+                manifolds_all,
+            ),
+            AnyVelocityConstraint::Nongrouped(c) => validate_constraint(
+                &[c.manifold_id],
+                &[c.mj_lambda1, c.mj_lambda2],
+                c.num_contacts,
+                |i| {
+                    let elt = &c.elements[i];
+                    std::iter::once(&elt.normal_part)
+                        .chain(elt.tangent_part.iter())
+                        .map(|part| (part.rhs, part.r))
+                        .collect()
+                },
+                num_bodies,
+                manifolds_all,
+            ),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::GroupedGround(c) => validate_constraint(
+                &c.manifold_id,
+                &c.mj_lambda2,
+                c.num_contacts,
+                |i| {
+                    let elt = &c.elements[i];
+                    std::iter::once(&elt.normal_part)
+                        .chain(elt.tangent_parts.iter())
+                        .flat_map(|part| {
+                            (0..SIMD_WIDTH)
+                                .map(move |ii| (part.rhs.extract(ii), part.r.extract(ii)))
+                        })
+                        .collect()
+                },
+                num_bodies,
+                manifolds_all,
+            ),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::Grouped(c) => {
+                let mut mj_lambdas = c.mj_lambda1.to_vec();
+                mj_lambdas.extend_from_slice(&c.mj_lambda2);
+                validate_constraint(
+                    &c.manifold_id,
+                    &mj_lambdas,
+                    c.num_contacts,
+                    |i| {
+                        let elt = &c.elements[i];
+                        std::iter::once(&elt.normal_part)
+                            .chain(elt.tangent_parts.iter())
+                            .flat_map(|part| {
+                                (0..SIMD_WIDTH)
+                                    .map(move |ii| (part.rhs.extract(ii), part.r.extract(ii)))
+                            })
+                            .collect()
+                    },
+                    num_bodies,
+                    manifolds_all,
+                )
+            }
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(_) => {}
             AnyVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -72,11 +199,86 @@ impl AnyVelocityConstraint {
             AnyVelocityConstraint::GroupedGround(c) => c.writeback_impulses(manifold_all),
             #[cfg(feature = "simd-is-enabled")]
             AnyVelocityConstraint::Grouped(c) => c.writeback_impulses(manifold_all),
+            #[cfg(not(feature = "parallel"))]
+            AnyVelocityConstraint::Custom(c) => c.writeback(),
             AnyVelocityConstraint::Empty => unreachable!(),
         }
     }
 }
 
+/// Checks the invariants shared by all the contact constraints.
+///
+/// The `parts` closure returns the `(rhs, r)` of all the constraint parts of the `i`-th contact.
+#[cfg(debug_assertions)]
+fn validate_constraint(
+    manifold_ids: &[ContactManifoldIndex],
+    mj_lambdas: &[usize],
+    num_contacts: u8,
+    parts: impl Fn(usize) -> Vec<(Real, Real)>,
+    num_bodies: usize,
+    manifolds_all: &[&mut ContactManifold],
+) {
+    let manifold_desc = || {
+        manifold_ids
+            .iter()
+            .map(|id| match manifolds_all.get(*id) {
+                Some(manifold) => format!(
+                    "#{} (bodies {:?} and {:?})",
+                    id, manifold.data.body_pair.body1, manifold.data.body_pair.body2
+                ),
+                None => format!("#{}", id),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    for id in manifold_ids {
+        assert!(
+            *id < manifolds_all.len(),
+            "Invalid velocity constraint for the contact manifold {}: the manifold index is out of bounds ({} manifolds).",
+            manifold_desc(),
+            manifolds_all.len()
+        );
+    }
+
+    assert!(
+        num_contacts as usize <= MAX_MANIFOLD_POINTS,
+        "Invalid velocity constraint for the contact manifold {}: {} contacts exceed the maximum of {}.",
+        manifold_desc(),
+        num_contacts,
+        MAX_MANIFOLD_POINTS
+    );
+
+    for mj_lambda in mj_lambdas {
+        assert!(
+            *mj_lambda < num_bodies,
+            "Invalid velocity constraint for the contact manifold {}: the body index {} is out of bounds ({} bodies).",
+            manifold_desc(),
+            mj_lambda,
+            num_bodies
+        );
+    }
+
+    for i in 0..num_contacts as usize {
+        for (rhs, r) in parts(i) {
+            assert!(
+                rhs.is_finite(),
+                "Invalid velocity constraint for the contact manifold {}: the contact {} has a non-finite rhs ({}).",
+                manifold_desc(),
+                i,
+                rhs
+            );
+            assert!(
+                r.is_finite() && r >= 0.0,
+                "Invalid velocity constraint for the contact manifold {}: the contact {} has an invalid effective mass ({}).",
+                manifold_desc(),
+                i,
+                r
+            );
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct VelocityConstraintElementPart {
     pub gcross1: AngVector<Real>,
@@ -121,16 +323,31 @@ pub(crate) struct VelocityConstraint {
     pub im1: Real,
     pub im2: Real,
     pub limit: Real,
+    pub solve_friction: bool,
+    #[cfg(feature = "dim3")]
+    pub tangent1: Vector<Real>, // One of the friction force directions.
+    #[cfg(feature = "dim3")]
+    pub limit2: Real, // The friction coefficient along the second friction force direction.
     pub mj_lambda1: usize,
     pub mj_lambda2: usize,
     pub manifold_id: ContactManifoldIndex,
     pub manifold_contact_id: [u8; MAX_MANIFOLD_POINTS],
+    pub max_normal_impulse: [Real; MAX_MANIFOLD_POINTS],
+    // The penetration bias, only resolved by `Self::solve_bias` with split impulses.
+    pub rhs_bias: [Real; MAX_MANIFOLD_POINTS],
+    pub bias_impulse: [Real; MAX_MANIFOLD_POINTS],
     pub num_contacts: u8,
     pub elements: [VelocityConstraintElement; MAX_MANIFOLD_POINTS],
 }
 
 impl VelocityConstraint {
-    #[cfg(feature = "parallel")]
+    /// The number of velocity constraints generated for the given contact manifold.
+    ///
+    /// The solver contacts of the manifold are split into consecutive chunks of at most
+    /// `MAX_MANIFOLD_POINTS` contacts, each chunk being resolved by its own constraint. So
+    /// `N` solver contacts result in `ceil(N / MAX_MANIFOLD_POINTS)` constraints, and only
+    /// the last one may involve less than `MAX_MANIFOLD_POINTS` contacts.
+    #[allow(dead_code)] // Only used by the parallel solver and the tests otherwise.
     pub fn num_active_constraints(manifold: &ContactManifold) -> usize {
         let rest = manifold.data.solver_contacts.len() % MAX_MANIFOLD_POINTS != 0;
         manifold.data.solver_contacts.len() / MAX_MANIFOLD_POINTS + rest as usize
@@ -147,14 +364,31 @@ impl VelocityConstraint {
         assert_eq!(manifold.data.relative_dominance, 0);
 
         let inv_dt = params.inv_dt();
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt()
+            * manifold
+                .data
+                .velocity_based_erp_multiplier(params.contact_erp_ramp_steps);
 
         let rb1 = &bodies[manifold.data.body_pair.body1];
         let rb2 = &bodies[manifold.data.body_pair.body2];
         let mj_lambda1 = rb1.active_set_offset;
         let mj_lambda2 = rb2.active_set_offset;
         let force_dir1 = -manifold.data.normal;
-        let warmstart_coeff = manifold.data.warmstart_multiplier * params.warmstart_coeff;
+        let warmstart_coeff =
+            manifold.data.effective_warmstart_multiplier() * params.warmstart_coeff;
+        let restitution_velocity_threshold = manifold
+            .data
+            .effective_restitution_velocity_threshold(params.restitution_velocity_threshold);
+        let split_impulse = params.split_impulse_enabled();
+        let tangent_warmstart_coeff = if params.friction_enabled {
+            warmstart_coeff
+        } else {
+            0.0
+        };
+        #[cfg(feature = "dim2")]
+        let tangents1 = force_dir1.orthonormal_basis();
+        #[cfg(feature = "dim3")]
+        let tangents1 = friction_tangents(&force_dir1, manifold);
 
         for (_l, manifold_points) in manifold
             .data
@@ -169,10 +403,18 @@ impl VelocityConstraint {
                 im1: rb1.effective_inv_mass,
                 im2: rb2.effective_inv_mass,
                 limit: 0.0,
+                solve_friction: params.friction_enabled,
+                #[cfg(feature = "dim3")]
+                tangent1: tangents1[0],
+                #[cfg(feature = "dim3")]
+                limit2: 0.0,
                 mj_lambda1,
                 mj_lambda2,
                 manifold_id,
                 manifold_contact_id: [0; MAX_MANIFOLD_POINTS],
+                max_normal_impulse: [Real::MAX; MAX_MANIFOLD_POINTS],
+                rhs_bias: [0.0; MAX_MANIFOLD_POINTS],
+                bias_impulse: [0.0; MAX_MANIFOLD_POINTS],
                 num_contacts: manifold_points.len() as u8,
             };
 
@@ -212,10 +454,18 @@ impl VelocityConstraint {
                 constraint.im1 = rb1.effective_inv_mass;
                 constraint.im2 = rb2.effective_inv_mass;
                 constraint.limit = 0.0;
+                constraint.solve_friction = params.friction_enabled;
+                #[cfg(feature = "dim3")]
+                {
+                    constraint.tangent1 = tangents1[0];
+                }
                 constraint.mj_lambda1 = mj_lambda1;
                 constraint.mj_lambda2 = mj_lambda2;
                 constraint.manifold_id = manifold_id;
                 constraint.manifold_contact_id = [0; MAX_MANIFOLD_POINTS];
+                constraint.max_normal_impulse = [Real::MAX; MAX_MANIFOLD_POINTS];
+                constraint.rhs_bias = [0.0; MAX_MANIFOLD_POINTS];
+                constraint.bias_impulse = [0.0; MAX_MANIFOLD_POINTS];
                 constraint.num_contacts = manifold_points.len() as u8;
             }
 
@@ -229,6 +479,7 @@ impl VelocityConstraint {
 
                 constraint.limit = manifold_point.friction;
                 constraint.manifold_contact_id[k] = manifold_point.contact_id;
+                constraint.max_normal_impulse[k] = manifold_point.max_normal_impulse;
 
                 // Normal part.
                 {
@@ -245,28 +496,41 @@ impl VelocityConstraint {
                             + gcross1.gdot(gcross1)
                             + gcross2.gdot(gcross2));
 
-                    let is_bouncy = manifold_point.is_bouncy() as u32 as Real;
+                    // Contacts approaching slower than the restitution velocity threshold don't bounce.
+                    let approach_speed = -(vel1 - vel2).dot(&force_dir1);
+                    let is_bouncy = (params.restitution_enabled
+                        && manifold_point.is_bouncy()
+                        && (restitution_velocity_threshold <= 0.0
+                            || approach_speed >= restitution_velocity_threshold))
+                        as u32 as Real;
                     let is_resting = 1.0 - is_bouncy;
 
                     let mut rhs = (1.0 + is_bouncy * manifold_point.restitution)
                         * (vel1 - vel2).dot(&force_dir1);
                     rhs += manifold_point.dist.max(0.0) * inv_dt;
                     rhs *= is_bouncy + is_resting * params.velocity_solve_fraction;
-                    rhs += is_resting * velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+
+                    // With split impulses, the penetration of all the contacts is corrected
+                    // without affecting the velocities.
+                    let penetration_bias = velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+                    if split_impulse {
+                        constraint.rhs_bias[k] = penetration_bias;
+                    } else {
+                        rhs += is_resting * penetration_bias;
+                    }
 
                     constraint.elements[k].normal_part = VelocityConstraintElementPart {
                         gcross1,
                         gcross2,
                         rhs,
-                        impulse: manifold_point.data.impulse * warmstart_coeff,
+                        impulse: (manifold_point.data.impulse * warmstart_coeff)
+                            .min(manifold_point.max_normal_impulse),
                         r,
                     };
                 }
 
                 // Tangent parts.
                 {
-                    let tangents1 = force_dir1.orthonormal_basis();
-
                     for j in 0..DIM - 1 {
                         let gcross1 = rb1
                             .effective_world_inv_inertia_sqrt
@@ -282,9 +546,10 @@ impl VelocityConstraint {
                         let rhs =
                             (vel1 - vel2 + manifold_point.tangent_velocity).dot(&tangents1[j]);
                         #[cfg(feature = "dim2")]
-                        let impulse = manifold_point.data.tangent_impulse * warmstart_coeff;
+                        let impulse = manifold_point.data.tangent_impulse * tangent_warmstart_coeff;
                         #[cfg(feature = "dim3")]
-                        let impulse = manifold_point.data.tangent_impulse[j] * warmstart_coeff;
+                        let impulse =
+                            manifold_point.data.tangent_impulse[j] * tangent_warmstart_coeff;
 
                         constraint.elements[k].tangent_part[j] = VelocityConstraintElementPart {
                             gcross1,
@@ -297,6 +562,16 @@ impl VelocityConstraint {
                 }
             }
 
+            #[cfg(feature = "dim3")]
+            {
+                constraint.limit2 = constraint.limit;
+
+                if let Some(anisotropic_friction) = &manifold.data.anisotropic_friction {
+                    constraint.limit *= anisotropic_friction.primary_friction;
+                    constraint.limit2 *= anisotropic_friction.secondary_friction;
+                }
+            }
+
             #[cfg(not(target_arch = "wasm32"))]
             if push {
                 out_constraints.push(AnyVelocityConstraint::Nongrouped(constraint));
@@ -307,6 +582,19 @@ impl VelocityConstraint {
         }
     }
 
+    #[cfg(feature = "dim2")]
+    fn friction_tangents_and_limits(&self) -> ([Vector<Real>; 1], [Real; 1]) {
+        (self.dir1.orthonormal_basis(), [self.limit])
+    }
+
+    #[cfg(feature = "dim3")]
+    fn friction_tangents_and_limits(&self) -> ([Vector<Real>; 2], [Real; 2]) {
+        (
+            [self.tangent1, self.dir1.cross(&self.tangent1)],
+            [self.limit, self.limit2],
+        )
+    }
+
     pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda1 = DeltaVel::zero();
         let mut mj_lambda2 = DeltaVel::zero();
@@ -320,15 +608,17 @@ impl VelocityConstraint {
             mj_lambda2.angular += elt.gcross2 * elt.impulse;
 
             // FIXME: move this out of the for loop?
-            let tangents1 = self.dir1.orthonormal_basis();
+            let (tangents1, _) = self.friction_tangents_and_limits();
 
-            for j in 0..DIM - 1 {
-                let elt = &self.elements[i].tangent_part[j];
-                mj_lambda1.linear += tangents1[j] * (self.im1 * elt.impulse);
-                mj_lambda1.angular += elt.gcross1 * elt.impulse;
+            if self.solve_friction {
+                for j in 0..DIM - 1 {
+                    let elt = &self.elements[i].tangent_part[j];
+                    mj_lambda1.linear += tangents1[j] * (self.im1 * elt.impulse);
+                    mj_lambda1.angular += elt.gcross1 * elt.impulse;
 
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
-                mj_lambda2.angular += elt.gcross2 * elt.impulse;
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
+                    mj_lambda2.angular += elt.gcross2 * elt.impulse;
+                }
             }
         }
 
@@ -343,27 +633,29 @@ impl VelocityConstraint {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         // Solve friction.
-        for i in 0..self.num_contacts as usize {
-            let tangents1 = self.dir1.orthonormal_basis();
-
-            for j in 0..DIM - 1 {
-                let normal_elt = &self.elements[i].normal_part;
-                let elt = &mut self.elements[i].tangent_part[j];
-                let dimpulse = tangents1[j].dot(&mj_lambda1.linear)
-                    + elt.gcross1.gdot(mj_lambda1.angular)
-                    - tangents1[j].dot(&mj_lambda2.linear)
-                    + elt.gcross2.gdot(mj_lambda2.angular)
-                    + elt.rhs;
-                let limit = self.limit * normal_elt.impulse;
-                let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
-                let dlambda = new_impulse - elt.impulse;
-                elt.impulse = new_impulse;
-
-                mj_lambda1.linear += tangents1[j] * (self.im1 * dlambda);
-                mj_lambda1.angular += elt.gcross1 * dlambda;
-
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
-                mj_lambda2.angular += elt.gcross2 * dlambda;
+        if self.solve_friction {
+            for i in 0..self.num_contacts as usize {
+                let (tangents1, limits) = self.friction_tangents_and_limits();
+
+                for j in 0..DIM - 1 {
+                    let normal_elt = &self.elements[i].normal_part;
+                    let elt = &mut self.elements[i].tangent_part[j];
+                    let dimpulse = tangents1[j].dot(&mj_lambda1.linear)
+                        + elt.gcross1.gdot(mj_lambda1.angular)
+                        - tangents1[j].dot(&mj_lambda2.linear)
+                        + elt.gcross2.gdot(mj_lambda2.angular)
+                        + elt.rhs;
+                    let limit = limits[j] * normal_elt.impulse;
+                    let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
+                    let dlambda = new_impulse - elt.impulse;
+                    elt.impulse = new_impulse;
+
+                    mj_lambda1.linear += tangents1[j] * (self.im1 * dlambda);
+                    mj_lambda1.angular += elt.gcross1 * dlambda;
+
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
+                    mj_lambda2.angular += elt.gcross2 * dlambda;
+                }
             }
         }
 
@@ -374,7 +666,9 @@ impl VelocityConstraint {
                 - self.dir1.dot(&mj_lambda2.linear)
                 + elt.gcross2.gdot(mj_lambda2.angular)
                 + elt.rhs;
-            let new_impulse = (elt.impulse - elt.r * dimpulse).max(0.0);
+            let new_impulse = (elt.impulse - elt.r * dimpulse)
+                .max(0.0)
+                .min(self.max_normal_impulse[i]);
             let dlambda = new_impulse - elt.impulse;
             elt.impulse = new_impulse;
 
@@ -389,6 +683,45 @@ impl VelocityConstraint {
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
+    pub fn solve_bias(&mut self, pseudo_mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = pseudo_mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = pseudo_mj_lambdas[self.mj_lambda2 as usize];
+
+        for i in 0..self.num_contacts as usize {
+            let elt = &self.elements[i].normal_part;
+            let dimpulse = self.dir1.dot(&mj_lambda1.linear) + elt.gcross1.gdot(mj_lambda1.angular)
+                - self.dir1.dot(&mj_lambda2.linear)
+                + elt.gcross2.gdot(mj_lambda2.angular)
+                + self.rhs_bias[i];
+            let new_impulse = (self.bias_impulse[i] - elt.r * dimpulse).max(0.0);
+            let dlambda = new_impulse - self.bias_impulse[i];
+            self.bias_impulse[i] = new_impulse;
+
+            mj_lambda1.linear += self.dir1 * (self.im1 * dlambda);
+            mj_lambda1.angular += elt.gcross1 * dlambda;
+
+            mj_lambda2.linear += self.dir1 * (-self.im2 * dlambda);
+            mj_lambda2.angular += elt.gcross2 * dlambda;
+        }
+
+        pseudo_mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        pseudo_mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn impulse_magnitude(&self) -> Real {
+        self.elements[..self.num_contacts as usize]
+            .iter()
+            .map(|elt| {
+                elt.normal_part.impulse.abs()
+                    + elt
+                        .tangent_part
+                        .iter()
+                        .map(|part| part.impulse.abs())
+                        .sum::<Real>()
+            })
+            .sum()
+    }
+
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
         let manifold = &mut manifolds_all[self.manifold_id];
 
@@ -396,6 +729,8 @@ impl VelocityConstraint {
             let contact_id = self.manifold_contact_id[k];
             let active_contact = &mut manifold.points[contact_id as usize];
             active_contact.data.impulse = self.elements[k].normal_part.impulse;
+            active_contact.data.max_normal_impulse_reached =
+                active_contact.data.impulse >= self.max_normal_impulse[k];
             #[cfg(feature = "dim2")]
             {
                 active_contact.data.tangent_impulse = self.elements[k].tangent_part[0].impulse;
@@ -410,3 +745,97 @@ impl VelocityConstraint {
         }
     }
 }
+
+/// The two friction force directions of the contacts of a 3D contact manifold.
+///
+/// If the contact manifold has an anisotropic friction, the first direction is its primary
+/// tangent projected on the plane orthogonal to `force_dir1`.
+#[cfg(feature = "dim3")]
+pub(crate) fn friction_tangents(
+    force_dir1: &Vector<Real>,
+    manifold: &ContactManifold,
+) -> [Vector<Real>; 2] {
+    AnisotropicFriction::friction_tangents(manifold.data.anisotropic_friction.as_ref(), force_dir1)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn max_normal_impulse() {
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+
+        struct MaxNormalImpulseHooks(Real);
+        impl PhysicsHooks for MaxNormalImpulseHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                for contact in context.solver_contacts.iter_mut() {
+                    contact.max_normal_impulse = self.0;
+                }
+            }
+        }
+
+        // Returns the final height of a ball resting on the ground, and whether
+        // its contact ever reached the maximum normal impulse.
+        let simulate = |max_normal_impulse: Real| -> (Real, bool) {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            let hooks = MaxNormalImpulseHooks(max_normal_impulse);
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            let ground_collider = world.colliders.insert(
+                ground_shape.modify_solver_contacts(true).build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                    .build(),
+            );
+            let ball_collider =
+                world
+                    .colliders
+                    .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+            let mut reached = false;
+            for _ in 0..30 {
+                world.step_with(&hooks, &());
+
+                if let Some(pair) = world
+                    .narrow_phase
+                    .contact_pair(ground_collider, ball_collider)
+                {
+                    reached |= pair
+                        .manifolds
+                        .iter()
+                        .flat_map(|m| m.points.iter())
+                        .any(|pt| pt.data.max_normal_impulse_reached);
+                }
+            }
+
+            (world.bodies[ball].position().translation.y, reached)
+        };
+
+        // The weight of the ball applies an impulse of about 0.1 per step.
+        let (height, reached) = simulate(1.0);
+        assert!(!reached);
+        assert!(height > 0.95);
+
+        let (height, reached) = simulate(0.01);
+        assert!(reached);
+        assert!(height < 0.8);
+    }
+}
@@ -52,10 +52,14 @@ pub(crate) struct WVelocityConstraint {
     pub im1: SimdReal,
     pub im2: SimdReal,
     pub limit: SimdReal,
+    pub solve_friction: bool,
     pub mj_lambda1: [usize; SIMD_WIDTH],
     pub mj_lambda2: [usize; SIMD_WIDTH],
     pub manifold_id: [ContactManifoldIndex; SIMD_WIDTH],
     pub manifold_contact_id: [[u8; SIMD_WIDTH]; MAX_MANIFOLD_POINTS],
+    // The penetration bias, only resolved by `Self::solve_bias` with split impulses.
+    pub rhs_bias: [SimdReal; MAX_MANIFOLD_POINTS],
+    pub bias_impulse: [SimdReal; MAX_MANIFOLD_POINTS],
 }
 
 impl WVelocityConstraint {
@@ -73,7 +77,17 @@ impl WVelocityConstraint {
 
         let inv_dt = SimdReal::splat(params.inv_dt());
         let velocity_solve_fraction = SimdReal::splat(params.velocity_solve_fraction);
-        let velocity_based_erp_inv_dt = SimdReal::splat(params.velocity_based_erp_inv_dt());
+        let restitution_enabled = SimdReal::splat(params.restitution_enabled as u32 as Real);
+        let restitution_velocity_threshold = SimdReal::from(array![|ii| manifolds[ii]
+            .data
+            .effective_restitution_velocity_threshold(params.restitution_velocity_threshold);
+            SIMD_WIDTH]);
+        let velocity_based_erp_multiplier = SimdReal::from(
+            array![|ii| manifolds[ii].data.velocity_based_erp_multiplier(params.contact_erp_ramp_steps); SIMD_WIDTH],
+        );
+        let velocity_based_erp_inv_dt =
+            SimdReal::splat(params.velocity_based_erp_inv_dt()) * velocity_based_erp_multiplier;
+        let split_impulse = params.split_impulse_enabled();
 
         let rbs1 = array![|ii| &bodies[manifolds[ii].data.body_pair.body1]; SIMD_WIDTH];
         let rbs2 = array![|ii| &bodies[manifolds[ii].data.body_pair.body2]; SIMD_WIDTH];
@@ -103,9 +117,15 @@ impl WVelocityConstraint {
         let mj_lambda1 = array![|ii| rbs1[ii].active_set_offset; SIMD_WIDTH];
         let mj_lambda2 = array![|ii| rbs2[ii].active_set_offset; SIMD_WIDTH];
 
-        let warmstart_multiplier =
-            SimdReal::from(array![|ii| manifolds[ii].data.warmstart_multiplier; SIMD_WIDTH]);
+        let warmstart_multiplier = SimdReal::from(
+            array![|ii| manifolds[ii].data.effective_warmstart_multiplier(); SIMD_WIDTH],
+        );
         let warmstart_coeff = warmstart_multiplier * SimdReal::splat(params.warmstart_coeff);
+        let tangent_warmstart_coeff = if params.friction_enabled {
+            warmstart_coeff
+        } else {
+            SimdReal::zero()
+        };
         let num_active_contacts = manifolds[0].data.num_active_contacts();
 
         for l in (0..num_active_contacts).step_by(MAX_MANIFOLD_POINTS) {
@@ -120,10 +140,13 @@ impl WVelocityConstraint {
                 im1,
                 im2,
                 limit: SimdReal::splat(0.0),
+                solve_friction: params.friction_enabled,
                 mj_lambda1,
                 mj_lambda2,
                 manifold_id,
                 manifold_contact_id: [[0; SIMD_WIDTH]; MAX_MANIFOLD_POINTS],
+                rhs_bias: [SimdReal::zero(); MAX_MANIFOLD_POINTS],
+                bias_impulse: [SimdReal::zero(); MAX_MANIFOLD_POINTS],
                 num_contacts: num_points as u8,
             };
 
@@ -134,8 +157,7 @@ impl WVelocityConstraint {
                     SimdReal::from(array![|ii| manifold_points[ii][k].restitution; SIMD_WIDTH]);
                 let is_bouncy = SimdReal::from(
                     array![|ii| manifold_points[ii][k].is_bouncy() as u32 as Real; SIMD_WIDTH],
-                );
-                let is_resting = SimdReal::splat(1.0) - is_bouncy;
+                ) * restitution_enabled;
                 let point = Point::from(array![|ii| manifold_points[ii][k].point; SIMD_WIDTH]);
                 let dist = SimdReal::from(array![|ii| manifold_points[ii][k].dist; SIMD_WIDTH]);
                 let tangent_velocity =
@@ -162,12 +184,28 @@ impl WVelocityConstraint {
                     let r = SimdReal::splat(1.0)
                         / (im1 + im2 + gcross1.gdot(gcross1) + gcross2.gdot(gcross2));
                     let projected_velocity = (vel1 - vel2).dot(&force_dir1);
+
+                    // Contacts approaching slower than the restitution velocity threshold
+                    // don't bounce.
+                    let above_threshold = (-projected_velocity)
+                        .simd_ge(restitution_velocity_threshold)
+                        | restitution_velocity_threshold.simd_le(SimdReal::zero());
+                    let is_bouncy =
+                        is_bouncy * SimdReal::splat(1.0).select(above_threshold, SimdReal::zero());
+                    let is_resting = SimdReal::splat(1.0) - is_bouncy;
+
                     let mut rhs =
                         (SimdReal::splat(1.0) + is_bouncy * restitution) * projected_velocity;
                     rhs += dist.simd_max(SimdReal::zero()) * inv_dt;
                     rhs *= is_bouncy + is_resting * velocity_solve_fraction;
-                    rhs +=
-                        dist.simd_min(SimdReal::zero()) * (velocity_based_erp_inv_dt * is_resting);
+
+                    let penetration_bias =
+                        dist.simd_min(SimdReal::zero()) * velocity_based_erp_inv_dt;
+                    if split_impulse {
+                        constraint.rhs_bias[k] = penetration_bias;
+                    } else {
+                        rhs += penetration_bias * is_resting;
+                    }
 
                     constraint.elements[k].normal_part = WVelocityConstraintElementPart {
                         gcross1,
@@ -201,7 +239,7 @@ impl WVelocityConstraint {
                         gcross1,
                         gcross2,
                         rhs,
-                        impulse: impulse * warmstart_coeff,
+                        impulse: impulse * tangent_warmstart_coeff,
                         r,
                     };
                 }
@@ -246,13 +284,15 @@ impl WVelocityConstraint {
             // FIXME: move this out of the for loop?
             let tangents1 = self.dir1.orthonormal_basis();
 
-            for j in 0..DIM - 1 {
-                let elt = &self.elements[i].tangent_parts[j];
-                mj_lambda1.linear += tangents1[j] * (self.im1 * elt.impulse);
-                mj_lambda1.angular += elt.gcross1 * elt.impulse;
+            if self.solve_friction {
+                for j in 0..DIM - 1 {
+                    let elt = &self.elements[i].tangent_parts[j];
+                    mj_lambda1.linear += tangents1[j] * (self.im1 * elt.impulse);
+                    mj_lambda1.angular += elt.gcross1 * elt.impulse;
 
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
-                mj_lambda2.angular += elt.gcross2 * elt.impulse;
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
+                    mj_lambda2.angular += elt.gcross2 * elt.impulse;
+                }
             }
         }
 
@@ -286,27 +326,29 @@ impl WVelocityConstraint {
         };
 
         // Solve friction first.
-        for i in 0..self.num_contacts as usize {
-            // FIXME: move this out of the for loop?
-            let tangents1 = self.dir1.orthonormal_basis();
-            let normal_elt = &self.elements[i].normal_part;
-
-            for j in 0..DIM - 1 {
-                let elt = &mut self.elements[i].tangent_parts[j];
-                let dimpulse = tangents1[j].dot(&mj_lambda1.linear)
-                    + elt.gcross1.gdot(mj_lambda1.angular)
-                    - tangents1[j].dot(&mj_lambda2.linear)
-                    + elt.gcross2.gdot(mj_lambda2.angular)
-                    + elt.rhs;
-                let limit = self.limit * normal_elt.impulse;
-                let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
-                let dlambda = new_impulse - elt.impulse;
-                elt.impulse = new_impulse;
-
-                mj_lambda1.linear += tangents1[j] * (self.im1 * dlambda);
-                mj_lambda1.angular += elt.gcross1 * dlambda;
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
-                mj_lambda2.angular += elt.gcross2 * dlambda;
+        if self.solve_friction {
+            for i in 0..self.num_contacts as usize {
+                // FIXME: move this out of the for loop?
+                let tangents1 = self.dir1.orthonormal_basis();
+                let normal_elt = &self.elements[i].normal_part;
+
+                for j in 0..DIM - 1 {
+                    let elt = &mut self.elements[i].tangent_parts[j];
+                    let dimpulse = tangents1[j].dot(&mj_lambda1.linear)
+                        + elt.gcross1.gdot(mj_lambda1.angular)
+                        - tangents1[j].dot(&mj_lambda2.linear)
+                        + elt.gcross2.gdot(mj_lambda2.angular)
+                        + elt.rhs;
+                    let limit = self.limit * normal_elt.impulse;
+                    let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
+                    let dlambda = new_impulse - elt.impulse;
+                    elt.impulse = new_impulse;
+
+                    mj_lambda1.linear += tangents1[j] * (self.im1 * dlambda);
+                    mj_lambda1.angular += elt.gcross1 * dlambda;
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
+                    mj_lambda2.angular += elt.gcross2 * dlambda;
+                }
             }
         }
 
@@ -337,6 +379,66 @@ impl WVelocityConstraint {
         }
     }
 
+    pub fn solve_bias(&mut self, pseudo_mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = DeltaVel {
+            linear: Vector::from(
+                array![|ii| pseudo_mj_lambdas[self.mj_lambda1[ii] as usize].linear; SIMD_WIDTH],
+            ),
+            angular: AngVector::from(
+                array![|ii| pseudo_mj_lambdas[self.mj_lambda1[ii] as usize].angular; SIMD_WIDTH],
+            ),
+        };
+
+        let mut mj_lambda2 = DeltaVel {
+            linear: Vector::from(
+                array![|ii| pseudo_mj_lambdas[self.mj_lambda2[ii] as usize].linear; SIMD_WIDTH],
+            ),
+            angular: AngVector::from(
+                array![|ii| pseudo_mj_lambdas[self.mj_lambda2[ii] as usize].angular; SIMD_WIDTH],
+            ),
+        };
+
+        for i in 0..self.num_contacts as usize {
+            let elt = &self.elements[i].normal_part;
+            let dimpulse = self.dir1.dot(&mj_lambda1.linear) + elt.gcross1.gdot(mj_lambda1.angular)
+                - self.dir1.dot(&mj_lambda2.linear)
+                + elt.gcross2.gdot(mj_lambda2.angular)
+                + self.rhs_bias[i];
+            let new_impulse = (self.bias_impulse[i] - elt.r * dimpulse).simd_max(SimdReal::zero());
+            let dlambda = new_impulse - self.bias_impulse[i];
+            self.bias_impulse[i] = new_impulse;
+
+            mj_lambda1.linear += self.dir1 * (self.im1 * dlambda);
+            mj_lambda1.angular += elt.gcross1 * dlambda;
+            mj_lambda2.linear += self.dir1 * (-self.im2 * dlambda);
+            mj_lambda2.angular += elt.gcross2 * dlambda;
+        }
+
+        for ii in 0..SIMD_WIDTH {
+            pseudo_mj_lambdas[self.mj_lambda1[ii] as usize].linear = mj_lambda1.linear.extract(ii);
+            pseudo_mj_lambdas[self.mj_lambda1[ii] as usize].angular =
+                mj_lambda1.angular.extract(ii);
+        }
+        for ii in 0..SIMD_WIDTH {
+            pseudo_mj_lambdas[self.mj_lambda2[ii] as usize].linear = mj_lambda2.linear.extract(ii);
+            pseudo_mj_lambdas[self.mj_lambda2[ii] as usize].angular =
+                mj_lambda2.angular.extract(ii);
+        }
+    }
+
+    pub fn impulse_magnitude(&self) -> Real {
+        let mut result = 0.0;
+
+        for elt in &self.elements[..self.num_contacts as usize] {
+            for part in std::iter::once(&elt.normal_part).chain(elt.tangent_parts.iter()) {
+                let impulses: [Real; SIMD_WIDTH] = part.impulse.into();
+                result += impulses.iter().map(|i| i.abs()).sum::<Real>();
+            }
+        }
+
+        result
+    }
+
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
         for k in 0..self.num_contacts as usize {
             let impulses: [_; SIMD_WIDTH] = self.elements[k].normal_part.impulse.into();
@@ -1,6 +1,10 @@
+#[cfg(feature = "dim3")]
+use super::velocity_constraint::friction_tangents;
 use super::{AnyVelocityConstraint, DeltaVel};
 use crate::math::{AngVector, Real, Vector, DIM, MAX_MANIFOLD_POINTS};
-use crate::utils::{WAngularInertia, WBasis, WCross, WDot};
+#[cfg(feature = "dim2")]
+use crate::utils::WBasis;
+use crate::utils::{WAngularInertia, WCross, WDot};
 
 use crate::dynamics::{IntegrationParameters, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
@@ -47,9 +51,18 @@ pub(crate) struct VelocityGroundConstraint {
     pub dir1: Vector<Real>, // Non-penetration force direction for the first body.
     pub im2: Real,
     pub limit: Real,
+    pub solve_friction: bool,
+    #[cfg(feature = "dim3")]
+    pub tangent1: Vector<Real>, // One of the friction force directions.
+    #[cfg(feature = "dim3")]
+    pub limit2: Real, // The friction coefficient along the second friction force direction.
     pub mj_lambda2: usize,
     pub manifold_id: ContactManifoldIndex,
     pub manifold_contact_id: [u8; MAX_MANIFOLD_POINTS],
+    pub max_normal_impulse: [Real; MAX_MANIFOLD_POINTS],
+    // The penetration bias, only resolved by `Self::solve_bias` with split impulses.
+    pub rhs_bias: [Real; MAX_MANIFOLD_POINTS],
+    pub bias_impulse: [Real; MAX_MANIFOLD_POINTS],
     pub num_contacts: u8,
     pub elements: [VelocityGroundConstraintElement; MAX_MANIFOLD_POINTS],
 }
@@ -64,7 +77,10 @@ impl VelocityGroundConstraint {
         push: bool,
     ) {
         let inv_dt = params.inv_dt();
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt()
+            * manifold
+                .data
+                .velocity_based_erp_multiplier(params.contact_erp_ramp_steps);
 
         let mut rb1 = &bodies[manifold.data.body_pair.body1];
         let mut rb2 = &bodies[manifold.data.body_pair.body2];
@@ -78,7 +94,21 @@ impl VelocityGroundConstraint {
         };
 
         let mj_lambda2 = rb2.active_set_offset;
-        let warmstart_coeff = manifold.data.warmstart_multiplier * params.warmstart_coeff;
+        let warmstart_coeff =
+            manifold.data.effective_warmstart_multiplier() * params.warmstart_coeff;
+        let restitution_velocity_threshold = manifold
+            .data
+            .effective_restitution_velocity_threshold(params.restitution_velocity_threshold);
+        let split_impulse = params.split_impulse_enabled();
+        let tangent_warmstart_coeff = if params.friction_enabled {
+            warmstart_coeff
+        } else {
+            0.0
+        };
+        #[cfg(feature = "dim2")]
+        let tangents1 = force_dir1.orthonormal_basis();
+        #[cfg(feature = "dim3")]
+        let tangents1 = friction_tangents(&force_dir1, manifold);
 
         for (_l, manifold_points) in manifold
             .data
@@ -92,9 +122,17 @@ impl VelocityGroundConstraint {
                 elements: [VelocityGroundConstraintElement::zero(); MAX_MANIFOLD_POINTS],
                 im2: rb2.effective_inv_mass,
                 limit: 0.0,
+                solve_friction: params.friction_enabled,
+                #[cfg(feature = "dim3")]
+                tangent1: tangents1[0],
+                #[cfg(feature = "dim3")]
+                limit2: 0.0,
                 mj_lambda2,
                 manifold_id,
                 manifold_contact_id: [0; MAX_MANIFOLD_POINTS],
+                max_normal_impulse: [Real::MAX; MAX_MANIFOLD_POINTS],
+                rhs_bias: [0.0; MAX_MANIFOLD_POINTS],
+                bias_impulse: [0.0; MAX_MANIFOLD_POINTS],
                 num_contacts: manifold_points.len() as u8,
             };
 
@@ -133,9 +171,17 @@ impl VelocityGroundConstraint {
                 constraint.dir1 = force_dir1;
                 constraint.im2 = rb2.effective_inv_mass;
                 constraint.limit = 0.0;
+                constraint.solve_friction = params.friction_enabled;
+                #[cfg(feature = "dim3")]
+                {
+                    constraint.tangent1 = tangents1[0];
+                }
                 constraint.mj_lambda2 = mj_lambda2;
                 constraint.manifold_id = manifold_id;
                 constraint.manifold_contact_id = [0; MAX_MANIFOLD_POINTS];
+                constraint.max_normal_impulse = [Real::MAX; MAX_MANIFOLD_POINTS];
+                constraint.rhs_bias = [0.0; MAX_MANIFOLD_POINTS];
+                constraint.bias_impulse = [0.0; MAX_MANIFOLD_POINTS];
                 constraint.num_contacts = manifold_points.len() as u8;
             }
 
@@ -148,6 +194,7 @@ impl VelocityGroundConstraint {
 
                 constraint.limit = manifold_point.friction;
                 constraint.manifold_contact_id[k] = manifold_point.contact_id;
+                constraint.max_normal_impulse[k] = manifold_point.max_normal_impulse;
 
                 // Normal part.
                 {
@@ -157,27 +204,38 @@ impl VelocityGroundConstraint {
 
                     let r = 1.0 / (rb2.effective_inv_mass + gcross2.gdot(gcross2));
 
-                    let is_bouncy = manifold_point.is_bouncy() as u32 as Real;
+                    // Contacts approaching slower than the restitution velocity threshold don't bounce.
+                    let approach_speed = -(vel1 - vel2).dot(&force_dir1);
+                    let is_bouncy = (params.restitution_enabled
+                        && manifold_point.is_bouncy()
+                        && (restitution_velocity_threshold <= 0.0
+                            || approach_speed >= restitution_velocity_threshold))
+                        as u32 as Real;
                     let is_resting = 1.0 - is_bouncy;
 
                     let mut rhs = (1.0 + is_bouncy * manifold_point.restitution)
                         * (vel1 - vel2).dot(&force_dir1);
                     rhs += manifold_point.dist.max(0.0) * inv_dt;
                     rhs *= is_bouncy + is_resting * params.velocity_solve_fraction;
-                    rhs += is_resting * velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+
+                    let penetration_bias = velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+                    if split_impulse {
+                        constraint.rhs_bias[k] = penetration_bias;
+                    } else {
+                        rhs += is_resting * penetration_bias;
+                    }
 
                     constraint.elements[k].normal_part = VelocityGroundConstraintElementPart {
                         gcross2,
                         rhs,
-                        impulse: manifold_point.data.impulse * warmstart_coeff,
+                        impulse: (manifold_point.data.impulse * warmstart_coeff)
+                            .min(manifold_point.max_normal_impulse),
                         r,
                     };
                 }
 
                 // Tangent parts.
                 {
-                    let tangents1 = force_dir1.orthonormal_basis();
-
                     for j in 0..DIM - 1 {
                         let gcross2 = rb2
                             .effective_world_inv_inertia_sqrt
@@ -187,9 +245,11 @@ impl VelocityGroundConstraint {
                             + flipped_multiplier * manifold_point.tangent_velocity)
                             .dot(&tangents1[j]);
                         #[cfg(feature = "dim2")]
-                        let impulse = manifold_points[k].data.tangent_impulse * warmstart_coeff;
+                        let impulse =
+                            manifold_points[k].data.tangent_impulse * tangent_warmstart_coeff;
                         #[cfg(feature = "dim3")]
-                        let impulse = manifold_points[k].data.tangent_impulse[j] * warmstart_coeff;
+                        let impulse =
+                            manifold_points[k].data.tangent_impulse[j] * tangent_warmstart_coeff;
 
                         constraint.elements[k].tangent_part[j] =
                             VelocityGroundConstraintElementPart {
@@ -202,6 +262,16 @@ impl VelocityGroundConstraint {
                 }
             }
 
+            #[cfg(feature = "dim3")]
+            {
+                constraint.limit2 = constraint.limit;
+
+                if let Some(anisotropic_friction) = &manifold.data.anisotropic_friction {
+                    constraint.limit *= anisotropic_friction.primary_friction;
+                    constraint.limit2 *= anisotropic_friction.secondary_friction;
+                }
+            }
+
             #[cfg(not(target_arch = "wasm32"))]
             if push {
                 out_constraints.push(AnyVelocityConstraint::NongroupedGround(constraint));
@@ -212,19 +282,34 @@ impl VelocityGroundConstraint {
         }
     }
 
+    #[cfg(feature = "dim2")]
+    fn friction_tangents_and_limits(&self) -> ([Vector<Real>; 1], [Real; 1]) {
+        (self.dir1.orthonormal_basis(), [self.limit])
+    }
+
+    #[cfg(feature = "dim3")]
+    fn friction_tangents_and_limits(&self) -> ([Vector<Real>; 2], [Real; 2]) {
+        (
+            [self.tangent1, self.dir1.cross(&self.tangent1)],
+            [self.limit, self.limit2],
+        )
+    }
+
     pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda2 = DeltaVel::zero();
-        let tangents1 = self.dir1.orthonormal_basis();
+        let (tangents1, _) = self.friction_tangents_and_limits();
 
         for i in 0..self.num_contacts as usize {
             let elt = &self.elements[i].normal_part;
             mj_lambda2.linear += self.dir1 * (-self.im2 * elt.impulse);
             mj_lambda2.angular += elt.gcross2 * elt.impulse;
 
-            for j in 0..DIM - 1 {
-                let elt = &self.elements[i].tangent_part[j];
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
-                mj_lambda2.angular += elt.gcross2 * elt.impulse;
+            if self.solve_friction {
+                for j in 0..DIM - 1 {
+                    let elt = &self.elements[i].tangent_part[j];
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * elt.impulse);
+                    mj_lambda2.angular += elt.gcross2 * elt.impulse;
+                }
             }
         }
 
@@ -236,22 +321,24 @@ impl VelocityGroundConstraint {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         // Solve friction.
-        let tangents1 = self.dir1.orthonormal_basis();
-
-        for i in 0..self.num_contacts as usize {
-            for j in 0..DIM - 1 {
-                let normal_elt = &self.elements[i].normal_part;
-                let elt = &mut self.elements[i].tangent_part[j];
-                let dimpulse = -tangents1[j].dot(&mj_lambda2.linear)
-                    + elt.gcross2.gdot(mj_lambda2.angular)
-                    + elt.rhs;
-                let limit = self.limit * normal_elt.impulse;
-                let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
-                let dlambda = new_impulse - elt.impulse;
-                elt.impulse = new_impulse;
-
-                mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
-                mj_lambda2.angular += elt.gcross2 * dlambda;
+        if self.solve_friction {
+            let (tangents1, limits) = self.friction_tangents_and_limits();
+
+            for i in 0..self.num_contacts as usize {
+                for j in 0..DIM - 1 {
+                    let normal_elt = &self.elements[i].normal_part;
+                    let elt = &mut self.elements[i].tangent_part[j];
+                    let dimpulse = -tangents1[j].dot(&mj_lambda2.linear)
+                        + elt.gcross2.gdot(mj_lambda2.angular)
+                        + elt.rhs;
+                    let limit = limits[j] * normal_elt.impulse;
+                    let new_impulse = (elt.impulse - elt.r * dimpulse).simd_clamp(-limit, limit);
+                    let dlambda = new_impulse - elt.impulse;
+                    elt.impulse = new_impulse;
+
+                    mj_lambda2.linear += tangents1[j] * (-self.im2 * dlambda);
+                    mj_lambda2.angular += elt.gcross2 * dlambda;
+                }
             }
         }
 
@@ -260,7 +347,9 @@ impl VelocityGroundConstraint {
             let elt = &mut self.elements[i].normal_part;
             let dimpulse =
                 -self.dir1.dot(&mj_lambda2.linear) + elt.gcross2.gdot(mj_lambda2.angular) + elt.rhs;
-            let new_impulse = (elt.impulse - elt.r * dimpulse).max(0.0);
+            let new_impulse = (elt.impulse - elt.r * dimpulse)
+                .max(0.0)
+                .min(self.max_normal_impulse[i]);
             let dlambda = new_impulse - elt.impulse;
             elt.impulse = new_impulse;
 
@@ -271,6 +360,39 @@ impl VelocityGroundConstraint {
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
+    pub fn solve_bias(&mut self, pseudo_mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = pseudo_mj_lambdas[self.mj_lambda2 as usize];
+
+        for i in 0..self.num_contacts as usize {
+            let elt = &self.elements[i].normal_part;
+            let dimpulse = -self.dir1.dot(&mj_lambda2.linear)
+                + elt.gcross2.gdot(mj_lambda2.angular)
+                + self.rhs_bias[i];
+            let new_impulse = (self.bias_impulse[i] - elt.r * dimpulse).max(0.0);
+            let dlambda = new_impulse - self.bias_impulse[i];
+            self.bias_impulse[i] = new_impulse;
+
+            mj_lambda2.linear += self.dir1 * (-self.im2 * dlambda);
+            mj_lambda2.angular += elt.gcross2 * dlambda;
+        }
+
+        pseudo_mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn impulse_magnitude(&self) -> Real {
+        self.elements[..self.num_contacts as usize]
+            .iter()
+            .map(|elt| {
+                elt.normal_part.impulse.abs()
+                    + elt
+                        .tangent_part
+                        .iter()
+                        .map(|part| part.impulse.abs())
+                        .sum::<Real>()
+            })
+            .sum()
+    }
+
     // FIXME: duplicated code. This is exactly the same as in the non-ground velocity constraint.
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
         let manifold = &mut manifolds_all[self.manifold_id];
@@ -279,6 +401,8 @@ impl VelocityGroundConstraint {
             let contact_id = self.manifold_contact_id[k];
             let active_contact = &mut manifold.points[contact_id as usize];
             active_contact.data.impulse = self.elements[k].normal_part.impulse;
+            active_contact.data.max_normal_impulse_reached =
+                active_contact.data.impulse >= self.max_normal_impulse[k];
             #[cfg(feature = "dim2")]
             {
                 active_contact.data.tangent_impulse = self.elements[k].tangent_part[0].impulse;
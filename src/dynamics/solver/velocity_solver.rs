@@ -1,23 +1,47 @@
 use super::AnyJointVelocityConstraint;
+use crate::counters::SolverCounters;
 use crate::dynamics::{
     solver::{AnyVelocityConstraint, DeltaVel},
-    IntegrationParameters, JointGraphEdge, RigidBodySet,
+    ConstraintOrder, IntegrationParameters, JointGraphEdge, RigidBodySet,
 };
 use crate::geometry::ContactManifold;
 use crate::math::Real;
+use crate::pipeline::{
+    IslandModificationContext, PhysicsHooks, PhysicsHooksFlags, VelocityIterationContext,
+};
 use crate::utils::WAngularInertia;
 
 pub(crate) struct VelocitySolver {
     pub mj_lambdas: Vec<DeltaVel<Real>>,
+    // The pseudo-velocities computed by the split-impulse penetration correction.
+    pub pseudo_mj_lambdas: Vec<DeltaVel<Real>>,
+    pub convergence_history: Vec<Real>,
+    pub num_iterations: usize,
+    prev_mj_lambdas: Vec<DeltaVel<Real>>,
+    // The delta-velocities due to the external forces only.
+    external_mj_lambdas: Vec<DeltaVel<Real>>,
 }
 
 impl VelocitySolver {
     pub fn new() -> Self {
         Self {
             mj_lambdas: Vec::new(),
+            pseudo_mj_lambdas: Vec::new(),
+            convergence_history: Vec::new(),
+            num_iterations: 0,
+            prev_mj_lambdas: Vec::new(),
+            external_mj_lambdas: Vec::new(),
         }
     }
 
+    pub fn shrink_to_fit(&mut self) {
+        self.mj_lambdas.shrink_to_fit();
+        self.pseudo_mj_lambdas.shrink_to_fit();
+        self.convergence_history.shrink_to_fit();
+        self.prev_mj_lambdas.shrink_to_fit();
+        self.external_mj_lambdas.shrink_to_fit();
+    }
+
     pub fn solve(
         &mut self,
         island_id: usize,
@@ -27,6 +51,114 @@ impl VelocitySolver {
         joints_all: &mut [JointGraphEdge],
         contact_constraints: &mut [AnyVelocityConstraint],
         joint_constraints: &mut [AnyJointVelocityConstraint],
+        iterations_multiplier: usize,
+        hooks: &dyn PhysicsHooks,
+        counters: &mut SolverCounters,
+    ) {
+        #[cfg(debug_assertions)]
+        {
+            let num_bodies = bodies.active_island(island_id).len();
+            for constraint in &*contact_constraints {
+                constraint.validate(num_bodies, manifolds_all);
+            }
+        }
+
+        self.init_and_warmstart(
+            island_id,
+            params,
+            bodies,
+            contact_constraints,
+            joint_constraints,
+        );
+
+        if hooks
+            .active_hooks()
+            .contains(PhysicsHooksFlags::MODIFY_ISLAND)
+        {
+            let mut context =
+                IslandModificationContext::new(island_id, bodies, &mut self.mj_lambdas);
+            hooks.modify_island(&mut context);
+        }
+
+        /*
+         * Solve constraints.
+         */
+        self.convergence_history.clear();
+        self.num_iterations = 0;
+        let (max_iterations, tolerance) = params.velocity_iterations_limits();
+        let max_iterations = max_iterations * iterations_multiplier;
+        let inspect_iterations = hooks
+            .active_hooks()
+            .contains(PhysicsHooksFlags::INSPECT_VELOCITY_ITERATIONS);
+
+        for _ in 0..max_iterations {
+            if tolerance.is_some() {
+                self.prev_mj_lambdas.clear();
+                self.prev_mj_lambdas.extend_from_slice(&self.mj_lambdas);
+            }
+
+            self.solve_one_iteration(params, contact_constraints, joint_constraints, counters);
+
+            if inspect_iterations {
+                let context = VelocityIterationContext::new(
+                    island_id,
+                    self.num_iterations,
+                    bodies,
+                    &self.mj_lambdas,
+                );
+                hooks.inspect_velocity_iteration(&context);
+            }
+
+            self.num_iterations += 1;
+
+            if params.record_velocity_convergence {
+                let total_impulse = contact_constraints
+                    .iter()
+                    .map(|c| c.impulse_magnitude())
+                    .sum();
+                self.convergence_history.push(total_impulse);
+            }
+
+            if let Some(tolerance) = tolerance {
+                if self.last_iteration_residual() <= tolerance {
+                    break;
+                }
+            }
+        }
+
+        if params.split_impulse_enabled() {
+            counters.velocity_contact_resolution_time.resume();
+            self.solve_penetration_biases(
+                bodies.active_island(island_id).len(),
+                contact_constraints,
+            );
+            counters.velocity_contact_resolution_time.pause();
+        }
+
+        self.writeback(
+            island_id,
+            bodies,
+            manifolds_all,
+            joints_all,
+            contact_constraints,
+            joint_constraints,
+        );
+    }
+
+    /// Initializes the delta-velocities with the external forces and warmstarts the constraints.
+    ///
+    /// This is the first stage of `Self::solve`. It can be followed by any number of calls to
+    /// `Self::solve_one_iteration` and a call to `Self::writeback`, e.g., to inspect each
+    /// Gauss-Seidel iteration when debugging the solver. These stages are internal to the crate
+    /// since the constraints they operate on are not public: use
+    /// `PhysicsHooks::inspect_velocity_iteration` to inspect each iteration from user code.
+    pub(crate) fn init_and_warmstart(
+        &mut self,
+        island_id: usize,
+        params: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+        contact_constraints: &[AnyVelocityConstraint],
+        joint_constraints: &[AnyJointVelocityConstraint],
     ) {
         self.mj_lambdas.clear();
         self.mj_lambdas
@@ -44,46 +176,822 @@ impl VelocitySolver {
             rb.torque = na::zero();
         });
 
+        self.external_mj_lambdas.clear();
+        self.external_mj_lambdas.extend_from_slice(&self.mj_lambdas);
+
         /*
          * Warmstart constraints.
          */
-        for constraint in &*joint_constraints {
+        if params.warmstart_coeff == 0.0 {
+            // All the cached impulses are zero so there is nothing to warmstart, except for
+            // the custom constraints which may apply arbitrary impulses there.
+            #[cfg(not(feature = "parallel"))]
+            for constraint in contact_constraints {
+                if let AnyVelocityConstraint::Custom(constraint) = constraint {
+                    constraint.warmstart(&mut self.mj_lambdas[..]);
+                }
+            }
+
+            return;
+        }
+
+        for constraint in joint_constraints {
             constraint.warmstart(&mut self.mj_lambdas[..]);
         }
 
-        for constraint in &*contact_constraints {
+        for constraint in contact_constraints {
             constraint.warmstart(&mut self.mj_lambdas[..]);
         }
+    }
 
-        /*
-         * Solve constraints.
-         */
-        for _ in 0..params.max_velocity_iterations {
-            for constraint in &mut *joint_constraints {
-                constraint.solve(&mut self.mj_lambdas[..]);
+    /// Runs a single Gauss-Seidel sweep over all the constraints, in the order given by
+    /// `params.constraint_order`.
+    ///
+    /// Returns the delta-velocities of the island's rigid-bodies at the end of this sweep,
+    /// indexed by their `active_set_offset`.
+    pub(crate) fn solve_one_iteration(
+        &mut self,
+        params: &IntegrationParameters,
+        contact_constraints: &mut [AnyVelocityConstraint],
+        joint_constraints: &mut [AnyJointVelocityConstraint],
+        counters: &mut SolverCounters,
+    ) -> &[DeltaVel<Real>] {
+        match params.constraint_order {
+            ConstraintOrder::JointsFirst => {
+                counters.velocity_joint_resolution_time.resume();
+                self.solve_joints(joint_constraints);
+                counters.velocity_joint_resolution_time.pause();
+
+                counters.velocity_contact_resolution_time.resume();
+                self.solve_contacts(contact_constraints);
+                counters.velocity_contact_resolution_time.pause();
+            }
+            ConstraintOrder::ContactsFirst => {
+                counters.velocity_contact_resolution_time.resume();
+                self.solve_contacts(contact_constraints);
+                counters.velocity_contact_resolution_time.pause();
+
+                counters.velocity_joint_resolution_time.resume();
+                self.solve_joints(joint_constraints);
+                counters.velocity_joint_resolution_time.pause();
+            }
+            ConstraintOrder::Interleaved => {
+                counters.velocity_contact_resolution_time.resume();
+                self.solve_interleaved(joint_constraints, contact_constraints);
+                counters.velocity_contact_resolution_time.pause();
             }
+        }
 
+        &self.mj_lambdas
+    }
+
+    fn solve_joints(&mut self, joint_constraints: &mut [AnyJointVelocityConstraint]) {
+        for constraint in joint_constraints {
+            constraint.solve(&mut self.mj_lambdas[..]);
+        }
+    }
+
+    fn solve_contacts(&mut self, contact_constraints: &mut [AnyVelocityConstraint]) {
+        for constraint in contact_constraints {
+            constraint.solve(&mut self.mj_lambdas[..]);
+        }
+    }
+
+    fn solve_interleaved(
+        &mut self,
+        joint_constraints: &mut [AnyJointVelocityConstraint],
+        contact_constraints: &mut [AnyVelocityConstraint],
+    ) {
+        let mut joints = joint_constraints.iter_mut();
+        let mut contacts = contact_constraints.iter_mut();
+
+        loop {
+            let (joint, contact) = (joints.next(), contacts.next());
+
+            if joint.is_none() && contact.is_none() {
+                break;
+            }
+
+            if let Some(joint) = joint {
+                joint.solve(&mut self.mj_lambdas[..]);
+            }
+
+            if let Some(contact) = contact {
+                contact.solve(&mut self.mj_lambdas[..]);
+            }
+        }
+    }
+
+    /// Computes the pseudo-velocities correcting the penetrations of the contacts, with as many
+    /// iterations as the velocity resolution.
+    ///
+    /// The pseudo-velocities are only used to integrate the positions of the rigid-bodies, so
+    /// this penetration correction does not add energy to the system.
+    fn solve_penetration_biases(
+        &mut self,
+        num_bodies: usize,
+        contact_constraints: &mut [AnyVelocityConstraint],
+    ) {
+        self.pseudo_mj_lambdas.clear();
+        self.pseudo_mj_lambdas.resize(num_bodies, DeltaVel::zero());
+
+        for _ in 0..self.num_iterations {
             for constraint in &mut *contact_constraints {
-                constraint.solve(&mut self.mj_lambdas[..]);
+                constraint.solve_bias(&mut self.pseudo_mj_lambdas[..]);
             }
         }
+    }
 
+    /// Moves the rigid-bodies of the island by the pseudo-velocities of the split-impulse
+    /// penetration correction.
+    pub fn integrate_pseudo_velocities(
+        &self,
+        island_id: usize,
+        params: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+    ) {
+        bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
+            rb.integrate_pseudo_velocity(self.pseudo_mj_lambdas[rb.active_set_offset], params.dt)
+        });
+    }
+
+    /// The largest change of the delta-velocity of a rigid-body during the last iteration.
+    ///
+    /// This assumes `self.prev_mj_lambdas` contains the delta-velocities before this iteration.
+    fn last_iteration_residual(&self) -> Real {
+        self.mj_lambdas
+            .iter()
+            .zip(self.prev_mj_lambdas.iter())
+            .map(|(curr, prev)| {
+                let linear = (curr.linear - prev.linear).norm();
+                #[cfg(feature = "dim2")]
+                let angular = (curr.angular - prev.angular).abs();
+                #[cfg(feature = "dim3")]
+                let angular = (curr.angular - prev.angular).norm();
+                linear.max(angular)
+            })
+            .fold(0.0, Real::max)
+    }
+
+    /// Applies the delta-velocities to the rigid-bodies and writes the impulses back into the
+    /// contact manifolds and joints, for warmstarting at the next timestep.
+    pub(crate) fn writeback(
+        &mut self,
+        island_id: usize,
+        bodies: &mut RigidBodySet,
+        manifolds_all: &mut [&mut ContactManifold],
+        joints_all: &mut [JointGraphEdge],
+        contact_constraints: &[AnyVelocityConstraint],
+        joint_constraints: &[AnyJointVelocityConstraint],
+    ) {
         // Update velocities.
         bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
             let dvel = self.mj_lambdas[rb.active_set_offset];
+            let external_dvel = self.external_mj_lambdas[rb.active_set_offset];
             rb.linvel += dvel.linear;
             rb.angvel += rb
                 .effective_world_inv_inertia_sqrt
                 .transform_vector(dvel.angular);
+            rb.solver_linvel_change = dvel.linear - external_dvel.linear;
+            rb.solver_angvel_change = rb
+                .effective_world_inv_inertia_sqrt
+                .transform_vector(dvel.angular - external_dvel.angular);
         });
 
         // Write impulses back into the manifold structures.
-        for constraint in &*joint_constraints {
+        for constraint in joint_constraints {
             constraint.writeback_impulses(joints_all);
         }
 
-        for constraint in &*contact_constraints {
+        for constraint in contact_constraints {
             constraint.writeback_impulses(manifolds_all);
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::VelocitySolver;
+    use crate::counters::SolverCounters;
+    use crate::dynamics::solver::{
+        AnyPositionConstraint, AnyVelocityConstraint, SolverConstraints,
+    };
+    use crate::dynamics::{IntegrationParameters, RigidBodyBuilder};
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn warmstart_skipped_without_warmstart_coeff() {
+        let simulate = |params: &IntegrationParameters| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params = *params;
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                    .build(),
+            );
+            let co = ColliderBuilder::ball(100.0).build();
+            world.colliders.insert(co, ground, &mut world.bodies);
+
+            for i in 0..3 {
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * (0.5 + i as Real), na::zero()))
+                    .build();
+                let handle = world.bodies.insert(rb);
+                let co = ColliderBuilder::ball(0.5).build();
+                world.colliders.insert(co, handle, &mut world.bodies);
+            }
+
+            for _ in 0..10 {
+                world.step();
+            }
+
+            world
+        };
+
+        let params = IntegrationParameters::default();
+        let mut world = simulate(&params);
+        let bodies = &mut world.bodies;
+        let mut manifolds = Vec::new();
+        let mut manifold_indices = vec![Vec::new(); bodies.num_islands()];
+        world.narrow_phase.sort_and_select_active_contacts(
+            bodies,
+            &mut manifolds,
+            &mut manifold_indices,
+        );
+
+        let mut constraints =
+            SolverConstraints::<AnyVelocityConstraint, AnyPositionConstraint>::new();
+        constraints.init(0, &params, bodies, &manifolds, &manifold_indices[0]);
+
+        // The constraints carry impulses from the previous timestep, but they are not applied
+        // at all without warmstart.
+        let mut solver = VelocitySolver::new();
+        let no_warmstart = IntegrationParameters {
+            warmstart_coeff: 0.0,
+            ..params
+        };
+        solver.init_and_warmstart(
+            0,
+            &no_warmstart,
+            bodies,
+            &constraints.velocity_constraints,
+            &[],
+        );
+        assert!(solver
+            .mj_lambdas
+            .iter()
+            .all(|dvel| dvel.linear.norm() == 0.0));
+
+        solver.init_and_warmstart(0, &params, bodies, &constraints.velocity_constraints, &[]);
+        assert!(solver
+            .mj_lambdas
+            .iter()
+            .any(|dvel| dvel.linear.norm() != 0.0));
+
+        // Simulations without warmstart are deterministic.
+        let bodies_a = simulate(&no_warmstart).bodies;
+        let bodies_b = simulate(&no_warmstart).bodies;
+        for ((_, rb_a), (_, rb_b)) in bodies_a.iter().zip(bodies_b.iter()) {
+            assert_eq!(rb_a.position(), rb_b.position());
+            assert_eq!(rb_a.linvel(), rb_b.linvel());
+        }
+    }
+
+    #[test]
+    fn single_iterations_match_batched_solve() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        let params = world.params;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                .build(),
+        );
+        let co = ColliderBuilder::ball(100.0).build();
+        world.colliders.insert(co, ground, &mut world.bodies);
+
+        for i in 0..3 {
+            let rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * (0.5 + i as Real), na::zero()))
+                .build();
+            let handle = world.bodies.insert(rb);
+            let co = ColliderBuilder::ball(0.5).build();
+            world.colliders.insert(co, handle, &mut world.bodies);
+        }
+
+        for _ in 0..10 {
+            world.step();
+        }
+
+        let bodies = &world.bodies;
+        assert_eq!(bodies.num_islands(), 1);
+        let mut manifolds = Vec::new();
+        let mut manifold_indices = vec![Vec::new(); bodies.num_islands()];
+        world.narrow_phase.sort_and_select_active_contacts(
+            bodies,
+            &mut manifolds,
+            &mut manifold_indices,
+        );
+        assert!(!manifold_indices[0].is_empty());
+
+        let mut constraints_a =
+            SolverConstraints::<AnyVelocityConstraint, AnyPositionConstraint>::new();
+        let mut constraints_b =
+            SolverConstraints::<AnyVelocityConstraint, AnyPositionConstraint>::new();
+        constraints_a.init(0, &params, bodies, &manifolds, &manifold_indices[0]);
+        constraints_b.init(0, &params, bodies, &manifolds, &manifold_indices[0]);
+
+        // Batched solve.
+        let mut bodies_a = bodies.clone();
+        VelocitySolver::new().solve(
+            0,
+            &params,
+            &mut bodies_a,
+            &mut manifolds,
+            &mut [],
+            &mut constraints_a.velocity_constraints,
+            &mut [],
+            1,
+            &(),
+            &mut SolverCounters::new(),
+        );
+
+        // Iteration by iteration.
+        let mut bodies_b = bodies.clone();
+        let mut solver = VelocitySolver::new();
+        solver.init_and_warmstart(
+            0,
+            &params,
+            &mut bodies_b,
+            &constraints_b.velocity_constraints,
+            &[],
+        );
+
+        let mut counters = SolverCounters::new();
+        for _ in 0..params.max_velocity_iterations {
+            let mj_lambdas = solver.solve_one_iteration(
+                &params,
+                &mut constraints_b.velocity_constraints,
+                &mut [],
+                &mut counters,
+            );
+            assert_eq!(mj_lambdas.len(), bodies.active_island(0).len());
+        }
+
+        solver.writeback(
+            0,
+            &mut bodies_b,
+            &mut manifolds,
+            &mut [],
+            &constraints_b.velocity_constraints,
+            &[],
+        );
+
+        for (handle, rb_a) in bodies_a.iter() {
+            let rb_b = &bodies_b[handle];
+            assert_eq!(rb_a.linvel(), rb_b.linvel());
+            assert_eq!(rb_a.angvel(), rb_b.angvel());
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the body index 1000 is out of bounds")]
+    fn corrupted_constraint_is_detected() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        let params = world.params;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::ball(10.0).build();
+        world.colliders.insert(co, ground, &mut world.bodies);
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 10.5, na::zero()))
+                .build(),
+        );
+        let co = ColliderBuilder::ball(0.5).build();
+        world.colliders.insert(co, ball, &mut world.bodies);
+
+        world.step();
+
+        let bodies = &mut world.bodies;
+        let mut manifolds = Vec::new();
+        let mut manifold_indices = vec![Vec::new(); bodies.num_islands()];
+        world.narrow_phase.sort_and_select_active_contacts(
+            bodies,
+            &mut manifolds,
+            &mut manifold_indices,
+        );
+        let mut constraints =
+            SolverConstraints::<AnyVelocityConstraint, AnyPositionConstraint>::new();
+        constraints.init(0, &params, bodies, &manifolds, &manifold_indices[0]);
+
+        // Corrupt the index of the dynamic body.
+        match &mut constraints.velocity_constraints[0] {
+            AnyVelocityConstraint::NongroupedGround(c) => c.mj_lambda2 = 1000,
+            _ => unreachable!(),
+        }
+
+        VelocitySolver::new().solve(
+            0,
+            &params,
+            bodies,
+            &mut manifolds,
+            &mut [],
+            &mut constraints.velocity_constraints,
+            &mut [],
+            1,
+            &(),
+            &mut SolverCounters::new(),
+        );
+    }
+
+    #[test]
+    fn solver_contacts_are_chunked_into_constraints() {
+        use crate::dynamics::solver::VelocityConstraint;
+        use crate::math::MAX_MANIFOLD_POINTS;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        let params = world.params;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::ball(10.0).build();
+        world.colliders.insert(co, ground, &mut world.bodies);
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 10.5, na::zero()))
+                .build(),
+        );
+        let co = ColliderBuilder::ball(0.5).build();
+        world.colliders.insert(co, ball, &mut world.bodies);
+
+        world.step();
+
+        let bodies = &world.bodies;
+        let mut manifolds = Vec::new();
+        let mut manifold_indices = vec![Vec::new(); bodies.num_islands()];
+        world.narrow_phase.sort_and_select_active_contacts(
+            bodies,
+            &mut manifolds,
+            &mut manifold_indices,
+        );
+        assert_eq!(manifold_indices[0].len(), 1);
+        let manifold_id = manifold_indices[0][0];
+        let contact = manifolds[manifold_id].data.solver_contacts[0];
+
+        for num_contacts in 0..=MAX_MANIFOLD_POINTS * 3 + 1 {
+            manifolds[manifold_id].data.solver_contacts = vec![contact; num_contacts];
+
+            let mut constraints =
+                SolverConstraints::<AnyVelocityConstraint, AnyPositionConstraint>::new();
+            constraints.init(0, &params, bodies, &manifolds, &manifold_indices[0]);
+
+            let expected_len = (num_contacts + MAX_MANIFOLD_POINTS - 1) / MAX_MANIFOLD_POINTS;
+            assert_eq!(constraints.velocity_constraints.len(), expected_len);
+            assert_eq!(
+                VelocityConstraint::num_active_constraints(&manifolds[manifold_id]),
+                expected_len
+            );
+
+            for (i, constraint) in constraints.velocity_constraints.iter().enumerate() {
+                match constraint {
+                    AnyVelocityConstraint::NongroupedGround(c) => {
+                        let expected =
+                            (num_contacts - i * MAX_MANIFOLD_POINTS).min(MAX_MANIFOLD_POINTS);
+                        assert_eq!(c.manifold_id, manifold_id);
+                        assert_eq!(c.num_contacts as usize, expected);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn robot_foot_contacts_constraint_order() {
+        use crate::dynamics::{ConstraintOrder, PrismaticJoint};
+        use crate::math::Point;
+
+        // A heavy torso standing on two light feet through motorized prismatic legs. The
+        // large mass ratio makes the last constraints solved at each iteration win: the
+        // feet drift into the ground if the legs are solved after the foot contacts.
+        let simulate = |constraint_order: ConstraintOrder| {
+            let mut world = TestWorld::new();
+            world.params = IntegrationParameters {
+                constraint_order,
+                ..IntegrationParameters::default()
+            };
+            world.gravity = Vector::y() * -9.81;
+
+            #[cfg(feature = "dim2")]
+            let (ground_shape, torso_shape, foot_shape) = (
+                ColliderBuilder::cuboid(10.0, 0.5),
+                ColliderBuilder::cuboid(0.7, 0.2),
+                ColliderBuilder::cuboid(0.2, 0.1),
+            );
+            #[cfg(feature = "dim3")]
+            let (ground_shape, torso_shape, foot_shape) = (
+                ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+                ColliderBuilder::cuboid(0.7, 0.2, 0.3),
+                ColliderBuilder::cuboid(0.2, 0.1, 0.2),
+            );
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let torso = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y(), na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(torso_shape.density(100.0).build(), torso, &mut world.bodies);
+
+            let mut feet = Vec::new();
+            for side in &[-0.5 as Real, 0.5] {
+                let foot = world.bodies.insert(
+                    RigidBodyBuilder::new_dynamic()
+                        .position(Isometry::new(
+                            Vector::x() * *side + Vector::y() * 0.1,
+                            na::zero(),
+                        ))
+                        .build(),
+                );
+                world
+                    .colliders
+                    .insert(foot_shape.build(), foot, &mut world.bodies);
+
+                let hip = Point::from(Vector::x() * *side);
+                #[cfg(feature = "dim2")]
+                let mut leg =
+                    PrismaticJoint::new(hip, Vector::y_axis(), Point::origin(), Vector::y_axis());
+                #[cfg(feature = "dim3")]
+                let mut leg = PrismaticJoint::new(
+                    hip,
+                    Vector::y_axis(),
+                    Vector::zeros(),
+                    Point::origin(),
+                    Vector::y_axis(),
+                    Vector::zeros(),
+                );
+                leg.configure_motor_position(-0.9, 1.0, 0.5);
+                world.joints.insert(&mut world.bodies, torso, foot, leg);
+                feet.push(foot);
+            }
+
+            for _ in 0..120 {
+                world.step();
+            }
+
+            // Accumulate how much the feet move while the robot should be standing still.
+            let mut drift = 0.0;
+            for _ in 0..60 {
+                world.step();
+
+                for foot in &feet {
+                    let foot = &world.bodies[*foot];
+                    drift += foot.linvel().norm() * world.params.dt;
+                    drift += (foot.position().translation.vector.y - 0.1).abs();
+                }
+            }
+
+            drift
+        };
+
+        let joints_first = simulate(ConstraintOrder::JointsFirst);
+        let contacts_first = simulate(ConstraintOrder::ContactsFirst);
+        let interleaved = simulate(ConstraintOrder::Interleaved);
+
+        assert!(joints_first.is_finite() && contacts_first.is_finite() && interleaved.is_finite());
+        // Solving the foot contacts last keeps the feet planted.
+        assert!(joints_first < contacts_first);
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn velocity_convergence_history() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.max_velocity_iterations = 10;
+        world.params.record_velocity_convergence = true;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Two balls stacked on the ground.
+        for y in [0.999, 1.998].iter() {
+            let body = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * *y, na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), body, &mut world.bodies);
+        }
+
+        world.step();
+
+        let history = world.pipeline.velocity_convergence_history(0).unwrap();
+        assert_eq!(history.len(), world.params.max_velocity_iterations);
+        assert!(history.iter().all(|h| *h > 0.0));
+
+        // The successive corrections shrink as the solver converges.
+        let deltas: Vec<Real> = history.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+        for w in deltas.windows(2) {
+            assert!(w[1] <= w[0] + 1.0e-5, "{:?}", history);
+        }
+
+        // Nothing is recorded when the flag is disabled.
+        world.params.record_velocity_convergence = false;
+        world.step();
+        assert!(world
+            .pipeline
+            .velocity_convergence_history(0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn stack_without_position_solver() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.max_position_iterations = 0;
+        world.params.velocity_based_erp = 0.2;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        #[cfg(feature = "dim2")]
+        let box_shape = ColliderBuilder::cuboid(0.5, 0.5);
+        #[cfg(feature = "dim3")]
+        let box_shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+        let mut stack = Vec::new();
+        for i in 0..5 {
+            let initial_pos = Isometry::new(Vector::y() * (1.0 + i as Real), na::zero());
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(initial_pos)
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(box_shape.build(), handle, &mut world.bodies);
+            stack.push((handle, initial_pos));
+        }
+
+        for _ in 0..300 {
+            world.step();
+        }
+
+        // The penetrations are only resolved by the velocity solver, but the stack stays upright.
+        for (handle, initial_pos) in stack {
+            let pos = world.bodies[handle].position();
+            assert!(
+                (pos.translation.vector - initial_pos.translation.vector).norm() < 0.05,
+                "{:?}",
+                pos
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn adaptive_velocity_iterations() {
+        use crate::dynamics::SolverMode;
+
+        let max_iterations = 50;
+        // Returns the total and maximum numbers of velocity iterations over all the timesteps.
+        let run = |num_boxes: usize| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params.solver_mode = SolverMode::Adaptive {
+                tolerance: 1.0e-4,
+                max_iterations,
+            };
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            for i in 0..num_boxes {
+                let body = world.bodies.insert(
+                    RigidBodyBuilder::new_dynamic()
+                        .position(Isometry::new(Vector::y() * (1.0 + i as Real), na::zero()))
+                        .can_sleep(false)
+                        .build(),
+                );
+                #[cfg(feature = "dim2")]
+                let shape = ColliderBuilder::cuboid(0.5, 0.5);
+                #[cfg(feature = "dim3")]
+                let shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+                world
+                    .colliders
+                    .insert(shape.build(), body, &mut world.bodies);
+            }
+
+            let mut total = 0;
+            let mut max = 0;
+            for _ in 0..60 {
+                world.step();
+
+                let iterations = world.pipeline.velocity_iterations(0).unwrap();
+                total += iterations;
+                max = max.max(iterations);
+            }
+
+            (total, max)
+        };
+
+        let (easy_total, easy_max) = run(1);
+        let (stack_total, stack_max) = run(10);
+        assert!(easy_total < stack_total, "{} {}", easy_total, stack_total);
+        assert!(easy_max < max_iterations);
+        assert!(stack_max <= max_iterations);
+    }
+
+    #[test]
+    fn contact_and_joint_resolution_timers() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // A ball resting on the ground, with a pendulum hanging from it.
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        world.colliders.insert(
+            ColliderBuilder::ball(1.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.5, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        let pendulum = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 5.0, na::zero()))
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::ball(0.1).build(),
+            pendulum,
+            &mut world.bodies,
+        );
+        world.joints.insert(
+            &mut world.bodies,
+            ball,
+            pendulum,
+            BallJoint::new(Point::from(Vector::y() * 3.5), Point::origin()),
+        );
+
+        world.step();
+
+        let counters = &world.pipeline.counters.solver;
+        assert!(counters.velocity_contact_resolution_time.time() > 0.0);
+        assert!(counters.velocity_joint_resolution_time.time() > 0.0);
+        assert!(
+            counters.velocity_contact_resolution_time.time()
+                + counters.velocity_joint_resolution_time.time()
+                <= counters.velocity_resolution_time.time()
+        );
+    }
+}
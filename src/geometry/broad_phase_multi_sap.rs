@@ -6,6 +6,7 @@ use bit_vec::BitVec;
 use parry::bounding_volume::{BoundingVolume, AABB};
 use parry::utils::hashmap::HashMap;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::ops::{Index, IndexMut};
 
 const NUM_SENTINELS: usize = 1;
@@ -482,6 +483,9 @@ pub struct BroadPhase {
         )
     )]
     reporting: HashMap<(u32, u32), bool>, // Workspace
+    // The pairs of colliders with intersecting AABBs, as of the last call to `find_pairs`.
+    // The colliders of each pair are sorted, see `ColliderPair::new_sorted`.
+    pairs: HashSet<ColliderPair>,
 }
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -558,10 +562,24 @@ impl BroadPhase {
             region_pool: Vec::new(),
             regions_to_remove: Vec::new(),
             reporting: HashMap::default(),
+            pairs: HashSet::new(),
             deleted_any: false,
         }
     }
 
+    /// The pairs of colliders with intersecting AABBs, as of the last broad-phase update.
+    ///
+    /// These are the candidate pairs that are passed to the narrow-phase, and are only
+    /// updated by the physics or collision pipelines, before the narrow-phase runs. The order
+    /// of the pairs is unspecified.
+    pub fn intersection_pairs(
+        &self,
+    ) -> impl Iterator<Item = (ColliderHandle, ColliderHandle)> + '_ {
+        self.pairs
+            .iter()
+            .map(|pair| (pair.collider1, pair.collider2))
+    }
+
     /// Maintain the broad-phase internal state by taking collider removal into account.
     pub fn maintain(&mut self, colliders: &mut ColliderSet) {
         // Ensure we already subscribed.
@@ -570,8 +588,17 @@ impl BroadPhase {
         }
 
         let cursor = self.removed_colliders.take().unwrap();
+        let mut removed_any = false;
         for collider in colliders.removed_colliders.read(&cursor) {
             self.remove_collider(collider.proxy_index);
+            removed_any = true;
+        }
+
+        if removed_any {
+            // Remove the pairs involving the removed colliders.
+            self.pairs.retain(|pair| {
+                colliders.contains(pair.collider1) && colliders.contains(pair.collider2)
+            });
         }
 
         colliders.removed_colliders.ack(&cursor);
@@ -638,7 +665,7 @@ impl BroadPhase {
         {
             for handle in &bodies[*body_handle].colliders {
                 let collider = &mut colliders[*handle];
-                let aabb = collider.compute_aabb().loosened(prediction_distance / 2.0);
+                let aabb = collider.compute_broad_phase_aabb(prediction_distance);
 
                 if let Some(proxy) = self.proxies.get_mut(collider.proxy_index) {
                     proxy.aabb = aabb;
@@ -736,11 +763,15 @@ impl BroadPhase {
             let handle2 = proxy2.handle;
 
             if *colliding {
+                self.pairs
+                    .insert(ColliderPair::new_sorted(handle1, handle2));
                 out_events.push(BroadPhasePairEvent::AddPair(ColliderPair::new(
                     handle1, handle2,
                 )));
             // num_add_events += 1;
             } else {
+                self.pairs
+                    .remove(&ColliderPair::new_sorted(handle1, handle2));
                 out_events.push(BroadPhasePairEvent::DeletePair(ColliderPair::new(
                     handle1, handle2,
                 )));
@@ -763,6 +794,7 @@ impl BroadPhase {
 mod test {
     use crate::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
     use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet};
+    use crate::math::{Isometry, Point, Vector};
 
     #[test]
     fn test_add_update_remove() {
@@ -791,4 +823,107 @@ mod test {
         // Make sure the proxy handles is recycled properly.
         broad_phase.update_aabbs(0.0, &bodies, &mut colliders);
     }
+
+    #[test]
+    fn intersection_pairs() {
+        let mut broad_phase = BroadPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut events = Vec::new();
+
+        // Only the AABBs of consecutive balls overlap.
+        let handles: Vec<_> = [0.0, 0.9, 1.8, 5.0]
+            .iter()
+            .map(|x| {
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::x() * *x, na::zero()))
+                    .build();
+                let hrb = bodies.insert(rb);
+                let co = ColliderBuilder::ball(0.5).build();
+                (hrb, colliders.insert(co, hrb, &mut bodies))
+            })
+            .collect();
+
+        let sorted_pairs = |broad_phase: &BroadPhase| {
+            let mut pairs: Vec<_> = broad_phase
+                .intersection_pairs()
+                .map(|(a, b)| {
+                    let (a, b) = (a.into_raw_parts().0, b.into_raw_parts().0);
+                    (a.min(b), a.max(b))
+                })
+                .collect();
+            pairs.sort();
+            pairs
+        };
+        let ids: Vec<_> = handles.iter().map(|h| h.1.into_raw_parts().0).collect();
+
+        assert_eq!(broad_phase.intersection_pairs().count(), 0);
+        bodies.maintain(&mut colliders);
+        broad_phase.update_aabbs(0.0, &bodies, &mut colliders);
+        broad_phase.find_pairs(&mut events);
+        assert_eq!(
+            sorted_pairs(&broad_phase),
+            vec![(ids[0], ids[1]), (ids[1], ids[2])]
+        );
+
+        // Removing a collider removes its pairs.
+        bodies.remove(handles[1].0, &mut colliders, &mut joints);
+        broad_phase.maintain(&mut colliders);
+        assert!(sorted_pairs(&broad_phase).is_empty());
+
+        // Moving a ball updates the pairs.
+        bodies
+            .get_mut(handles[3].0)
+            .unwrap()
+            .set_position(Isometry::new(Vector::x() * 2.5, na::zero()), true);
+        bodies.maintain(&mut colliders);
+        broad_phase.update_aabbs(0.0, &bodies, &mut colliders);
+        broad_phase.find_pairs(&mut events);
+        assert_eq!(sorted_pairs(&broad_phase), vec![(ids[2], ids[3])]);
+    }
+
+    #[test]
+    fn collider_aabbs() {
+        let mut broad_phase = BroadPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+
+        let rb = RigidBodyBuilder::new_dynamic()
+            .position(Isometry::new(Vector::x() * 1.0, na::zero()))
+            .build();
+        let hrb = bodies.insert(rb);
+        #[cfg(feature = "dim2")]
+        let (co, mins, maxs) = (
+            ColliderBuilder::cuboid(0.5, 1.0).translation(0.0, 2.0),
+            Point::new(0.5, 1.0),
+            Point::new(1.5, 3.0),
+        );
+        #[cfg(feature = "dim3")]
+        let (co, mins, maxs) = (
+            ColliderBuilder::cuboid(0.5, 1.0, 1.5).translation(0.0, 2.0, 0.0),
+            Point::new(0.5, 1.0, -1.5),
+            Point::new(1.5, 3.0, 1.5),
+        );
+        let hco = colliders.insert(co.contact_skin(0.1).build(), hrb, &mut bodies);
+
+        let aabb = colliders[hco].compute_aabb();
+        assert!((aabb.mins - mins).norm() < 1.0e-5);
+        assert!((aabb.maxs - maxs).norm() < 1.0e-5);
+        assert_eq!(colliders.aabbs().collect::<Vec<_>>(), vec![(hco, aabb)]);
+
+        let sphere = colliders[hco].compute_bounding_sphere();
+        assert!((sphere.center - na::center(&mins, &maxs)).norm() < 1.0e-5);
+        assert!((sphere.radius - (maxs - mins).norm() / 2.0).abs() < 1.0e-5);
+
+        // The broad-phase enlarges the AABBs by half the prediction distance and the contact skin.
+        let prediction_distance = 0.2;
+        bodies.maintain(&mut colliders);
+        broad_phase.update_aabbs(prediction_distance, &bodies, &mut colliders);
+        let proxy_aabb = broad_phase.proxies[colliders[hco].proxy_index].aabb;
+        let expected = colliders[hco].compute_broad_phase_aabb(prediction_distance);
+        assert_eq!(proxy_aabb, expected);
+        assert!((expected.mins - (mins - Vector::repeat(0.2))).norm() < 1.0e-5);
+        assert!((expected.maxs - (maxs + Vector::repeat(0.2))).norm() < 1.0e-5);
+    }
 }
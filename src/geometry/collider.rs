@@ -1,9 +1,13 @@
 use crate::dynamics::{CoefficientCombineRule, MassProperties, RigidBodyHandle};
-use crate::geometry::{InteractionGroups, SharedShape, SolverFlags};
+#[cfg(feature = "dim3")]
+use crate::geometry::{heightfield_corrected_normal, InternalEdges};
+use crate::geometry::{
+    ColliderMaterial, ColliderPartMaterials, InteractionGroups, SharedShape, SolverFlags,
+};
 use crate::math::{AngVector, Isometry, Point, Real, Rotation, Vector, DIM};
 use crate::parry::transformation::vhacd::VHACDParameters;
-use parry::bounding_volume::AABB;
-use parry::shape::Shape;
+use parry::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
+use parry::shape::{Cuboid, Shape};
 
 bitflags::bitflags! {
     #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -14,6 +18,7 @@ bitflags::bitflags! {
         const FRICTION_COMBINE_RULE_10 = 1 << 2;
         const RESTITUTION_COMBINE_RULE_01 = 1 << 3;
         const RESTITUTION_COMBINE_RULE_10 = 1 << 4;
+        const DISABLED = 1 << 5;
     }
 }
 
@@ -22,6 +27,10 @@ impl ColliderFlags {
         self.contains(ColliderFlags::SENSOR)
     }
 
+    pub fn is_enabled(self) -> bool {
+        !self.contains(ColliderFlags::DISABLED)
+    }
+
     pub fn friction_combine_rule_value(self) -> u8 {
         (self.bits & 0b0000_0110) >> 1
     }
@@ -41,6 +50,35 @@ impl ColliderFlags {
     }
 }
 
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+// The solid sub-shapes of a compound shape with some hollow sub-shapes.
+pub(crate) struct CompoundSolidParts {
+    solid: Vec<bool>,
+    // The compound made of the solid sub-shapes only, used by the scene queries.
+    shape: Option<SharedShape>,
+}
+
+impl CompoundSolidParts {
+    fn new(shape: &SharedShape, solid: Vec<bool>) -> Option<Self> {
+        let compound = shape.as_compound()?;
+        let solid_shapes: Vec<_> = compound
+            .shapes()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| solid.get(*i).copied().unwrap_or(true))
+            .map(|(_, subshape)| subshape.clone())
+            .collect();
+        let shape = if solid_shapes.is_empty() {
+            None
+        } else {
+            Some(SharedShape::compound(solid_shapes))
+        };
+
+        Some(Self { solid, shape })
+    }
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone)]
 /// A geometric entity that can be attached to a body so it can be affected by contacts and proximity queries.
@@ -48,6 +86,8 @@ impl ColliderFlags {
 /// To build a new collider, use the `ColliderBuilder` structure.
 pub struct Collider {
     shape: SharedShape,
+    unscaled_shape: Option<SharedShape>,
+    scale: Vector<Real>,
     density: Real,
     pub(crate) flags: ColliderFlags,
     pub(crate) solver_flags: SolverFlags,
@@ -59,6 +99,16 @@ pub struct Collider {
     pub friction: Real,
     /// The restitution coefficient of this collider.
     pub restitution: Real,
+    pub(crate) part_materials: Option<Box<ColliderPartMaterials>>,
+    pub(crate) compound_solid_parts: Option<Box<CompoundSolidParts>>,
+    pub(crate) contact_force_event_threshold: Real,
+    pub(crate) contact_skin: Real,
+    pub(crate) min_approach_speed: Real,
+    pub(crate) restitution_velocity_threshold: Option<Real>,
+    #[cfg(feature = "dim3")]
+    pub(crate) internal_edges: Option<Box<InternalEdges>>,
+    #[cfg(feature = "dim3")]
+    pub(crate) one_sided: bool,
     pub(crate) collision_groups: InteractionGroups,
     pub(crate) solver_groups: InteractionGroups,
     pub(crate) proxy_index: usize,
@@ -82,6 +132,85 @@ impl Collider {
         self.flags.is_sensor()
     }
 
+    /// Is this collider enabled?
+    ///
+    /// A disabled collider does not generate any contact or intersection.
+    pub fn is_enabled(&self) -> bool {
+        self.flags.is_enabled()
+    }
+
+    /// Enables or disables this collider.
+    ///
+    /// A disabled collider does not generate any contact or intersection, but it keeps
+    /// its handle and its contact history. Note that this does not wake up the rigid-bodies
+    /// touching this collider.
+    ///
+    /// Disabling a collider only affects the narrow-phase: the collider stays in the
+    /// broad-phase, so its pairs are still reported by `BroadPhase::intersection_pairs` and
+    /// the contact pairs of the narrow-phase are kept, which preserves their warmstart impulses
+    /// if the collider is enabled again shortly after. The collider is also still taken into
+    /// account by the scene queries of the `QueryPipeline`.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.flags.set(ColliderFlags::DISABLED, !enabled);
+    }
+
+    /// The contact force above which a `ContactForceEvent` is emitted for the contacts
+    /// involving this collider.
+    ///
+    /// See `ColliderSet::set_contact_force_event_threshold` to modify it.
+    pub fn contact_force_event_threshold(&self) -> Real {
+        self.contact_force_event_threshold
+    }
+
+    /// The separation this collider maintains with the colliders it is resting on.
+    pub fn contact_skin(&self) -> Real {
+        self.contact_skin
+    }
+
+    /// The relative velocity along the contact normal below which the contacts involving
+    /// this collider are ignored by the constraints solver.
+    pub fn min_approach_speed(&self) -> Real {
+        self.min_approach_speed
+    }
+
+    /// Sets the relative velocity along the contact normal below which the contacts involving
+    /// this collider are ignored by the constraints solver.
+    ///
+    /// See `ColliderBuilder::min_approach_speed` for details.
+    pub fn set_min_approach_speed(&mut self, speed: Real) {
+        self.min_approach_speed = speed;
+    }
+
+    /// The approach speed below which the contacts involving this collider don't bounce, if it
+    /// overrides `IntegrationParameters::restitution_velocity_threshold`.
+    pub fn restitution_velocity_threshold(&self) -> Option<Real> {
+        self.restitution_velocity_threshold
+    }
+
+    /// Sets the approach speed below which the contacts involving this collider don't bounce.
+    ///
+    /// See `ColliderBuilder::restitution_velocity_threshold` for details.
+    pub fn set_restitution_velocity_threshold(&mut self, threshold: Option<Real>) {
+        self.restitution_velocity_threshold = threshold;
+    }
+
+    /// Does this collider only collide with the front face of the triangles of its triangle mesh?
+    ///
+    /// See `ColliderBuilder::one_sided` for details.
+    #[cfg(feature = "dim3")]
+    pub fn is_one_sided(&self) -> bool {
+        self.one_sided
+    }
+
+    /// Sets whether this collider only collides with the front face of the triangles of its
+    /// triangle mesh.
+    ///
+    /// See `ColliderBuilder::one_sided` for details.
+    #[cfg(feature = "dim3")]
+    pub fn set_one_sided(&mut self, one_sided: bool) {
+        self.one_sided = one_sided;
+    }
+
     #[doc(hidden)]
     pub fn set_position_debug(&mut self, position: Isometry<Real>) {
         self.position = position;
@@ -123,11 +252,220 @@ impl Collider {
         &*self.shape.0
     }
 
+    /// The scale applied to the shape of this collider with `ColliderSet::set_scale`.
+    ///
+    /// This is reset to 1 whenever the shape of this collider is replaced.
+    pub fn scale(&self) -> Vector<Real> {
+        self.scale
+    }
+
+    /// The materials assigned to the parts of this collider's shape, if any.
+    pub fn part_materials(&self) -> Option<&ColliderPartMaterials> {
+        self.part_materials.as_deref()
+    }
+
+    /// Sets the materials assigned to the parts (heightfield cells or compound sub-shapes)
+    /// of this collider's shape.
+    pub fn set_part_materials(&mut self, part_materials: Option<ColliderPartMaterials>) {
+        self.part_materials = part_materials.map(Box::new);
+    }
+
+    /// Is the given sub-shape of this collider's compound shape solid?
+    ///
+    /// This is always `true` if this collider's shape isn't a compound shape.
+    /// See `ColliderBuilder::compound_solid_flags` for details.
+    pub fn is_subshape_solid(&self, subshape: u32) -> bool {
+        self.compound_solid_parts.as_ref().map_or(true, |parts| {
+            parts.solid.get(subshape as usize).copied().unwrap_or(true)
+        })
+    }
+
+    /// Sets which sub-shapes of this collider's compound shape are solid.
+    ///
+    /// See `ColliderBuilder::compound_solid_flags` for details.
+    pub fn set_compound_solid_flags(&mut self, solid: Option<Vec<bool>>) {
+        self.compound_solid_parts = solid
+            .and_then(|solid| CompoundSolidParts::new(&self.shape, solid))
+            .map(Box::new);
+    }
+
+    // Replaces the shape of this collider, updating the data derived from it.
+    pub(crate) fn set_shape_internal(&mut self, shape: SharedShape) -> SharedShape {
+        let old_shape = std::mem::replace(&mut self.shape, shape);
+        self.unscaled_shape = None;
+        self.scale = Vector::repeat(1.0);
+
+        if let Some(parts) = self.compound_solid_parts.take() {
+            self.set_compound_solid_flags(Some(parts.solid));
+        }
+
+        #[cfg(feature = "dim3")]
+        if self.internal_edges.is_some() {
+            self.internal_edges = InternalEdges::for_shape(&*self.shape.0).map(Box::new);
+        }
+
+        old_shape
+    }
+
+    // Computes the shape of this collider with the given scale applied to its unscaled shape.
+    pub(crate) fn compute_scaled_shape(&self, scale: &Vector<Real>) -> Option<SharedShape> {
+        let unscaled_shape = self.unscaled_shape.as_ref().unwrap_or(&self.shape);
+        scale_shape(&*unscaled_shape.0, scale)
+    }
+
+    // Replaces the shape of this collider by a scaled version of its unscaled shape.
+    pub(crate) fn set_scaled_shape_internal(
+        &mut self,
+        shape: SharedShape,
+        scale: Vector<Real>,
+    ) -> SharedShape {
+        let unscaled_shape = self
+            .unscaled_shape
+            .take()
+            .unwrap_or_else(|| self.shape.clone());
+        let old_shape = self.set_shape_internal(shape);
+
+        if scale != Vector::repeat(1.0) {
+            self.unscaled_shape = Some(unscaled_shape);
+            self.scale = scale;
+        }
+
+        old_shape
+    }
+
+    /// The shape used by the scene queries, i.e., the shape of this collider without the
+    /// hollow sub-shapes of its compound shape.
+    ///
+    /// Returns `None` if none of the sub-shapes of the compound shape are solid.
+    pub(crate) fn query_shape(&self) -> Option<&dyn Shape> {
+        match &self.compound_solid_parts {
+            Some(parts) => parts.shape.as_ref().map(|shape| &*shape.0),
+            None => Some(self.shape()),
+        }
+    }
+
+    /// Does the computation of contact coefficients need to identify the shape's part
+    /// touched by each contact?
+    pub(crate) fn has_part_properties(&self) -> bool {
+        #[cfg(feature = "dim3")]
+        {
+            if self.shape.as_heightfield().is_some() {
+                return true;
+            }
+        }
+
+        self.part_materials.is_some()
+    }
+
+    /// The index of the part of this collider's shape touched by a contact.
+    ///
+    /// The `subshape` is the sub-shape identifier reported by the contact manifold and
+    /// `local_point` is the contact point expressed in the local-space of this collider.
+    pub(crate) fn part_id(&self, subshape: u32, local_point: &Point<Real>) -> Option<u32> {
+        #[cfg(feature = "dim3")]
+        {
+            if let Some(heightfield) = self.shape.as_heightfield() {
+                return heightfield
+                    .cell_at_point(local_point)
+                    .map(|(i, j)| (i + j * heightfield.nrows()) as u32);
+            }
+        }
+
+        if self.shape.as_compound().is_some() {
+            Some(subshape)
+        } else {
+            None
+        }
+    }
+
+    /// The normal to use, instead of `local_normal`, for a contact with the triangle `subshape`
+    /// of this collider's triangle mesh or heightfield, if the contact lies on internal edges.
+    ///
+    /// This returns `None` unless this collider was built with `ColliderBuilder::fix_internal_edges`.
+    /// The points and normal are expressed in the local-space of this collider.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn internal_edge_normal(
+        &self,
+        subshape: u32,
+        local_points: impl IntoIterator<Item = Point<Real>>,
+        local_normal: &Vector<Real>,
+    ) -> Option<Vector<Real>> {
+        match &**self.internal_edges.as_ref()? {
+            InternalEdges::TriMesh(internal_edges) => {
+                let trimesh = self.shape.as_trimesh()?;
+                internal_edges.corrected_normal(trimesh, subshape, local_points, local_normal)
+            }
+            InternalEdges::HeightField => {
+                let heightfield = self.shape.as_heightfield()?;
+                heightfield_corrected_normal(heightfield, local_points, local_normal)
+            }
+        }
+    }
+
+    /// Does a contact with the triangle `subshape` of this collider's triangle mesh, with the
+    /// given outward normal, touch the back face of this triangle?
+    ///
+    /// This returns `false` unless this collider is one-sided. The normal is expressed in the
+    /// local-space of this collider.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn is_backface_contact(&self, subshape: u32, local_normal: &Vector<Real>) -> bool {
+        if !self.one_sided {
+            return false;
+        }
+
+        self.shape.as_trimesh().map_or(false, |trimesh| {
+            trimesh.triangle(subshape).scaled_normal().dot(local_normal) < 0.0
+        })
+    }
+
+    /// The material of the part of this collider's shape touched by a contact.
+    ///
+    /// See `Self::part_id` for details about the arguments.
+    pub(crate) fn part_material(
+        &self,
+        subshape: u32,
+        local_point: &Point<Real>,
+    ) -> Option<&ColliderMaterial> {
+        let part_materials = self.part_materials.as_ref()?;
+        part_materials.part_material(self.part_id(subshape, local_point)?)
+    }
+
+    /// Is the given point, expressed in the local-space of this collider, located on a hole
+    /// of its heightfield?
+    #[cfg(feature = "dim3")]
+    pub(crate) fn is_hole_at(&self, local_point: &Point<Real>) -> bool {
+        use crate::geometry::HeightFieldCellStatus;
+
+        if let Some(heightfield) = self.shape.as_heightfield() {
+            if let Some((i, j)) = heightfield.cell_at_point(local_point) {
+                return heightfield
+                    .cell_status(i, j)
+                    .contains(HeightFieldCellStatus::CELL_REMOVED);
+            }
+        }
+
+        false
+    }
+
     /// Compute the axis-aligned bounding box of this collider.
     pub fn compute_aabb(&self) -> AABB {
         self.shape.compute_aabb(&self.position)
     }
 
+    /// Compute the axis-aligned bounding box of this collider, as used by the broad-phase.
+    ///
+    /// This is the result of `Self::compute_aabb` enlarged by half the prediction distance
+    /// (see `IntegrationParameters::prediction_distance`) and by the contact skin of this collider.
+    pub fn compute_broad_phase_aabb(&self, prediction_distance: Real) -> AABB {
+        self.compute_aabb()
+            .loosened(prediction_distance / 2.0 + self.contact_skin)
+    }
+
+    /// Compute the bounding sphere of this collider.
+    pub fn compute_bounding_sphere(&self) -> BoundingSphere {
+        self.shape.compute_bounding_sphere(&self.position)
+    }
+
     // pub(crate) fn compute_aabb_with_prediction(&self) -> AABB {
     //     let aabb1 = self.shape.compute_aabb(&self.position);
     //     let aabb2 = self.shape.compute_aabb(&self.predicted_position);
@@ -140,6 +478,68 @@ impl Collider {
     }
 }
 
+// Scales the given shape non-uniformly, if the result can be represented exactly.
+fn scale_shape(shape: &dyn Shape, scale: &Vector<Real>) -> Option<SharedShape> {
+    if scale.iter().any(|s| *s <= 0.0) {
+        return None;
+    }
+
+    let uniform = scale.iter().all(|s| *s == scale.x);
+
+    if let Some(cuboid) = shape.as_cuboid() {
+        let half_extents = cuboid.half_extents.component_mul(scale);
+        return Some(SharedShape::new(Cuboid::new(half_extents)));
+    }
+
+    if let Some(ball) = shape.as_ball() {
+        return if uniform {
+            Some(SharedShape::ball(ball.radius * scale.x))
+        } else {
+            None
+        };
+    }
+
+    if let Some(capsule) = shape.as_capsule() {
+        let radius_scale = if uniform {
+            scale.x
+        } else {
+            // The capsule stays a capsule only if its segment is aligned with
+            // a coordinate axis and the scale is uniform along the other axes.
+            let dir = capsule.segment.scaled_direction();
+            let axis = dir.iamax();
+            let mut others = (0..DIM).filter(|i| *i != axis);
+            let first = others.next()?;
+
+            if others.any(|i| scale[i] != scale[first])
+                || (0..DIM).any(|i| i != axis && dir[i] != 0.0)
+            {
+                return None;
+            }
+
+            scale[first]
+        };
+
+        let a = Point::from(capsule.segment.a.coords.component_mul(scale));
+        let b = Point::from(capsule.segment.b.coords.component_mul(scale));
+        return Some(SharedShape::capsule(a, b, capsule.radius * radius_scale));
+    }
+
+    #[cfg(feature = "dim2")]
+    let points = shape.as_convex_polygon().map(|poly| poly.points());
+    #[cfg(feature = "dim3")]
+    let points = shape.as_convex_polyhedron().map(|poly| poly.points());
+
+    if let Some(points) = points {
+        let scaled: Vec<_> = points
+            .iter()
+            .map(|pt| Point::from(pt.coords.component_mul(scale)))
+            .collect();
+        return SharedShape::convex_hull(&scaled);
+    }
+
+    None
+}
+
 /// A structure responsible for building a new collider.
 #[derive(Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -156,10 +556,33 @@ pub struct ColliderBuilder {
     pub restitution: Real,
     /// The rule used to combine two restitution coefficients.
     pub restitution_combine_rule: CoefficientCombineRule,
+    /// The materials assigned to the parts of the shape of the collider to be built.
+    pub part_materials: Option<ColliderPartMaterials>,
+    /// For each sub-shape of the compound shape of the collider to be built, is it solid?
+    pub compound_solid_flags: Option<Vec<bool>>,
+    /// The contact force above which a `ContactForceEvent` is emitted for the collider to be built.
+    pub contact_force_event_threshold: Real,
+    /// The separation maintained by the collider to be built with the colliders it is resting on.
+    pub contact_skin: Real,
+    /// The approach speed below which the contacts of the collider to be built are ignored.
+    pub min_approach_speed: Real,
+    /// The approach speed below which the contacts of the collider to be built don't bounce,
+    /// if it overrides the global one.
+    pub restitution_velocity_threshold: Option<Real>,
+    /// Whether the normals of the contacts on the internal edges of the triangle mesh of the
+    /// collider being built are corrected.
+    #[cfg(feature = "dim3")]
+    pub fix_internal_edges: bool,
+    /// Whether the collider being built only collides with the front face of the triangles of
+    /// its triangle mesh.
+    #[cfg(feature = "dim3")]
+    pub one_sided: bool,
     /// The position of this collider relative to the local frame of the rigid-body it is attached to.
     pub delta: Isometry<Real>,
     /// Is this collider a sensor?
     pub is_sensor: bool,
+    /// Is the collider to be built enabled?
+    pub enabled: bool,
     /// Do we have to always call the contact modifier
     /// on this collider?
     pub modify_solver_contacts: bool,
@@ -179,8 +602,19 @@ impl ColliderBuilder {
             density: None,
             friction: Self::default_friction(),
             restitution: 0.0,
+            part_materials: None,
+            compound_solid_flags: None,
+            contact_force_event_threshold: Real::MAX,
+            contact_skin: 0.0,
+            min_approach_speed: -Real::MAX,
+            restitution_velocity_threshold: None,
+            #[cfg(feature = "dim3")]
+            fix_internal_edges: false,
+            #[cfg(feature = "dim3")]
+            one_sided: false,
             delta: Isometry::identity(),
             is_sensor: false,
+            enabled: true,
             user_data: 0,
             collision_groups: InteractionGroups::all(),
             solver_groups: InteractionGroups::all(),
@@ -201,6 +635,26 @@ impl ColliderBuilder {
         Self::new(SharedShape::compound(shapes))
     }
 
+    /// Initialize a new collider builder with a compound shape where each sub-shape has its own material.
+    ///
+    /// Contacts involving a sub-shape use its friction and restitution coefficients instead
+    /// of the ones of the collider.
+    pub fn compound_with_materials(
+        shapes: Vec<(Isometry<Real>, SharedShape, ColliderMaterial)>,
+    ) -> Self {
+        let mut subshapes = Vec::with_capacity(shapes.len());
+        let mut materials = Vec::with_capacity(shapes.len());
+
+        for (pos, shape, material) in shapes {
+            subshapes.push((pos, shape));
+            materials.push(material);
+        }
+
+        let part_material_ids = (0..materials.len() as u32).collect();
+        Self::compound(subshapes)
+            .part_materials(ColliderPartMaterials::new(materials, part_material_ids))
+    }
+
     /// Initialize a new collider builder with a ball shape defined by its radius.
     pub fn ball(radius: Real) -> Self {
         Self::new(SharedShape::ball(radius))
@@ -311,6 +765,11 @@ impl ColliderBuilder {
     }
 
     /// Initializes a collider builder with a triangle mesh shape defined by its vertex and index buffers.
+    ///
+    /// This computes the bounding volume hierarchy of the triangles, which may be slow for large
+    /// meshes. With the `serde-serialize` feature enabled, the resulting shape (including its
+    /// bounding volume hierarchy) can be "cooked" once by serializing `self.shape`, and later
+    /// loaded without any rebuild by deserializing it and passing it to [`ColliderBuilder::new`].
     pub fn trimesh(vertices: Vec<Point<Real>>, indices: Vec<[u32; 3]>) -> Self {
         Self::new(SharedShape::trimesh(vertices, indices))
     }
@@ -337,6 +796,10 @@ impl ColliderBuilder {
 
     /// Initializes a collider builder with a compound shape obtained from the decomposition of
     /// the given trimesh (in 3D) or polyline (in 2D) into convex parts.
+    ///
+    /// The `params` control the quality of the decomposition, e.g., `params.resolution` for the
+    /// resolution of the voxelization, and `params.concavity` for the maximum concavity of
+    /// each convex part.
     pub fn convex_decomposition_with_params(
         vertices: &[Point<Real>],
         indices: &[[u32; DIM]],
@@ -426,6 +889,33 @@ impl ColliderBuilder {
         Self::new(SharedShape::heightfield(heights, scale))
     }
 
+    /// Initializes a collider builder with a heightfield shape defined by its set of height, a scale
+    /// factor along each coordinate axis, and a set of holes.
+    ///
+    /// The cell on the row `i` and column `j` of the heightfield is removed (no contact and no
+    /// scene query will hit it) if `holes[(i, j)]` is `true`. The `holes` matrix must have one
+    /// less row and one less column than `heights`.
+    #[cfg(feature = "dim3")]
+    pub fn heightfield_with_holes(
+        heights: na::DMatrix<Real>,
+        scale: Vector<Real>,
+        holes: &na::DMatrix<bool>,
+    ) -> Self {
+        use crate::geometry::{HeightField, HeightFieldCellStatus};
+
+        let mut heightfield = HeightField::new(heights, scale);
+
+        for j in 0..heightfield.ncols() {
+            for i in 0..heightfield.nrows() {
+                if holes[(i, j)] {
+                    heightfield.set_cell_status(i, j, HeightFieldCellStatus::CELL_REMOVED);
+                }
+            }
+        }
+
+        Self::new(SharedShape::new(heightfield))
+    }
+
     /// The default friction coefficient used by the collider builder.
     pub fn default_friction() -> Real {
         0.5
@@ -461,6 +951,14 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets whether or not the collider built by this builder is enabled.
+    ///
+    /// See `Collider::set_enabled` for the effects of disabling a collider.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     /// If set to `true` then the physics hooks will always run to modify
     /// contacts involving this collider.
     pub fn modify_solver_contacts(mut self, modify_solver_contacts: bool) -> Self {
@@ -492,6 +990,97 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets, for each sub-shape of the compound shape of the collider this builder will build,
+    /// whether it is solid.
+    ///
+    /// A hollow sub-shape (with a `false` flag), e.g., a doorway, is ignored by the scene queries
+    /// of the `QueryPipeline` and its contacts are ignored by the constraints solver, so rays
+    /// and objects pass through it. Sub-shapes without any flag are solid. This has no effect
+    /// if the shape isn't a compound shape.
+    pub fn compound_solid_flags(mut self, solid: Vec<bool>) -> Self {
+        self.compound_solid_flags = Some(solid);
+        self
+    }
+
+    /// Sets the materials assigned to the parts (heightfield cells or compound sub-shapes)
+    /// of the shape of the collider this builder will build.
+    pub fn part_materials(mut self, part_materials: ColliderPartMaterials) -> Self {
+        self.part_materials = Some(part_materials);
+        self
+    }
+
+    /// Sets the contact force above which a `ContactForceEvent` is emitted for the
+    /// collider this builder will build.
+    pub fn contact_force_event_threshold(mut self, threshold: Real) -> Self {
+        self.contact_force_event_threshold = threshold;
+        self
+    }
+
+    /// Sets the contact skin of the collider this builder will build (default: `0.0`).
+    ///
+    /// The constraints solver will keep a gap of this size between the collider and the colliders
+    /// it touches, instead of letting them touch exactly. If both colliders have a skin, the gap is
+    /// the sum of their skins. The restitution applies when the gap is reached. This can be used to
+    /// avoid visual interpenetrations, or jitter, for delicate objects at rest.
+    pub fn contact_skin(mut self, skin: Real) -> Self {
+        assert!(skin >= 0.0, "The contact skin cannot be negative.");
+        self.contact_skin = skin;
+        self
+    }
+
+    /// Sets the approach speed below which the contacts of the collider this builder will build
+    /// are ignored by the constraints solver (default: `-Real::MAX`, i.e., disabled).
+    ///
+    /// The approach speed of a contact is the relative velocity of the two bodies at the contact
+    /// point, along the contact normal, positive if they move toward each other. With a positive
+    /// speed, the colliders approaching each other slowly interpenetrate, while the fast impacts
+    /// are still resolved. This is useful, e.g., for particle systems. Contacts are ignored if
+    /// their approach speed is smaller than the largest minimum approach speed of both colliders.
+    pub fn min_approach_speed(mut self, speed: Real) -> Self {
+        self.min_approach_speed = speed;
+        self
+    }
+
+    /// Sets the approach speed below which the contacts of the collider this builder will build
+    /// don't bounce, overriding `IntegrationParameters::restitution_velocity_threshold`.
+    ///
+    /// For example, a trampoline can use a small threshold to bounce even slow objects, while
+    /// the global threshold keeps the other objects from jittering when they come to rest. If
+    /// both colliders of a contact override the global threshold, the smallest one is used.
+    pub fn restitution_velocity_threshold(mut self, threshold: Real) -> Self {
+        self.restitution_velocity_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets whether the normals of the contacts on the internal edges of the triangle mesh or
+    /// heightfield of the collider this builder will build are corrected (default: `false`).
+    ///
+    /// An internal edge is an edge shared by two coplanar triangles. Contacts on such an edge may
+    /// have a normal tilted toward the edge, making objects rolling or sliding on the mesh catch
+    /// on it. If enabled, the normal of these contacts is replaced by the normal of the triangle.
+    /// For heightfields, this applies to the edges between the cells, and to the diagonal of each
+    /// cell, which is typically what makes capsule characters bump when walking on terrain.
+    /// This has no effect if the shape isn't a triangle mesh or a heightfield.
+    #[cfg(feature = "dim3")]
+    pub fn fix_internal_edges(mut self, enabled: bool) -> Self {
+        self.fix_internal_edges = enabled;
+        self
+    }
+
+    /// Sets whether the collider this builder will build only collides with the front face of
+    /// the triangles of its triangle mesh (default: `false`).
+    ///
+    /// The front face of a triangle is the one its counterclockwise winding points toward. If
+    /// enabled, contacts touching the back face of a triangle are ignored by the constraints
+    /// solver, letting objects coming from behind the mesh pass through it. Ray casts can ignore
+    /// these back faces with `QueryPipeline::cast_ray_and_get_normal_ignoring_backfaces`.
+    /// This has no effect if the shape isn't a triangle mesh.
+    #[cfg(feature = "dim3")]
+    pub fn one_sided(mut self, one_sided: bool) -> Self {
+        self.one_sided = one_sided;
+        self
+    }
+
     /// Sets the density of the collider this builder will build.
     pub fn density(mut self, density: Real) -> Self {
         self.density = Some(density);
@@ -543,6 +1132,7 @@ impl ColliderBuilder {
         let density = self.get_density();
         let mut flags = ColliderFlags::empty();
         flags.set(ColliderFlags::SENSOR, self.is_sensor);
+        flags.set(ColliderFlags::DISABLED, !self.enabled);
         flags = flags
             .with_friction_combine_rule(self.friction_combine_rule)
             .with_restitution_combine_rule(self.restitution_combine_rule);
@@ -554,9 +1144,29 @@ impl ColliderBuilder {
 
         Collider {
             shape: self.shape.clone(),
+            unscaled_shape: None,
+            scale: Vector::repeat(1.0),
             density,
             friction: self.friction,
             restitution: self.restitution,
+            part_materials: self.part_materials.clone().map(Box::new),
+            compound_solid_parts: self
+                .compound_solid_flags
+                .clone()
+                .and_then(|solid| CompoundSolidParts::new(&self.shape, solid))
+                .map(Box::new),
+            contact_force_event_threshold: self.contact_force_event_threshold,
+            contact_skin: self.contact_skin,
+            min_approach_speed: self.min_approach_speed,
+            restitution_velocity_threshold: self.restitution_velocity_threshold,
+            #[cfg(feature = "dim3")]
+            internal_edges: if self.fix_internal_edges {
+                InternalEdges::for_shape(&*self.shape.0).map(Box::new)
+            } else {
+                None
+            },
+            #[cfg(feature = "dim3")]
+            one_sided: self.one_sided,
             delta: self.delta,
             flags,
             solver_flags,
@@ -570,3 +1180,542 @@ impl ColliderBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn collider_toggle_enabled() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let co = ColliderBuilder::cuboid(10.0, 0.1).build();
+        #[cfg(feature = "dim3")]
+        let co = ColliderBuilder::cuboid(10.0, 0.1, 10.0).build();
+        let ground_co = world.colliders.insert(co, ground, &mut world.bodies);
+
+        let rb = RigidBodyBuilder::new_dynamic()
+            .position(Isometry::new(Vector::y() * 0.6, na::zero()))
+            .can_sleep(false)
+            .build();
+        let ball = world.bodies.insert(rb);
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        // The ball rests on the ground.
+        for _ in 0..60 {
+            world.step();
+        }
+        assert!(world.bodies[ball].position().translation.y > 0.55);
+
+        // The ball falls through the disabled ground.
+        world.colliders[ground_co].set_enabled(false);
+        for _ in 0..10 {
+            world.step();
+        }
+        assert!(world.bodies[ball].position().translation.y < 0.55);
+        assert!(world.bodies[ball].linvel().y < -1.0);
+
+        // The ball is pushed back on top of the ground once it is enabled again.
+        world.colliders[ground_co].set_enabled(true);
+        for _ in 0..60 {
+            world.step();
+        }
+        assert!(world.bodies[ball].position().translation.y > 0.55);
+        assert!(world.bodies[ball].linvel().norm() < 0.1);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn convex_decomposition_of_l_shape() {
+        use crate::geometry::Ball;
+        use crate::math::Point;
+        use parry::transformation::vhacd::VHACDParameters;
+
+        // An L-shaped prism: the L of the `xy` plane extruded along `z`.
+        let outline = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+        ];
+        let mut vertices = Vec::new();
+        for z in [0.0, 1.0].iter() {
+            for [x, y] in outline.iter() {
+                vertices.push(Point::new(*x, *y, *z));
+            }
+        }
+
+        let n = outline.len() as u32;
+        let mut indices = Vec::new();
+        for i in 0..n {
+            let j = (i + 1) % n;
+            indices.push([i, j, j + n]);
+            indices.push([i, j + n, i + n]);
+        }
+        // The caps, made of the two rectangles of the L.
+        for offset in [0, n].iter() {
+            for [a, b, c, d] in [[0, 1, 2, 3], [0, 3, 4, 5]].iter() {
+                indices.push([a + offset, b + offset, c + offset]);
+                indices.push([a + offset, c + offset, d + offset]);
+            }
+        }
+
+        let params = VHACDParameters {
+            resolution: 64,
+            ..VHACDParameters::default()
+        };
+        let collider =
+            ColliderBuilder::convex_decomposition_with_params(&vertices, &indices, &params).build();
+
+        // The decomposition has several convex parts, none of them covering the notch of the L.
+        let compound = collider.shape().as_compound().unwrap();
+        assert!(compound.shapes().len() > 1);
+
+        let notch = Ball::new(0.2);
+        let notch_pos = Isometry::translation(1.5, 1.5, 0.5);
+        for (pos, part) in compound.shapes() {
+            let pos12 = pos.inv_mul(&notch_pos);
+            assert!(!parry::query::intersection_test(&pos12, &**part, &notch).unwrap());
+        }
+
+        // A dynamic L-shaped body falling on the ground comes to rest on it.
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        world.colliders.insert(
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        let body = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y(), na::zero()))
+                .build(),
+        );
+        world.colliders.insert(collider, body, &mut world.bodies);
+
+        for _ in 0..200 {
+            world.step();
+        }
+
+        let rb = &world.bodies[body];
+        assert!(rb.linvel().norm() < 0.1);
+        assert!((rb.position().translation.vector.y - 0.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn resting_on_contact_skin() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        let skin = 0.05;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world.colliders.insert(
+            ground_shape.contact_skin(skin).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..200 {
+            world.step();
+        }
+
+        // The ball rests on the ground with a gap equal to the skin.
+        let gap = world.bodies[ball].position().translation.vector.y - 1.0;
+        assert!(
+            (gap - skin).abs() <= world.params.allowed_linear_error + 1.0e-3,
+            "{}",
+            gap
+        );
+        assert!(world.bodies[ball].linvel().norm() < 1.0e-3);
+    }
+
+    #[test]
+    fn min_approach_speed() {
+        // Returns the final height of a particle launched toward the ground.
+        let simulate = |speed: Real| {
+            let mut world = TestWorld::new();
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let mut rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.1, na::zero()))
+                .build();
+            rb.set_linvel(Vector::y() * -speed, true);
+            let particle = world.bodies.insert(rb);
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).min_approach_speed(1.0).build(),
+                particle,
+                &mut world.bodies,
+            );
+
+            for _ in 0..120 {
+                world.step();
+            }
+
+            world.bodies[particle].position().translation.vector.y
+        };
+
+        // Slow particles pass through the ground.
+        assert!(simulate(0.5) < 0.5);
+        // Fast particles collide with the ground.
+        assert!(simulate(5.0) > 0.9);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn one_sided_trimesh() {
+        use crate::math::Point;
+
+        // Shoots a ball at a one-sided square facing +y, and returns its final height.
+        let shoot = |start_y: Real, vel_y: Real| {
+            let mut world = TestWorld::new();
+
+            let vertices = vec![
+                Point::new(-5.0, 0.0, -5.0),
+                Point::new(-5.0, 0.0, 5.0),
+                Point::new(5.0, 0.0, 5.0),
+                Point::new(5.0, 0.0, -5.0),
+            ];
+            let indices = vec![[0, 1, 2], [0, 2, 3]];
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            world.colliders.insert(
+                ColliderBuilder::trimesh(vertices, indices)
+                    .one_sided(true)
+                    .build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .translation(0.0, start_y, 0.0)
+                    .linvel(0.0, vel_y, 0.0)
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+            for _ in 0..60 {
+                world.step();
+            }
+
+            world.bodies[ball].position().translation.y
+        };
+
+        // Coming from the front, the ball is stopped by the mesh.
+        let y = shoot(2.0, -10.0);
+        assert!(y > 0.4 && y < 0.6, "{}", y);
+
+        // Coming from behind, the ball passes through the mesh.
+        let y = shoot(-2.0, 10.0);
+        assert!(y > 5.0, "{}", y);
+    }
+
+    #[test]
+    fn restitution_velocity_threshold_override() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.restitution_velocity_threshold = 2.0;
+
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(1.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(1.0, 0.5, 1.0);
+
+        let mut balls = vec![];
+
+        // The balls hit the ground at about 1m/s: below the global threshold,
+        // but above the trampoline's one.
+        for (i, threshold) in [Some(0.1), None].iter().enumerate() {
+            let x = i as Real * 10.0;
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(
+                        Vector::x() * x - Vector::y() * 0.5,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            let mut ground_shape = ground_shape.clone().restitution(1.0);
+            if let Some(threshold) = threshold {
+                ground_shape = ground_shape.restitution_velocity_threshold(*threshold);
+            }
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * x + Vector::y() * 0.55,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).restitution(1.0).build(),
+                ball,
+                &mut world.bodies,
+            );
+            balls.push(ball);
+        }
+
+        let mut max_upward_speed = [0.0 as Real; 2];
+
+        for _ in 0..60 {
+            world.step();
+
+            for (speed, ball) in max_upward_speed.iter_mut().zip(balls.iter()) {
+                *speed = speed.max(world.bodies[*ball].linvel().y);
+            }
+        }
+
+        assert!(max_upward_speed[0] > 0.5);
+        assert!(max_upward_speed[1] < 0.2);
+    }
+
+    #[test]
+    fn compound_sub_shape_materials() {
+        use crate::dynamics::CoefficientCombineRule;
+        use crate::geometry::{ColliderMaterial, SharedShape};
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                .build(),
+        );
+        let ground_collider = ColliderBuilder::ball(100.0).friction(1.0).build();
+        let ground_handle = world
+            .colliders
+            .insert(ground_collider, ground, &mut world.bodies);
+
+        // A slippery sub-shape and a grippy sub-shape.
+        let slippery = ColliderMaterial::new(0.0, 0.0);
+        let grippy = ColliderMaterial::new(1.0, 0.0);
+        let shapes = vec![
+            (
+                Isometry::new(Vector::x() * -1.0, na::zero()),
+                SharedShape::ball(0.5),
+                slippery,
+            ),
+            (
+                Isometry::new(Vector::x() * 1.0, na::zero()),
+                SharedShape::ball(0.5),
+                grippy,
+            ),
+        ];
+        let body = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.5, na::zero()))
+                .build(),
+        );
+        let compound = ColliderBuilder::compound_with_materials(shapes)
+            .friction_combine_rule(CoefficientCombineRule::Multiply)
+            .build();
+        let compound_handle = world.colliders.insert(compound, body, &mut world.bodies);
+
+        for _ in 0..5 {
+            world.step();
+        }
+
+        let pair = world
+            .narrow_phase
+            .contact_pair(ground_handle, compound_handle)
+            .unwrap();
+        let mut touched_parts = [false; 2];
+
+        for manifold in &pair.manifolds {
+            let part = if pair.pair.collider1 == compound_handle {
+                manifold.subshape1
+            } else {
+                manifold.subshape2
+            };
+            let expected_friction = if part == 0 { 0.0 } else { 1.0 };
+
+            for contact in &manifold.data.solver_contacts {
+                touched_parts[part as usize] = true;
+                assert!((contact.friction - expected_friction).abs() < 1.0e-6);
+            }
+        }
+
+        assert_eq!(touched_parts, [true, true]);
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn heightfield_holes_and_cell_materials() {
+        use crate::dynamics::CoefficientCombineRule;
+        use crate::geometry::{
+            ColliderMaterial, ColliderPartMaterials, HeightField, InteractionGroups, Ray,
+        };
+        use crate::math::Point;
+        use crate::pipeline::QueryPipeline;
+
+        let mut world = TestWorld::new();
+        let mut query_pipeline = QueryPipeline::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // A flat 10x10 cells heightfield, with cells of size 2x2.
+        let heights = na::DMatrix::zeros(11, 11);
+        let scale = Vector::new(20.0, 1.0, 20.0);
+        let heightfield = HeightField::new(heights.clone(), scale);
+        let cell_id = |x: Real, z: Real| {
+            let (i, j) = heightfield.cell_at_point(&Point::new(x, 0.0, z)).unwrap();
+            (i, j, i + j * heightfield.nrows())
+        };
+
+        // One hole, icy cells for x < 0, and rough cells for x > 0.
+        let mut holes = na::DMatrix::repeat(10, 10, false);
+        let (hole_i, hole_j, _) = cell_id(5.0, 5.0);
+        holes[(hole_i, hole_j)] = true;
+
+        let mut part_material_ids = vec![0; 100];
+        for x in (-9..=9).step_by(2) {
+            for z in (-9..=9).step_by(2) {
+                let (_, _, id) = cell_id(x as Real, z as Real);
+                part_material_ids[id] = if x < 0 { 0 } else { 1 };
+            }
+        }
+        let materials = ColliderPartMaterials::new(
+            vec![
+                ColliderMaterial::new(0.0, 0.0),
+                ColliderMaterial::new(1.0, 0.0),
+            ],
+            part_material_ids,
+        );
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::heightfield_with_holes(heights, scale, &holes)
+            .part_materials(materials)
+            .build();
+        world.colliders.insert(co, ground, &mut world.bodies);
+
+        // Ray-cast through the hole.
+        query_pipeline.update(&world.bodies, &world.colliders);
+        let groups = InteractionGroups::all();
+        let ray = Ray::new(Point::new(5.0, 10.0, 5.0), -Vector::y());
+        assert!(query_pipeline
+            .cast_ray(&world.colliders, &ray, Real::MAX, true, groups)
+            .is_none());
+        let ray = Ray::new(Point::new(-5.0, 10.0, 5.0), -Vector::y());
+        assert!(query_pipeline
+            .cast_ray(&world.colliders, &ray, Real::MAX, true, groups)
+            .is_some());
+
+        // Slide one box on an icy cell, and another on a rough cell.
+        let mut boxes = [None; 2];
+        for (k, x) in [-5.0, 5.0].iter().enumerate() {
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(*x, 0.2, -5.0)
+                .linvel(0.0, 0.0, 3.0)
+                .build();
+            let handle = world.bodies.insert(rb);
+            let co = ColliderBuilder::cuboid(0.5, 0.2, 0.5)
+                .friction(0.0)
+                .friction_combine_rule(CoefficientCombineRule::Max)
+                .build();
+            world.colliders.insert(co, handle, &mut world.bodies);
+            boxes[k] = Some(handle);
+        }
+
+        for _ in 0..90 {
+            world.step();
+        }
+
+        let icy_dz = world.bodies[boxes[0].unwrap()].position().translation.z + 5.0;
+        let rough_dz = world.bodies[boxes[1].unwrap()].position().translation.z + 5.0;
+        assert!(icy_dz > 3.0);
+        assert!(rough_dz < 1.0);
+    }
+
+    #[cfg(all(feature = "dim3", feature = "serde-serialize"))]
+    #[test]
+    fn cooked_trimesh_matches_fresh_trimesh() {
+        use crate::geometry::{Ball, Ray, SharedShape};
+        use crate::math::Point;
+
+        let n = 30;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for i in 0..n {
+            for j in 0..n {
+                let height = ((i * j) % 7) as Real * 0.1;
+                vertices.push(Point::new(i as Real, height, j as Real));
+            }
+        }
+
+        for i in 0..n - 1 {
+            for j in 0..n - 1 {
+                let a = i * n + j;
+                indices.push([a, a + n, a + 1]);
+                indices.push([a + 1, a + n, a + n + 1]);
+            }
+        }
+
+        let fresh = ColliderBuilder::trimesh(vertices, indices).shape;
+        let cooked = bincode::serialize(&fresh).unwrap();
+        let loaded: SharedShape = bincode::deserialize(&cooked).unwrap();
+
+        // The loaded shape, including its BVH, is identical to the cooked one.
+        assert_eq!(bincode::serialize(&loaded).unwrap(), cooked);
+        let collider = ColliderBuilder::new(loaded).build();
+
+        let pos = Isometry::identity();
+        let ball = Ball::new(0.5);
+
+        for k in 0..20 {
+            let origin = Point::new(k as Real * 1.37, 5.0, k as Real * 0.91);
+            let ray = Ray::new(origin, -Vector::y());
+            assert_eq!(
+                fresh.cast_ray(&pos, &ray, 10.0, true),
+                collider.shape().cast_ray(&pos, &ray, 10.0, true)
+            );
+
+            let ball_pos = Isometry::translation(origin.x, 0.3, origin.z);
+            let contact = |shape: &dyn crate::geometry::Shape| {
+                parry::query::contact(&pos, shape, &ball_pos, &ball, 0.1)
+                    .unwrap()
+                    .map(|c| (c.point1, c.point2, c.normal1, c.dist))
+            };
+            assert_eq!(contact(&*fresh), contact(collider.shape()));
+        }
+    }
+}
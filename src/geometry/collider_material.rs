@@ -0,0 +1,58 @@
+use crate::math::Real;
+
+/// The friction and restitution coefficients of a part of a collider.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct ColliderMaterial {
+    /// The friction coefficient of this material.
+    pub friction: Real,
+    /// The restitution coefficient of this material.
+    pub restitution: Real,
+}
+
+impl ColliderMaterial {
+    /// Creates a new material with the given friction and restitution coefficients.
+    pub fn new(friction: Real, restitution: Real) -> Self {
+        Self {
+            friction,
+            restitution,
+        }
+    }
+}
+
+/// Materials assigned to the parts of the shape of a collider.
+///
+/// The parts of a shape are:
+/// - The cells of a heightfield (3D only). The cell on the row `i` and column `j` is
+///   the part with index `i + j * heightfield.nrows()`.
+/// - The sub-shapes of a compound shape. The part index is the index of the sub-shape.
+///
+/// Contacts involving one of these parts use the part's material instead of the
+/// friction and restitution coefficients of the collider.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct ColliderPartMaterials {
+    /// The set of materials that can be assigned to the parts.
+    pub materials: Vec<ColliderMaterial>,
+    /// For each part, the index of its material in `self.materials`.
+    ///
+    /// A part without any entry, or with an index that is out of the bounds
+    /// of `self.materials`, uses the collider's coefficients.
+    pub part_material_ids: Vec<u32>,
+}
+
+impl ColliderPartMaterials {
+    /// Creates a new set of materials assigned to parts of a shape.
+    pub fn new(materials: Vec<ColliderMaterial>, part_material_ids: Vec<u32>) -> Self {
+        Self {
+            materials,
+            part_material_ids,
+        }
+    }
+
+    /// The material assigned to the given part, if any.
+    pub fn part_material(&self, part_id: u32) -> Option<&ColliderMaterial> {
+        let material_id = *self.part_material_ids.get(part_id as usize)?;
+        self.materials.get(material_id as usize)
+    }
+}
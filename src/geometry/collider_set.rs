@@ -1,12 +1,14 @@
 use crate::data::arena::Arena;
 use crate::data::pubsub::PubSub;
 use crate::dynamics::{RigidBodyHandle, RigidBodySet};
-use crate::geometry::Collider;
+use crate::geometry::{Collider, SharedShape, AABB};
+use crate::math::{Real, Vector};
 use parry::partitioning::IndexedData;
+use std::collections::HashSet;
 use std::ops::{Index, IndexMut};
 
 /// The unique identifier of a collider added to a collider set.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[repr(transparent)]
 pub struct ColliderHandle(pub(crate) crate::data::arena::Index);
@@ -54,6 +56,11 @@ pub(crate) struct RemovedCollider {
 pub struct ColliderSet {
     pub(crate) removed_colliders: PubSub<RemovedCollider>,
     pub(crate) colliders: Arena<Collider>,
+    // The colliders which changed shape since the last update of the narrow-phase, and whether
+    // their contacts have to be preserved.
+    pub(crate) modified_shapes: Vec<(ColliderHandle, bool)>,
+    // The number of colliders with a finite contact force event threshold.
+    num_contact_force_event_colliders: usize,
 }
 
 impl ColliderSet {
@@ -62,6 +69,8 @@ impl ColliderSet {
         ColliderSet {
             removed_colliders: PubSub::new(),
             colliders: Arena::new(),
+            modified_shapes: Vec::new(),
+            num_contact_force_event_colliders: 0,
         }
     }
 
@@ -75,6 +84,14 @@ impl ColliderSet {
         self.colliders.iter().map(|(h, c)| (ColliderHandle(h), c))
     }
 
+    /// An iterator through the axis-aligned bounding boxes of all the colliders on this set.
+    ///
+    /// These are computed from the current position and shape of each collider, without the
+    /// margin added by the broad-phase (see `Collider::compute_broad_phase_aabb`).
+    pub fn aabbs(&self) -> impl ExactSizeIterator<Item = (ColliderHandle, AABB)> + '_ {
+        self.iter().map(|(h, c)| (h, c.compute_aabb()))
+    }
+
     /// The number of colliders on this set.
     pub fn len(&self) -> usize {
         self.colliders.len()
@@ -110,12 +127,64 @@ impl ColliderSet {
             .expect("Parent rigid body not found.");
         coll.position = parent.position * coll.delta;
         coll.predicted_position = parent.predicted_position * coll.delta;
+        if coll.contact_force_event_threshold < Real::MAX {
+            self.num_contact_force_event_colliders += 1;
+        }
         let handle = ColliderHandle(self.colliders.insert(coll));
         let coll = self.colliders.get(handle.0).unwrap();
         parent.add_collider(handle, &coll);
         handle
     }
 
+    /// Inserts several colliders to this set and retrieve their handles.
+    ///
+    /// Each collider is attached to the rigid-body it is paired with. This yields the same
+    /// handles as calling `self.insert` on each collider in order, but the mass properties
+    /// of each parent rigid-body are only updated once for the whole batch.
+    pub fn insert_batch(
+        &mut self,
+        colliders: Vec<(Collider, RigidBodyHandle)>,
+        bodies: &mut RigidBodySet,
+    ) -> Vec<ColliderHandle> {
+        // Only reserve if there are no free slots, to preserve the handle allocation order.
+        if self.colliders.len() == self.colliders.capacity() {
+            self.colliders.reserve(colliders.len());
+        }
+
+        let mut handles = Vec::with_capacity(colliders.len());
+        let mut parents = HashSet::new();
+
+        for (mut coll, parent_handle) in colliders {
+            coll.reset_internal_references();
+            coll.parent = parent_handle;
+
+            // NOTE: we use `get_mut` instead of `get_mut_internal` so that the
+            // modification flag is updated properly.
+            let parent = bodies
+                .get_mut(parent_handle)
+                .expect("Parent rigid body not found.");
+            coll.position = parent.position * coll.delta;
+            coll.predicted_position = parent.predicted_position * coll.delta;
+            if coll.contact_force_event_threshold < Real::MAX {
+                self.num_contact_force_event_colliders += 1;
+            }
+            let handle = ColliderHandle(self.colliders.insert(coll));
+            let coll = self.colliders.get(handle.0).unwrap();
+            parent.add_collider_without_mass_update(handle, &coll);
+            parents.insert(parent_handle);
+            handles.push(handle);
+        }
+
+        for parent_handle in parents {
+            bodies
+                .get_mut_internal(parent_handle)
+                .unwrap()
+                .update_local_mass_properties();
+        }
+
+        handles
+    }
+
     /// Remove a collider from this set and update its parent accordingly.
     ///
     /// If `wake_up` is `true`, the rigid-body the removed collider is attached to
@@ -128,6 +197,10 @@ impl ColliderSet {
     ) -> Option<Collider> {
         let collider = self.colliders.remove(handle.0)?;
 
+        if collider.contact_force_event_threshold < Real::MAX {
+            self.num_contact_force_event_colliders -= 1;
+        }
+
         /*
          * Delete the collider from its parent body.
          */
@@ -154,6 +227,114 @@ impl ColliderSet {
         Some(collider)
     }
 
+    /// Sets the contact force above which a `ContactForceEvent` is emitted for the contacts
+    /// involving the given collider.
+    ///
+    /// The threshold actually used for a pair of colliders is the smallest of their thresholds
+    /// and of `IntegrationParameters::contact_force_event_threshold`. Set it to `Real::MAX`
+    /// to rely only on the other thresholds.
+    pub fn set_contact_force_event_threshold(&mut self, handle: ColliderHandle, threshold: Real) {
+        if let Some(collider) = self.colliders.get_mut(handle.0) {
+            let was_finite = collider.contact_force_event_threshold < Real::MAX;
+            collider.contact_force_event_threshold = threshold;
+
+            match (was_finite, threshold < Real::MAX) {
+                (false, true) => self.num_contact_force_event_colliders += 1,
+                (true, false) => self.num_contact_force_event_colliders -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Does any collider of this set have a finite contact force event threshold?
+    pub(crate) fn has_contact_force_event_thresholds(&self) -> bool {
+        self.num_contact_force_event_colliders > 0
+    }
+
+    /// Replaces the shape of a collider and updates the mass properties of its parent accordingly.
+    ///
+    /// The contacts of this collider are recomputed from scratch during the next timestep. If
+    /// `preserve_contacts` is `true`, the data of each contact (including the impulses used to
+    /// warmstart the constraints solver) is transferred to the closest new contact, if any, so
+    /// swapping to a similar shape (e.g., a shorter capsule for a crouching character) doesn't
+    /// disturb the simulation. Otherwise, the new contacts start without any impulse.
+    ///
+    /// The rigid-body the collider is attached to is woken up. Returns the previous shape of
+    /// the collider, or `None` if the collider doesn't exist.
+    pub fn set_shape(
+        &mut self,
+        handle: ColliderHandle,
+        shape: SharedShape,
+        bodies: &mut RigidBodySet,
+        preserve_contacts: bool,
+    ) -> Option<SharedShape> {
+        self.replace_shape(handle, bodies, preserve_contacts, |collider| {
+            collider.set_shape_internal(shape)
+        })
+    }
+
+    /// Scales the shape of a collider non-uniformly and updates the mass properties of its
+    /// parent accordingly.
+    ///
+    /// The scale is applied to the shape the collider had before any call to `set_scale`, so
+    /// the scale doesn't accumulate when this is called repeatedly, e.g., to animate a
+    /// squash-and-stretch effect. The contacts of the collider are preserved as with
+    /// `ColliderSet::set_shape`.
+    ///
+    /// Only cuboids, convex polygons/polyhedra, balls, and capsules can be scaled. A ball
+    /// only supports uniform scaling, and a capsule only supports non-uniform scaling if its
+    /// segment is aligned with a coordinate axis and the scale is uniform along the other
+    /// axes, because the result wouldn't be a ball or a capsule otherwise. In these cases, or
+    /// if any component of `scale` isn't positive, this returns `false` and leaves the
+    /// collider unchanged. You may then replace the shape with an approximation of your
+    /// choice using `ColliderSet::set_shape`.
+    pub fn set_scale(
+        &mut self,
+        handle: ColliderHandle,
+        scale: Vector<Real>,
+        bodies: &mut RigidBodySet,
+    ) -> bool {
+        let shape = match self
+            .get(handle)
+            .and_then(|collider| collider.compute_scaled_shape(&scale))
+        {
+            Some(shape) => shape,
+            None => return false,
+        };
+
+        self.replace_shape(handle, bodies, true, |collider| {
+            collider.set_scaled_shape_internal(shape, scale)
+        })
+        .is_some()
+    }
+
+    fn replace_shape(
+        &mut self,
+        handle: ColliderHandle,
+        bodies: &mut RigidBodySet,
+        preserve_contacts: bool,
+        replace: impl FnOnce(&mut Collider) -> SharedShape,
+    ) -> Option<SharedShape> {
+        let collider = self.colliders.get_mut(handle.0)?;
+        let old_mass_properties = collider
+            .mass_properties()
+            .transform_by(collider.position_wrt_parent());
+        let old_shape = replace(collider);
+        let new_mass_properties = collider
+            .mass_properties()
+            .transform_by(collider.position_wrt_parent());
+
+        // NOTE: we use `get_mut` instead of `get_mut_internal` so that the
+        // modification flag is updated properly.
+        if let Some(parent) = bodies.get_mut(collider.parent) {
+            parent.replace_collider_mass_properties(old_mass_properties, new_mass_properties);
+            parent.wake_up(true);
+        }
+
+        self.modified_shapes.push((handle, preserve_contacts));
+        Some(old_shape)
+    }
+
     /// Gets the collider with the given handle without a known generation.
     ///
     /// This is useful when you know you want the collider at position `i` but
@@ -229,3 +410,201 @@ impl IndexMut<ColliderHandle> for ColliderSet {
         &mut self.colliders[index.0]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBody, RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{ColliderBuilder, ColliderSet};
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn batch_insertion_matches_individual_insertion() {
+        let make_bodies = || {
+            (0..10)
+                .map(|i| {
+                    RigidBodyBuilder::new_dynamic()
+                        .position(Isometry::new(Vector::x() * i as Real, na::zero()))
+                        .build()
+                })
+                .collect::<Vec<_>>()
+        };
+        let make_colliders = |bodies: &[crate::dynamics::RigidBodyHandle]| {
+            bodies
+                .iter()
+                .enumerate()
+                .flat_map(|(i, body)| {
+                    (0..3).map(move |j| {
+                        let collider = ColliderBuilder::ball(0.1 + j as Real * 0.1)
+                            .position(Isometry::new(Vector::y() * (i + j) as Real, na::zero()))
+                            .build();
+                        (collider, *body)
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut bodies1 = RigidBodySet::new();
+        let mut colliders1 = ColliderSet::new();
+        let body_handles1: Vec<_> = make_bodies()
+            .into_iter()
+            .map(|rb| bodies1.insert(rb))
+            .collect();
+        let collider_handles1: Vec<_> = make_colliders(&body_handles1)
+            .into_iter()
+            .map(|(co, parent)| colliders1.insert(co, parent, &mut bodies1))
+            .collect();
+
+        let mut bodies2 = RigidBodySet::new();
+        let mut colliders2 = ColliderSet::new();
+        let body_handles2 = bodies2.insert_batch(make_bodies());
+        let collider_handles2 =
+            colliders2.insert_batch(make_colliders(&body_handles2), &mut bodies2);
+
+        assert_eq!(body_handles1, body_handles2);
+        assert_eq!(collider_handles1, collider_handles2);
+
+        for handle in &body_handles1 {
+            let (rb1, rb2) = (&bodies1[*handle], &bodies2[*handle]);
+            assert_eq!(rb1.position(), rb2.position());
+            assert_eq!(rb1.colliders(), rb2.colliders());
+            assert_eq!(rb1.mass(), rb2.mass());
+            assert_eq!(
+                rb1.mass_properties().local_com,
+                rb2.mass_properties().local_com
+            );
+        }
+
+        for handle in &collider_handles1 {
+            let (co1, co2) = (&colliders1[*handle], &colliders2[*handle]);
+            assert_eq!(co1.position(), co2.position());
+            assert_eq!(co1.parent(), co2.parent());
+        }
+    }
+
+    #[test]
+    fn swap_shape_preserving_contacts() {
+        // Swaps a tall capsule resting on the ground for a short one, and returns the vertical
+        // velocity right after the swap, and the age of the new contacts.
+        let simulate = |preserve_contacts: bool| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let character = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                    .lock_rotations()
+                    .can_sleep(false)
+                    .build(),
+            );
+            let collider = world.colliders.insert(
+                ColliderBuilder::capsule_y(1.0, 0.5).build(),
+                character,
+                &mut world.bodies,
+            );
+
+            for _ in 0..100 {
+                world.step();
+            }
+
+            // Crouch: the capsule is shortened, and the body lowered to keep its feet on the ground.
+            let short_capsule = ColliderBuilder::capsule_y(0.5, 0.5).shape;
+            assert!(world
+                .colliders
+                .set_shape(
+                    collider,
+                    short_capsule,
+                    &mut world.bodies,
+                    preserve_contacts
+                )
+                .is_some());
+            let pos = *world.bodies[character].position();
+            let offset = Isometry::new(Vector::y() * -0.5, na::zero());
+            world.bodies[character].set_position(offset * pos, true);
+            world.step();
+
+            let frames_alive = world
+                .narrow_phase
+                .contacts_with(collider)
+                .unwrap()
+                .flat_map(|(_, _, pair)| pair.manifolds.iter())
+                .flat_map(|manifold| manifold.points.iter())
+                .map(|contact| contact.data.frames_alive)
+                .max()
+                .unwrap();
+            (world.bodies[character].linvel().y, frames_alive)
+        };
+
+        let (vel, frames_alive) = simulate(true);
+        assert!(vel.abs() < 1.0e-2, "{}", vel);
+        assert!(frames_alive > 50);
+
+        let (_, frames_alive) = simulate(false);
+        assert_eq!(frames_alive, 1);
+    }
+
+    #[test]
+    fn scale_collider_non_uniformly() {
+        let mut colliders = ColliderSet::new();
+        let mut bodies = RigidBodySet::new();
+
+        #[cfg(feature = "dim2")]
+        let (cuboid, scaled_cuboid, torque) = (
+            ColliderBuilder::cuboid(0.5, 0.5),
+            ColliderBuilder::cuboid(1.0, 0.5),
+            1.0,
+        );
+        #[cfg(feature = "dim3")]
+        let (cuboid, scaled_cuboid, torque) = (
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            ColliderBuilder::cuboid(1.0, 0.5, 0.5),
+            Vector::new(1.0, 2.0, 3.0),
+        );
+
+        let body = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let collider = colliders.insert(cuboid.build(), body, &mut bodies);
+        let reference = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        colliders.insert(scaled_cuboid.build(), reference, &mut bodies);
+
+        let initial_mass = bodies[body].mass();
+        let mut scale = Vector::repeat(1.0);
+        scale.x = 2.0;
+        assert!(colliders.set_scale(collider, scale, &mut bodies));
+        assert_eq!(colliders[collider].scale(), scale);
+
+        // The collision extent is scaled.
+        let aabb = colliders[collider].compute_aabb();
+        assert!((aabb.maxs.x - 1.0).abs() < 1.0e-5);
+        assert!((aabb.maxs.y - 0.5).abs() < 1.0e-5);
+
+        // The mass and inertia match those of a collider built with the scaled shape.
+        #[cfg(feature = "dim2")]
+        let inertia =
+            |rb: &RigidBody| Vector::x() * (rb.effective_angular_inertia_inverse() * torque);
+        #[cfg(feature = "dim3")]
+        let inertia = |rb: &RigidBody| rb.effective_angular_inertia_inverse() * torque;
+        assert!((bodies[body].mass() - initial_mass * 2.0).abs() < 1.0e-4);
+        assert!((inertia(&bodies[body]) - inertia(&bodies[reference])).norm() < 1.0e-4);
+
+        // Scaling is relative to the unscaled shape and doesn't accumulate.
+        assert!(colliders.set_scale(collider, scale, &mut bodies));
+        assert!((colliders[collider].compute_aabb().maxs.x - 1.0).abs() < 1.0e-5);
+        assert!(colliders.set_scale(collider, Vector::repeat(1.0), &mut bodies));
+        assert!((bodies[body].mass() - initial_mass).abs() < 1.0e-4);
+
+        // A ball can only be scaled uniformly.
+        let ball = colliders.insert(ColliderBuilder::ball(0.5).build(), body, &mut bodies);
+        assert!(!colliders.set_scale(ball, scale, &mut bodies));
+        assert!(colliders.set_scale(ball, Vector::repeat(2.0), &mut bodies));
+        assert!((colliders[ball].compute_aabb().maxs.y - 1.0).abs() < 1.0e-5);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::dynamics::{BodyPair, RigidBodyHandle};
-use crate::geometry::{ColliderPair, ContactManifold};
+use crate::geometry::{ColliderHandle, ColliderPair, ContactManifold, FeatureId, PackedFeatureId};
 use crate::math::{Point, Real, Vector};
+#[cfg(feature = "dim3")]
+use crate::utils::WBasis;
 use parry::query::ContactManifoldsWorkspace;
 
 bitflags::bitflags! {
@@ -38,6 +40,24 @@ pub struct ContactData {
     /// collider's rigid-body.
     #[cfg(feature = "dim3")]
     pub tangent_impulse: [Real; 2],
+    /// The number of consecutive timesteps this contact has existed, including the current one.
+    ///
+    /// A contact is considered the same as the previous timestep if it involves the same
+    /// features (vertex, edge, face) of both shapes. This is reset whenever the contact is lost.
+    pub frames_alive: u32,
+    /// A user-defined payload attached to this contact.
+    ///
+    /// It is kept unchanged from one timestep to the next as long as the contact persists,
+    /// and copied into the `SolverContact::data` generated for this contact. Modifications
+    /// of the `SolverContact::data.user_data` by `PhysicsHooks::modify_solver_contacts` are
+    /// copied back here, so the payload can be read alongside the impulses computed by the
+    /// constraints solver after the timestep.
+    pub user_data: Real,
+    /// Was the normal impulse of this contact clamped to `SolverContact::max_normal_impulse` by
+    /// the constraints solver during the last timestep?
+    ///
+    /// This can be used to react to overloaded contacts, e.g., to break or crush an object.
+    pub max_normal_impulse_reached: bool,
 }
 
 impl ContactData {
@@ -57,6 +77,9 @@ impl Default for ContactData {
         Self {
             impulse: 0.0,
             tangent_impulse: Self::zero_tangent_impulse(),
+            frames_alive: 0,
+            user_data: 0.0,
+            max_normal_impulse_reached: false,
         }
     }
 }
@@ -74,6 +97,9 @@ pub struct ContactPair {
     /// Is there any active contact in this contact pair?
     pub has_any_active_contact: bool,
     pub(crate) workspace: Option<ContactManifoldsWorkspace>,
+    // The world-space points on both colliders, and the data, of the contacts which existed
+    // before one of the colliders changed shape. They are transferred to the new contacts.
+    pub(crate) swapped_shape_contacts: Vec<(Point<Real>, Point<Real>, ContactData)>,
 }
 
 impl ContactPair {
@@ -83,10 +109,20 @@ impl ContactPair {
             has_any_active_contact: false,
             manifolds: Vec::new(),
             workspace: None,
+            swapped_shape_contacts: Vec::new(),
         }
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// The ground supporting a collider, as detected by `NarrowPhase::ground_contact`.
+pub struct GroundInfo {
+    /// The collider supporting the queried collider.
+    pub collider: ColliderHandle,
+    /// The world-space contact normal, pointing from the ground toward the queried collider.
+    pub normal: Vector<Real>,
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 /// A contact manifold between two colliders.
@@ -98,6 +134,10 @@ pub struct ContactManifoldData {
     /// The pair of body involved in this contact manifold.
     pub body_pair: BodyPair,
     pub(crate) warmstart_multiplier: Real,
+    pub(crate) age: u32,
+    // The number of consecutive timesteps during which the colliders or the collision pair
+    // of this contact manifold were disabled.
+    pub(crate) disabled_steps: u32,
     // The two following are set by the constraints solver.
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     pub(crate) constraint_index: usize,
@@ -112,6 +152,11 @@ pub struct ContactManifoldData {
     // to this field as well.
     pub normal: Vector<Real>,
     /// The contacts that will be seen by the constraints solver for computing forces.
+    ///
+    /// The constraints solver splits these contacts into consecutive chunks of at most
+    /// `MAX_MANIFOLD_POINTS` contacts, and generates one constraint per chunk. Therefore,
+    /// adding or removing solver contacts (e.g. with `PhysicsHooks::modify_solver_contacts`)
+    /// may change the number of constraints generated for this contact manifold.
     // NOTE: unfortunately, we can't ignore this field when serialize
     // the contact manifold data. The reason is that the solver contacts
     // won't be updated for sleeping bodies. So it means that for one
@@ -133,6 +178,71 @@ pub struct ContactManifoldData {
     pub relative_dominance: i16,
     /// A user-defined piece of data.
     pub user_data: u32,
+    /// Are the contact impulses of the last timestep used to warmstart the constraints solver?
+    ///
+    /// If `false`, the solver impulses of this contact manifold are computed from scratch.
+    /// This is reset to `true` by the narrow-phase at each timestep, and can be set to `false`
+    /// with `PhysicsHooks::modify_solver_contacts`.
+    pub warmstart_enabled: bool,
+    /// Anisotropic friction applied to all the contacts of this contact manifold.
+    ///
+    /// This is reset to `None` by the narrow-phase at each timestep, and can be set
+    /// with `PhysicsHooks::modify_solver_contacts`. If `None`, the friction is isotropic.
+    /// Its friction coefficients are multiplied by the friction coefficient of each contact.
+    #[cfg(feature = "dim3")]
+    pub anisotropic_friction: Option<AnisotropicFriction>,
+    /// The approach speed below which the contacts of this contact manifold don't bounce.
+    ///
+    /// This is set by the narrow-phase from the colliders' restitution velocity thresholds
+    /// at each timestep. If `None`, `IntegrationParameters::restitution_velocity_threshold`
+    /// is used instead.
+    pub restitution_velocity_threshold: Option<Real>,
+}
+
+/// Friction coefficients differing along two orthogonal tangent directions.
+///
+/// This can be used, e.g., to simulate tires that roll freely along their rolling
+/// direction but resist sideways skidding.
+#[cfg(feature = "dim3")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct AnisotropicFriction {
+    /// The world-space primary friction direction.
+    ///
+    /// It is projected on the plane orthogonal to the contact normal, so it doesn't
+    /// need to be exactly tangent to the contact. If it is (almost) parallel to the contact
+    /// normal, the friction directions are chosen arbitrarily.
+    pub primary_tangent: Vector<Real>,
+    /// The multiplier of the friction coefficient of each contact along the primary tangent
+    /// direction.
+    pub primary_friction: Real,
+    /// The multiplier of the friction coefficient of each contact along the direction
+    /// orthogonal to both the contact normal and the primary tangent direction.
+    pub secondary_friction: Real,
+}
+
+#[cfg(feature = "dim3")]
+impl AnisotropicFriction {
+    /// The two friction force directions of the contacts with the normal `force_dir1`.
+    ///
+    /// If there is an anisotropic friction, the first direction is its primary tangent
+    /// projected on the plane orthogonal to `force_dir1`. Otherwise, or if the primary
+    /// tangent is (almost) parallel to `force_dir1`, the directions are chosen arbitrarily.
+    pub(crate) fn friction_tangents(
+        anisotropic_friction: Option<&Self>,
+        force_dir1: &Vector<Real>,
+    ) -> [Vector<Real>; 2] {
+        if let Some(anisotropic_friction) = anisotropic_friction {
+            let primary = anisotropic_friction.primary_tangent;
+            let projected = primary - force_dir1 * force_dir1.dot(&primary);
+
+            if let Some(tangent1) = projected.try_normalize(crate::math::DEFAULT_EPSILON) {
+                return [tangent1, force_dir1.cross(&tangent1)];
+            }
+        }
+
+        force_dir1.orthonormal_basis()
+    }
 }
 
 /// A contact seen by the constraints solver for computing forces.
@@ -141,9 +251,14 @@ pub struct ContactManifoldData {
 pub struct SolverContact {
     /// The index of the manifold contact used to generate this solver contact.
     pub(crate) contact_id: u8,
+    /// The feature of the first collider's shape that generated this contact.
+    pub(crate) fid1: PackedFeatureId,
+    /// The feature of the second collider's shape that generated this contact.
+    pub(crate) fid2: PackedFeatureId,
     /// The world-space contact point.
     pub point: Point<Real>,
-    /// The distance between the two original contacts points along the contact normal.
+    /// The distance between the two original contacts points along the contact normal,
+    /// minus the contact skins of both colliders.
     /// If negative, this is measures the penetration depth.
     pub dist: Real,
     /// The effective friction coefficient at this contact point.
@@ -155,12 +270,39 @@ pub struct SolverContact {
     /// This is set to zero by default. Set to a non-zero value to
     /// simulate, e.g., conveyor belts.
     pub tangent_velocity: Vector<Real>,
+    /// The maximum normal impulse this contact can apply during one timestep
+    /// (default: `Real::MAX`, i.e., unbounded).
+    ///
+    /// Set to a finite value, e.g., with `PhysicsHooks::modify_solver_contacts`, to let an
+    /// overloaded contact yield instead of holding infinitely. The contacts whose impulse reached
+    /// this maximum are flagged with `ContactData::max_normal_impulse_reached`. Contact manifolds
+    /// with a bounded normal impulse are not resolved with SIMD constraints.
+    pub max_normal_impulse: Real,
     /// Associated contact data used to warm-start the constraints
     /// solver.
     pub data: ContactData,
 }
 
 impl SolverContact {
+    /// Are the two colliders actually touching at this contact point?
+    ///
+    /// If `false`, this is a speculative contact, i.e., the colliders are still separated by
+    /// a distance smaller than the prediction distance. The solver will only prevent them from
+    /// penetrating if they keep approaching each other.
+    pub fn is_touching(&self) -> bool {
+        self.dist <= 0.0
+    }
+
+    /// The pair of shape features that generated this contact.
+    ///
+    /// Unlike the contact points, this pair doesn't change while the contact slides along the
+    /// same features from one step to the next, so it can be used to identify a contact across
+    /// frames. The features are relative to the shapes of the manifold, i.e., to the sub-shapes
+    /// `ContactManifold::subshape1` and `ContactManifold::subshape2` for composite shapes.
+    pub fn feature_ids(&self) -> (FeatureId, FeatureId) {
+        (self.fid1.unpack(), self.fid2.unpack())
+    }
+
     /// Should we treat this contact as a bouncy contact?
     /// If `true`, use [`Self::restitution`].
     pub fn is_bouncy(&self) -> bool {
@@ -190,6 +332,8 @@ impl ContactManifoldData {
         Self {
             body_pair,
             warmstart_multiplier: Self::min_warmstart_multiplier(),
+            age: 0,
+            disabled_steps: 0,
             constraint_index: 0,
             position_constraint_index: 0,
             solver_flags,
@@ -197,9 +341,20 @@ impl ContactManifoldData {
             solver_contacts: Vec::new(),
             relative_dominance: 0,
             user_data: 0,
+            warmstart_enabled: true,
+            #[cfg(feature = "dim3")]
+            anisotropic_friction: None,
+            restitution_velocity_threshold: None,
         }
     }
 
+    /// Does any solver contact of this manifold have a bounded normal impulse?
+    pub(crate) fn has_bounded_normal_impulse(&self) -> bool {
+        self.solver_contacts
+            .iter()
+            .any(|contact| contact.max_normal_impulse != Real::MAX)
+    }
+
     /// Number of actives contacts, i.e., contacts that will be seen by
     /// the constraints solver.
     #[inline]
@@ -207,6 +362,55 @@ impl ContactManifoldData {
         self.solver_contacts.len()
     }
 
+    /// The number of consecutive timesteps, including the current one, during which the oldest
+    /// active contact of this contact manifold existed.
+    ///
+    /// This is the largest `ContactData::frames_alive` of its active contacts, so it is reset
+    /// together with the contact data, e.g., when a rigid-body is teleported, and preserved when
+    /// the contact data are transferred to new contacts, e.g., after a shape swap.
+    #[inline]
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    pub(crate) fn update_age(&mut self) {
+        self.age = self
+            .solver_contacts
+            .iter()
+            .map(|contact| contact.data.frames_alive)
+            .max()
+            .unwrap_or(0);
+    }
+
+    /// The multiplier applied to the `velocity_based_erp` of this contact manifold,
+    /// ramping up from the age of the manifold over `ramp_steps` timesteps.
+    #[inline]
+    pub(crate) fn velocity_based_erp_multiplier(&self, ramp_steps: usize) -> Real {
+        if ramp_steps == 0 {
+            1.0
+        } else {
+            (self.age as Real / (ramp_steps + 1) as Real).min(1.0)
+        }
+    }
+
+    /// The approach speed below which the contacts of this manifold don't bounce, given the
+    /// global `IntegrationParameters::restitution_velocity_threshold`.
+    #[inline]
+    pub(crate) fn effective_restitution_velocity_threshold(&self, global_threshold: Real) -> Real {
+        self.restitution_velocity_threshold
+            .unwrap_or(global_threshold)
+    }
+
+    /// The multiplier applied to the impulses used for warmstarting this contact manifold.
+    #[inline]
+    pub(crate) fn effective_warmstart_multiplier(&self) -> Real {
+        if self.warmstart_enabled {
+            self.warmstart_multiplier
+        } else {
+            0.0
+        }
+    }
+
     pub(crate) fn min_warmstart_multiplier() -> Real {
         // Multiplier used to reduce the amount of warm-starting.
         // This coefficient increases exponentially over time, until it reaches 1.0.
@@ -236,4 +440,119 @@ impl ContactManifoldData {
     //     // Reset the multiplier.
     //     manifold.data.warmstart_multiplier = Self::min_warmstart_multiplier()
     // }
+
+    /// Expresses the tangent impulses of the solver contacts in the friction basis of
+    /// `self.anisotropic_friction`, given that they are expressed in the friction basis of
+    /// `prev_anisotropic_friction`.
+    ///
+    /// This keeps the friction impulses used for warmstarting the solver consistent when the
+    /// anisotropic friction of this manifold changes from one timestep to the next.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn reproject_tangent_impulses(
+        &mut self,
+        prev_anisotropic_friction: Option<&AnisotropicFriction>,
+    ) {
+        if prev_anisotropic_friction == self.anisotropic_friction.as_ref() {
+            return;
+        }
+
+        let force_dir1 = -self.normal;
+        let prev_tangents =
+            AnisotropicFriction::friction_tangents(prev_anisotropic_friction, &force_dir1);
+        let tangents =
+            AnisotropicFriction::friction_tangents(self.anisotropic_friction.as_ref(), &force_dir1);
+
+        for solver_contact in &mut self.solver_contacts {
+            let tangent_impulse = solver_contact.data.tangent_impulse;
+            let impulse =
+                prev_tangents[0] * tangent_impulse[0] + prev_tangents[1] * tangent_impulse[1];
+            solver_contact.data.tangent_impulse =
+                [impulse.dot(&tangents[0]), impulse.dot(&tangents[1])];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn anisotropic_friction_tire() {
+        use crate::geometry::AnisotropicFriction;
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+
+        // The local `x` axis of the wheel is its rolling direction.
+        struct TireHooks;
+
+        impl PhysicsHooks for TireHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                let wheel = if context.rigid_body1.is_dynamic() {
+                    context.rigid_body1
+                } else {
+                    context.rigid_body2
+                };
+
+                *context.anisotropic_friction = Some(AnisotropicFriction {
+                    primary_tangent: wheel.position().rotation * Vector::x(),
+                    primary_friction: 0.0,
+                    secondary_friction: 1.0,
+                });
+            }
+        }
+
+        let slowdown = |linvel: Vector<Real>, friction: Real| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            world.colliders.insert(
+                ColliderBuilder::cuboid(100.0, 0.5, 100.0)
+                    .friction(friction)
+                    .build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            // The wheel is rotated so that it rolls along the world `z` axis.
+            let wheel_pos = Isometry::new(
+                Vector::y() * 0.749,
+                Vector::y() * std::f32::consts::FRAC_PI_2 as Real,
+            );
+            let wheel = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(wheel_pos)
+                    .linvel(linvel.x, linvel.y, linvel.z)
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::cuboid(0.5, 0.25, 0.5)
+                    .friction(friction)
+                    .modify_solver_contacts(true)
+                    .build(),
+                wheel,
+                &mut world.bodies,
+            );
+
+            for _ in 0..10 {
+                world.step_with(&TireHooks, &());
+            }
+
+            linvel.norm() - world.bodies[wheel].linvel().norm()
+        };
+
+        // Rolling forward along the rolling direction is free.
+        assert!(slowdown(Vector::z() * 10.0, 1.0) < 0.1);
+        // Skidding sideways is resisted.
+        assert!(slowdown(Vector::x() * 10.0, 1.0) > 1.0);
+        // The anisotropic coefficients scale the friction coefficient of the contacts.
+        assert!(slowdown(Vector::x() * 10.0, 0.0) < 0.1);
+    }
 }
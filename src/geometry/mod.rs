@@ -2,17 +2,22 @@
 
 pub use self::broad_phase_multi_sap::BroadPhase;
 pub use self::collider::{Collider, ColliderBuilder};
+pub use self::collider_material::{ColliderMaterial, ColliderPartMaterials};
 pub use self::collider_set::{ColliderHandle, ColliderSet};
+#[cfg(feature = "dim3")]
+pub use self::contact_pair::AnisotropicFriction;
 pub use self::contact_pair::{ContactData, ContactManifoldData};
-pub use self::contact_pair::{ContactPair, SolverContact, SolverFlags};
+pub use self::contact_pair::{ContactPair, GroundInfo, SolverContact, SolverFlags};
 pub use self::interaction_graph::{
     ColliderGraphIndex, InteractionGraph, RigidBodyGraphIndex, TemporaryInteractionIndex,
 };
 pub use self::interaction_groups::InteractionGroups;
-pub use self::narrow_phase::NarrowPhase;
+pub use self::narrow_phase::{ContactMatching, NarrowPhase};
 
 pub use parry::query::TrackedContact;
 
+use crate::math::{Real, Vector};
+
 /// A contact between two colliders.
 pub type Contact = parry::query::TrackedContact<ContactData>;
 /// A contact manifold between two colliders.
@@ -37,6 +42,8 @@ pub type Cylinder = parry::shape::Cylinder;
 pub type Cone = parry::shape::Cone;
 /// An axis-aligned bounding box.
 pub type AABB = parry::bounding_volume::AABB;
+/// A bounding sphere.
+pub type BoundingSphere = parry::bounding_volume::BoundingSphere;
 /// A ray that can be cast against colliders.
 pub type Ray = parry::query::Ray;
 /// The intersection between a ray and a  collider.
@@ -45,7 +52,7 @@ pub type RayIntersection = parry::query::RayIntersection;
 pub type PointProjection = parry::query::PointProjection;
 /// The the time of impact between two shapes.
 pub type TOI = parry::query::TOI;
-pub use parry::shape::SharedShape;
+pub use parry::shape::{ShapeType, SharedShape};
 
 #[derive(Copy, Clone, Hash, Debug)]
 /// Events occurring when two collision objects start or stop being in contact (or penetration).
@@ -84,9 +91,31 @@ impl IntersectionEvent {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+/// Event occurring when the sum of the magnitudes of the contact forces between two
+/// colliders exceeds a threshold.
+///
+/// See `IntegrationParameters::contact_force_event_threshold` and
+/// `ColliderSet::set_contact_force_event_threshold` to configure this threshold.
+pub struct ContactForceEvent {
+    /// The first collider involved in the contact.
+    pub collider1: ColliderHandle,
+    /// The second collider involved in the contact.
+    pub collider2: ColliderHandle,
+    /// The sum of all the forces between the two colliders, oriented along the contact normals
+    /// pointing from `collider1` toward `collider2`.
+    pub total_force: Vector<Real>,
+    /// The sum of the magnitudes of each contact force between the two colliders.
+    pub total_force_magnitude: Real,
+    /// The magnitude of the largest contact force between the two colliders.
+    pub max_force_magnitude: Real,
+}
+
 pub(crate) use self::broad_phase_multi_sap::{BroadPhasePairEvent, ColliderPair};
 pub(crate) use self::collider_set::RemovedCollider;
 pub(crate) use self::narrow_phase::ContactManifoldIndex;
+#[cfg(feature = "dim3")]
+pub(crate) use self::trimesh_internal_edges::{heightfield_corrected_normal, InternalEdges};
 pub(crate) use parry::partitioning::SimdQuadTree;
 pub use parry::shape::*;
 
@@ -103,8 +132,11 @@ pub(crate) fn default_query_dispatcher() -> std::sync::Arc<dyn parry::query::Que
 
 mod broad_phase_multi_sap;
 mod collider;
+mod collider_material;
 mod collider_set;
 mod contact_pair;
 mod interaction_graph;
 mod interaction_groups;
 mod narrow_phase;
+#[cfg(feature = "dim3")]
+mod trimesh_internal_edges;
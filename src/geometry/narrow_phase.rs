@@ -3,21 +3,111 @@ use rayon::prelude::*;
 
 use crate::data::pubsub::Subscription;
 use crate::data::Coarena;
-use crate::dynamics::{BodyPair, CoefficientCombineRule, RigidBodySet};
+use crate::dynamics::{BodyPair, CoefficientCombineRule, IntegrationParameters, RigidBodySet};
 use crate::geometry::{
     BroadPhasePairEvent, ColliderGraphIndex, ColliderHandle, ColliderSet, ContactData,
-    ContactEvent, ContactManifold, ContactManifoldData, ContactPair, InteractionGraph,
-    IntersectionEvent, RemovedCollider, SolverContact, SolverFlags,
+    ContactEvent, ContactForceEvent, ContactManifold, ContactManifoldData, ContactPair, GroundInfo,
+    InteractionGraph, IntersectionEvent, RemovedCollider, SolverContact, SolverFlags,
 };
-use crate::math::{Real, Vector};
+use crate::math::{Isometry, Point, Real, Vector};
 use crate::pipeline::{
     ContactModificationContext, EventHandler, PairFilterContext, PhysicsHooks, PhysicsHooksFlags,
 };
+use crate::utils::DeterministicMath;
+use na::Unit;
 use parry::query::{DefaultQueryDispatcher, PersistentQueryDispatcher};
+use parry::shape::ShapeType;
 use parry::utils::IsometryOpt;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 
+/// The number of timesteps during which the contact impulses of a disabled collider or collision
+/// pair are kept to warmstart the constraints solver once it is enabled again.
+const MAX_DISABLED_WARMSTART_STEPS: u32 = 10;
+
+/// The default value of `NarrowPhase::contact_matching_tolerance`.
+const DEFAULT_CONTACT_MATCHING_TOLERANCE: Real = 0.1;
+
+/// How the contacts computed at a timestep are matched with the contacts of the previous
+/// timestep, to inherit their data (impulses used for warmstarting, age, user data).
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ContactMatching {
+    /// Contacts are matched if they involve the same features (vertex, edge, face) of both shapes.
+    FeatureId,
+    /// Contacts are matched with the closest previous contact, if their distance is smaller
+    /// than `NarrowPhase::contact_matching_tolerance`.
+    ///
+    /// This is better suited for curved surfaces, where the features of the contacts are not
+    /// discriminating enough.
+    Spatial,
+}
+
+impl Default for ContactMatching {
+    fn default() -> Self {
+        ContactMatching::FeatureId
+    }
+}
+
+// Transfers the data of the previous contacts to the closest contacts of the manifolds.
+// Only one of the colliders moved or changed shape in general, so the contact points on the
+// other one are compared too.
+fn transfer_contact_data(
+    manifolds: &mut [ContactManifold],
+    pos1: &Isometry<Real>,
+    pos2: &Isometry<Real>,
+    mut previous_contacts: Vec<(Point<Real>, Point<Real>, ContactData)>,
+    tolerance: Real,
+) {
+    for manifold in manifolds {
+        let world_pos1 = manifold.subshape_pos1.prepend_to(pos1);
+        let world_pos2 = manifold.subshape_pos2.prepend_to(pos2);
+
+        for contact in &mut manifold.points {
+            let p1 = world_pos1 * contact.local_p1;
+            let p2 = world_pos2 * contact.local_p2;
+            let closest = previous_contacts
+                .iter()
+                .enumerate()
+                .map(|(i, (prev_p1, prev_p2, _))| {
+                    let dist = na::distance(prev_p1, &p1).min(na::distance(prev_p2, &p2));
+                    (i, dist)
+                })
+                .filter(|(_, dist)| *dist <= tolerance)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+            if let Some((i, _)) = closest {
+                contact.data = previous_contacts.swap_remove(i).2;
+            }
+        }
+    }
+}
+
+// The world-space contact points of the manifolds, with their data.
+fn world_contacts(
+    manifolds: &[ContactManifold],
+    pos1: &Isometry<Real>,
+    pos2: &Isometry<Real>,
+) -> Vec<(Point<Real>, Point<Real>, ContactData)> {
+    let mut result = Vec::new();
+
+    for manifold in manifolds {
+        let world_pos1 = manifold.subshape_pos1.prepend_to(pos1);
+        let world_pos2 = manifold.subshape_pos2.prepend_to(pos2);
+
+        for contact in &manifold.points {
+            result.push((
+                world_pos1 * contact.local_p1,
+                world_pos2 * contact.local_p2,
+                contact.data,
+            ));
+        }
+    }
+
+    result
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 struct ColliderGraphIndices {
@@ -47,6 +137,28 @@ pub struct NarrowPhase {
     intersection_graph: InteractionGraph<ColliderHandle, bool>,
     graph_indices: Coarena<ColliderGraphIndices>,
     removed_colliders: Option<Subscription<RemovedCollider>>,
+    disabled_collision_pairs: BTreeSet<(ColliderHandle, ColliderHandle)>,
+    // Bit `i` is set if the shapes of type `i` use `ContactMatching::Spatial`.
+    spatial_contact_matching: u64,
+    contact_matching_tolerance: Real,
+    // The events buffered by the parallel narrow-phase, with the index of their graph edge.
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    pending_intersection_events: Vec<(usize, IntersectionEvent)>,
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    pending_contact_events: Vec<(usize, ContactEvent)>,
+}
+
+fn sorted_pair(
+    collider1: ColliderHandle,
+    collider2: ColliderHandle,
+) -> (ColliderHandle, ColliderHandle) {
+    if collider1 <= collider2 {
+        (collider1, collider2)
+    } else {
+        (collider2, collider1)
+    }
 }
 
 pub(crate) type ContactManifoldIndex = usize;
@@ -68,9 +180,81 @@ impl NarrowPhase {
             intersection_graph: InteractionGraph::new(),
             graph_indices: Coarena::new(),
             removed_colliders: None,
+            disabled_collision_pairs: BTreeSet::new(),
+            spatial_contact_matching: 0,
+            contact_matching_tolerance: DEFAULT_CONTACT_MATCHING_TOLERANCE,
+            #[cfg(feature = "parallel")]
+            pending_intersection_events: Vec::new(),
+            #[cfg(feature = "parallel")]
+            pending_contact_events: Vec::new(),
+        }
+    }
+
+    /// Disables the contacts and intersections between two specific colliders.
+    ///
+    /// This does not affect the interactions of these colliders with any other collider. The
+    /// pair is automatically re-enabled when one of the two colliders is removed.
+    pub fn disable_collision(&mut self, collider1: ColliderHandle, collider2: ColliderHandle) {
+        let _ = self
+            .disabled_collision_pairs
+            .insert(sorted_pair(collider1, collider2));
+    }
+
+    /// Re-enables the contacts and intersections between two colliders previously disabled
+    /// with `NarrowPhase::disable_collision`.
+    pub fn enable_collision(&mut self, collider1: ColliderHandle, collider2: ColliderHandle) {
+        let _ = self
+            .disabled_collision_pairs
+            .remove(&sorted_pair(collider1, collider2));
+    }
+
+    /// Are the contacts and intersections between these two colliders disabled by
+    /// `NarrowPhase::disable_collision`?
+    pub fn is_collision_disabled(
+        &self,
+        collider1: ColliderHandle,
+        collider2: ColliderHandle,
+    ) -> bool {
+        self.disabled_collision_pairs
+            .contains(&sorted_pair(collider1, collider2))
+    }
+
+    /// Sets how the contacts involving a shape of the given type are matched with the contacts
+    /// of the previous timestep.
+    ///
+    /// A pair of colliders uses `ContactMatching::Spatial` if any of its shapes does. All the shape
+    /// types use `ContactMatching::FeatureId` by default.
+    pub fn set_contact_matching(&mut self, shape_type: ShapeType, matching: ContactMatching) {
+        let bit = 1 << shape_type as u64;
+
+        match matching {
+            ContactMatching::FeatureId => self.spatial_contact_matching &= !bit,
+            ContactMatching::Spatial => self.spatial_contact_matching |= bit,
         }
     }
 
+    /// How the contacts involving a shape of the given type are matched with the contacts of the
+    /// previous timestep.
+    pub fn contact_matching(&self, shape_type: ShapeType) -> ContactMatching {
+        if self.spatial_contact_matching & (1 << shape_type as u64) != 0 {
+            ContactMatching::Spatial
+        } else {
+            ContactMatching::FeatureId
+        }
+    }
+
+    /// The maximum distance between two contacts for them to be matched by
+    /// `ContactMatching::Spatial`, or across a shape swap (see `ColliderSet::set_shape`).
+    pub fn contact_matching_tolerance(&self) -> Real {
+        self.contact_matching_tolerance
+    }
+
+    /// Sets the maximum distance between two contacts for them to be matched by
+    /// `ContactMatching::Spatial`, or across a shape swap (see `ColliderSet::set_shape`).
+    pub fn set_contact_matching_tolerance(&mut self, tolerance: Real) {
+        self.contact_matching_tolerance = tolerance;
+    }
+
     /// The contact graph containing all contact pairs and their contact information.
     pub fn contact_graph(&self) -> &InteractionGraph<ColliderHandle, ContactPair> {
         &self.contact_graph
@@ -82,6 +266,10 @@ impl NarrowPhase {
     }
 
     /// All the contacts involving the given collider.
+    ///
+    /// This only traverses the contact pairs of this collider in the contact graph, so its cost
+    /// doesn't depend on the total number of contact pairs. This returns `None` if the collider
+    /// isn't known by the narrow-phase yet.
     pub fn contacts_with(
         &self,
         collider: ColliderHandle,
@@ -90,6 +278,80 @@ impl NarrowPhase {
         Some(self.contact_graph.interactions_with(id.contact_graph_index))
     }
 
+    /// The contact supporting the given collider from below, if any.
+    ///
+    /// This scans the contact manifolds with active contacts involving `collider` and returns
+    /// the one with the normal closest to `up_dir`, provided the angle between them does not
+    /// exceed `max_angle` (in radians). The returned normal points toward `collider`.
+    pub fn ground_contact(
+        &self,
+        collider: ColliderHandle,
+        up_dir: &Unit<Vector<Real>>,
+        max_angle: Real,
+    ) -> Option<GroundInfo> {
+        let mut best_cos = max_angle.det_cos();
+        let mut result = None;
+
+        for (_, _, pair) in self.contacts_with(collider)? {
+            let (ground, sign) = if pair.pair.collider1 == collider {
+                (pair.pair.collider2, -1.0)
+            } else {
+                (pair.pair.collider1, 1.0)
+            };
+
+            for manifold in &pair.manifolds {
+                if manifold.data.num_active_contacts() == 0 {
+                    continue;
+                }
+
+                let normal = manifold.data.normal * sign;
+                let cos = normal.dot(up_dir);
+
+                if cos >= best_cos {
+                    best_cos = cos;
+                    result = Some(GroundInfo {
+                        collider: ground,
+                        normal,
+                    });
+                }
+            }
+        }
+
+        result
+    }
+
+    /// The deepest penetration of the given collider with any other collider, if any.
+    ///
+    /// This scans the contact manifolds involving `collider` and returns the other collider of
+    /// the most penetrating contact, the penetration depth, and the contact normal pointing toward
+    /// `collider`. Returns `None` if `collider` doesn't penetrate any other collider.
+    pub fn deepest_penetration(
+        &self,
+        collider: ColliderHandle,
+    ) -> Option<(ColliderHandle, Real, Vector<Real>)> {
+        let mut result: Option<(ColliderHandle, Real, Vector<Real>)> = None;
+
+        for (_, _, pair) in self.contacts_with(collider)? {
+            let (other, sign) = if pair.pair.collider1 == collider {
+                (pair.pair.collider2, -1.0)
+            } else {
+                (pair.pair.collider1, 1.0)
+            };
+
+            for manifold in &pair.manifolds {
+                for contact in &manifold.points {
+                    let depth = -contact.dist;
+
+                    if depth > result.map_or(0.0, |r| r.1) {
+                        result = Some((other, depth, manifold.data.normal * sign));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
     /// All the intersections involving the given collider.
     pub fn intersections_with(
         &self,
@@ -120,6 +382,37 @@ impl NarrowPhase {
             .map(|c| c.2)
     }
 
+    /// The mutable contact data of a specific contact point between two colliders.
+    ///
+    /// The contact point is identified by the index of its manifold in `ContactPair::manifolds`
+    /// and by its index in `ContactManifold::points`. Modifying the `impulse` and `tangent_impulse`
+    /// of the returned data before the next timestep overrides the impulses used to warmstart the
+    /// constraints solver for this contact, e.g., to restore the authoritative state of a networked
+    /// simulation. These impulses are expressed relative to the colliders order given by
+    /// `ContactPair::pair`, and are still multiplied by `IntegrationParameters::warmstart_coeff`.
+    ///
+    /// The overridden impulses are only used if the contact still exists, with the same features,
+    /// during the next narrow-phase update.
+    pub fn contact_data_mut(
+        &mut self,
+        collider1: ColliderHandle,
+        collider2: ColliderHandle,
+        manifold_id: usize,
+        contact_id: usize,
+    ) -> Option<&mut ContactData> {
+        let id1 = self.graph_indices.get(collider1.0)?;
+        let id2 = self.graph_indices.get(collider2.0)?;
+        let (_, _, pair) = self
+            .contact_graph
+            .interaction_pair_mut(id1.contact_graph_index, id2.contact_graph_index)?;
+        let point = pair
+            .manifolds
+            .get_mut(manifold_id)?
+            .points
+            .get_mut(contact_id)?;
+        Some(&mut point.data)
+    }
+
     /// The intersection pair involving two specific colliders.
     ///
     /// If this returns `None` or `Some(false)`, then there is no intersection between the two colliders.
@@ -155,7 +448,8 @@ impl NarrowPhase {
     //     &mut self.contact_graph.interactions
     // }
 
-    /// Maintain the narrow-phase internal state by taking collider removal into account.
+    /// Maintain the narrow-phase internal state by taking collider removal and rigid-body
+    /// teleportation into account.
     pub fn maintain(&mut self, colliders: &mut ColliderSet, bodies: &mut RigidBodySet) {
         // Ensure we already subscribed.
         if self.removed_colliders.is_none() {
@@ -172,6 +466,12 @@ impl NarrowPhase {
         let mut i = 0;
 
         while let Some(collider) = colliders.removed_colliders.read_ith(&cursor, i) {
+            if !self.disabled_collision_pairs.is_empty() {
+                let handle = collider.handle;
+                self.disabled_collision_pairs
+                    .retain(|pair| pair.0 != handle && pair.1 != handle);
+            }
+
             // NOTE: if the collider does not have any graph indices currently, there is nothing
             // to remove in the narrow-phase for this collider.
             if let Some(graph_idx) = self.graph_indices.get(collider.handle.0) {
@@ -199,6 +499,52 @@ impl NarrowPhase {
 
         colliders.removed_colliders.ack(&cursor);
         self.removed_colliders = Some(cursor);
+
+        // Reset the contacts of the colliders which changed shape, keeping their data aside if
+        // it has to be transferred to the new contacts.
+        let modified_shapes = std::mem::replace(&mut colliders.modified_shapes, Vec::new());
+        for (handle, preserve_contacts) in modified_shapes {
+            if let Some(graph_idx) = self.graph_indices.get(handle.0) {
+                for (_, _, _, pair) in self
+                    .contact_graph
+                    .interactions_with_mut(graph_idx.contact_graph_index)
+                {
+                    if preserve_contacts {
+                        let co1 = &colliders[pair.pair.collider1];
+                        let co2 = &colliders[pair.pair.collider2];
+                        pair.swapped_shape_contacts.extend(world_contacts(
+                            &pair.manifolds,
+                            co1.position(),
+                            co2.position(),
+                        ));
+                    }
+
+                    pair.manifolds.clear();
+                    pair.workspace = None;
+                }
+            }
+        }
+
+        // Reset the contacts of the teleported bodies so they don't warmstart the solver.
+        let teleported_bodies = std::mem::replace(&mut bodies.teleported_bodies, Vec::new());
+        for handle in teleported_bodies {
+            if let Some(rb) = bodies.get(handle) {
+                for collider in &rb.colliders {
+                    if let Some(graph_idx) = self.graph_indices.get(collider.0) {
+                        for (_, _, _, pair) in self
+                            .contact_graph
+                            .interactions_with_mut(graph_idx.contact_graph_index)
+                        {
+                            for manifold in &mut pair.manifolds {
+                                for contact in &mut manifold.points {
+                                    contact.data = ContactData::default();
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
     }
 
     pub(crate) fn remove_collider(
@@ -394,13 +740,44 @@ impl NarrowPhase {
         let nodes = &self.intersection_graph.graph.nodes;
         let query_dispatcher = &*self.query_dispatcher;
         let active_hooks = hooks.active_hooks();
+        let disabled_collision_pairs = &self.disabled_collision_pairs;
+
+        // With the `parallel` feature, the events are buffered and emitted afterwards in the
+        // order of the graph edges, so their order doesn't depend on the scheduling of the
+        // parallel iteration. Otherwise, they are emitted right away.
+        #[cfg(feature = "parallel")]
+        let pending_events =
+            std::sync::Mutex::new(std::mem::take(&mut self.pending_intersection_events));
+        let emit_event = |_edge_id: usize, event: IntersectionEvent| {
+            #[cfg(not(feature = "parallel"))]
+            {
+                events.handle_intersection_event(event);
+            }
+            #[cfg(feature = "parallel")]
+            {
+                pending_events.lock().unwrap().push((_edge_id, event));
+            }
+        };
 
-        par_iter_mut!(&mut self.intersection_graph.graph.edges).for_each(|edge| {
+        let edges = par_iter_mut!(&mut self.intersection_graph.graph.edges).enumerate();
+        edges.for_each(|(edge_id, edge)| {
             let handle1 = nodes[edge.source().index()].weight;
             let handle2 = nodes[edge.target().index()].weight;
             let co1 = &colliders[handle1];
             let co2 = &colliders[handle2];
 
+            if !co1.is_enabled()
+                || !co2.is_enabled()
+                || disabled_collision_pairs.contains(&sorted_pair(handle1, handle2))
+            {
+                // Disabled colliders and disabled pairs don't intersect anything.
+                if edge.weight {
+                    edge.weight = false;
+                    emit_event(edge_id, IntersectionEvent::new(handle1, handle2, false));
+                }
+                return;
+            }
+
             // FIXME: avoid lookup into bodies.
             let rb1 = &bodies[co1.parent];
             let rb2 = &bodies[co2.parent];
@@ -449,14 +826,23 @@ impl NarrowPhase {
             {
                 if intersection != edge.weight {
                     edge.weight = intersection;
-                    events.handle_intersection_event(IntersectionEvent::new(
-                        handle1,
-                        handle2,
-                        intersection,
-                    ));
+                    emit_event(
+                        edge_id,
+                        IntersectionEvent::new(handle1, handle2, intersection),
+                    );
                 }
             }
         });
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut pending_events = pending_events.into_inner().unwrap();
+            pending_events.sort_by_key(|(edge_id, _)| *edge_id);
+            for (_, event) in pending_events.drain(..) {
+                events.handle_intersection_event(event);
+            }
+            self.pending_intersection_events = pending_events;
+        }
     }
 
     pub(crate) fn compute_contacts(
@@ -469,12 +855,69 @@ impl NarrowPhase {
     ) {
         let query_dispatcher = &*self.query_dispatcher;
         let active_hooks = hooks.active_hooks();
+        let disabled_collision_pairs = &self.disabled_collision_pairs;
+        let spatial_contact_matching = self.spatial_contact_matching;
+        let contact_matching_tolerance = self.contact_matching_tolerance;
+
+        // With the `parallel` feature, the events are buffered and emitted afterwards in the
+        // order of the graph edges, so their order doesn't depend on the scheduling of the
+        // parallel iteration. Otherwise, they are emitted right away. The contact manifolds
+        // are computed in-place, so their order is the same with or without the `parallel`
+        // feature.
+        #[cfg(feature = "parallel")]
+        let pending_events =
+            std::sync::Mutex::new(std::mem::take(&mut self.pending_contact_events));
+        let emit_event = |_edge_id: usize, event: ContactEvent| {
+            #[cfg(not(feature = "parallel"))]
+            {
+                events.handle_contact_event(event);
+            }
+            #[cfg(feature = "parallel")]
+            {
+                pending_events.lock().unwrap().push((_edge_id, event));
+            }
+        };
 
-        par_iter_mut!(&mut self.contact_graph.graph.edges).for_each(|edge| {
+        let edges = par_iter_mut!(&mut self.contact_graph.graph.edges).enumerate();
+        edges.for_each(|(edge_id, edge)| {
             let pair = &mut edge.weight;
             let co1 = &colliders[pair.pair.collider1];
             let co2 = &colliders[pair.pair.collider2];
 
+            if !co1.is_enabled()
+                || !co2.is_enabled()
+                || disabled_collision_pairs
+                    .contains(&sorted_pair(pair.pair.collider1, pair.pair.collider2))
+            {
+                // Disabled colliders and disabled pairs don't generate any solver contact.
+                // We keep the contact points so their impulses can still be used for
+                // warmstarting if the collider or the pair is enabled again shortly after.
+                // The contacts reappearing with the same features will then get their
+                // impulses back.
+                for manifold in &mut pair.manifolds {
+                    manifold.data.solver_contacts.clear();
+                    manifold.data.update_age();
+                    manifold.data.disabled_steps = manifold.data.disabled_steps.saturating_add(1);
+
+                    if manifold.data.disabled_steps > MAX_DISABLED_WARMSTART_STEPS {
+                        // The impulses are too old to be relevant.
+                        for contact in &mut manifold.points {
+                            contact.data.impulse = 0.0;
+                            contact.data.tangent_impulse = ContactData::zero_tangent_impulse();
+                        }
+                    }
+                }
+
+                if pair.has_any_active_contact {
+                    pair.has_any_active_contact = false;
+                    emit_event(
+                        edge_id,
+                        ContactEvent::Stopped(pair.pair.collider1, pair.pair.collider2),
+                    );
+                }
+                return;
+            }
+
             // FIXME: avoid lookup into bodies.
             let rb1 = &bodies[co1.parent];
             let rb2 = &bodies[co2.parent];
@@ -525,7 +968,28 @@ impl NarrowPhase {
                 solver_flags.remove(SolverFlags::COMPUTE_IMPULSES);
             }
 
+            // The contact skins are handled as an offset of the contact distances.
+            let skin = co1.contact_skin + co2.contact_skin;
+            let min_approach_speed = co1.min_approach_speed.max(co2.min_approach_speed);
+            let restitution_velocity_threshold = match (
+                co1.restitution_velocity_threshold,
+                co2.restitution_velocity_threshold,
+            ) {
+                (Some(t1), Some(t2)) => Some(t1.min(t2)),
+                (t1, t2) => t1.or(t2),
+            };
+            let prediction_distance = prediction_distance + skin;
             let pos12 = co1.position().inv_mul(co2.position());
+            let spatial_matching = spatial_contact_matching
+                & ((1 << co1.shape().shape_type() as u64) | (1 << co2.shape().shape_type() as u64))
+                != 0;
+            // The previous contacts, attached to the current positions of the colliders.
+            let previous_contacts = if spatial_matching {
+                world_contacts(&pair.manifolds, co1.position(), co2.position())
+            } else {
+                Vec::new()
+            };
+
             let _ = query_dispatcher.contact_manifolds(
                 &pos12,
                 co1.shape(),
@@ -535,6 +999,37 @@ impl NarrowPhase {
                 &mut pair.workspace,
             );
 
+            if spatial_matching {
+                // Discard the data inherited from the feature-based matching.
+                for manifold in &mut pair.manifolds {
+                    for contact in &mut manifold.points {
+                        contact.data = ContactData::default();
+                    }
+                }
+
+                transfer_contact_data(
+                    &mut pair.manifolds,
+                    co1.position(),
+                    co2.position(),
+                    previous_contacts,
+                    contact_matching_tolerance,
+                );
+            }
+
+            // Transfer the data of the contacts preceding a shape swap to the closest new
+            // contacts.
+            if !pair.swapped_shape_contacts.is_empty() {
+                let previous_contacts =
+                    std::mem::replace(&mut pair.swapped_shape_contacts, Vec::new());
+                transfer_contact_data(
+                    &mut pair.manifolds,
+                    co1.position(),
+                    co2.position(),
+                    previous_contacts,
+                    contact_matching_tolerance,
+                );
+            }
+
             let mut has_any_active_contact = false;
 
             let friction = CoefficientCombineRule::combine(
@@ -550,15 +1045,69 @@ impl NarrowPhase {
                 co2.flags.restitution_combine_rule_value(),
             );
 
+            let has_part_properties = co1.has_part_properties() || co2.has_part_properties();
+
             for manifold in &mut pair.manifolds {
                 let world_pos1 = manifold.subshape_pos1.prepend_to(co1.position());
                 manifold.data.solver_contacts.clear();
+                manifold.data.disabled_steps = 0;
+                manifold.data.warmstart_enabled = true;
+                #[cfg(feature = "dim3")]
+                let prev_anisotropic_friction = manifold.data.anisotropic_friction.take();
                 manifold.data.body_pair = BodyPair::new(co1.parent(), co2.parent());
                 manifold.data.solver_flags = solver_flags;
+                manifold.data.restitution_velocity_threshold = restitution_velocity_threshold;
                 manifold.data.relative_dominance =
                     rb1.effective_dominance_group() - rb2.effective_dominance_group();
                 manifold.data.normal = world_pos1 * manifold.local_n1;
 
+                #[cfg(feature = "dim3")]
+                {
+                    // Contacts on the internal edges of a triangle mesh use the triangle
+                    // normal. The triangles are expressed in the local-space of the mesh.
+                    let corrected_normal = co1
+                        .internal_edge_normal(
+                            manifold.subshape1,
+                            manifold.points.iter().map(|c| c.local_p1),
+                            &manifold.local_n1,
+                        )
+                        .map(|n| co1.position() * n)
+                        .or_else(|| {
+                            co2.internal_edge_normal(
+                                manifold.subshape2,
+                                manifold.points.iter().map(|c| c.local_p2),
+                                &manifold.local_n2,
+                            )
+                            .map(|n| -(co2.position() * n))
+                        });
+
+                    if let Some(normal) = corrected_normal {
+                        let world_pos2 = manifold.subshape_pos2.prepend_to(co2.position());
+                        manifold.data.normal = normal;
+
+                        for contact in &mut manifold.points {
+                            let p1 = world_pos1 * contact.local_p1;
+                            let p2 = world_pos2 * contact.local_p2;
+                            contact.dist = (p2 - p1).dot(&normal);
+                        }
+                    }
+                }
+
+                // Contacts with the hollow sub-shapes of a compound shape, or touching the
+                // back face of a one-sided triangle mesh, are ignored.
+                let is_ignored = !co1.is_subshape_solid(manifold.subshape1)
+                    || !co2.is_subshape_solid(manifold.subshape2);
+                #[cfg(feature = "dim3")]
+                let is_ignored = is_ignored
+                    || co1.is_backface_contact(manifold.subshape1, &manifold.local_n1)
+                    || co2.is_backface_contact(manifold.subshape2, &manifold.local_n2);
+
+                // Contacts matched with the previous timestep kept their data, including their age.
+                for contact in &mut manifold.points {
+                    contact.data.frames_alive = contact.data.frames_alive.saturating_add(1);
+                    contact.data.max_normal_impulse_reached = false;
+                }
+
                 // Generate solver contacts.
                 for (contact_id, contact) in manifold.points.iter().enumerate() {
                     assert!(
@@ -566,16 +1115,69 @@ impl NarrowPhase {
                         "A contact manifold cannot contain more than 255 contacts currently."
                     );
 
-                    if contact.dist < prediction_distance {
+                    if contact.dist < prediction_distance && !is_ignored {
+                        let (mut friction, mut restitution) = (friction, restitution);
+
+                        if has_part_properties {
+                            // The contact may involve a part of a shape (a heightfield cell or a
+                            // compound sub-shape) with its own properties.
+                            let local_p1 = manifold
+                                .subshape_pos1
+                                .map_or(contact.local_p1, |pos| pos * contact.local_p1);
+                            let local_p2 = manifold
+                                .subshape_pos2
+                                .map_or(contact.local_p2, |pos| pos * contact.local_p2);
+
+                            #[cfg(feature = "dim3")]
+                            {
+                                if co1.is_hole_at(&local_p1) || co2.is_hole_at(&local_p2) {
+                                    continue;
+                                }
+                            }
+
+                            let material1 = co1.part_material(manifold.subshape1, &local_p1);
+                            let material2 = co2.part_material(manifold.subshape2, &local_p2);
+
+                            if material1.is_some() || material2.is_some() {
+                                friction = CoefficientCombineRule::combine(
+                                    material1.map_or(co1.friction, |m| m.friction),
+                                    material2.map_or(co2.friction, |m| m.friction),
+                                    co1.flags.friction_combine_rule_value(),
+                                    co2.flags.friction_combine_rule_value(),
+                                );
+                                restitution = CoefficientCombineRule::combine(
+                                    material1.map_or(co1.restitution, |m| m.restitution),
+                                    material2.map_or(co2.restitution, |m| m.restitution),
+                                    co1.flags.restitution_combine_rule_value(),
+                                    co2.flags.restitution_combine_rule_value(),
+                                );
+                            }
+                        }
+
+                        let point = world_pos1 * contact.local_p1
+                            + manifold.data.normal * contact.dist / 2.0;
+
+                        // Ignore the contacts approaching too slowly.
+                        if min_approach_speed != -Real::MAX {
+                            let approach_speed = (rb1.velocity_at_point(&point)
+                                - rb2.velocity_at_point(&point))
+                            .dot(&manifold.data.normal);
+                            if approach_speed < min_approach_speed {
+                                continue;
+                            }
+                        }
+
                         // Generate the solver contact.
                         let solver_contact = SolverContact {
                             contact_id: contact_id as u8,
-                            point: world_pos1 * contact.local_p1
-                                + manifold.data.normal * contact.dist / 2.0,
-                            dist: contact.dist,
+                            fid1: contact.fid1,
+                            fid2: contact.fid2,
+                            point,
+                            dist: contact.dist - skin,
                             friction,
                             restitution,
                             tangent_velocity: Vector::zeros(),
+                            max_normal_impulse: Real::MAX,
                             data: contact.data,
                         };
 
@@ -595,6 +1197,9 @@ impl NarrowPhase {
                         std::mem::replace(&mut manifold.data.solver_contacts, Vec::new());
                     let mut modifiable_user_data = manifold.data.user_data;
                     let mut modifiable_normal = manifold.data.normal;
+                    let mut modifiable_warmstart_enabled = manifold.data.warmstart_enabled;
+                    #[cfg(feature = "dim3")]
+                    let mut modifiable_anisotropic_friction = manifold.data.anisotropic_friction;
 
                     let mut context = ContactModificationContext {
                         rigid_body1: rb1,
@@ -607,32 +1212,132 @@ impl NarrowPhase {
                         solver_contacts: &mut modifiable_solver_contacts,
                         normal: &mut modifiable_normal,
                         user_data: &mut modifiable_user_data,
+                        warmstart_enabled: &mut modifiable_warmstart_enabled,
+                        #[cfg(feature = "dim3")]
+                        anisotropic_friction: &mut modifiable_anisotropic_friction,
                     };
 
                     hooks.modify_solver_contacts(&mut context);
 
+                    // Keep the user-defined payloads of the contacts.
+                    for solver_contact in &modifiable_solver_contacts {
+                        if let Some(contact) =
+                            manifold.points.get_mut(solver_contact.contact_id as usize)
+                        {
+                            contact.data.user_data = solver_contact.data.user_data;
+                        }
+                    }
+
                     manifold.data.solver_contacts = modifiable_solver_contacts;
                     manifold.data.normal = modifiable_normal;
                     manifold.data.user_data = modifiable_user_data;
+                    manifold.data.warmstart_enabled = modifiable_warmstart_enabled;
+                    #[cfg(feature = "dim3")]
+                    {
+                        manifold.data.anisotropic_friction = modifiable_anisotropic_friction;
+                    }
+                }
+
+                // The tangent impulses are expressed in the friction basis of the last
+                // timestep, which depends on the anisotropic friction.
+                #[cfg(feature = "dim3")]
+                {
+                    manifold
+                        .data
+                        .reproject_tangent_impulses(prev_anisotropic_friction.as_ref());
                 }
+
+                manifold.data.update_age();
             }
 
             if has_any_active_contact != pair.has_any_active_contact {
+                pair.has_any_active_contact = has_any_active_contact;
+
                 if has_any_active_contact {
-                    events.handle_contact_event(ContactEvent::Started(
-                        pair.pair.collider1,
-                        pair.pair.collider2,
-                    ));
+                    emit_event(
+                        edge_id,
+                        ContactEvent::Started(pair.pair.collider1, pair.pair.collider2),
+                    );
                 } else {
-                    events.handle_contact_event(ContactEvent::Stopped(
-                        pair.pair.collider1,
-                        pair.pair.collider2,
-                    ));
+                    emit_event(
+                        edge_id,
+                        ContactEvent::Stopped(pair.pair.collider1, pair.pair.collider2),
+                    );
                 }
-
-                pair.has_any_active_contact = has_any_active_contact;
             }
         });
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut pending_events = pending_events.into_inner().unwrap();
+            pending_events.sort_by_key(|(edge_id, _)| *edge_id);
+            for (_, event) in pending_events.drain(..) {
+                events.handle_contact_event(event);
+            }
+            self.pending_contact_events = pending_events;
+        }
+    }
+
+    /// Emits a `ContactForceEvent` for each contact pair with contact forces exceeding
+    /// the force threshold of its colliders.
+    ///
+    /// This must be called after the constraints solver wrote its impulses back into
+    /// the contact manifolds.
+    pub(crate) fn emit_contact_force_events(
+        &self,
+        params: &IntegrationParameters,
+        colliders: &ColliderSet,
+        events: &dyn EventHandler,
+    ) {
+        if params.contact_force_event_threshold >= Real::MAX
+            && !colliders.has_contact_force_event_thresholds()
+        {
+            // No pair can exceed its threshold.
+            return;
+        }
+
+        let inv_dt = params.inv_dt();
+
+        for pair in self.contact_pairs() {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+
+            let co1 = &colliders[pair.pair.collider1];
+            let co2 = &colliders[pair.pair.collider2];
+            let threshold = params
+                .contact_force_event_threshold
+                .min(co1.contact_force_event_threshold)
+                .min(co2.contact_force_event_threshold);
+
+            if threshold >= Real::MAX {
+                continue;
+            }
+
+            let mut total_force = Vector::zeros();
+            let mut total_force_magnitude = 0.0;
+            let mut max_force_magnitude: Real = 0.0;
+
+            for manifold in &pair.manifolds {
+                for solver_contact in &manifold.data.solver_contacts {
+                    let contact = &manifold.points[solver_contact.contact_id as usize];
+                    let force = contact.data.impulse * inv_dt;
+                    total_force += manifold.data.normal * force;
+                    total_force_magnitude += force;
+                    max_force_magnitude = max_force_magnitude.max(force);
+                }
+            }
+
+            if total_force_magnitude > threshold {
+                events.handle_contact_force_event(ContactForceEvent {
+                    collider1: pair.pair.collider1,
+                    collider2: pair.pair.collider2,
+                    total_force,
+                    total_force_magnitude,
+                    max_force_magnitude,
+                });
+            }
+        }
     }
 
     /// Retrieve all the interactions with at least one contact point, happening between two active bodies.
@@ -674,3 +1379,992 @@ impl NarrowPhase {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn contact_force_events_threshold() {
+        use crate::geometry::ContactForceEvent;
+        use crate::pipeline::ChannelEventCollector;
+
+        fn count_contact_force_events(initial_height: Real) -> usize {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            let (intersection_send, _) = crossbeam::channel::unbounded();
+            let (contact_send, _) = crossbeam::channel::unbounded();
+            let (force_send, force_recv) = crossbeam::channel::unbounded();
+            let events = ChannelEventCollector::new(intersection_send, contact_send)
+                .with_contact_force_event_sender(force_send);
+
+            let ground = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(100.0).build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            let rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(
+                    Vector::y() * (0.5 + initial_height),
+                    na::zero(),
+                ))
+                .build();
+            let handle = world.bodies.insert(rb);
+            let collider = ColliderBuilder::ball(0.5)
+                .contact_force_event_threshold(100.0)
+                .build();
+            let collider_handle = world.colliders.insert(collider, handle, &mut world.bodies);
+
+            for _ in 0..120 {
+                world.step_with(&(), &events);
+            }
+
+            let events: Vec<ContactForceEvent> = force_recv.try_iter().collect();
+
+            for event in &events {
+                assert!(event.collider1 == collider_handle || event.collider2 == collider_handle);
+                assert!(event.total_force_magnitude > 100.0);
+                assert!(event.max_force_magnitude <= event.total_force_magnitude);
+            }
+
+            events.len()
+        }
+
+        // A hard landing.
+        assert!(count_contact_force_events(5.0) > 0);
+        // A gentle touch.
+        assert_eq!(count_contact_force_events(0.0), 0);
+    }
+
+    #[test]
+    fn disabled_collision_pair() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                .build(),
+        );
+        let ground_co = world.colliders.insert(
+            ColliderBuilder::ball(100.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        let mut balls = Vec::new();
+        let mut ball_colliders = Vec::new();
+
+        for height in &[0.5, 3.0] {
+            let rb = RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * *height, na::zero()))
+                .build();
+            let handle = world.bodies.insert(rb);
+            ball_colliders.push(world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                handle,
+                &mut world.bodies,
+            ));
+            balls.push(handle);
+        }
+
+        world
+            .narrow_phase
+            .disable_collision(ball_colliders[1], ball_colliders[0]);
+        assert!(world
+            .narrow_phase
+            .is_collision_disabled(ball_colliders[0], ball_colliders[1]));
+        assert!(!world
+            .narrow_phase
+            .is_collision_disabled(ball_colliders[0], ground_co));
+
+        for _ in 0..200 {
+            world.step();
+        }
+
+        // The upper ball fell through the lower one, but both rest on the ground.
+        for ball in &balls {
+            let y = world.bodies[*ball].position().translation.vector.y;
+            assert!((y - 0.5).abs() < 0.05);
+        }
+
+        let pair = world
+            .narrow_phase
+            .contact_pair(ball_colliders[0], ball_colliders[1])
+            .unwrap();
+        assert!(!pair.has_any_active_contact);
+        assert!(pair
+            .manifolds
+            .iter()
+            .all(|m| m.data.solver_contacts.is_empty()));
+        assert!(
+            world
+                .narrow_phase
+                .contact_pair(ball_colliders[1], ground_co)
+                .unwrap()
+                .has_any_active_contact
+        );
+
+        world
+            .narrow_phase
+            .enable_collision(ball_colliders[0], ball_colliders[1]);
+        assert!(!world
+            .narrow_phase
+            .is_collision_disabled(ball_colliders[0], ball_colliders[1]));
+    }
+
+    #[test]
+    fn ground_contact_max_angle() {
+        let is_grounded = |slope_angle: Real| {
+            let mut world = TestWorld::new();
+
+            #[cfg(feature = "dim2")]
+            let rotation = slope_angle;
+            #[cfg(feature = "dim3")]
+            let rotation = Vector::z() * slope_angle;
+
+            let slope_pos = Isometry::new(na::zero(), rotation);
+            let slope_normal = slope_pos * Vector::y();
+            let slope = world
+                .bodies
+                .insert(RigidBodyBuilder::new_static().position(slope_pos).build());
+            #[cfg(feature = "dim2")]
+            let slope_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let slope_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            let slope_collider =
+                world
+                    .colliders
+                    .insert(slope_shape.build(), slope, &mut world.bodies);
+
+            // A box lying on the slope, slightly penetrating it.
+            let box_pos = Isometry::new(slope_normal * 0.99, rotation);
+            let body = world
+                .bodies
+                .insert(RigidBodyBuilder::new_dynamic().position(box_pos).build());
+            #[cfg(feature = "dim2")]
+            let box_shape = ColliderBuilder::cuboid(0.5, 0.5);
+            #[cfg(feature = "dim3")]
+            let box_shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+            let box_collider = world
+                .colliders
+                .insert(box_shape.build(), body, &mut world.bodies);
+
+            world.step();
+
+            let ground = world.narrow_phase.ground_contact(
+                box_collider,
+                &Vector::y_axis(),
+                (45.0 as Real).to_radians(),
+            );
+
+            if let Some(ground) = ground {
+                assert_eq!(ground.collider, slope_collider);
+                assert!((ground.normal - slope_normal).norm() < 1.0e-3);
+            }
+
+            ground.is_some()
+        };
+
+        assert!(is_grounded((20.0 as Real).to_radians()));
+        assert!(!is_grounded((80.0 as Real).to_radians()));
+    }
+
+    #[test]
+    fn contact_frames_alive() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // A ball resting on the ground.
+        let resting_pos = Isometry::new(Vector::y() * 0.999, na::zero());
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(resting_pos)
+                .build(),
+        );
+        let ball_collider =
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        let frames_alive = |nf: &NarrowPhase| {
+            nf.contact_pair(ground_collider, ball_collider)
+                .and_then(|pair| pair.manifolds.iter().flat_map(|m| m.points.iter()).next())
+                .map(|contact| contact.data.frames_alive)
+        };
+
+        for i in 1..=10 {
+            world.step();
+            assert_eq!(frames_alive(&world.narrow_phase), Some(i));
+        }
+
+        // Break the contact.
+        world.bodies[ball].set_position(Isometry::new(Vector::y() * 5.0, na::zero()), true);
+        world.step();
+        assert_eq!(frames_alive(&world.narrow_phase), None);
+
+        // Restore the contact, its age must start over.
+        world.bodies[ball].set_position(resting_pos, true);
+        world.step();
+        assert_eq!(frames_alive(&world.narrow_phase), Some(1));
+    }
+
+    #[test]
+    fn stacked_balls_spatial_contact_matching() {
+        use crate::geometry::{ContactMatching, ShapeType};
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        assert_eq!(
+            world.narrow_phase.contact_matching(ShapeType::Ball),
+            ContactMatching::FeatureId
+        );
+        world
+            .narrow_phase
+            .set_contact_matching(ShapeType::Ball, ContactMatching::Spatial);
+        assert_eq!(
+            world.narrow_phase.contact_matching(ShapeType::Ball),
+            ContactMatching::Spatial
+        );
+        assert_eq!(
+            world.narrow_phase.contact_matching(ShapeType::Cuboid),
+            ContactMatching::FeatureId
+        );
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Two balls stacked on the ground.
+        let mut ball_colliders = Vec::new();
+        for i in 0..2 {
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * (1.0 + i as Real), na::zero()))
+                    .build(),
+            );
+            ball_colliders.push(world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                ball,
+                &mut world.bodies,
+            ));
+        }
+
+        let ball_contact = |nf: &NarrowPhase| {
+            nf.contact_pair(ball_colliders[0], ball_colliders[1])
+                .and_then(|pair| pair.manifolds.iter().flat_map(|m| m.points.iter()).next())
+                .map(|contact| contact.data)
+        };
+
+        let mut prev_impulse = 0.0;
+        for i in 1..=60 {
+            world.step();
+
+            // The contact between the balls is never lost, so it keeps warmstarting the solver.
+            let contact = ball_contact(&world.narrow_phase).unwrap();
+            assert_eq!(contact.frames_alive, i);
+
+            if i > 20 {
+                assert!((contact.impulse - prev_impulse).abs() <= prev_impulse * 0.05);
+            }
+            prev_impulse = contact.impulse;
+        }
+
+        assert!(prev_impulse > 0.0);
+    }
+
+    #[test]
+    fn per_manifold_warmstart_disabled() {
+        use crate::geometry::ColliderHandle;
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        struct NoWarmstartHooks {
+            collider: ColliderHandle,
+            enabled: AtomicBool,
+        }
+
+        impl PhysicsHooks for NoWarmstartHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                if self.enabled.load(Ordering::SeqCst)
+                    && (context.collider_handle1 == self.collider
+                        || context.collider_handle2 == self.collider)
+                {
+                    *context.warmstart_enabled = false;
+                }
+            }
+        }
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Two balls resting on the ground, far apart.
+        let mut balls = Vec::new();
+        for x in [-5.0, 5.0].iter() {
+            let body = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * 0.999,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            balls.push(
+                world.colliders.insert(
+                    ColliderBuilder::ball(0.5)
+                        .modify_solver_contacts(true)
+                        .build(),
+                    body,
+                    &mut world.bodies,
+                ),
+            );
+        }
+        let hooks = NoWarmstartHooks {
+            collider: balls[0],
+            enabled: AtomicBool::new(false),
+        };
+
+        for _ in 0..20 {
+            world.step_with(&hooks, &());
+        }
+
+        // Without any velocity iteration, the impulses are only the ones
+        // obtained from warmstarting.
+        hooks.enabled.store(true, Ordering::SeqCst);
+        world.params.max_velocity_iterations = 0;
+        world.step_with(&hooks, &());
+
+        let impulse = |nf: &NarrowPhase, ball: ColliderHandle| {
+            nf.contact_pair(ground_collider, ball)
+                .unwrap()
+                .manifolds
+                .iter()
+                .flat_map(|m| m.points.iter())
+                .map(|c| c.data.impulse)
+                .sum::<Real>()
+        };
+
+        assert_eq!(impulse(&world.narrow_phase, balls[0]), 0.0);
+        assert!(impulse(&world.narrow_phase, balls[1]) > 0.0);
+
+        // The warmstart is enabled again once the hook stops disabling it.
+        hooks.enabled.store(false, Ordering::SeqCst);
+        world.params.max_velocity_iterations = 4;
+        world.step_with(&hooks, &());
+
+        let pair = world
+            .narrow_phase
+            .contact_pair(ground_collider, balls[0])
+            .unwrap();
+        assert!(pair.manifolds.iter().all(|m| m.data.warmstart_enabled));
+    }
+
+    #[test]
+    fn seeded_warmstart_impulses() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.999, na::zero()))
+                .build(),
+        );
+        let ball_collider =
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..20 {
+            world.step();
+        }
+
+        // Without any velocity iteration, the ball velocity only results from
+        // gravity and the warmstart impulses.
+        world.params.max_velocity_iterations = 0;
+        let resting_impulse = world
+            .narrow_phase
+            .contact_data_mut(ground_collider, ball_collider, 0, 0)
+            .unwrap()
+            .impulse;
+        assert!(resting_impulse > 0.0);
+
+        let mut step_with_seed = |scale: Real| {
+            let data = world
+                .narrow_phase
+                .contact_data_mut(ground_collider, ball_collider, 0, 0)
+                .unwrap();
+            data.impulse = resting_impulse * scale;
+            world.bodies[ball].set_linvel(Vector::zeros(), true);
+            world.step();
+            world.bodies[ball].linvel().y
+        };
+
+        // The stored impulse exactly compensates gravity.
+        assert!(step_with_seed(1.0).abs() < 1.0e-3);
+        // Removing it lets the ball fall, doubling it pushes the ball upward.
+        assert!(step_with_seed(0.0) < -0.1);
+        assert!(step_with_seed(2.0) > 0.1);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_narrow_phase_is_deterministic() {
+        use crate::pipeline::{ChannelEventCollector, CollisionPipeline};
+
+        // Runs the collision detection on a grid of overlapping balls, with the given number of
+        // threads, and returns a description of the contact manifolds and events, in order.
+        let run = |num_threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let mut colliders = ColliderSet::new();
+            let mut bodies = RigidBodySet::new();
+
+            for i in 0..20 {
+                for j in 0..20 {
+                    let body = bodies.insert(
+                        RigidBodyBuilder::new_dynamic()
+                            .position(Isometry::new(
+                                Vector::x() * (i as Real * 0.9) + Vector::y() * (j as Real * 0.9),
+                                na::zero(),
+                            ))
+                            .build(),
+                    );
+                    colliders.insert(ColliderBuilder::ball(0.5).build(), body, &mut bodies);
+                }
+            }
+
+            let (contact_send, contact_recv) = crossbeam::channel::unbounded();
+            let (intersection_send, _) = crossbeam::channel::unbounded();
+            let events = ChannelEventCollector::new(intersection_send, contact_send);
+            let mut pipeline = CollisionPipeline::new();
+            let mut bf = BroadPhase::new();
+            let mut nf = NarrowPhase::new();
+
+            pool.install(|| {
+                pipeline.step(
+                    0.002,
+                    &mut bf,
+                    &mut nf,
+                    &mut bodies,
+                    &mut colliders,
+                    &(),
+                    &events,
+                )
+            });
+
+            let contacts: Vec<String> = nf
+                .contact_pairs()
+                .map(|pair| {
+                    let points: Vec<_> = pair
+                        .manifolds
+                        .iter()
+                        .flat_map(|m| m.points.iter())
+                        .map(|pt| (pt.local_p1, pt.local_p2, pt.dist))
+                        .collect();
+                    format!("{:?} {:?}", pair.pair, points)
+                })
+                .collect();
+            let events: Vec<String> = contact_recv
+                .try_iter()
+                .map(|e| format!("{:?}", e))
+                .collect();
+            (contacts, events)
+        };
+
+        let (serial_contacts, serial_events) = run(1);
+        let (parallel_contacts, parallel_events) = run(4);
+        assert!(!serial_events.is_empty());
+        assert_eq!(serial_contacts, parallel_contacts);
+        assert_eq!(serial_events, parallel_events);
+    }
+
+    #[test]
+    fn speculative_then_touching_contacts() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.prediction_distance = 0.1;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.3, na::zero()))
+                .build(),
+        );
+        let ball_collider =
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        let mut seen_speculative = false;
+        let mut seen_touching = false;
+
+        for _ in 0..100 {
+            world.step();
+
+            if let Some(pair) = world
+                .narrow_phase
+                .contact_pair(ground_collider, ball_collider)
+            {
+                for manifold in &pair.manifolds {
+                    for contact in &manifold.data.solver_contacts {
+                        if contact.is_touching() {
+                            assert!(contact.dist <= 0.0);
+                            seen_touching = true;
+                        } else {
+                            // All the speculative contacts are reported before the touching ones.
+                            assert!(!seen_touching);
+                            assert!(
+                                contact.dist > 0.0
+                                    && contact.dist <= world.params.prediction_distance
+                            );
+                            seen_speculative = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        assert!(seen_speculative);
+        assert!(seen_touching);
+    }
+
+    #[test]
+    fn contact_user_data_is_kept_through_the_solver() {
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+
+        // Sets the payload of all the solver contacts to a value derived from their distance.
+        struct DepthPayloadHooks;
+        impl PhysicsHooks for DepthPayloadHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                for contact in context.solver_contacts.iter_mut() {
+                    contact.data.user_data = 1.0 - contact.dist;
+                }
+            }
+        }
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .build(),
+        );
+        let ball_collider = world.colliders.insert(
+            ColliderBuilder::ball(0.5)
+                .modify_solver_contacts(true)
+                .build(),
+            ball,
+            &mut world.bodies,
+        );
+
+        for _ in 0..10 {
+            world.step();
+        }
+
+        // A payload set before the step is kept.
+        world
+            .narrow_phase
+            .contact_data_mut(ground_collider, ball_collider, 0, 0)
+            .unwrap()
+            .user_data = 42.0;
+        world.step();
+        let data = world
+            .narrow_phase
+            .contact_data_mut(ground_collider, ball_collider, 0, 0)
+            .unwrap();
+        assert_eq!(data.user_data, 42.0);
+        assert!(data.impulse > 0.0);
+
+        // A payload set by the hooks is copied back to the contact.
+        world.step_with(&DepthPayloadHooks, &());
+        let pair = world
+            .narrow_phase
+            .contact_pair(ground_collider, ball_collider)
+            .unwrap();
+        let manifold = &pair.manifolds[0];
+        let solver_contact = &manifold.data.solver_contacts[0];
+        let contact = &manifold.points[solver_contact.contact_id as usize];
+        assert_eq!(contact.data.user_data, 1.0 - solver_contact.dist);
+        assert!(contact.data.impulse > 0.0);
+    }
+
+    #[test]
+    fn disabled_collider_keeps_warmstart_impulses() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let ground_collider =
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .can_sleep(false)
+                .build(),
+        );
+        let ball_collider =
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        let impulse = |nf: &NarrowPhase| -> Real {
+            nf.contact_pair(ground_collider, ball_collider)
+                .unwrap()
+                .manifolds
+                .iter()
+                .flat_map(|m| m.points.iter())
+                .map(|pt| pt.data.impulse)
+                .sum()
+        };
+
+        // Let the ball come to rest on the ground.
+        for _ in 0..60 {
+            world.step();
+        }
+        let resting_impulse = impulse(&world.narrow_phase);
+        assert!(resting_impulse > 0.0);
+
+        // Disable the ball collider for a couple of steps: the impulses are kept.
+        world
+            .colliders
+            .get_mut(ball_collider)
+            .unwrap()
+            .set_enabled(false);
+        for _ in 0..2 {
+            world.step();
+        }
+        assert_eq!(impulse(&world.narrow_phase), resting_impulse);
+
+        // Once enabled again, the warmstarted contact doesn't make the ball bounce.
+        world
+            .colliders
+            .get_mut(ball_collider)
+            .unwrap()
+            .set_enabled(true);
+        for _ in 0..10 {
+            world.step();
+            assert!(world.bodies[ball].linvel().norm() < 0.5);
+        }
+
+        // Impulses of contacts disabled for too long are dropped.
+        world
+            .colliders
+            .get_mut(ball_collider)
+            .unwrap()
+            .set_enabled(false);
+        for _ in 0..12 {
+            world.step();
+        }
+        assert_eq!(impulse(&world.narrow_phase), 0.0);
+    }
+
+    #[test]
+    fn contacts_with_platform() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let platform = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let platform = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let platform = world
+            .colliders
+            .insert(platform.build(), ground, &mut world.bodies);
+
+        // Three boxes resting on the platform, and another one far away.
+        for x in &[-4.0, 0.0, 4.0, 50.0] {
+            let body = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * 1.0,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            #[cfg(feature = "dim2")]
+            let cuboid = ColliderBuilder::cuboid(0.5, 0.5);
+            #[cfg(feature = "dim3")]
+            let cuboid = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+            world
+                .colliders
+                .insert(cuboid.build(), body, &mut world.bodies);
+        }
+
+        for _ in 0..10 {
+            world.step();
+        }
+
+        let num_supported = world
+            .narrow_phase
+            .contacts_with(platform)
+            .unwrap()
+            .filter(|(_, _, pair)| pair.has_any_active_contact)
+            .count();
+        assert_eq!(num_supported, 3);
+    }
+
+    #[test]
+    fn deepest_penetration() {
+        let mut world = TestWorld::new();
+
+        // The top of the floor is at y = 0.
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let floor = world
+            .colliders
+            .insert(floor.build(), ground, &mut world.bodies);
+
+        // A box buried 0.2 deep into the floor.
+        let body = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.3, na::zero()))
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let cuboid = ColliderBuilder::cuboid(0.5, 0.5);
+        #[cfg(feature = "dim3")]
+        let cuboid = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+        let cuboid = world
+            .colliders
+            .insert(cuboid.build(), body, &mut world.bodies);
+
+        world.step();
+
+        let (other, depth, normal) = world.narrow_phase.deepest_penetration(cuboid).unwrap();
+        assert_eq!(other, floor);
+        assert!((depth - 0.2).abs() < 1.0e-3, "{}", depth);
+        assert!((normal - Vector::y()).norm() < 1.0e-3, "{}", normal);
+
+        // Seen from the floor, the normal points downward.
+        let (other, depth, normal) = world.narrow_phase.deepest_penetration(floor).unwrap();
+        assert_eq!(other, cuboid);
+        assert!((depth - 0.2).abs() < 1.0e-3, "{}", depth);
+        assert!((normal + Vector::y()).norm() < 1.0e-3, "{}", normal);
+    }
+
+    #[test]
+    fn sliding_contact_keeps_its_feature_ids() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        #[cfg(feature = "dim2")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -0.5, na::zero()))
+                .build(),
+        );
+        let ground_collider = world.colliders.insert(
+            ground_shape.friction(0.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+        let body = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 0.5, na::zero()))
+                .build(),
+        );
+        let box_collider =
+            world
+                .colliders
+                .insert(box_shape.friction(0.0).build(), body, &mut world.bodies);
+        world.bodies[body].set_linvel(Vector::x(), true);
+
+        let contact_keys = |nf: &NarrowPhase| {
+            let pair = nf.contact_pair(ground_collider, box_collider).unwrap();
+            let mut keys: Vec<_> = pair
+                .manifolds
+                .iter()
+                .flat_map(|m| m.data.solver_contacts.iter())
+                .map(|c| (format!("{:?}", c.feature_ids()), c.point))
+                .collect();
+            keys.sort_by(|a, b| a.0.cmp(&b.0));
+            keys
+        };
+
+        for _ in 0..5 {
+            world.step();
+        }
+        let before = contact_keys(&world.narrow_phase);
+        for _ in 0..30 {
+            world.step();
+        }
+        let after = contact_keys(&world.narrow_phase);
+
+        assert!(!before.is_empty());
+        assert_eq!(before.len(), after.len());
+
+        for ((key_before, pt_before), (key_after, pt_after)) in before.iter().zip(after.iter()) {
+            // The contact points moved, but they are still generated by the same features.
+            assert_eq!(key_before, key_after);
+            assert!(pt_after.x - pt_before.x > 0.2);
+        }
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn trimesh_bowl_on_trimesh_floor() {
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let floor_vertices = vec![
+            Point::new(-10.0, 0.0, -10.0),
+            Point::new(10.0, 0.0, -10.0),
+            Point::new(10.0, 0.0, 10.0),
+            Point::new(-10.0, 0.0, 10.0),
+        ];
+        let floor_indices = vec![[0, 2, 1], [0, 3, 2]];
+        let floor = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        let floor_collider = ColliderBuilder::trimesh(floor_vertices, floor_indices).build();
+        world
+            .colliders
+            .insert(floor_collider, floor, &mut world.bodies);
+
+        // A concave bowl: a square bottom surrounded by four slanted walls.
+        let bowl_vertices = vec![
+            Point::new(-1.0, 0.0, -1.0),
+            Point::new(1.0, 0.0, -1.0),
+            Point::new(1.0, 0.0, 1.0),
+            Point::new(-1.0, 0.0, 1.0),
+            Point::new(-1.5, 0.5, -1.5),
+            Point::new(1.5, 0.5, -1.5),
+            Point::new(1.5, 0.5, 1.5),
+            Point::new(-1.5, 0.5, 1.5),
+        ];
+        let bowl_indices = vec![
+            [0, 1, 2],
+            [0, 2, 3],
+            [0, 4, 5],
+            [0, 5, 1],
+            [1, 5, 6],
+            [1, 6, 2],
+            [2, 6, 7],
+            [2, 7, 3],
+            [3, 7, 4],
+            [3, 4, 0],
+        ];
+        // Triangle meshes don't have any mass so we have to set it on the rigid-body.
+        let bowl = RigidBodyBuilder::new_dynamic()
+            .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+            .mass(1.0)
+            .principal_angular_inertia(Vector::repeat(0.5))
+            .build();
+        let bowl = world.bodies.insert(bowl);
+        let bowl_collider = ColliderBuilder::trimesh(bowl_vertices, bowl_indices).build();
+        world
+            .colliders
+            .insert(bowl_collider, bowl, &mut world.bodies);
+
+        for _ in 0..300 {
+            world.step();
+        }
+
+        let rb = &world.bodies[bowl];
+        assert!(rb.position().translation.y.abs() < 0.1);
+        assert!(rb.position().rotation.angle() < 0.1);
+        assert!(rb.linvel().norm() < 0.1);
+        assert!(rb.angvel().norm() < 0.1);
+    }
+}
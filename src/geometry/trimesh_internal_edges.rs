@@ -0,0 +1,366 @@
+use crate::geometry::{HeightField, Shape, TriMesh};
+use crate::math::{Point, Real, Vector};
+use parry::query::PointQuery;
+use std::collections::HashMap;
+
+/// Two adjacent triangles are considered coplanar if the cosine of the angle
+/// between their normals is larger than this value.
+const COPLANARITY_THRESHOLD: Real = 0.9999;
+
+/// The internal edges of a triangle mesh or heightfield, used to fix the normals of the contacts
+/// lying on them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub(crate) enum InternalEdges {
+    /// The internal edges of a triangle mesh, computed once for all.
+    TriMesh(TriMeshInternalEdges),
+    /// The internal edges of a heightfield are identified on the fly from its grid structure
+    /// (see `heightfield_corrected_normal`), so nothing needs to be precomputed.
+    HeightField,
+}
+
+impl InternalEdges {
+    /// Identifies the internal edges of the given shape, if it is a triangle mesh or a heightfield.
+    pub fn for_shape(shape: &dyn Shape) -> Option<Self> {
+        if let Some(trimesh) = shape.as_trimesh() {
+            Some(InternalEdges::TriMesh(TriMeshInternalEdges::new(trimesh)))
+        } else if shape.as_heightfield().is_some() {
+            Some(InternalEdges::HeightField)
+        } else {
+            None
+        }
+    }
+}
+
+/// The internal edges of a triangle mesh, i.e., the edges shared by two coplanar triangles.
+///
+/// A contact located on an internal edge has a normal orthogonal to the edge instead of to
+/// the mesh surface, which makes objects sliding or rolling on the mesh catch on the edge.
+/// The normal of such contacts is replaced by the normal of the triangle.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub(crate) struct TriMeshInternalEdges {
+    /// For each triangle, the bit `i` is set if the edge between its vertices `i` and `(i + 1) % 3`
+    /// is an internal edge.
+    internal_edges: Vec<u8>,
+}
+
+impl TriMeshInternalEdges {
+    /// Identifies the internal edges of the given triangle mesh.
+    pub fn new(trimesh: &TriMesh) -> Self {
+        let indices = trimesh.indices();
+        let mut internal_edges = vec![0; indices.len()];
+        let mut edge_triangles: HashMap<(u32, u32), Vec<(usize, usize)>> = HashMap::new();
+
+        for (tri_id, idx) in indices.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (idx[i], idx[(i + 1) % 3]);
+                edge_triangles
+                    .entry((a.min(b), a.max(b)))
+                    .or_insert_with(Vec::new)
+                    .push((tri_id, i));
+            }
+        }
+
+        for triangles in edge_triangles.values() {
+            if let [(tri1, edge1), (tri2, edge2)] = triangles[..] {
+                let n1 = trimesh.triangle(tri1 as u32).normal();
+                let n2 = trimesh.triangle(tri2 as u32).normal();
+
+                if let (Some(n1), Some(n2)) = (n1, n2) {
+                    if n1.dot(&n2) > COPLANARITY_THRESHOLD {
+                        internal_edges[tri1] |= 1 << edge1;
+                        internal_edges[tri2] |= 1 << edge2;
+                    }
+                }
+            }
+        }
+
+        Self { internal_edges }
+    }
+
+    /// The normal to use for contacts between the given triangle and another shape.
+    ///
+    /// The `local_points` and `local_normal` are the contact points and the contact normal
+    /// (pointing outward the triangle) expressed in the local-space of the triangle mesh.
+    /// Returns the normal of the triangle if all the points lie on its internal edges and the
+    /// contact normal isn't already orthogonal to the triangle. Returns `None` otherwise.
+    pub fn corrected_normal(
+        &self,
+        trimesh: &TriMesh,
+        triangle_id: u32,
+        local_points: impl IntoIterator<Item = Point<Real>>,
+        local_normal: &Vector<Real>,
+    ) -> Option<Vector<Real>> {
+        let edges = *self.internal_edges.get(triangle_id as usize)?;
+        if edges == 0 {
+            return None;
+        }
+
+        let triangle = trimesh.triangle(triangle_id);
+        let normal = triangle.normal()?;
+        let normal = if normal.dot(local_normal) < 0.0 {
+            -normal.into_inner()
+        } else {
+            normal.into_inner()
+        };
+
+        if normal.dot(local_normal) > COPLANARITY_THRESHOLD {
+            // The contact normal is already correct.
+            return None;
+        }
+
+        let vertices = [triangle.a, triangle.b, triangle.c];
+        let mut has_points = false;
+
+        for pt in local_points {
+            let mut on_internal_edge = false;
+
+            for i in 0..3 {
+                let (a, b) = (vertices[i], vertices[(i + 1) % 3]);
+                let ab = b - a;
+                if ab.norm_squared() == 0.0 {
+                    continue;
+                }
+
+                let tolerance = ab.norm() * 1.0e-3;
+                let t = (pt - a).dot(&ab) / ab.norm_squared();
+                let dist_to_edge = (pt - (a + ab * t.max(0.0).min(1.0))).norm();
+
+                if dist_to_edge <= tolerance {
+                    if edges & (1 << i) == 0 {
+                        // The point lies on a boundary or sharp edge.
+                        return None;
+                    }
+
+                    on_internal_edge = true;
+                }
+            }
+
+            if !on_internal_edge {
+                return None;
+            }
+
+            has_points = true;
+        }
+
+        if has_points {
+            Some(normal)
+        } else {
+            None
+        }
+    }
+}
+
+/// The normal to use for contacts between a heightfield and another shape.
+///
+/// The `local_points` and `local_normal` are the contact points and the contact normal
+/// (pointing outward the heightfield) expressed in the local-space of the heightfield.
+/// Returns the normal of the heightfield triangles touched by the contact points if they are
+/// all coplanar and the contact normal isn't already orthogonal to them. Returns `None`
+/// otherwise, e.g., if a contact point lies on a ridge.
+pub(crate) fn heightfield_corrected_normal(
+    heightfield: &HeightField,
+    local_points: impl IntoIterator<Item = Point<Real>>,
+    local_normal: &Vector<Real>,
+) -> Option<Vector<Real>> {
+    let mut normal: Option<Vector<Real>> = None;
+
+    for pt in local_points {
+        let (i, j) = heightfield.cell_at_point(&pt)?;
+        let mut touches_triangle = false;
+
+        // The point may lie on the boundary of its cell, so we check the neighbor cells too.
+        for ii in i.saturating_sub(1)..(i + 2).min(heightfield.nrows()) {
+            for jj in j.saturating_sub(1)..(j + 2).min(heightfield.ncols()) {
+                let (tri1, tri2) = heightfield.triangles_at(ii, jj);
+
+                for tri in tri1.iter().chain(tri2.iter()) {
+                    let tolerance = (tri.b - tri.a).norm() * 1.0e-3;
+                    if tri.distance_to_local_point(&pt, true) > tolerance {
+                        continue;
+                    }
+
+                    let tri_normal = match tri.normal() {
+                        Some(n) if n.y < 0.0 => -n.into_inner(),
+                        Some(n) => n.into_inner(),
+                        None => continue,
+                    };
+
+                    if let Some(normal) = normal {
+                        if normal.dot(&tri_normal) <= COPLANARITY_THRESHOLD {
+                            // The point lies on a sharp edge.
+                            return None;
+                        }
+                    } else {
+                        normal = Some(tri_normal);
+                    }
+
+                    touches_triangle = true;
+                }
+            }
+        }
+
+        if !touches_triangle {
+            return None;
+        }
+    }
+
+    let normal = normal?;
+    let normal = if normal.dot(local_normal) < 0.0 {
+        -normal
+    } else {
+        normal
+    };
+
+    if normal.dot(local_normal) > COPLANARITY_THRESHOLD {
+        // The contact normal is already correct.
+        None
+    } else {
+        Some(normal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Point, Real, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn heightfield_ridge_internal_edges() {
+        // A 4x4 heightfield shaped like a pyramid: each quadrant is planar, and the ridges
+        // along the x and z axes are sharp edges.
+        let heights = na::DMatrix::from_fn(5, 5, |i, j| {
+            1.0 - 0.25 * ((i as Real - 2.0).abs() + (j as Real - 2.0).abs())
+        });
+        let scale = Vector::new(4.0, 1.0, 4.0);
+        let fixed = ColliderBuilder::heightfield(heights.clone(), scale)
+            .fix_internal_edges(true)
+            .build();
+        let unfixed = ColliderBuilder::heightfield(heights, scale).build();
+
+        // A contact on the edge between two coplanar cells, with a normal orthogonal to that edge.
+        let on_internal_edge = Point::new(1.0, 0.625, 0.5);
+        let edge_normal = Vector::x();
+        let plane_normal = Vector::new(0.25, 1.0, 0.25).normalize();
+
+        let corrected = fixed
+            .internal_edge_normal(0, Some(on_internal_edge), &edge_normal)
+            .unwrap();
+        assert!((corrected - plane_normal).norm() < 1.0e-5);
+        assert!(unfixed
+            .internal_edge_normal(0, Some(on_internal_edge), &edge_normal)
+            .is_none());
+
+        // A contact on the ridge is left untouched.
+        let on_ridge = Point::new(0.0, 0.875, 0.5);
+        assert!(fixed
+            .internal_edge_normal(0, Some(on_ridge), &edge_normal)
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn ball_rolls_smoothly_across_trimesh_internal_edges() {
+        use crate::math::Point;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // A flat grid of 10x10 squares, each split into two triangles.
+        let n = 10;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for i in 0..=n {
+            for j in 0..=n {
+                vertices.push(Point::new(i as Real - 5.0, 0.0, j as Real - 5.0));
+            }
+        }
+        for i in 0..n {
+            for j in 0..n {
+                let id = |i: u32, j: u32| i * (n + 1) + j;
+                indices.push([id(i, j), id(i, j + 1), id(i + 1, j + 1)]);
+                indices.push([id(i, j), id(i + 1, j + 1), id(i + 1, j)]);
+            }
+        }
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        world.colliders.insert(
+            ColliderBuilder::trimesh(vertices, indices)
+                .fix_internal_edges(true)
+                .build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .translation(-2.5, 0.5, -1.0)
+                .linvel(3.0, 0.0, 1.0)
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..100 {
+            world.step();
+
+            // The ball crosses many triangle edges without being pushed upward.
+            let rb = &world.bodies[ball];
+            assert!(rb.linvel().y.abs() < 1.0e-2, "{}", rb.linvel().y);
+            assert!(rb.position().translation.y < 0.5 + 1.0e-3);
+        }
+
+        assert!(world.bodies[ball].position().translation.x > 1.0);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn capsule_slides_smoothly_across_heightfield_cells() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // A flat heightfield of 20x20 cells of size 1x1. The capsule crosses the edges between
+        // the cells and the diagonal of each cell.
+        let heights = na::DMatrix::zeros(21, 21);
+        let scale = Vector::new(20.0, 1.0, 20.0);
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        world.colliders.insert(
+            ColliderBuilder::heightfield(heights, scale)
+                .fix_internal_edges(true)
+                .friction(0.0)
+                .build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        // A character-like capsule resting on the heightfield, sliding along the x axis.
+        let capsule = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .translation(-8.0, 0.8, 0.3)
+                .linvel(3.0, 0.0, 0.0)
+                .lock_rotations()
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::capsule_y(0.5, 0.3).friction(0.0).build(),
+            capsule,
+            &mut world.bodies,
+        );
+
+        for _ in 0..200 {
+            world.step();
+
+            // The capsule crosses many triangle edges without being pushed upward.
+            let rb = &world.bodies[capsule];
+            assert!(rb.linvel().y.abs() < 1.0e-2, "{}", rb.linvel().y);
+            assert!(rb.position().translation.y < 0.8 + 1.0e-3);
+            assert!(rb.linvel().x > 2.9, "{}", rb.linvel().x);
+        }
+
+        assert!(world.bodies[capsule].position().translation.x > 1.0);
+    }
+}
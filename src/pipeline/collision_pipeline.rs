@@ -85,5 +85,7 @@ impl CollisionPipeline {
         });
 
         bodies.modified_inactive_set.clear();
+        bodies.teleported_bodies.clear();
+        colliders.modified_shapes.clear();
     }
 }
@@ -1,5 +1,6 @@
-use crate::geometry::{ContactEvent, IntersectionEvent};
+use crate::geometry::{ContactEvent, ContactForceEvent, IntersectionEvent};
 use crossbeam::channel::Sender;
+use std::sync::Mutex;
 
 /// Trait implemented by structures responsible for handling events generated by the physics engine.
 ///
@@ -14,6 +15,12 @@ pub trait EventHandler: Send + Sync {
     /// A contact event is emitted when two collider start or stop touching, independently from the
     /// number of contact points involved.
     fn handle_contact_event(&self, event: ContactEvent);
+    /// Handle a contact force event.
+    ///
+    /// A contact force event is emitted at the end of a timestep when the contact forces between
+    /// two colliders exceed the threshold configured with `IntegrationParameters::contact_force_event_threshold`
+    /// or `ColliderSet::set_contact_force_event_threshold`. Does nothing by default.
+    fn handle_contact_force_event(&self, _event: ContactForceEvent) {}
 }
 
 impl EventHandler for () {
@@ -25,6 +32,7 @@ impl EventHandler for () {
 pub struct ChannelEventCollector {
     intersection_event_sender: Sender<IntersectionEvent>,
     contact_event_sender: Sender<ContactEvent>,
+    contact_force_event_sender: Option<Sender<ContactForceEvent>>,
 }
 
 impl ChannelEventCollector {
@@ -36,8 +44,20 @@ impl ChannelEventCollector {
         Self {
             intersection_event_sender,
             contact_event_sender,
+            contact_force_event_sender: None,
         }
     }
+
+    /// Sets the crossbeam channel sender used to collect the contact force events.
+    ///
+    /// The contact force events are ignored if no sender is set.
+    pub fn with_contact_force_event_sender(
+        mut self,
+        contact_force_event_sender: Sender<ContactForceEvent>,
+    ) -> Self {
+        self.contact_force_event_sender = Some(contact_force_event_sender);
+        self
+    }
 }
 
 impl EventHandler for ChannelEventCollector {
@@ -48,4 +68,47 @@ impl EventHandler for ChannelEventCollector {
     fn handle_contact_event(&self, event: ContactEvent) {
         let _ = self.contact_event_sender.send(event);
     }
+
+    fn handle_contact_force_event(&self, event: ContactForceEvent) {
+        if let Some(sender) = &self.contact_force_event_sender {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// The events generated during a timestep, as returned by `PhysicsPipeline::step_collect`.
+#[derive(Clone, Debug, Default)]
+pub struct CollectedEvents {
+    /// The contact events, in the order they were emitted.
+    pub contact_events: Vec<ContactEvent>,
+    /// The intersection events, in the order they were emitted.
+    pub intersection_events: Vec<IntersectionEvent>,
+    /// The contact force events, in the order they were emitted.
+    pub contact_force_events: Vec<ContactForceEvent>,
+}
+
+/// A physics event handler that collects events into vectors.
+#[derive(Default)]
+pub(crate) struct VecEventCollector {
+    events: Mutex<CollectedEvents>,
+}
+
+impl VecEventCollector {
+    pub fn into_events(self) -> CollectedEvents {
+        self.events.into_inner().unwrap()
+    }
+}
+
+impl EventHandler for VecEventCollector {
+    fn handle_intersection_event(&self, event: IntersectionEvent) {
+        self.events.lock().unwrap().intersection_events.push(event);
+    }
+
+    fn handle_contact_event(&self, event: ContactEvent) {
+        self.events.lock().unwrap().contact_events.push(event);
+    }
+
+    fn handle_contact_force_event(&self, event: ContactForceEvent) {
+        self.events.lock().unwrap().contact_force_events.push(event);
+    }
 }
@@ -0,0 +1,154 @@
+use crate::dynamics::{IntegrationParameters, RigidBody};
+use crate::math::{Point, Real, Vector};
+
+/// A user-defined generator of forces applied to the rigid-bodies at each timestep.
+///
+/// The force generators registered into `PhysicsPipeline::force_generators` are called once
+/// per awake dynamic rigid-body at each timestep, right before the gravity is applied and
+/// the constraints are resolved. The forces they generate keep the islands with
+/// `IntegrationParameters::skip_resting_islands` from being skipped.
+pub trait ForceGenerator: Send + Sync {
+    /// Applies forces to the given rigid-body, e.g., with `RigidBody::apply_force`.
+    fn apply(&self, params: &IntegrationParameters, body: &mut RigidBody);
+}
+
+/// A wind, i.e., a flowing fluid dragging the rigid-bodies toward its velocity.
+///
+/// The velocity of the fluid depends on the position, and is evaluated at the center of mass
+/// of each rigid-body. The drag force is `drag * (fluid_velocity - body_velocity)`, so the
+/// lighter bodies are accelerated faster toward the fluid velocity. This force is clamped so
+/// a body never overshoots the fluid velocity within a single timestep.
+pub struct WindForceGenerator<F> {
+    velocity_field: F,
+    /// The drag coefficient of the fluid.
+    ///
+    /// The larger it is, the faster the rigid-bodies reach the velocity of the fluid.
+    pub drag: Real,
+}
+
+impl<F> WindForceGenerator<F>
+where
+    F: Fn(&Point<Real>) -> Vector<Real> + Send + Sync,
+{
+    /// Creates a wind flowing with the velocity given by `velocity_field` at each point.
+    pub fn new(velocity_field: F, drag: Real) -> Self {
+        Self {
+            velocity_field,
+            drag,
+        }
+    }
+}
+
+impl<F> ForceGenerator for WindForceGenerator<F>
+where
+    F: Fn(&Point<Real>) -> Vector<Real> + Send + Sync,
+{
+    fn apply(&self, params: &IntegrationParameters, body: &mut RigidBody) {
+        let fluid_velocity = (self.velocity_field)(&body.world_com);
+        // The largest drag that doesn't make the body overshoot the fluid velocity.
+        let max_drag = body.mass() * params.inv_dt();
+        let drag = self.drag.min(max_drag);
+        body.apply_force((fluid_velocity - body.linvel()) * drag, false);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn wind_force_generator() {
+        use crate::pipeline::WindForceGenerator;
+
+        let mut world = TestWorld::new();
+
+        let light = world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).density(0.1).build(),
+            light,
+            &mut world.bodies,
+        );
+        let heavy = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 5.0, na::zero()))
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::ball(0.5).density(1000.0).build(),
+            heavy,
+            &mut world.bodies,
+        );
+
+        let wind_velocity = Vector::x() * 10.0;
+        world
+            .pipeline
+            .force_generators
+            .push(Box::new(WindForceGenerator::new(
+                move |_| wind_velocity,
+                1.0,
+            )));
+
+        for _ in 0..120 {
+            world.step();
+        }
+
+        let light_vel = *world.bodies[light].linvel();
+        let heavy_vel = *world.bodies[heavy].linvel();
+        assert!((light_vel - wind_velocity).norm() < 1.0, "{}", light_vel);
+        assert!(light_vel.x <= wind_velocity.x + 1.0e-5);
+        assert!(heavy_vel.x > 0.0 && heavy_vel.x < 0.1, "{}", heavy_vel);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[test]
+    fn wind_force_generator_wakes_resting_islands() {
+        use crate::pipeline::WindForceGenerator;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.min_island_size = 1;
+        world.params.skip_resting_islands = true;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        for _ in 0..10 {
+            world.step();
+        }
+        assert!(world.pipeline.solved_islands().is_empty());
+
+        // The drag of the wind must not be discarded as if the ball was still at rest.
+        let wind_velocity = Vector::x() * 10.0;
+        world
+            .pipeline
+            .force_generators
+            .push(Box::new(WindForceGenerator::new(
+                move |_| wind_velocity,
+                1.0,
+            )));
+
+        let position = *world.bodies[ball].position();
+        world.step();
+        assert_eq!(world.pipeline.solved_islands().len(), 1);
+        assert!(world.bodies[ball].linvel().x > 0.0);
+        assert_ne!(world.bodies[ball].position(), &position);
+    }
+}
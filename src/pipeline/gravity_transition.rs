@@ -0,0 +1,136 @@
+use crate::math::{Real, Vector};
+
+/// A gradual change of gravity over a given number of timesteps.
+///
+/// This interpolates linearly between two gravity vectors, e.g., to enter or leave a
+/// zero-gravity zone without an abrupt change of the forces applied to the bodies.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GravityTransition {
+    start: Vector<Real>,
+    end: Vector<Real>,
+    num_steps: u32,
+    step: u32,
+}
+
+impl GravityTransition {
+    /// Creates a transition from the gravity `start` to the gravity `end` reached after `num_steps`
+    /// timesteps.
+    pub fn new(start: Vector<Real>, end: Vector<Real>, num_steps: u32) -> Self {
+        Self {
+            start,
+            end,
+            num_steps,
+            step: 0,
+        }
+    }
+
+    /// The gravity to pass to the next call to `PhysicsPipeline::step`.
+    ///
+    /// Each call advances the transition by one timestep. Once the transition is finished,
+    /// this always returns the final gravity.
+    pub fn next_gravity(&mut self) -> Vector<Real> {
+        if self.step >= self.num_steps {
+            return self.end;
+        }
+
+        self.step += 1;
+        let t = self.step as Real / self.num_steps as Real;
+        self.start + (self.end - self.start) * t
+    }
+
+    /// Did this transition reach its final gravity?
+    pub fn is_finished(&self) -> bool {
+        self.step >= self.num_steps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn toggling_gravity() {
+        use crate::pipeline::GravityTransition;
+
+        let mut world = TestWorld::new();
+        let gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // One ball resting on the ground, one ball floating.
+        let mut balls = Vec::new();
+        for height in [1.0, 5.0].iter() {
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * *height, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                handle,
+                &mut world.bodies,
+            );
+            balls.push(handle);
+        }
+        let (resting, floating) = (balls[0], balls[1]);
+
+        // Let the resting ball settle, and keep the floating ball in place.
+        world.gravity = gravity;
+        for _ in 0..100 {
+            world.bodies[floating].set_linvel(Vector::zeros(), true);
+            world.bodies[floating].set_position(Isometry::new(Vector::y() * 5.0, na::zero()), true);
+            world.step();
+        }
+        world.bodies[floating].set_linvel(Vector::zeros(), true);
+
+        // Toggle the gravity off. The resting ball must not be pushed up by the
+        // warmstarting of its contacts with the ground.
+        world.gravity = Vector::zeros();
+        for _ in 0..100 {
+            world.step();
+            assert!(world.bodies[resting].linvel().norm() < 1.0e-3);
+            assert!(world.bodies[resting].position().translation.vector.y < 1.0 + 1.0e-2);
+        }
+
+        // The floating ball doesn't move when the gravity is off, and falls asleep.
+        let floating_pos = world.bodies[floating].position().translation.vector;
+        for _ in 0..200 {
+            world.step();
+        }
+        assert!(world.bodies[floating].is_sleeping());
+        let floating_y = world.bodies[floating].position().translation.vector.y;
+        assert!((floating_y - floating_pos.y).abs() < 0.1);
+
+        // Toggle the gravity back on, progressively. The floating ball wakes up and falls.
+        let mut transition = GravityTransition::new(Vector::zeros(), gravity, 10);
+        let mut prev_gravity = Vector::zeros();
+        while !transition.is_finished() {
+            let g = transition.next_gravity();
+            assert!(g.y < prev_gravity.y && g.y >= gravity.y);
+            prev_gravity = g;
+            world.gravity = g;
+            world.step();
+        }
+        assert_eq!(transition.next_gravity(), gravity);
+        assert!(!world.bodies[floating].is_sleeping());
+
+        let vel_before = world.bodies[floating].linvel().y;
+        world.gravity = gravity;
+        for _ in 0..10 {
+            world.step();
+        }
+        let expected_vel = vel_before + gravity.y * world.params.dt * 10.0;
+        assert!((world.bodies[floating].linvel().y - expected_vel).abs() < 1.0e-3);
+        assert!(world.bodies[floating].position().translation.vector.y < floating_y);
+    }
+}
@@ -1,15 +1,25 @@
 //! Structure for combining the various physics components to perform an actual simulation.
 
 pub use collision_pipeline::CollisionPipeline;
-pub use event_handler::{ChannelEventCollector, EventHandler};
+pub(crate) use event_handler::VecEventCollector;
+pub use event_handler::{ChannelEventCollector, CollectedEvents, EventHandler};
+pub use force_generator::{ForceGenerator, WindForceGenerator};
+pub use gravity_transition::GravityTransition;
 pub use physics_hooks::{
-    ContactModificationContext, PairFilterContext, PhysicsHooks, PhysicsHooksFlags,
+    ContactCoefficientsHooks, ContactModificationContext, IslandModificationContext,
+    PairFilterContext, PhysicsHooks, PhysicsHooksFlags, VelocityIterationContext,
 };
-pub use physics_pipeline::PhysicsPipeline;
-pub use query_pipeline::QueryPipeline;
+pub use physics_pipeline::{MergedHandles, PhysicsPipeline};
+pub use query_pipeline::{PointProjectionWithNormal, QueryPipeline, RayTriangleIntersection};
+#[cfg(test)]
+pub(crate) use test_world::TestWorld;
 
 mod collision_pipeline;
 mod event_handler;
+mod force_generator;
+mod gravity_transition;
 mod physics_hooks;
 mod physics_pipeline;
 mod query_pipeline;
+#[cfg(test)]
+mod test_world;
@@ -1,7 +1,9 @@
-use crate::dynamics::RigidBody;
+use crate::dynamics::{DeltaVel, RigidBody, RigidBodyHandle, RigidBodySet};
+#[cfg(feature = "dim3")]
+use crate::geometry::AnisotropicFriction;
 use crate::geometry::{Collider, ColliderHandle, ContactManifold, SolverContact, SolverFlags};
-use crate::math::{Real, Vector};
-use na::ComplexField;
+use crate::math::{AngVector, Real, Vector};
+use crate::utils::{DeterministicMath, WAngularInertia};
 
 /// Context given to custom collision filters to filter-out collisions.
 pub struct PairFilterContext<'a> {
@@ -43,6 +45,11 @@ pub struct ContactModificationContext<'a> {
     // NOTE: we keep this a &'a mut u32 to emphasize the
     // fact that this can be modified.
     pub user_data: &'a mut u32,
+    /// Should the impulses of the last timestep be used to warmstart this manifold's constraints?
+    pub warmstart_enabled: &'a mut bool,
+    /// The anisotropic friction applied to all the contacts of this manifold, if any.
+    #[cfg(feature = "dim3")]
+    pub anisotropic_friction: &'a mut Option<AnisotropicFriction>,
 }
 
 impl<'a> ContactModificationContext<'a> {
@@ -65,7 +72,7 @@ impl<'a> ContactModificationContext<'a> {
         const CONTACT_CURRENTLY_ALLOWED: u32 = 1;
         const CONTACT_CURRENTLY_FORBIDDEN: u32 = 2;
 
-        let cang = ComplexField::cos(allowed_angle);
+        let cang = allowed_angle.det_cos();
 
         // Test the allowed normal with the local-space contact normal that
         // points towards the exterior of context.collider1.
@@ -107,6 +114,143 @@ impl<'a> ContactModificationContext<'a> {
     }
 }
 
+/// Context given to custom island modifiers, right before the constraints solver resolves an
+/// active island.
+///
+/// The velocity changes applied through this context are taken into account by the constraints
+/// solver, exactly like the velocity changes due to external forces.
+pub struct IslandModificationContext<'a> {
+    /// The index of the active island being solved.
+    pub island_id: usize,
+    bodies: &'a RigidBodySet,
+    delta_vels: &'a mut [DeltaVel<Real>],
+}
+
+impl<'a> IslandModificationContext<'a> {
+    pub(crate) fn new(
+        island_id: usize,
+        bodies: &'a RigidBodySet,
+        delta_vels: &'a mut [DeltaVel<Real>],
+    ) -> Self {
+        Self {
+            island_id,
+            bodies,
+            delta_vels,
+        }
+    }
+
+    /// The handles of the dynamic rigid-bodies of this island.
+    pub fn island_bodies(&self) -> &[RigidBodyHandle] {
+        self.bodies.active_island(self.island_id)
+    }
+
+    /// The rigid-body of this island with the given handle, if it exists.
+    ///
+    /// Its velocities do not include the changes applied through this context yet.
+    pub fn rigid_body(&self, handle: RigidBodyHandle) -> Option<&RigidBody> {
+        let rb = self.bodies.get(handle)?;
+        let in_island = self.island_bodies().get(rb.active_set_offset) == Some(&handle);
+        Some(rb).filter(|_| in_island)
+    }
+
+    /// Changes the linear velocity of a rigid-body of this island by `delta_linvel`.
+    ///
+    /// This does nothing if the rigid-body is not part of this island, or if its translations
+    /// are locked.
+    pub fn apply_linvel_change(&mut self, handle: RigidBodyHandle, delta_linvel: Vector<Real>) {
+        if let Some(rb) = self.rigid_body(handle) {
+            if rb.effective_inv_mass != 0.0 {
+                self.delta_vels[rb.active_set_offset].linear += delta_linvel;
+            }
+        }
+    }
+
+    /// Applies an impulse at the center-of-mass of a rigid-body of this island.
+    ///
+    /// This does nothing if the rigid-body is not part of this island.
+    pub fn apply_impulse(&mut self, handle: RigidBodyHandle, impulse: Vector<Real>) {
+        if let Some(rb) = self.rigid_body(handle) {
+            self.delta_vels[rb.active_set_offset].linear += impulse * rb.effective_inv_mass;
+        }
+    }
+
+    /// Applies an angular impulse at the center-of-mass of a rigid-body of this island.
+    ///
+    /// This does nothing if the rigid-body is not part of this island.
+    pub fn apply_torque_impulse(
+        &mut self,
+        handle: RigidBodyHandle,
+        torque_impulse: AngVector<Real>,
+    ) {
+        if let Some(rb) = self.rigid_body(handle) {
+            // The angular delta-velocity is multiplied by the square root of the inertia tensor.
+            self.delta_vels[rb.active_set_offset].angular +=
+                rb.effective_world_inv_inertia_sqrt * torque_impulse;
+        }
+    }
+}
+
+/// Context given to custom velocity solver inspectors, right after each Gauss-Seidel iteration
+/// of the constraints solver on an active island.
+pub struct VelocityIterationContext<'a> {
+    /// The index of the active island being solved.
+    pub island_id: usize,
+    /// The index of the iteration that just completed, starting at 0.
+    pub iteration: usize,
+    bodies: &'a RigidBodySet,
+    delta_vels: &'a [DeltaVel<Real>],
+}
+
+impl<'a> VelocityIterationContext<'a> {
+    pub(crate) fn new(
+        island_id: usize,
+        iteration: usize,
+        bodies: &'a RigidBodySet,
+        delta_vels: &'a [DeltaVel<Real>],
+    ) -> Self {
+        Self {
+            island_id,
+            iteration,
+            bodies,
+            delta_vels,
+        }
+    }
+
+    /// The handles of the dynamic rigid-bodies of this island.
+    pub fn island_bodies(&self) -> &[RigidBodyHandle] {
+        self.bodies.active_island(self.island_id)
+    }
+
+    /// The delta-velocities computed so far by the solver, in the same order as `self.island_bodies()`.
+    ///
+    /// Their angular part is multiplied by the square root of the inertia tensor of the rigid-body.
+    /// See `Self::velocity_change` for the actual angular velocity change.
+    pub fn delta_vels(&self) -> &[DeltaVel<Real>] {
+        self.delta_vels
+    }
+
+    /// The linear and angular velocity changes computed so far by the solver for a rigid-body
+    /// of this island, including the effect of the external forces.
+    ///
+    /// Returns `None` if the rigid-body is not part of this island.
+    pub fn velocity_change(
+        &self,
+        handle: RigidBodyHandle,
+    ) -> Option<(Vector<Real>, AngVector<Real>)> {
+        let rb = self.bodies.get(handle)?;
+
+        if self.island_bodies().get(rb.active_set_offset) != Some(&handle) {
+            return None;
+        }
+
+        let dvel = self.delta_vels[rb.active_set_offset];
+        let angvel = rb
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(dvel.angular);
+        Some((dvel.linear, angvel))
+    }
+}
+
 bitflags::bitflags! {
     #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
     /// Flags affecting the behavior of the constraints solver for a given contact manifold.
@@ -117,6 +261,10 @@ bitflags::bitflags! {
         const FILTER_INTERSECTION_PAIR = 0b0010;
         /// If set, Rapier will call `PhysicsHooks::modify_solver_contact` whenever relevant.
         const MODIFY_SOLVER_CONTACTS = 0b0100;
+        /// If set, Rapier will call `PhysicsHooks::modify_island` whenever relevant.
+        const MODIFY_ISLAND = 0b1000;
+        /// If set, Rapier will call `PhysicsHooks::inspect_velocity_iteration` whenever relevant.
+        const INSPECT_VELOCITY_ITERATIONS = 0b10000;
     }
 }
 
@@ -188,11 +336,16 @@ pub trait PhysicsHooks: Send + Sync {
     /// Note that if all the contacts have to be ignored by the constraint solver, you may simply
     /// do `context.solver_contacts.clear()`.
     ///
+    /// The solver contacts are resolved by chunks of at most `MAX_MANIFOLD_POINTS` contacts, each chunk
+    /// being a separate constraint. So the number of constraints generated for this contact manifold
+    /// is `ceil(context.solver_contacts.len() / MAX_MANIFOLD_POINTS)`.
+    ///
     /// Modifying the solver contacts allow you to achieve various effects, including:
     /// - Simulating conveyor belts by setting the `surface_velocity` of a solver contact.
     /// - Simulating shapes with multiply materials by modifying the friction and restitution
     ///   coefficient depending of the features in contacts.
     /// - Simulating one-way platforms depending on the contact normal.
+    /// - Simulating tires by setting an anisotropic friction in `context.anisotropic_friction` (3D only).
     ///
     /// Each contact manifold is given a `u32` user-defined data that is persistent between
     /// timesteps (as long as the contact manifold exists). This user-defined data is initialized
@@ -200,6 +353,33 @@ pub trait PhysicsHooks: Send + Sync {
     ///
     /// The world-space contact normal can be modified in `context.normal`.
     fn modify_solver_contacts(&self, _context: &mut ContactModificationContext) {}
+
+    /// Modifies the velocities of the rigid-bodies of an active island before it is solved.
+    ///
+    /// Note that this method will only be called if `self.active_hooks()`
+    /// contains the `PhysicsHooksFlags::MODIFY_ISLAND` flags.
+    ///
+    /// This is called once per active island and per timestep, after the constraints of the
+    /// island are assembled and before they are solved. This can be used to apply island-wide
+    /// effects, e.g., a wind gust, consistently with the constraints solver.
+    ///
+    /// With the `parallel` feature, this may be called concurrently for different islands, from
+    /// different threads. The context only gives access to the rigid-bodies of its own island,
+    /// so any other state accessed by this method must be synchronized.
+    fn modify_island(&self, _context: &mut IslandModificationContext) {}
+
+    /// Inspects the delta-velocities computed by the constraints solver after each of its iterations.
+    ///
+    /// Note that this method will only be called if `self.active_hooks()`
+    /// contains the `PhysicsHooksFlags::INSPECT_VELOCITY_ITERATIONS` flags.
+    ///
+    /// This is called after each Gauss-Seidel sweep of the velocity solver over the constraints
+    /// of an active island, so debugging and teaching tools can single-step the solver, e.g., by
+    /// setting a breakpoint here or by recording the delta-velocities of each iteration.
+    ///
+    /// This is not called by the parallel solver, which interleaves the iterations of several
+    /// threads.
+    fn inspect_velocity_iteration(&self, _context: &VelocityIterationContext) {}
 }
 
 impl PhysicsHooks for () {
@@ -216,4 +396,271 @@ impl PhysicsHooks for () {
     }
 
     fn modify_solver_contacts(&self, _: &mut ContactModificationContext) {}
+
+    fn modify_island(&self, _: &mut IslandModificationContext) {}
+}
+
+/// Physics hooks computing the friction and restitution coefficients of each solver contact
+/// with a user-defined closure.
+///
+/// The closure is given both colliders involved in the contact, as well as the solver contact
+/// itself, and returns the `(friction, restitution)` pair to use for that contact. This overrides
+/// the coefficients computed from the collider materials and their combine rules.
+///
+/// Only contacts involving at least one collider with its `modify_solver_contacts` flag set
+/// (see `ColliderBuilder::modify_solver_contacts`) are affected.
+pub struct ContactCoefficientsHooks<F> {
+    coefficients: F,
+}
+
+impl<F> ContactCoefficientsHooks<F>
+where
+    F: Fn(&Collider, &Collider, &SolverContact) -> (Real, Real) + Send + Sync,
+{
+    /// Initializes physics hooks computing contact coefficients with the given closure.
+    pub fn new(coefficients: F) -> Self {
+        Self { coefficients }
+    }
+}
+
+impl<F> PhysicsHooks for ContactCoefficientsHooks<F>
+where
+    F: Fn(&Collider, &Collider, &SolverContact) -> (Real, Real) + Send + Sync,
+{
+    fn active_hooks(&self) -> PhysicsHooksFlags {
+        PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+    }
+
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        for contact in context.solver_contacts.iter_mut() {
+            let (friction, restitution) =
+                (self.coefficients)(context.collider1, context.collider2, contact);
+            contact.friction = friction;
+            contact.restitution = restitution;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::RigidBodyBuilder;
+    use crate::geometry::ColliderBuilder;
+    use crate::math::{Isometry, Vector};
+    use crate::pipeline::TestWorld;
+
+    #[test]
+    fn contact_coefficients_hooks_wet_surface() {
+        use crate::pipeline::ContactCoefficientsHooks;
+
+        const WET: u128 = 1;
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        // Wet colliders have no friction at all.
+        let hooks = ContactCoefficientsHooks::new(|co1, co2, _| {
+            if co1.user_data == WET || co2.user_data == WET {
+                (0.0, 0.0)
+            } else {
+                (1.0, 0.0)
+            }
+        });
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(100.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(100.0, 0.5, 100.0);
+        world.colliders.insert(
+            ground_shape.friction(0.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        // A box sliding on the ground.
+        let body_builder = RigidBodyBuilder::new_dynamic()
+            .position(Isometry::new(Vector::y() * 0.999, na::zero()));
+        #[cfg(feature = "dim2")]
+        let body_builder = body_builder.linvel(10.0, 0.0);
+        #[cfg(feature = "dim3")]
+        let body_builder = body_builder.linvel(10.0, 0.0, 0.0);
+        let body = world.bodies.insert(body_builder.build());
+        #[cfg(feature = "dim2")]
+        let box_shape = ColliderBuilder::cuboid(0.5, 0.5);
+        #[cfg(feature = "dim3")]
+        let box_shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+        let box_collider = world.colliders.insert(
+            box_shape.friction(0.0).modify_solver_contacts(true).build(),
+            body,
+            &mut world.bodies,
+        );
+
+        let slide = |world: &mut TestWorld| {
+            let vel_before = world.bodies[body].linvel().x;
+
+            for _ in 0..10 {
+                world.step_with(&hooks, &());
+            }
+
+            vel_before - world.bodies[body].linvel().x
+        };
+
+        // The material friction is zero, so any slowdown comes from the hooks.
+        let dry_slowdown = slide(&mut world);
+        world.colliders.get_mut(box_collider).unwrap().user_data = WET;
+        let wet_slowdown = slide(&mut world);
+
+        assert!(dry_slowdown > 1.0);
+        assert!(wet_slowdown < 0.1);
+    }
+
+    #[test]
+    fn island_hook_velocity_change() {
+        use crate::pipeline::{IslandModificationContext, PhysicsHooks, PhysicsHooksFlags};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        struct GustHooks {
+            enabled: AtomicBool,
+        }
+
+        impl PhysicsHooks for GustHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_ISLAND
+            }
+
+            fn modify_island(&self, context: &mut IslandModificationContext) {
+                if self.enabled.load(Ordering::SeqCst) {
+                    let gust = Vector::x() * 1.0 - Vector::y() * 5.0;
+                    for handle in context.island_bodies().to_vec() {
+                        context.apply_linvel_change(handle, gust);
+                    }
+                }
+            }
+        }
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world.colliders.insert(
+            ground_shape.friction(0.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        // One ball resting on the ground, and one ball in the air.
+        let mut balls = Vec::new();
+        for (x, y) in [(-5.0, 0.999), (5.0, 50.0)].iter() {
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * *y,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).friction(0.0).build(),
+                handle,
+                &mut world.bodies,
+            );
+            balls.push(handle);
+        }
+
+        let hooks = GustHooks {
+            enabled: AtomicBool::new(false),
+        };
+
+        for _ in 0..20 {
+            world.step_with(&hooks, &());
+        }
+
+        world.bodies[balls[1]].set_linvel(Vector::zeros(), true);
+        hooks.enabled.store(true, Ordering::SeqCst);
+        world.step_with(&hooks, &());
+
+        // The flying ball keeps the whole velocity change.
+        let expected = Vector::x() * 1.0 + Vector::y() * (-5.0 - 9.81 * world.params.dt);
+        assert!((world.bodies[balls[1]].linvel() - expected).norm() < 1.0e-3);
+
+        // The contact with the ground cancels the downward velocity change of the resting ball.
+        let linvel = world.bodies[balls[0]].linvel();
+        assert!((linvel.x - 1.0).abs() < 1.0e-3);
+        assert!(linvel.y.abs() < 0.1);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[test]
+    fn velocity_iteration_hook_single_steps_the_solver() {
+        use crate::dynamics::RigidBodyHandle;
+        use crate::math::Real;
+        use crate::pipeline::{PhysicsHooks, PhysicsHooksFlags, VelocityIterationContext};
+        use std::sync::Mutex;
+
+        struct RecordingHooks {
+            ball: RigidBodyHandle,
+            // The iteration index, and the linear velocity change of the ball.
+            iterations: Mutex<Vec<(usize, Vector<Real>)>>,
+        }
+
+        impl PhysicsHooks for RecordingHooks {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::INSPECT_VELOCITY_ITERATIONS
+            }
+
+            fn inspect_velocity_iteration(&self, context: &VelocityIterationContext) {
+                assert_eq!(context.delta_vels().len(), context.island_bodies().len());
+
+                if let Some((linvel, _)) = context.velocity_change(self.ball) {
+                    let mut iterations = self.iterations.lock().unwrap();
+                    iterations.push((context.iteration, linvel));
+                }
+            }
+        }
+
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        let ball = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::y() * 1.0, na::zero()))
+                .build(),
+        );
+        world
+            .colliders
+            .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+        let hooks = RecordingHooks {
+            ball,
+            iterations: Mutex::new(Vec::new()),
+        };
+
+        for _ in 0..10 {
+            hooks.iterations.lock().unwrap().clear();
+            let linvel = *world.bodies[ball].linvel();
+            world.step_with(&hooks, &());
+
+            // One call per iteration, and the last one gives the final velocity change.
+            let iterations = hooks.iterations.lock().unwrap();
+            let island_id = world.bodies[ball].active_island_id;
+            let num_iterations = world.pipeline.velocity_iterations(island_id).unwrap();
+            assert_eq!(iterations.len(), num_iterations);
+            assert!(iterations.iter().enumerate().all(|(i, it)| it.0 == i));
+            let last_change = iterations.last().unwrap().1;
+            assert!((world.bodies[ball].linvel() - (linvel + last_change)).norm() < 1.0e-5);
+        }
+    }
 }
@@ -2,21 +2,31 @@
 
 use crate::counters::Counters;
 #[cfg(not(feature = "parallel"))]
-use crate::dynamics::IslandSolver;
-use crate::dynamics::{IntegrationParameters, JointSet, RigidBodySet};
+use crate::dynamics::{CustomVelocityConstraint, IslandSolver};
+use crate::dynamics::{
+    IntegrationParameters, JointHandle, JointSet, MassProperties, RigidBody, RigidBodyHandle,
+    RigidBodySet,
+};
 #[cfg(feature = "parallel")]
 use crate::dynamics::{JointGraphEdge, ParallelIslandSolver as IslandSolver};
+#[cfg(not(feature = "parallel"))]
+use crate::geometry::ContactManifold;
 use crate::geometry::{
-    BroadPhase, BroadPhasePairEvent, ColliderPair, ColliderSet, ContactManifoldIndex, NarrowPhase,
+    BroadPhase, BroadPhasePairEvent, ColliderHandle, ColliderPair, ColliderSet,
+    ContactManifoldIndex, NarrowPhase,
 };
-use crate::math::{Real, Vector};
-use crate::pipeline::{EventHandler, PhysicsHooks};
+use crate::math::{Real, Translation, Vector};
+use crate::pipeline::{
+    CollectedEvents, EventHandler, ForceGenerator, PhysicsHooks, VecEventCollector,
+};
+use std::collections::HashMap;
 
 /// The physics pipeline, responsible for stepping the whole physics simulation.
 ///
-/// This structure only contains temporary data buffers. It can be dropped and replaced by a fresh
-/// copy at any time. For performance reasons it is recommended to reuse the same physics pipeline
-/// instance to benefit from the cached data.
+/// Except for the user-defined `custom_constraints` and `force_generators`, this structure only
+/// contains temporary data buffers. It can be dropped and replaced by a fresh copy at any time. For
+/// performance reasons it is recommended to reuse the same physics pipeline instance to benefit
+/// from the cached data.
 ///
 /// Rapier relies on a time-stepping scheme. Its force computations
 /// uses two solvers:
@@ -31,6 +41,33 @@ pub struct PhysicsPipeline {
     broadphase_collider_pairs: Vec<ColliderPair>,
     broad_phase_events: Vec<BroadPhasePairEvent>,
     solvers: Vec<IslandSolver>,
+    last_gravity: Option<Vector<Real>>,
+    /// User-defined generators of forces applied to the rigid-bodies at each timestep.
+    pub force_generators: Vec<Box<dyn ForceGenerator>>,
+    /// User-defined velocity constraints solved alongside the contact and joint constraints.
+    #[cfg(not(feature = "parallel"))]
+    pub custom_constraints: Vec<Box<dyn CustomVelocityConstraint>>,
+    #[cfg(not(feature = "parallel"))]
+    custom_constraint_islands: Vec<usize>,
+    #[cfg(not(feature = "parallel"))]
+    island_custom_constraints: Vec<Vec<Box<dyn CustomVelocityConstraint>>>,
+    #[cfg(not(feature = "parallel"))]
+    resting_islands: Vec<bool>,
+    #[cfg(not(feature = "parallel"))]
+    solved_islands: Vec<usize>,
+}
+
+/// The new handles of the rigid-bodies, colliders, and joints copied by `PhysicsPipeline::merge`.
+///
+/// Each map associates the handle of an element in the merged sets to the handle of its copy.
+#[derive(Clone, Debug, Default)]
+pub struct MergedHandles {
+    /// The new handles of the merged rigid-bodies.
+    pub bodies: HashMap<RigidBodyHandle, RigidBodyHandle>,
+    /// The new handles of the merged colliders.
+    pub colliders: HashMap<ColliderHandle, ColliderHandle>,
+    /// The new handles of the merged joints.
+    pub joints: HashMap<JointHandle, JointHandle>,
 }
 
 impl Default for PhysicsPipeline {
@@ -55,10 +92,240 @@ impl PhysicsPipeline {
             joint_constraint_indices: Vec::new(),
             broadphase_collider_pairs: Vec::new(),
             broad_phase_events: Vec::new(),
+            last_gravity: None,
+            force_generators: Vec::new(),
+            #[cfg(not(feature = "parallel"))]
+            custom_constraints: Vec::new(),
+            #[cfg(not(feature = "parallel"))]
+            custom_constraint_islands: Vec::new(),
+            #[cfg(not(feature = "parallel"))]
+            island_custom_constraints: Vec::new(),
+            #[cfg(not(feature = "parallel"))]
+            resting_islands: Vec::new(),
+            #[cfg(not(feature = "parallel"))]
+            solved_islands: Vec::new(),
+        }
+    }
+
+    /// Releases the memory of the workspace buffers that is not currently used.
+    ///
+    /// The buffers used by the physics pipeline, including the ones storing the contact and
+    /// joint constraints, are reused from one step to the other and only grow when needed.
+    /// This avoids reallocations on scenes with a fluctuating number of contacts, at the cost
+    /// of keeping the memory needed by the most demanding steps. Call this method (for example
+    /// during a loading screen) to release the memory that is not currently used.
+    pub fn shrink_to_fit(&mut self) {
+        for indices in &mut self.manifold_indices {
+            indices.shrink_to_fit();
+        }
+
+        for indices in &mut self.joint_constraint_indices {
+            indices.shrink_to_fit();
+        }
+
+        for solver in &mut self.solvers {
+            solver.shrink_to_fit();
+        }
+
+        self.broadphase_collider_pairs.shrink_to_fit();
+        self.broad_phase_events.shrink_to_fit();
+    }
+
+    /// The convergence history of the velocity solver for the given active island during the
+    /// last timestep.
+    ///
+    /// The `i`-th element is the sum of the magnitudes of all the contact impulses (normal and
+    /// friction) of the island after the `i`-th velocity iteration. Joint impulses are not
+    /// included. This is empty unless `IntegrationParameters::record_velocity_convergence` was
+    /// enabled, and `None` if no solver was ever allocated for this island.
+    #[cfg(not(feature = "parallel"))]
+    pub fn velocity_convergence_history(&self, island_id: usize) -> Option<&[Real]> {
+        self.solvers
+            .get(island_id)
+            .map(|solver| solver.velocity_convergence_history())
+    }
+
+    /// The number of iterations performed by the velocity solver for the given active island
+    /// during the last timestep.
+    ///
+    /// This is smaller than the maximum number of iterations if the island converged early
+    /// with `SolverMode::Adaptive`, and zero if the island had no constraint to solve. This
+    /// is `None` if no solver was ever allocated for this island.
+    #[cfg(not(feature = "parallel"))]
+    pub fn velocity_iterations(&self, island_id: usize) -> Option<usize> {
+        self.solvers
+            .get(island_id)
+            .map(|solver| solver.num_velocity_iterations())
+    }
+
+    /// The active islands solved by the constraints solver during the last timestep.
+    ///
+    /// This contains all the active islands, unless `IntegrationParameters::skip_resting_islands`
+    /// is enabled, in which case the islands at rest are omitted.
+    #[cfg(not(feature = "parallel"))]
+    pub fn solved_islands(&self) -> &[usize] {
+        &self.solved_islands
+    }
+
+    /// A hash of the dynamic state of the simulation, e.g., to detect desynchronizations
+    /// between networked peers.
+    ///
+    /// This hashes the handles, positions and velocities of all the rigid-bodies, as well as the
+    /// impulses of all the contact points tracked by the narrow-phase. Both sets are traversed in
+    /// their storage order, which only depends on the sequence of operations applied to them, so
+    /// the result is reproducible from one run to the other on the same platform. Enable the
+    /// `enhanced-determinism` feature to obtain the same hash across platforms.
+    pub fn state_hash(bodies: &RigidBodySet, narrow_phase: &NarrowPhase) -> u64 {
+        // FNV-1a, so the hash doesn't depend on the hasher of the standard library.
+        let mut hash = 0xcbf29ce484222325u64;
+        let mut hash_u64 = |val: u64| {
+            for byte in val.to_le_bytes().iter() {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+
+        for (handle, rb) in bodies.iter() {
+            let (index, generation) = handle.into_raw_parts();
+            hash_u64(index as u64);
+            hash_u64(generation);
+
+            let pos = rb.position().to_homogeneous();
+            #[cfg(feature = "dim2")]
+            let angvel = [rb.angvel()];
+            #[cfg(feature = "dim3")]
+            let angvel = *rb.angvel();
+
+            for val in pos.iter().chain(rb.linvel().iter()).chain(angvel.iter()) {
+                hash_u64(val.to_bits() as u64);
+            }
+        }
+
+        for pair in narrow_phase.contact_pairs() {
+            for handle in [pair.pair.collider1, pair.pair.collider2].iter() {
+                let (index, generation) = handle.into_raw_parts();
+                hash_u64(index as u64);
+                hash_u64(generation);
+            }
+
+            for manifold in &pair.manifolds {
+                for contact in &manifold.points {
+                    hash_u64(contact.data.impulse.to_bits() as u64);
+                    #[cfg(feature = "dim2")]
+                    hash_u64(contact.data.tangent_impulse.to_bits() as u64);
+                    #[cfg(feature = "dim3")]
+                    for val in contact.data.tangent_impulse.iter() {
+                        hash_u64(val.to_bits() as u64);
+                    }
+                }
+            }
+        }
+
+        hash
+    }
+
+    /// Copies all the rigid-bodies, colliders, and joints of another world into these sets.
+    ///
+    /// This is useful to merge two worlds simulated separately, e.g., two chunks of a large
+    /// world, once they start interacting. The copied elements get new handles, returned by this
+    /// method, and the anchor colliders of the copied joints are remapped accordingly. The
+    /// positions, velocities, and sleep states of the rigid-bodies are preserved, as well as the
+    /// impulses of the joints used for warmstarting. The contacts of the other world are not
+    /// copied: they are computed again by the broad-phase and narrow-phase of this world at the
+    /// next timestep, along with the contacts between elements of both worlds. The active islands
+    /// are rebuilt at the next timestep too.
+    pub fn merge(
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        other_bodies: &RigidBodySet,
+        other_colliders: &ColliderSet,
+        other_joints: &JointSet,
+    ) -> MergedHandles {
+        let mut handles = MergedHandles::default();
+
+        for (handle, rb) in other_bodies.iter() {
+            let mut rb = rb.clone();
+            // The mass properties of the colliders are added back when the colliders are inserted.
+            rb.colliders_mass_properties = MassProperties::zero();
+            handles.bodies.insert(handle, bodies.insert(rb));
+        }
+
+        let other_collider_handles: Vec<_> = other_colliders.iter().map(|(h, _)| h).collect();
+        let new_collider_handles = colliders.insert_batch(
+            other_colliders
+                .iter()
+                .map(|(_, co)| (co.clone(), handles.bodies[&co.parent()]))
+                .collect(),
+            bodies,
+        );
+        handles.colliders = other_collider_handles
+            .into_iter()
+            .zip(new_collider_handles.into_iter())
+            .collect();
+
+        for (handle, joint) in other_joints.iter() {
+            let body1 = handles.bodies[&joint.body1];
+            let body2 = handles.bodies[&joint.body2];
+            let new_handle = joints.insert(bodies, body1, body2, joint.params);
+            let [collider1, collider2] = joint.anchor_colliders;
+            let new_joint = joints.get_mut(new_handle).unwrap();
+            new_joint.enabled = joint.enabled;
+            new_joint.compliance = joint.compliance;
+            new_joint.solver_iterations_multiplier = joint.solver_iterations_multiplier;
+            new_joint.set_anchor_colliders(
+                collider1.and_then(|h| handles.colliders.get(&h).copied()),
+                collider2.and_then(|h| handles.colliders.get(&h).copied()),
+            );
+            handles.joints.insert(handle, new_handle);
+        }
+
+        handles
+    }
+
+    /// Translates the whole simulation by `-offset`, e.g., to keep the region of interest close
+    /// to the origin in a large world (a.k.a. "floating origin").
+    ///
+    /// This moves every rigid-body and collider in one pass, without waking them up or
+    /// modifying their velocities. The contact and joint impulses used for warmstarting are
+    /// preserved because they are expressed in the local frames of the colliders and bodies.
+    /// The broad-phase updates the bounding boxes of all the colliders at the next timestep.
+    /// Any `QueryPipeline` must be updated before being used again.
+    pub fn rebase(
+        &mut self,
+        offset: &Vector<Real>,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+    ) {
+        let shift = Translation::from(-offset);
+
+        for (handle, rb) in bodies.bodies.iter_mut() {
+            rb.position = shift * rb.position;
+            rb.predicted_position = shift * rb.predicted_position;
+            rb.previous_position = shift * rb.previous_position;
+            rb.world_com = shift * rb.world_com;
+            rb.update_colliders_positions(colliders);
+
+            // The broad-phase only updates the bounding boxes of the
+            // active bodies, and of the inactive ones we flag here.
+            if !rb.is_dynamic() || rb.is_sleeping() {
+                bodies.modified_inactive_set.push(RigidBodyHandle(handle));
+            }
         }
     }
 
     /// Executes one timestep of the physics simulation.
+    ///
+    /// The gravity can change from one step to the next, e.g., using a `GravityTransition`.
+    /// Whenever it differs from the gravity of the previous step, all the sleeping dynamic
+    /// rigid-bodies are woken up so they react to the new gravity.
+    ///
+    /// With the `parallel` feature, the constraints of each island are colored so that the
+    /// constraints of a same color never share a dynamic rigid-body. The constraints of a color
+    /// are solved concurrently by the threads of the current rayon thread pool, and the colors
+    /// are solved one after the other. This ordering differs from the one used without the
+    /// `parallel` feature, so the results of both builds are close but not identical. They don't
+    /// depend on the number of threads though.
     pub fn step(
         &mut self,
         gravity: &Vector<Real>,
@@ -70,12 +337,82 @@ impl PhysicsPipeline {
         joints: &mut JointSet,
         hooks: &dyn PhysicsHooks,
         events: &dyn EventHandler,
+    ) {
+        if self.last_gravity.map_or(false, |g| g != *gravity) {
+            bodies.wake_up_all(true);
+        }
+        self.last_gravity = Some(*gravity);
+
+        self.step_with_gravity_fn(
+            |_| *gravity,
+            integration_parameters,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            joints,
+            hooks,
+            events,
+        )
+    }
+
+    /// Executes one timestep of the physics simulation and returns the events it generated.
+    ///
+    /// This is equivalent to `Self::step` with an event handler collecting all the events
+    /// into vectors.
+    pub fn step_collect(
+        &mut self,
+        gravity: &Vector<Real>,
+        integration_parameters: &IntegrationParameters,
+        broad_phase: &mut BroadPhase,
+        narrow_phase: &mut NarrowPhase,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        hooks: &dyn PhysicsHooks,
+    ) -> CollectedEvents {
+        let events = VecEventCollector::default();
+        self.step(
+            gravity,
+            integration_parameters,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            joints,
+            hooks,
+            &events,
+        );
+        events.into_events()
+    }
+
+    /// Executes one timestep of the physics simulation with a gravity depending on each rigid-body.
+    ///
+    /// The `gravity_fn` closure is called once per awake dynamic rigid-body at each timestep, before
+    /// the velocities are integrated. It returns the gravitational acceleration applied to this
+    /// rigid-body (which is still scaled by the rigid-body's gravity scale). The bodies are always
+    /// visited in the same order, so this closure is called deterministically. Sleeping bodies are not
+    /// woken up automatically when the gravity changes, see `RigidBodySet::wake_up_all`.
+    pub fn step_with_gravity_fn(
+        &mut self,
+        gravity_fn: impl Fn(&RigidBody) -> Vector<Real>,
+        integration_parameters: &IntegrationParameters,
+        broad_phase: &mut BroadPhase,
+        narrow_phase: &mut NarrowPhase,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        hooks: &dyn PhysicsHooks,
+        events: &dyn EventHandler,
     ) {
         self.counters.step_started();
         bodies.maintain(colliders);
         broad_phase.maintain(colliders);
         narrow_phase.maintain(colliders, bodies);
 
+        // Save the positions of the bodies that may move during this timestep for interpolation.
+        bodies.foreach_active_body_mut_internal(|_, rb| rb.previous_position = rb.position);
+
         // Update kinematic bodies velocities.
         // TODO: what is the best place for this? It should at least be
         // located before the island computation because we test the velocity
@@ -145,14 +482,32 @@ impl PhysicsPipeline {
             &mut self.manifold_indices,
         );
         joints.select_active_interactions(bodies, &mut self.joint_constraint_indices);
+        joints.update_anchor_collider_frames(colliders);
 
         self.counters.cd.narrow_phase_time.pause();
         self.counters.stages.collision_detection_time.pause();
 
         self.counters.stages.update_time.start();
+        let force_generators = &self.force_generators;
         bodies.foreach_active_dynamic_body_mut_internal(|_, b| {
             b.update_world_mass_properties();
-            b.add_gravity(*gravity)
+
+            for generator in force_generators {
+                generator.apply(integration_parameters, b);
+            }
+        });
+        self.counters.stages.update_time.pause();
+
+        // This must be done before the gravity is applied, but after the force generators,
+        // so the user-defined and generated forces can be detected.
+        #[cfg(not(feature = "parallel"))]
+        self.find_resting_islands(integration_parameters, bodies, &manifolds, &gravity_fn);
+
+        self.counters.stages.update_time.start();
+        bodies.foreach_active_dynamic_body_mut_internal(|_, b| {
+            let gravity = gravity_fn(b);
+            b.add_gravity(gravity);
+            b.apply_solver_mass_multiplier();
         });
         self.counters.stages.update_time.pause();
 
@@ -167,7 +522,43 @@ impl PhysicsPipeline {
         {
             enable_flush_to_zero!();
 
-            for island_id in 0..bodies.num_islands() {
+            // Dispatch the custom constraints to the island of their first awake dynamic body.
+            // The last bucket contains the constraints that don't affect any awake dynamic body.
+            let num_islands = bodies.num_islands();
+            self.island_custom_constraints
+                .resize_with(num_islands + 1, Vec::new);
+            self.custom_constraint_islands.clear();
+
+            for constraint in self.custom_constraints.drain(..) {
+                let island_id = constraint
+                    .bodies()
+                    .iter()
+                    .map(|handle| &bodies[*handle])
+                    .find(|rb| rb.is_dynamic() && !rb.is_sleeping())
+                    .map(|rb| rb.active_island_id)
+                    .unwrap_or(num_islands);
+                self.custom_constraint_islands.push(island_id);
+                self.island_custom_constraints[island_id].push(constraint);
+            }
+
+            self.solved_islands.clear();
+
+            for island_id in 0..num_islands {
+                if self.resting_islands[island_id]
+                    && self.island_custom_constraints[island_id].is_empty()
+                {
+                    bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
+                        rb.linvel = na::zero();
+                        rb.angvel = na::zero();
+                        rb.force = na::zero();
+                        rb.torque = na::zero();
+                        rb.solver_linvel_change = na::zero();
+                        rb.solver_angvel_change = na::zero();
+                    });
+                    continue;
+                }
+
+                self.solved_islands.push(island_id);
                 self.solvers[island_id].solve_island(
                     island_id,
                     &mut self.counters,
@@ -177,8 +568,21 @@ impl PhysicsPipeline {
                     &self.manifold_indices[island_id],
                     joints.joints_mut(),
                     &self.joint_constraint_indices[island_id],
+                    &mut self.island_custom_constraints[island_id],
+                    hooks,
                 )
             }
+
+            // Put the custom constraints back, in their original order.
+            let mut island_custom_constraints: Vec<_> = self.island_custom_constraints
+                [..num_islands + 1]
+                .iter_mut()
+                .map(|constraints| constraints.drain(..))
+                .collect();
+            for island_id in &self.custom_constraint_islands {
+                let constraint = island_custom_constraints[*island_id].next().unwrap();
+                self.custom_constraints.push(constraint);
+            }
         }
 
         #[cfg(feature = "parallel")]
@@ -218,6 +622,7 @@ impl PhysicsPipeline {
                             &manifold_indices[island_id],
                             joints,
                             &joint_constraint_indices[island_id],
+                            hooks,
                         )
                     });
             });
@@ -231,25 +636,112 @@ impl PhysicsPipeline {
                 rb.linvel = na::zero();
                 rb.angvel = na::zero();
             } else {
+                rb.reset_solver_mass_multiplier();
                 rb.update_predicted_position(integration_parameters.dt);
             }
 
             rb.update_colliders_positions(colliders);
         });
 
+        #[cfg(feature = "dim3")]
+        joints.update_revolute_angles(bodies);
+
         self.counters.stages.solver_time.pause();
 
+        narrow_phase.emit_contact_force_events(integration_parameters, colliders, events);
+
         bodies.modified_inactive_set.clear();
+        bodies.update_position_snapshot();
         self.counters.step_completed();
     }
+
+    #[cfg(not(feature = "parallel"))]
+    fn find_resting_islands(
+        &mut self,
+        params: &IntegrationParameters,
+        bodies: &RigidBodySet,
+        manifolds: &[&mut ContactManifold],
+        gravity_fn: &dyn Fn(&RigidBody) -> Vector<Real>,
+    ) {
+        use crate::utils::WDot;
+
+        let is_resting = |rb: &RigidBody| {
+            rb.linvel.norm_squared() + rb.angvel.gdot(rb.angvel) <= rb.activation.threshold
+                && rb.force == na::zero()
+                && rb.torque == na::zero()
+        };
+
+        let manifold_indices = &self.manifold_indices;
+        let mut supported = Vec::new();
+
+        // A body moving slowly is not at rest unless it lies on a contact opposing its gravity.
+        // Otherwise, a body spawned in mid-air, or at the apex of a jump, would be frozen there.
+        let mut is_supported = |island_id: usize| {
+            let island = bodies.active_island(island_id);
+            supported.clear();
+            supported.resize(island.len(), false);
+
+            for id in &manifold_indices[island_id] {
+                let manifold = &manifolds[*id];
+
+                if manifold.data.solver_contacts.is_empty() {
+                    continue;
+                }
+
+                // The normal points toward the second body.
+                let handles = [manifold.data.body_pair.body1, manifold.data.body_pair.body2];
+                let normals = [-manifold.data.normal, manifold.data.normal];
+
+                for (handle, normal) in handles.iter().zip(normals.iter()) {
+                    let rb = &bodies[*handle];
+
+                    if island.get(rb.active_set_offset) == Some(handle)
+                        && normal.dot(&gravity_fn(rb)) < 0.0
+                    {
+                        supported[rb.active_set_offset] = true;
+                    }
+                }
+            }
+
+            supported.iter().all(|s| *s)
+        };
+
+        let mut resting_islands = std::mem::replace(&mut self.resting_islands, Vec::new());
+        resting_islands.clear();
+
+        for island_id in 0..bodies.num_islands() {
+            let resting = params.skip_resting_islands
+                && self.joint_constraint_indices[island_id].is_empty()
+                && bodies
+                    .active_island(island_id)
+                    .iter()
+                    .all(|handle| is_resting(&bodies[*handle]))
+                && manifold_indices[island_id].iter().all(|id| {
+                    let manifold = &manifolds[*id];
+                    let (body1, body2) =
+                        (manifold.data.body_pair.body1, manifold.data.body_pair.body2);
+                    is_resting(&bodies[body1])
+                        && is_resting(&bodies[body2])
+                        && manifold.points.iter().all(|pt| {
+                            pt.data.frames_alive > 1 && pt.dist >= -params.allowed_linear_error
+                        })
+                })
+                && is_supported(island_id);
+            resting_islands.push(resting);
+        }
+
+        self.resting_islands = resting_islands;
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::dynamics::{IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet};
+    use crate::dynamics::{
+        IntegrationParameters, JointSet, RigidBody, RigidBodyBuilder, RigidBodySet,
+    };
     use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
-    use crate::math::Vector;
-    use crate::pipeline::PhysicsPipeline;
+    use crate::math::{Isometry, Real, Vector};
+    use crate::pipeline::{CollectedEvents, PhysicsPipeline, TestWorld};
 
     #[test]
     fn kinematic_and_static_contact_crash() {
@@ -326,6 +818,482 @@ mod test {
         );
     }
 
+    #[test]
+    fn gravity_fn_circular_orbit() {
+        let mut world = TestWorld::new();
+
+        // Inverse-square attractor located at the origin.
+        let gm: Real = 100.0;
+        let radius: Real = 10.0;
+        let speed = (gm / radius).sqrt();
+        let mut rb = RigidBodyBuilder::new_dynamic()
+            .position(Isometry::new(Vector::x() * radius, na::zero()))
+            .can_sleep(false)
+            .build();
+        rb.set_linvel(Vector::y() * speed, true);
+        let handle = world.bodies.insert(rb);
+        world.colliders.insert(
+            ColliderBuilder::ball(0.1).build(),
+            handle,
+            &mut world.bodies,
+        );
+
+        let gravity_fn = |rb: &RigidBody| {
+            let dir = -rb.position().translation.vector;
+            let dist = dir.norm();
+            dir * (gm / (dist * dist * dist))
+        };
+
+        // Simulate a bit more than one full revolution.
+        for _ in 0..1500 {
+            world.pipeline.step_with_gravity_fn(
+                &gravity_fn,
+                &world.params,
+                &mut world.broad_phase,
+                &mut world.narrow_phase,
+                &mut world.bodies,
+                &mut world.colliders,
+                &mut world.joints,
+                &(),
+                &(),
+            );
+
+            let dist = world.bodies[handle].position().translation.vector.norm();
+            assert!((dist - radius).abs() < radius * 0.05);
+        }
+    }
+
+    fn canned_scene_state_hash() -> u64 {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * -100.0, na::zero()))
+                .build(),
+        );
+        world.colliders.insert(
+            ColliderBuilder::ball(100.0).build(),
+            ground,
+            &mut world.bodies,
+        );
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let shift = Vector::x() * (i as Real * 0.3) + Vector::y() * (1.0 + j as Real);
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(shift, na::zero()))
+                    .build();
+                let handle = world.bodies.insert(rb);
+                let collider = if (i + j) % 2 == 0 {
+                    ColliderBuilder::ball(0.4).build()
+                } else {
+                    ColliderBuilder::capsule_y(0.2, 0.3).build()
+                };
+                world.colliders.insert(collider, handle, &mut world.bodies);
+            }
+        }
+
+        for _ in 0..200 {
+            world.step();
+        }
+
+        PhysicsPipeline::state_hash(&world.bodies, &world.narrow_phase)
+    }
+
+    // The CI runs this test with `enhanced-determinism` on several platforms
+    // and checks that the printed hashes match.
+    #[test]
+    fn canned_scene_determinism() {
+        let hash = canned_scene_state_hash();
+        assert_eq!(hash, canned_scene_state_hash());
+        println!("canned scene state hash: {:016x}", hash);
+    }
+
+    fn simulated_state_hash(perturbation: Real) -> u64 {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        for i in 0..3 {
+            let shift =
+                Vector::x() * (i as Real * 0.2 + perturbation) + Vector::y() * (1.0 + i as Real);
+            let handle = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(shift, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.4).build(),
+                handle,
+                &mut world.bodies,
+            );
+        }
+
+        for _ in 0..100 {
+            world.step();
+        }
+
+        PhysicsPipeline::state_hash(&world.bodies, &world.narrow_phase)
+    }
+
+    #[test]
+    fn state_hash() {
+        let hash = simulated_state_hash(0.0);
+        assert_eq!(hash, simulated_state_hash(0.0));
+        assert_ne!(hash, simulated_state_hash(1.0e-3));
+    }
+
+    #[test]
+    fn rebase_preserves_relative_dynamics() {
+        let offset = Vector::x() * 8.0 - Vector::y() * 4.0;
+
+        // Simulates a ball rolling down a ramp, rebasing the world
+        // before the step `rebase_step`.
+        let run = |rebase_step: Option<usize>| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+
+            #[cfg(feature = "dim2")]
+            let tilt = 0.1;
+            #[cfg(feature = "dim3")]
+            let tilt = Vector::z() * 0.1;
+            let ground = world
+                .bodies
+                .insert(RigidBodyBuilder::new_static().rotation(tilt).build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+
+            for i in 0..100 {
+                if rebase_step == Some(i) {
+                    world
+                        .pipeline
+                        .rebase(&offset, &mut world.bodies, &mut world.colliders);
+                }
+
+                world.step();
+            }
+
+            let rel_pos = world.bodies[ball].position().translation.vector
+                - world.bodies[ground].position().translation.vector;
+            (
+                rel_pos,
+                *world.bodies[ball].linvel(),
+                world.bodies[ground].position().translation.vector,
+            )
+        };
+
+        let (rel_pos1, linvel1, ground_pos1) = run(None);
+        let (rel_pos2, linvel2, ground_pos2) = run(Some(50));
+
+        assert!((ground_pos2 - (ground_pos1 - offset)).norm() < 1.0e-5);
+        assert!((rel_pos1 - rel_pos2).norm() < 1.0e-3);
+        assert!((linvel1 - linvel2).norm() < 1.0e-3);
+        // The ball is rolling down the ramp.
+        assert!(linvel1.norm() > 0.1);
+    }
+
+    #[test]
+    fn step_collect_matches_event_handler() {
+        use crate::pipeline::ChannelEventCollector;
+
+        // Simulates a bouncing ball, collecting the events of each step either through
+        // `step_collect` or through a `ChannelEventCollector`.
+        let run = |use_step_collect: bool| {
+            let mut world = TestWorld::new();
+            world.gravity = Vector::y() * -9.81;
+            world.params.contact_force_event_threshold = 1.0;
+
+            let (intersection_send, intersection_recv) = crossbeam::channel::unbounded();
+            let (contact_send, contact_recv) = crossbeam::channel::unbounded();
+            let (force_send, force_recv) = crossbeam::channel::unbounded();
+            let handler = ChannelEventCollector::new(intersection_send, contact_send)
+                .with_contact_force_event_sender(force_send);
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            world
+                .colliders
+                .insert(ground_shape.build(), ground, &mut world.bodies);
+
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(Vector::y() * 3.0, na::zero()))
+                    .build(),
+            );
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).restitution(0.8).build(),
+                ball,
+                &mut world.bodies,
+            );
+            // A sensor the ball goes through.
+            #[cfg(feature = "dim2")]
+            let sensor_shape = ColliderBuilder::cuboid(1.0, 0.1);
+            #[cfg(feature = "dim3")]
+            let sensor_shape = ColliderBuilder::cuboid(1.0, 0.1, 1.0);
+            world.colliders.insert(
+                sensor_shape
+                    .sensor(true)
+                    .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                    .build(),
+                ground,
+                &mut world.bodies,
+            );
+
+            let mut steps = Vec::new();
+            for _ in 0..200 {
+                let events = if use_step_collect {
+                    world.pipeline.step_collect(
+                        &world.gravity,
+                        &world.params,
+                        &mut world.broad_phase,
+                        &mut world.narrow_phase,
+                        &mut world.bodies,
+                        &mut world.colliders,
+                        &mut world.joints,
+                        &(),
+                    )
+                } else {
+                    world.step_with(&(), &handler);
+                    CollectedEvents {
+                        contact_events: contact_recv.try_iter().collect(),
+                        intersection_events: intersection_recv.try_iter().collect(),
+                        contact_force_events: force_recv.try_iter().collect(),
+                    }
+                };
+                steps.push(format!("{:?}", events));
+            }
+
+            steps
+        };
+
+        let collected = run(true);
+        assert_eq!(collected, run(false));
+        assert!(collected.iter().any(|e| e.contains("Started")));
+        assert!(collected.iter().any(|e| e.contains("Stopped")));
+        assert!(collected.iter().any(|e| e.contains("intersecting: true")));
+        assert!(collected.iter().any(|e| e.contains("total_force")));
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn skip_resting_islands() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.min_island_size = 1;
+        world.params.skip_resting_islands = true;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // Two balls touching the ground, far from each other.
+        let mut balls = vec![];
+        for x in [-5.0, 5.0].iter() {
+            let ball = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * 1.0,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.5).build(), ball, &mut world.bodies);
+            balls.push(ball);
+        }
+
+        let step = |world: &mut TestWorld| {
+            world.step();
+            world.pipeline.solved_islands().to_vec()
+        };
+
+        // The first step solves the new contacts.
+        assert_eq!(step(&mut world).len(), 2);
+
+        // Then both islands are at rest and are no longer solved.
+        let positions: Vec<_> = balls.iter().map(|h| *world.bodies[*h].position()).collect();
+        for _ in 0..10 {
+            assert!(step(&mut world).is_empty());
+        }
+
+        for (handle, pos) in balls.iter().zip(positions.iter()) {
+            assert!(!world.bodies[*handle].is_sleeping());
+            assert_eq!(world.bodies[*handle].position(), pos);
+        }
+
+        // Touching one ball only solves its island.
+        world
+            .bodies
+            .get_mut(balls[1])
+            .unwrap()
+            .apply_impulse(Vector::x() * 0.1, true);
+        let solved = step(&mut world);
+        assert_eq!(solved, vec![world.bodies[balls[1]].active_island_id]);
+        assert_eq!(world.bodies[balls[0]].position(), &positions[0]);
+        assert_ne!(world.bodies[balls[1]].position(), &positions[1]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel"))]
+    fn skip_resting_islands_in_mid_air() {
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+        world.params.min_island_size = 1;
+        world.params.skip_resting_islands = true;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // A ball spawned at rest in mid-air, and a ball jumping slowly enough to be considered
+        // at rest at the apex of its trajectory.
+        let spawned = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(
+                    Vector::x() * -5.0 + Vector::y() * 5.0,
+                    na::zero(),
+                ))
+                .build(),
+        );
+        let jumping = world.bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(Vector::x() * 5.0 + Vector::y(), na::zero()))
+                .build(),
+        );
+        for handle in &[spawned, jumping] {
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                *handle,
+                &mut world.bodies,
+            );
+        }
+        world.bodies[jumping].set_linvel(Vector::y() * 1.0, true);
+
+        for _ in 0..120 {
+            world.step();
+        }
+
+        // Both balls fell back on the ground.
+        for handle in &[spawned, jumping] {
+            assert!((world.bodies[*handle].position().translation.y - 1.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn merge_worlds() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        // The first world only contains the ground.
+        let mut world = TestWorld::new();
+        world.gravity = Vector::y() * -9.81;
+
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // The second world contains two balls linked by a joint, falling toward the ground
+        // of the first world.
+        let mut other_colliders = ColliderSet::new();
+        let mut other_joints = JointSet::new();
+        let mut other_bodies = RigidBodySet::new();
+        let mut balls = vec![];
+        for x in [-1.0, 1.0].iter() {
+            let ball = other_bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(
+                        Vector::x() * *x + Vector::y() * 2.0,
+                        na::zero(),
+                    ))
+                    .build(),
+            );
+            other_colliders.insert(ColliderBuilder::ball(0.5).build(), ball, &mut other_bodies);
+            balls.push(ball);
+        }
+        let joint = BallJoint::new(Point::origin(), Point::from(Vector::x() * -2.0));
+        let other_joint = other_joints.insert(&mut other_bodies, balls[0], balls[1], joint);
+        other_bodies
+            .get_mut(balls[0])
+            .unwrap()
+            .set_linvel(Vector::y() * -1.0, true);
+
+        let handles = PhysicsPipeline::merge(
+            &mut world.bodies,
+            &mut world.colliders,
+            &mut world.joints,
+            &other_bodies,
+            &other_colliders,
+            &other_joints,
+        );
+
+        assert_eq!(world.bodies.len(), 3);
+        assert_eq!(world.colliders.len(), 3);
+        assert_eq!(world.joints.len(), 1);
+        let new_balls: Vec<_> = balls.iter().map(|h| handles.bodies[h]).collect();
+        let new_joint = &world.joints.get(handles.joints[&other_joint]).unwrap();
+        assert_eq!(
+            (new_joint.body1, new_joint.body2),
+            (new_balls[0], new_balls[1])
+        );
+        assert_eq!(world.bodies[new_balls[0]].linvel(), &(Vector::y() * -1.0));
+        assert_eq!(
+            world.bodies[new_balls[0]].mass(),
+            other_bodies[balls[0]].mass()
+        );
+
+        for _ in 0..200 {
+            world.step();
+        }
+
+        // The balls of the second world rest on the ground of the first one.
+        for handle in &new_balls {
+            let pos = world.bodies[*handle].position().translation.vector;
+            assert!((pos.y - 1.0).abs() < 0.05);
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn rigid_body_removal_snapshot_handle_determinism() {
@@ -1,10 +1,12 @@
 use crate::dynamics::RigidBodySet;
 use crate::geometry::{
     Collider, ColliderHandle, ColliderSet, InteractionGroups, PointProjection, Ray,
-    RayIntersection, SimdQuadTree,
+    RayIntersection, SimdQuadTree, AABB,
 };
 use crate::math::{Isometry, Point, Real, Vector};
 use crate::parry::motion::RigidMotion;
+use crate::utils::DeterministicMath;
+use na::Unit;
 use parry::query::details::{
     IntersectionCompositeShapeShapeBestFirstVisitor,
     NonlinearTOICompositeShapeShapeBestFirstVisitor, PointCompositeShapeProjBestFirstVisitor,
@@ -15,8 +17,10 @@ use parry::query::details::{
 use parry::query::visitors::{
     BoundingVolumeIntersectionsVisitor, PointIntersectionsVisitor, RayIntersectionsVisitor,
 };
-use parry::query::{DefaultQueryDispatcher, QueryDispatcher, TOI};
-use parry::shape::{FeatureId, Shape, TypedSimdCompositeShape};
+use parry::query::{DefaultQueryDispatcher, QueryDispatcher, RayCast, TOIStatus, TOI};
+#[cfg(feature = "dim3")]
+use parry::shape::TriMesh;
+use parry::shape::{FeatureId, Shape, Triangle, TypedSimdCompositeShape};
 use std::sync::Arc;
 
 /// A pipeline for performing queries on all the colliders of a scene.
@@ -33,6 +37,38 @@ pub struct QueryPipeline {
     dilation_factor: Real,
 }
 
+/// The projection of a point on a collider, together with the outward normal
+/// of the collider's boundary at the projected point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct PointProjectionWithNormal {
+    /// The collider the point was projected on.
+    pub collider: ColliderHandle,
+    /// The projection of the point on the collider.
+    pub point: Point<Real>,
+    /// The outward normal of the collider's boundary at the point projected on this boundary.
+    pub normal: Unit<Vector<Real>>,
+    /// Is the projected point located inside of the collider?
+    pub is_inside: bool,
+}
+
+/// The intersection between a ray and a triangle of a triangle mesh or heightfield collider.
+#[derive(Copy, Clone, Debug)]
+pub struct RayTriangleIntersection {
+    /// The collider hit by the ray.
+    pub collider: ColliderHandle,
+    /// The intersection between the ray and the collider.
+    pub intersection: RayIntersection,
+    /// The triangle hit by the ray, as `FeatureId::Face(i)`.
+    ///
+    /// For a triangle mesh, `i` is the index of the triangle in `TriMesh::indices`. For a
+    /// heightfield, `i` is `2 * (row + col * nrows) + k` where `k` is `0` or `1` depending
+    /// on which of the two triangles of the cell `(row, col)` was hit.
+    pub triangle: FeatureId,
+    /// The barycentric coordinates of the hit point with respect to the vertices of the triangle hit.
+    pub barycentric_coords: [Real; 3],
+}
+
 struct QueryPipelineAsCompositeShape<'a> {
     query_pipeline: &'a QueryPipeline,
     colliders: &'a ColliderSet,
@@ -50,7 +86,9 @@ impl<'a> TypedSimdCompositeShape for QueryPipelineAsCompositeShape<'a> {
     ) {
         if let Some(collider) = self.colliders.get(shape_id) {
             if collider.collision_groups.test(self.groups) {
-                f(Some(collider.position()), collider.shape())
+                if let Some(shape) = collider.query_shape() {
+                    f(Some(collider.position()), shape)
+                }
             }
         }
     }
@@ -181,6 +219,122 @@ impl QueryPipeline {
         self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1)
     }
 
+    /// Find the closest intersection between a ray and a set of collider, ignoring the back faces
+    /// of one-sided triangle meshes.
+    ///
+    /// This behaves like `Self::cast_ray_and_get_normal` except that rays hitting the back face
+    /// of a triangle of a collider built with `ColliderBuilder::one_sided` go through it.
+    ///
+    /// # Parameters
+    /// - `ray`: the ray to cast.
+    /// - `max_toi`: the maximum time-of-impact that can be reported by this cast. This effectively
+    ///   limits the length of the ray to `ray.dir.norm() * max_toi`. Use `Real::MAX` for an unbounded ray.
+    #[cfg(feature = "dim3")]
+    pub fn cast_ray_and_get_normal_ignoring_backfaces(
+        &self,
+        colliders: &ColliderSet,
+        ray: &Ray,
+        max_toi: Real,
+        solid: bool,
+        groups: InteractionGroups,
+    ) -> Option<(ColliderHandle, RayIntersection)> {
+        let mut best: Option<(ColliderHandle, RayIntersection)> = None;
+        let mut leaf_callback = |handle: &ColliderHandle| {
+            if let Some(coll) = colliders.get(*handle) {
+                if coll.collision_groups.test(groups) {
+                    let hit = match coll.shape().as_trimesh() {
+                        Some(trimesh) if coll.is_one_sided() => {
+                            cast_ray_on_front_faces(trimesh, coll.position(), ray, max_toi, solid)
+                        }
+                        _ => coll.query_shape().and_then(|shape| {
+                            shape.cast_ray_and_get_normal(coll.position(), ray, max_toi, solid)
+                        }),
+                    };
+
+                    if let Some(hit) = hit {
+                        if best.map_or(true, |b| hit.toi < b.1.toi) {
+                            best = Some((*handle, hit));
+                        }
+                    }
+                }
+            }
+
+            true
+        };
+
+        let mut visitor = RayIntersectionsVisitor::new(ray, max_toi, &mut leaf_callback);
+        self.quadtree.traverse_depth_first(&mut visitor);
+        best
+    }
+
+    /// Find the closest intersection between a ray and a set of collider, together with the
+    /// triangle hit by the ray.
+    ///
+    /// This returns `None` if no collider is hit, or if the closest collider hit is neither a
+    /// triangle mesh, nor a heightfield (3D only).
+    ///
+    /// # Parameters
+    /// - `ray`: the ray to cast.
+    /// - `max_toi`: the maximum time-of-impact that can be reported by this cast. This effectively
+    ///   limits the length of the ray to `ray.dir.norm() * max_toi`. Use `Real::MAX` for an unbounded ray.
+    pub fn cast_ray_and_get_triangle(
+        &self,
+        colliders: &ColliderSet,
+        ray: &Ray,
+        max_toi: Real,
+        solid: bool,
+        groups: InteractionGroups,
+    ) -> Option<RayTriangleIntersection> {
+        let (handle, intersection) =
+            self.cast_ray_and_get_normal(colliders, ray, max_toi, solid, groups)?;
+        let collider = colliders.get(handle)?;
+        let local_ray = ray.inverse_transform_by(collider.position());
+        // Allow for some numerical errors when re-casting the ray on the candidate triangles.
+        let max_toi = intersection.toi + crate::math::DEFAULT_EPSILON.det_sqrt();
+        let mut best: Option<(u32, Triangle, Real)> = None;
+        let mut test_triangle = |id: u32, triangle: Triangle| {
+            if let Some(toi) = triangle.cast_local_ray(&local_ray, max_toi, solid) {
+                if best.map(|b| toi < b.2).unwrap_or(true) {
+                    best = Some((id, triangle, toi));
+                }
+            }
+        };
+
+        if let Some(trimesh) = collider.shape().as_trimesh() {
+            let mut leaf_callback = |id: &u32| {
+                test_triangle(*id, trimesh.triangle(*id));
+                true
+            };
+            let mut visitor = RayIntersectionsVisitor::new(&local_ray, max_toi, &mut leaf_callback);
+            trimesh.quadtree().traverse_depth_first(&mut visitor);
+        }
+
+        #[cfg(feature = "dim3")]
+        if let Some(heightfield) = collider.shape().as_heightfield() {
+            let local_hit = local_ray.point_at(intersection.toi);
+            let (i, j) = heightfield.cell_at_point(&local_hit)?;
+            let cell_id = (i + j * heightfield.nrows()) as u32;
+            let (tri1, tri2) = heightfield.triangles_at(i, j);
+
+            if let Some(tri1) = tri1 {
+                test_triangle(cell_id * 2, tri1);
+            }
+            if let Some(tri2) = tri2 {
+                test_triangle(cell_id * 2 + 1, tri2);
+            }
+        }
+
+        let (id, triangle, toi) = best?;
+        let barycentric_coords = barycentric_coordinates(&triangle, &local_ray.point_at(toi));
+
+        Some(RayTriangleIntersection {
+            collider: handle,
+            intersection,
+            triangle: FeatureId::Face(id),
+            barycentric_coords,
+        })
+    }
+
     /// Find the all intersections between a ray and a set of collider and passes them to a callback.
     ///
     /// # Parameters
@@ -203,10 +357,9 @@ impl QueryPipeline {
         let mut leaf_callback = &mut |handle: &ColliderHandle| {
             if let Some(coll) = colliders.get(*handle) {
                 if coll.collision_groups.test(groups) {
-                    if let Some(hit) =
-                        coll.shape()
-                            .cast_ray_and_get_normal(coll.position(), ray, max_toi, solid)
-                    {
+                    if let Some(hit) = coll.query_shape().and_then(|shape| {
+                        shape.cast_ray_and_get_normal(coll.position(), ray, max_toi, solid)
+                    }) {
                         return callback(*handle, coll, hit);
                     }
                 }
@@ -275,6 +428,66 @@ impl QueryPipeline {
             .map(|h| (h.1 .1, h.1 .0))
     }
 
+    /// Find the projection of a point on the closest collider, as well as the outward normal
+    /// of this collider's boundary.
+    ///
+    /// The normal is computed at the projection of the point on the collider's boundary. If the
+    /// point is located inside of the collider, this is the normal at the closest exit point.
+    /// Returns `None` if there is no collider or if the normal is undefined at the projected
+    /// point (for example if the point lies on the boundary of a shape that does not expose
+    /// its feature normals).
+    ///
+    /// # Parameters
+    /// * `colliders` - The set of colliders taking part in this pipeline.
+    /// * `point` - The point to project.
+    /// * `solid` - If this is set to `true` then the collider shapes are considered to
+    ///   be plain (if the point is located inside of a plain shape, its projection is the point
+    ///   itself). If it is set to `false` the collider shapes are considered to be hollow
+    ///   (if the point is located inside of an hollow shape, it is projected on the shape's
+    ///   boundary).
+    /// * `groups` - The bit groups and filter associated to the point to project, in order to only
+    ///   project on colliders with collision groups compatible with the ray's group.
+    pub fn project_point_and_normal(
+        &self,
+        colliders: &ColliderSet,
+        point: &Point<Real>,
+        solid: bool,
+        groups: InteractionGroups,
+    ) -> Option<PointProjectionWithNormal> {
+        let (handle, proj) = self.project_point(colliders, point, solid, groups)?;
+        let collider = colliders.get(handle)?;
+
+        // The normal is always computed on the collider's boundary, even if it is solid.
+        let (boundary_proj, feature) = collider
+            .query_shape()?
+            .project_point_and_get_feature(collider.position(), point);
+        let dpt = boundary_proj.point - point;
+
+        let normal = if let Some(dir) = Unit::try_new(dpt, crate::math::DEFAULT_EPSILON) {
+            if boundary_proj.is_inside {
+                dir
+            } else {
+                -dir
+            }
+        } else {
+            // The point lies on the boundary: use the normal of the feature it is on.
+            let local_point = collider
+                .position()
+                .inverse_transform_point(&boundary_proj.point);
+            let local_normal = collider
+                .query_shape()?
+                .feature_normal_at_point(feature, &local_point)?;
+            collider.position() * local_normal
+        };
+
+        Some(PointProjectionWithNormal {
+            collider: handle,
+            point: proj.point,
+            normal,
+            is_inside: proj.is_inside,
+        })
+    }
+
     /// Find all the colliders containing the given point.
     ///
     /// # Parameters
@@ -294,7 +507,9 @@ impl QueryPipeline {
         let mut leaf_callback = &mut |handle: &ColliderHandle| {
             if let Some(coll) = colliders.get(*handle) {
                 if coll.collision_groups.test(groups)
-                    && coll.shape().contains_point(coll.position(), point)
+                    && coll
+                        .query_shape()
+                        .map_or(false, |shape| shape.contains_point(coll.position(), point))
                 {
                     return callback(*handle, coll);
                 }
@@ -350,6 +565,15 @@ impl QueryPipeline {
     ///   limits the distance traveled by the shape to `shapeVel.norm() * maxToi`.
     /// * `groups` - The bit groups and filter associated to the shape to cast, in order to only
     ///   test on colliders with collision groups compatible with this group.
+    ///
+    /// The witness point and normal on the hit collider are given in world-space, while the
+    /// witness point and normal on the cast shape are given in its local-space.
+    ///
+    /// If the shape is penetrating the hit collider at its initial position, the returned status
+    /// is `TOIStatus::Penetrating` with a time-of-impact of zero, and the witness points and
+    /// normals are the ones of the deepest penetration: the cast shape can be pushed out of the
+    /// collider by translating it along `normal1` by the penetration depth
+    /// `(witness1 - shape_pos * witness2).dot(&normal1)`.
     pub fn cast_shape<'a>(
         &self,
         colliders: &'a ColliderSet,
@@ -370,7 +594,26 @@ impl QueryPipeline {
             max_toi,
             target_distance,
         );
-        self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1)
+        let (handle, mut toi) = self.quadtree.traverse_best_first(&mut visitor)?.1;
+
+        if toi.status == TOIStatus::Penetrating {
+            // The time-of-impact doesn't give any meaningful witness points or normals in
+            // this case, so replace them by the ones of the deepest penetration.
+            let co = &colliders[handle];
+            let pos12 = co.position().inv_mul(shape_pos);
+
+            if let Ok(Some(contact)) =
+                self.query_dispatcher
+                    .contact(&pos12, co.query_shape()?, shape, 0.0)
+            {
+                toi.witness1 = co.position() * contact.point1;
+                toi.witness2 = contact.point2;
+                toi.normal1 = co.position() * contact.normal1;
+                toi.normal2 = contact.normal2;
+            }
+        }
+
+        Some((handle, toi))
     }
 
     /// Casts a shape with an arbitrary continuous motion and retrieve the first collider it hits.
@@ -430,8 +673,10 @@ impl QueryPipeline {
                 if coll.collision_groups.test(groups) {
                     let pos12 = inv_shape_pos * coll.position();
 
-                    if dispatcher.intersection_test(&pos12, shape, coll.shape()) == Ok(true) {
-                        return callback(*handle, coll);
+                    if let Some(coll_shape) = coll.query_shape() {
+                        if dispatcher.intersection_test(&pos12, shape, coll_shape) == Ok(true) {
+                            return callback(*handle, coll);
+                        }
                     }
                 }
             }
@@ -444,4 +689,444 @@ impl QueryPipeline {
 
         self.quadtree.traverse_depth_first(&mut visitor);
     }
+
+    /// Retrieve all the colliders with an AABB intersecting the given AABB.
+    ///
+    /// This is cheaper than `intersections_with_shape` with a cuboid since no exact
+    /// shape-shape intersection test is performed.
+    ///
+    /// # Parameters
+    /// * `colliders` - The set of colliders taking part in this pipeline.
+    /// * `aabb` - The AABB to test.
+    /// * `groups` - The bit groups and filter associated to the AABB to test, in order to only
+    ///   test on colliders with collision groups compatible with this group.
+    /// * `callback` - A function called with the handles of each collider with an AABB
+    ///   intersecting `aabb`. Return `false` from this callback to stop the traversal.
+    pub fn colliders_with_aabb_intersecting<'a>(
+        &self,
+        colliders: &'a ColliderSet,
+        aabb: &AABB,
+        groups: InteractionGroups,
+        mut callback: impl FnMut(ColliderHandle, &'a Collider) -> bool,
+    ) {
+        let mut leaf_callback = &mut |handle: &ColliderHandle| {
+            if let Some(coll) = colliders.get(*handle) {
+                // The AABBs stored by the quadtree are dilated, so we need
+                // to check the actual collider AABB too.
+                if coll.collision_groups.test(groups) && coll.compute_aabb().intersects(aabb) {
+                    return callback(*handle, coll);
+                }
+            }
+
+            true
+        };
+
+        let mut visitor = BoundingVolumeIntersectionsVisitor::new(aabb, &mut leaf_callback);
+        self.quadtree.traverse_depth_first(&mut visitor);
+    }
+}
+
+// Casts a ray on the triangles of a mesh that face the ray origin.
+#[cfg(feature = "dim3")]
+fn cast_ray_on_front_faces(
+    trimesh: &TriMesh,
+    position: &Isometry<Real>,
+    ray: &Ray,
+    max_toi: Real,
+    solid: bool,
+) -> Option<RayIntersection> {
+    let local_ray = ray.inverse_transform_by(position);
+    let mut best: Option<RayIntersection> = None;
+    let mut leaf_callback = |id: &u32| {
+        let triangle = trimesh.triangle(*id);
+
+        if triangle.scaled_normal().dot(&local_ray.dir) < 0.0 {
+            if let Some(mut hit) =
+                triangle.cast_local_ray_and_get_normal(&local_ray, max_toi, solid)
+            {
+                if best.map_or(true, |b| hit.toi < b.toi) {
+                    // Report the triangle like the ray casts on the whole mesh do.
+                    hit.feature = FeatureId::Face(*id);
+                    best = Some(hit);
+                }
+            }
+        }
+
+        true
+    };
+
+    let mut visitor = RayIntersectionsVisitor::new(&local_ray, max_toi, &mut leaf_callback);
+    trimesh.quadtree().traverse_depth_first(&mut visitor);
+    best.map(|mut hit| {
+        hit.normal = position * hit.normal;
+        hit
+    })
+}
+
+// The barycentric coordinates of a point, assumed to lie on the plane of the given triangle.
+fn barycentric_coordinates(triangle: &Triangle, point: &Point<Real>) -> [Real; 3] {
+    let ab = triangle.b - triangle.a;
+    let ac = triangle.c - triangle.a;
+    let ap = point - triangle.a;
+    let d00 = ab.dot(&ab);
+    let d01 = ab.dot(&ac);
+    let d11 = ac.dot(&ac);
+    let d20 = ap.dot(&ab);
+    let d21 = ap.dot(&ac);
+    let inv_denom = crate::utils::inv(d00 * d11 - d01 * d01);
+    let v = (d11 * d20 - d01 * d21) * inv_denom;
+    let w = (d00 * d21 - d01 * d20) * inv_denom;
+    [1.0 - v - w, v, w]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{ColliderBuilder, ColliderSet, InteractionGroups};
+    use crate::math::{Isometry, Point, Real, Vector};
+    use crate::pipeline::QueryPipeline;
+
+    #[test]
+    fn cast_shape_starting_in_penetration() {
+        use crate::geometry::Ball;
+        use parry::query::TOIStatus;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        let body = bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::y() * 2.0, na::zero()))
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let cuboid = ColliderBuilder::cuboid(1.0, 1.0);
+        #[cfg(feature = "dim3")]
+        let cuboid = ColliderBuilder::cuboid(1.0, 1.0, 1.0);
+        let handle = colliders.insert(cuboid.build(), body, &mut bodies);
+        query_pipeline.update(&bodies, &colliders);
+
+        // A ball starting inside the box, close to its +x face.
+        let shape_pos = Isometry::new(Vector::x() * 0.8 + Vector::y() * 2.0, na::zero());
+        let (hit, toi) = query_pipeline
+            .cast_shape(
+                &colliders,
+                &shape_pos,
+                &-Vector::y(),
+                &Ball::new(0.1),
+                10.0,
+                0.0,
+                InteractionGroups::all(),
+            )
+            .unwrap();
+
+        assert_eq!(hit, handle);
+        assert_eq!(toi.status, TOIStatus::Penetrating);
+        assert_eq!(toi.toi, 0.0);
+
+        let depth = (toi.witness1 - shape_pos * toi.witness2).dot(&toi.normal1);
+        assert!((toi.normal1.into_inner() - Vector::x()).norm() < 1.0e-5);
+        assert!((depth - 0.3).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn colliders_with_aabb_intersecting_boundary() {
+        use crate::geometry::AABB;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        // Unit balls centered along the x axis. The query AABB spans [-1, 1] along
+        // each axis, so the balls at x = 1.5 and x = -1.8 straddle its boundary, the
+        // one at x = 2.05 is just outside of it, and the one at x = 5.0 is far away.
+        let xs = [0.0, 1.5, -1.8, 2.05, 5.0];
+        let expected = [true, true, true, false, false];
+        let mut handles = Vec::new();
+
+        for x in xs.iter() {
+            let body = bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(Vector::x() * *x, na::zero()))
+                    .build(),
+            );
+            handles.push(colliders.insert(ColliderBuilder::ball(1.0).build(), body, &mut bodies));
+        }
+
+        // A collider fully inside of the AABB, but with incompatible collision groups.
+        let filtered_body = bodies.insert(RigidBodyBuilder::new_static().build());
+        let filtered = colliders.insert(
+            ColliderBuilder::ball(0.5)
+                .collision_groups(InteractionGroups::none())
+                .build(),
+            filtered_body,
+            &mut bodies,
+        );
+
+        query_pipeline.update(&bodies, &colliders);
+
+        let aabb = AABB::new(
+            Point::origin() - Vector::repeat(1.0),
+            Point::origin() + Vector::repeat(1.0),
+        );
+        let mut found = Vec::new();
+        query_pipeline.colliders_with_aabb_intersecting(
+            &colliders,
+            &aabb,
+            InteractionGroups::all(),
+            |handle, _| {
+                found.push(handle);
+                true
+            },
+        );
+
+        for (handle, expected) in handles.iter().zip(expected.iter()) {
+            assert_eq!(found.contains(handle), *expected);
+        }
+        assert!(!found.contains(&filtered));
+
+        // Returning `false` stops the traversal.
+        let mut num_found = 0;
+        query_pipeline.colliders_with_aabb_intersecting(
+            &colliders,
+            &aabb,
+            InteractionGroups::all(),
+            |_, _| {
+                num_found += 1;
+                false
+            },
+        );
+        assert_eq!(num_found, 1);
+    }
+
+    #[test]
+    fn cast_ray_and_get_triangle_on_trimesh() {
+        use crate::geometry::{FeatureId, Ray};
+        use crate::math::Real;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        // A unit square made of two triangles, split along its diagonal.
+        #[cfg(feature = "dim2")]
+        let (vertices, origins, dir) = (
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(1.0, 0.0),
+                Point::new(1.0, 1.0),
+                Point::new(0.0, 1.0),
+            ],
+            [Point::new(0.75, -1.0), Point::new(-1.0, 0.75)],
+            [Vector::y(), Vector::x()],
+        );
+        #[cfg(feature = "dim3")]
+        let (vertices, origins, dir) = (
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 1.0),
+                Point::new(0.0, 0.0, 1.0),
+            ],
+            [Point::new(0.75, 1.0, 0.25), Point::new(0.25, 1.0, 0.75)],
+            [-Vector::y(), -Vector::y()],
+        );
+        let indices = vec![[0, 1, 2], [0, 2, 3]];
+
+        let ground = bodies.insert(
+            RigidBodyBuilder::new_static()
+                .position(Isometry::new(Vector::x() * 3.0, na::zero()))
+                .build(),
+        );
+        let trimesh = colliders.insert(
+            ColliderBuilder::trimesh(vertices, indices).build(),
+            ground,
+            &mut bodies,
+        );
+        query_pipeline.update(&bodies, &colliders);
+
+        #[cfg(feature = "dim2")]
+        let expected_coords: [[Real; 3]; 2] = [[0.25, 0.75, 0.0], [0.25, 0.0, 0.75]];
+        #[cfg(feature = "dim3")]
+        let expected_coords: [[Real; 3]; 2] = [[0.25, 0.5, 0.25], [0.25, 0.25, 0.5]];
+
+        for k in 0..2 {
+            let origin = origins[k] + Vector::x() * 3.0;
+            let ray = Ray::new(origin, dir[k]);
+            let hit = query_pipeline
+                .cast_ray_and_get_triangle(
+                    &colliders,
+                    &ray,
+                    Real::MAX,
+                    true,
+                    InteractionGroups::all(),
+                )
+                .unwrap();
+
+            assert_eq!(hit.collider, trimesh);
+            assert_eq!(hit.triangle, FeatureId::Face(k as u32));
+            assert!((hit.intersection.toi - 1.0).abs() < 1.0e-5);
+
+            for i in 0..3 {
+                assert!((hit.barycentric_coords[i] - expected_coords[k][i]).abs() < 1.0e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn project_point_and_normal_inside_and_outside() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ColliderBuilder::ball(1.0).build(), ground, &mut bodies);
+        #[cfg(feature = "dim2")]
+        let cuboid = ColliderBuilder::cuboid(1.0, 1.0);
+        #[cfg(feature = "dim3")]
+        let cuboid = ColliderBuilder::cuboid(1.0, 1.0, 1.0);
+        let cuboid = cuboid
+            .position(Isometry::new(Vector::x() * 5.0, na::zero()))
+            .build();
+        colliders.insert(cuboid, ground, &mut bodies);
+        query_pipeline.update(&bodies, &colliders);
+
+        let groups = InteractionGroups::all();
+
+        // Inside of the ball: the normal points toward the closest exit point.
+        let point = Point::from(Vector::x() * 0.5);
+        let proj = query_pipeline
+            .project_point_and_normal(&colliders, &point, true, groups)
+            .unwrap();
+        assert!(proj.is_inside);
+        assert_eq!(proj.point, point);
+        assert!((*proj.normal - Vector::x()).norm() < 1.0e-5);
+
+        // Outside of the ball.
+        let point = Point::from(-Vector::x() * 2.0);
+        let proj = query_pipeline
+            .project_point_and_normal(&colliders, &point, true, groups)
+            .unwrap();
+        assert!(!proj.is_inside);
+        assert!((proj.point - Point::from(-Vector::x())).norm() < 1.0e-5);
+        assert!((*proj.normal + Vector::x()).norm() < 1.0e-5);
+
+        // Inside of the cuboid, closer to its top face.
+        let point = Point::from(Vector::x() * 5.0 + Vector::y() * 0.8);
+        let proj = query_pipeline
+            .project_point_and_normal(&colliders, &point, false, groups)
+            .unwrap();
+        assert!(proj.is_inside);
+        assert!((proj.point.coords - (Vector::x() * 5.0 + Vector::y())).norm() < 1.0e-5);
+        assert!((*proj.normal - Vector::y()).norm() < 1.0e-5);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn cast_ray_ignoring_backfaces() {
+        use crate::geometry::{FeatureId, Ray};
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        // A square facing +y.
+        let vertices = vec![
+            Point::new(-5.0, 0.0, -5.0),
+            Point::new(-5.0, 0.0, 5.0),
+            Point::new(5.0, 0.0, 5.0),
+            Point::new(5.0, 0.0, -5.0),
+        ];
+        let indices = vec![[0, 1, 2], [0, 2, 3]];
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let handle = colliders.insert(
+            ColliderBuilder::trimesh(vertices, indices)
+                .one_sided(true)
+                .build(),
+            ground,
+            &mut bodies,
+        );
+        query_pipeline.update(&bodies, &colliders);
+
+        let groups = InteractionGroups::all();
+        let from_front = Ray::new(Point::new(1.0, 2.0, 3.0), -Vector::y());
+        let from_back = Ray::new(Point::new(1.0, -2.0, 1.0), Vector::y());
+
+        let (hit_handle, hit) = query_pipeline
+            .cast_ray_and_get_normal_ignoring_backfaces(&colliders, &from_front, 10.0, true, groups)
+            .unwrap();
+        assert_eq!(hit_handle, handle);
+        assert!((hit.toi - 2.0).abs() < 1.0e-5);
+        assert!((hit.normal - Vector::y()).norm() < 1.0e-5);
+        assert_eq!(hit.feature, FeatureId::Face(0));
+
+        assert!(query_pipeline
+            .cast_ray_and_get_normal_ignoring_backfaces(&colliders, &from_back, 10.0, true, groups)
+            .is_none());
+
+        // The regular ray casts still hit the back faces.
+        assert!(query_pipeline
+            .cast_ray_and_get_normal(&colliders, &from_back, 10.0, true, groups)
+            .is_some());
+    }
+
+    #[test]
+    fn cast_ray_through_hollow_compound_subshape() {
+        use crate::geometry::{Ray, SharedShape};
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut query_pipeline = QueryPipeline::new();
+
+        #[cfg(feature = "dim2")]
+        let cuboid = SharedShape::cuboid(0.5, 2.0);
+        #[cfg(feature = "dim3")]
+        let cuboid = SharedShape::cuboid(0.5, 2.0, 2.0);
+
+        // A doorway, followed by a wall.
+        let building = ColliderBuilder::compound(vec![
+            (Isometry::identity(), cuboid.clone()),
+            (Isometry::new(Vector::x() * 5.0, na::zero()), cuboid),
+        ])
+        .compound_solid_flags(vec![false, true])
+        .build();
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let handle = colliders.insert(building, ground, &mut bodies);
+        query_pipeline.update(&bodies, &colliders);
+
+        let groups = InteractionGroups::all();
+        let ray = Ray::new(Point::from(-Vector::x() * 5.0), Vector::x());
+
+        // The ray goes through the doorway and hits the wall.
+        let (hit_handle, hit) = query_pipeline
+            .cast_ray_and_get_normal(&colliders, &ray, Real::MAX, true, groups)
+            .unwrap();
+        assert_eq!(hit_handle, handle);
+        assert!((hit.toi - 9.5).abs() < 1.0e-5);
+        assert!((hit.normal + Vector::x()).norm() < 1.0e-5);
+
+        let mut tois = Vec::new();
+        query_pipeline.intersections_with_ray(
+            &colliders,
+            &ray,
+            Real::MAX,
+            true,
+            groups,
+            |_, _, hit| {
+                tois.push(hit.toi);
+                true
+            },
+        );
+        assert_eq!(tois.len(), 1);
+        assert!((tois[0] - 9.5).abs() < 1.0e-5);
+
+        // Points inside of the doorway aren't inside of the collider.
+        let mut num_hits = 0;
+        query_pipeline.intersections_with_point(&colliders, &Point::origin(), groups, |_, _| {
+            num_hits += 1;
+            true
+        });
+        assert_eq!(num_hits, 0);
+    }
 }
@@ -0,0 +1,56 @@
+//! A small simulation world shared by the unit tests.
+
+use crate::dynamics::{IntegrationParameters, JointSet, RigidBodySet};
+use crate::geometry::{BroadPhase, ColliderSet, NarrowPhase};
+use crate::math::{Real, Vector};
+use crate::pipeline::{EventHandler, PhysicsHooks, PhysicsPipeline};
+
+/// All the sets and pipelines needed to simulate a scene.
+pub(crate) struct TestWorld {
+    /// The gravity applied at each timestep (default: zero).
+    pub gravity: Vector<Real>,
+    /// The integration parameters used at each timestep.
+    pub params: IntegrationParameters,
+    pub pipeline: PhysicsPipeline,
+    pub broad_phase: BroadPhase,
+    pub narrow_phase: NarrowPhase,
+    pub bodies: RigidBodySet,
+    pub colliders: ColliderSet,
+    pub joints: JointSet,
+}
+
+impl TestWorld {
+    /// Creates an empty world without gravity and with the default integration parameters.
+    pub fn new() -> Self {
+        Self {
+            gravity: Vector::zeros(),
+            params: IntegrationParameters::default(),
+            pipeline: PhysicsPipeline::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            joints: JointSet::new(),
+        }
+    }
+
+    /// Executes one timestep without physics hooks nor event handler.
+    pub fn step(&mut self) {
+        self.step_with(&(), &())
+    }
+
+    /// Executes one timestep with the given physics hooks and event handler.
+    pub fn step_with(&mut self, hooks: &dyn PhysicsHooks, events: &dyn EventHandler) {
+        self.pipeline.step(
+            &self.gravity,
+            &self.params,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.joints,
+            hooks,
+            events,
+        );
+    }
+}
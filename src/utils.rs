@@ -101,6 +101,80 @@ impl WComponent for SimdReal {
     }
 }
 
+/// Square root and trigonometric functions used by the physics engine.
+///
+/// Without the `enhanced-determinism` feature, these call the platform's math functions.
+/// With `enhanced-determinism` enabled, they are computed by a software implementation
+/// (`libm`) which yields bit-identical results on every platform, at the cost of some
+/// performance.
+///
+/// Every scalar square root and trigonometric function computed by rapier goes through this
+/// trait. The ones computed by `nalgebra` and `parry` (norms, normalizations, rotations) are
+/// also implemented with `libm` when `enhanced-determinism` is enabled. Cross-platform
+/// determinism is not guaranteed for the functions called by user code, e.g., in
+/// `PhysicsHooks` or in a `gravity_fn`.
+pub trait DeterministicMath: Copy {
+    /// The square root of `self`.
+    fn det_sqrt(self) -> Self;
+    /// The sine of `self`.
+    fn det_sin(self) -> Self;
+    /// The cosine of `self`.
+    fn det_cos(self) -> Self;
+    /// The four-quadrant arctangent of `self` (y) and `other` (x).
+    fn det_atan2(self, other: Self) -> Self;
+}
+
+#[cfg(not(feature = "enhanced-determinism"))]
+impl DeterministicMath for Real {
+    #[inline]
+    fn det_sqrt(self) -> Self {
+        self.sqrt()
+    }
+    #[inline]
+    fn det_sin(self) -> Self {
+        self.sin()
+    }
+    #[inline]
+    fn det_cos(self) -> Self {
+        self.cos()
+    }
+    #[inline]
+    fn det_atan2(self, other: Self) -> Self {
+        self.atan2(other)
+    }
+}
+
+// NOTE: the `enhanced-determinism` feature enables `simba/libm_force`
+// so the `ComplexField` and `RealField` methods are implemented with `libm`.
+#[cfg(feature = "enhanced-determinism")]
+impl DeterministicMath for Real {
+    #[inline]
+    fn det_sqrt(self) -> Self {
+        na::ComplexField::sqrt(self)
+    }
+    #[inline]
+    fn det_sin(self) -> Self {
+        na::ComplexField::sin(self)
+    }
+    #[inline]
+    fn det_cos(self) -> Self {
+        na::ComplexField::cos(self)
+    }
+    #[inline]
+    fn det_atan2(self, other: Self) -> Self {
+        na::RealField::atan2(self, other)
+    }
+}
+
+/// The norm of `v`, computed with `DeterministicMath::det_sqrt`.
+#[inline]
+pub(crate) fn det_norm<D: na::DimName>(v: &na::VectorN<Real, D>) -> Real
+where
+    na::DefaultAllocator: na::allocator::Allocator<Real, D>,
+{
+    v.norm_squared().det_sqrt()
+}
+
 /// Trait to compute the orthonormal basis of a vector.
 pub trait WBasis: Sized {
     /// The type of the array of orthonormal vectors.
@@ -153,6 +153,9 @@ impl Box2dWorld {
 
                     self.world.create_joint(&def);
                 }
+                JointParams::GearJoint(_) => {
+                    eprintln!("Joint type currently unsupported by the Box2D backend: GearJoint.")
+                }
             }
         }
     }
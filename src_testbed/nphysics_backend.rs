@@ -121,6 +121,11 @@ impl NPhysicsWorld {
                     }
 
                     nphysics_joints.insert(c);
+                }
+                JointParams::GearJoint(_) => {
+                    eprintln!(
+                        "Joint type currently unsupported by the nphysics backend: GearJoint."
+                    )
                 } // JointParams::GenericJoint(_) => {
                   //     eprintln!(
                   //         "Joint type currently unsupported by the nphysics backend: GenericJoint."
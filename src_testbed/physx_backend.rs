@@ -434,6 +434,11 @@ impl PhysxWorld {
                             actor2,
                             &frame2 as *const _,
                         );
+                    }
+                    JointParams::GearJoint(_) => {
+                        eprintln!(
+                            "Joint type currently unsupported by the PhysX backend: GearJoint."
+                        )
                     } // JointParams::GenericJoint(_) => {
                       //     eprintln!(
                       //         "Joint type currently unsupported by the PhysX backend: GenericJoint."